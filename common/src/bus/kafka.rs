@@ -0,0 +1,222 @@
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use rdkafka::consumer::{BaseConsumer, Consumer, ConsumerContext, Rebalance, StreamConsumer};
+use rdkafka::error::KafkaError;
+use rdkafka::message::{Header, Headers, OwnedHeaders};
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use rdkafka::types::RDKafkaErrorCode;
+use rdkafka::util::Timeout;
+use rdkafka::{ClientConfig, ClientContext, Message, Offset, TopicPartitionList};
+use tokio::sync::broadcast;
+
+use super::{
+    BusError, BusMessage, DeliveryReceipt, MessageBus, MessageConsumer, MessageProducer,
+    OutgoingMessage, RebalanceEvent, TopicPartition,
+};
+
+const SEND_TIMEOUT_MS: u64 = 1500;
+/// 已记录位点的自动提交间隔
+const AUTO_COMMIT_INTERVAL_MS: &str = "1000";
+/// 再均衡事件缓冲
+const REBALANCE_EVENT_CAPACITY: usize = 16;
+
+impl From<KafkaError> for BusError {
+    fn from(err: KafkaError) -> Self {
+        match err {
+            KafkaError::MessageProduction(RDKafkaErrorCode::QueueFull) => BusError::QueueFull,
+            KafkaError::MessageProduction(RDKafkaErrorCode::MessageTimedOut) => BusError::Timeout,
+            err => BusError::Backend(err.to_string()),
+        }
+    }
+}
+
+/// Kafka 消息总线
+pub struct KafkaBus {
+    brokers: String,
+    producer: FutureProducer,
+    send_timeout: Duration,
+}
+
+impl KafkaBus {
+    /// 使用可靠投递的默认生产者配置：全副本确认、幂等、snappy 压缩
+    pub fn new(brokers: &str, client_id: &str) -> Result<Self, BusError> {
+        let mut config = ClientConfig::new();
+        config
+            .set("bootstrap.servers", brokers)
+            .set("client.id", client_id)
+            .set("acks", "all")
+            .set("enable.idempotence", "true")
+            .set("message.timeout.ms", "30000")
+            .set("request.timeout.ms", "15000")
+            .set("retries", "3")
+            .set("retry.backoff.ms", "100")
+            .set("compression.type", "snappy")
+            .set("queue.buffering.max.messages", "10000")
+            .set("queue.buffering.max.ms", "5");
+        Self::with_producer_config(brokers, &config)
+    }
+
+    /// 使用自定义生产者配置
+    pub fn with_producer_config(brokers: &str, config: &ClientConfig) -> Result<Self, BusError> {
+        Ok(Self {
+            brokers: brokers.to_string(),
+            producer: config.create()?,
+            send_timeout: Duration::from_millis(SEND_TIMEOUT_MS),
+        })
+    }
+
+    /// 设置发送超时
+    pub fn with_send_timeout(mut self, timeout: Duration) -> Self {
+        self.send_timeout = timeout;
+        self
+    }
+
+    /// 底层 Kafka 生产者，用于健康检查
+    pub fn kafka_producer(&self) -> FutureProducer {
+        self.producer.clone()
+    }
+}
+
+impl MessageBus for KafkaBus {
+    fn producer(&self) -> Arc<dyn MessageProducer> {
+        Arc::new(KafkaProducer {
+            producer: self.producer.clone(),
+            send_timeout: self.send_timeout,
+        })
+    }
+
+    /// 位点由业务处理完成后显式记录（`enable.auto.offset.store=false`），
+    /// 客户端只自动提交已记录的位点，分区收回前也会提交一次
+    fn subscribe(&self, group_id: &str, topics: &[&str]) -> Result<Arc<dyn MessageConsumer>, BusError> {
+        let (events, _) = broadcast::channel(REBALANCE_EVENT_CAPACITY);
+        let consumer: StreamConsumer<RebalanceContext> = ClientConfig::new()
+            .set("group.id", group_id)
+            .set("bootstrap.servers", &self.brokers)
+            .set("enable.auto.commit", "true")
+            .set("enable.auto.offset.store", "false")
+            .set("auto.commit.interval.ms", AUTO_COMMIT_INTERVAL_MS)
+            .set("auto.offset.reset", "earliest")
+            .set("max.poll.interval.ms", "300000")
+            .set("session.timeout.ms", "30000")
+            .create_with_context(RebalanceContext { events: events.clone() })?;
+        consumer.subscribe(topics)?;
+        Ok(Arc::new(KafkaConsumer { consumer, events }))
+    }
+}
+
+struct KafkaProducer {
+    producer: FutureProducer,
+    send_timeout: Duration,
+}
+
+#[async_trait]
+impl MessageProducer for KafkaProducer {
+    async fn send(&self, topic: &str, message: OutgoingMessage) -> Result<DeliveryReceipt, BusError> {
+        let headers = message.headers.iter().fold(OwnedHeaders::new(), |headers, (key, value)| {
+            headers.insert(Header {
+                key: key.as_str(),
+                value: Some(value.as_bytes()),
+            })
+        });
+
+        let mut record = FutureRecord::to(topic)
+            .payload(&message.payload)
+            .headers(headers);
+        if let Some(key) = &message.key {
+            record = record.key(key);
+        }
+        if let Some(timestamp) = message.timestamp {
+            record = record.timestamp(timestamp);
+        }
+
+        let (partition, offset) = self
+            .producer
+            .send(record, Timeout::After(self.send_timeout))
+            .await
+            .map_err(|(err, _)| BusError::from(err))?;
+        Ok(DeliveryReceipt { partition, offset })
+    }
+}
+
+/// 转发分区分配变化
+struct RebalanceContext {
+    events: broadcast::Sender<RebalanceEvent>,
+}
+
+impl ClientContext for RebalanceContext {}
+
+impl ConsumerContext for RebalanceContext {
+    fn pre_rebalance(&self, _consumer: &BaseConsumer<Self>, rebalance: &Rebalance<'_>) {
+        if let Rebalance::Revoke(partitions) = rebalance {
+            let _ = self.events.send(RebalanceEvent::Revoked(topic_partitions(partitions)));
+        }
+    }
+
+    fn post_rebalance(&self, _consumer: &BaseConsumer<Self>, rebalance: &Rebalance<'_>) {
+        if let Rebalance::Assign(partitions) = rebalance {
+            let _ = self.events.send(RebalanceEvent::Assigned(topic_partitions(partitions)));
+        }
+    }
+}
+
+fn topic_partitions(list: &TopicPartitionList) -> Vec<TopicPartition> {
+    list.elements()
+        .iter()
+        .map(|e| (e.topic().to_string(), e.partition()))
+        .collect()
+}
+
+struct KafkaConsumer {
+    consumer: StreamConsumer<RebalanceContext>,
+    events: broadcast::Sender<RebalanceEvent>,
+}
+
+#[async_trait]
+impl MessageConsumer for KafkaConsumer {
+    async fn recv(&self) -> Result<BusMessage, BusError> {
+        let message = self.consumer.recv().await?;
+
+        let headers = message
+            .headers()
+            .map(|headers| {
+                headers
+                    .iter()
+                    .filter_map(|header| {
+                        let value = std::str::from_utf8(header.value?).ok()?;
+                        Some((header.key.to_string(), value.to_string()))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(BusMessage {
+            topic: message.topic().to_string(),
+            partition: message.partition(),
+            offset: message.offset(),
+            key: message
+                .key()
+                .map(|key| String::from_utf8_lossy(key).into_owned()),
+            payload: message.payload().map(<[u8]>::to_vec).unwrap_or_default(),
+            headers,
+            timestamp: message.timestamp().to_millis().unwrap_or_else(|| {
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_millis() as i64
+            }),
+        })
+    }
+
+    async fn commit(&self, topic: &str, partition: i32, offset: i64) -> Result<(), BusError> {
+        let mut offsets = TopicPartitionList::new();
+        offsets.add_partition_offset(topic, partition, Offset::Offset(offset))?;
+        self.consumer.store_offsets(&offsets)?;
+        Ok(())
+    }
+
+    fn rebalance_events(&self) -> broadcast::Receiver<RebalanceEvent> {
+        self.events.subscribe()
+    }
+}
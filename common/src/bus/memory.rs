@@ -0,0 +1,358 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use tokio::sync::{broadcast, Notify};
+
+use super::{
+    BusError, BusMessage, DeliveryReceipt, MessageBus, MessageConsumer, MessageProducer,
+    OutgoingMessage, RebalanceEvent, TopicPartition,
+};
+use crate::topic::TopicRegistry;
+
+const DEFAULT_PARTITIONS: i32 = 4;
+const REBALANCE_EVENT_CAPACITY: usize = 16;
+
+/// 进程内消息总线
+///
+/// 每个主题按分区保存全部消息，消费组内按成员分摊分区并记录已提交位点。
+/// 成员加入或离开时触发再均衡，各成员从已提交位点继续消费，语义与 Kafka 一致：
+/// 未提交的消息在再均衡后会被重新投递。
+#[derive(Clone)]
+pub struct InMemoryBus {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    notify: Notify,
+    default_partitions: i32,
+    partition_counts: HashMap<String, i32>,
+    round_robin: AtomicUsize,
+    next_member_id: AtomicU64,
+}
+
+#[derive(Default)]
+struct State {
+    /// 主题 -> 分区 -> 消息
+    topics: HashMap<String, Vec<Vec<BusMessage>>>,
+    groups: HashMap<String, GroupState>,
+}
+
+#[derive(Default)]
+struct GroupState {
+    members: Vec<u64>,
+    generation: u64,
+    committed: HashMap<(String, i32), i64>,
+}
+
+impl Default for InMemoryBus {
+    fn default() -> Self {
+        Self::new(DEFAULT_PARTITIONS)
+    }
+}
+
+impl InMemoryBus {
+    /// 未在注册表中的主题使用 `default_partitions` 个分区
+    pub fn new(default_partitions: i32) -> Self {
+        Self::build(default_partitions, HashMap::new())
+    }
+
+    /// 按主题注册表设置分区数
+    pub fn with_registry(registry: &TopicRegistry) -> Self {
+        let partition_counts = registry
+            .topics()
+            .iter()
+            .map(|spec| (spec.name.to_string(), spec.partitions))
+            .collect();
+        Self::build(DEFAULT_PARTITIONS, partition_counts)
+    }
+
+    fn build(default_partitions: i32, partition_counts: HashMap<String, i32>) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                state: Mutex::new(State::default()),
+                notify: Notify::new(),
+                default_partitions: default_partitions.max(1),
+                partition_counts,
+                round_robin: AtomicUsize::new(0),
+                next_member_id: AtomicU64::new(1),
+            }),
+        }
+    }
+
+    /// 主题当前消息总数，便于测试断言
+    pub fn message_count(&self, topic: &str) -> usize {
+        let state = self.shared.state.lock().unwrap();
+        state
+            .topics
+            .get(topic)
+            .map(|partitions| partitions.iter().map(Vec::len).sum())
+            .unwrap_or(0)
+    }
+}
+
+impl Shared {
+    fn partitions_of<'a>(&self, state: &'a mut State, topic: &str) -> &'a mut Vec<Vec<BusMessage>> {
+        let count = self
+            .partition_counts
+            .get(topic)
+            .copied()
+            .unwrap_or(self.default_partitions);
+        state
+            .topics
+            .entry(topic.to_string())
+            .or_insert_with(|| vec![Vec::new(); count as usize])
+    }
+
+    fn select_partition(&self, key: Option<&str>, count: usize) -> usize {
+        match key {
+            Some(key) => {
+                let mut hasher = DefaultHasher::new();
+                key.hash(&mut hasher);
+                (hasher.finish() % count as u64) as usize
+            }
+            None => self.round_robin.fetch_add(1, Ordering::Relaxed) % count,
+        }
+    }
+}
+
+impl MessageBus for InMemoryBus {
+    fn producer(&self) -> Arc<dyn MessageProducer> {
+        Arc::new(self.clone())
+    }
+
+    fn subscribe(&self, group_id: &str, topics: &[&str]) -> Result<Arc<dyn MessageConsumer>, BusError> {
+        let member_id = self.shared.next_member_id.fetch_add(1, Ordering::Relaxed);
+        {
+            let mut state = self.shared.state.lock().unwrap();
+            for topic in topics {
+                self.shared.partitions_of(&mut state, topic);
+            }
+            let group = state.groups.entry(group_id.to_string()).or_default();
+            group.members.push(member_id);
+            group.generation += 1;
+        }
+        self.shared.notify.notify_waiters();
+
+        Ok(Arc::new(InMemoryConsumer {
+            shared: self.shared.clone(),
+            group_id: group_id.to_string(),
+            member_id,
+            topics: topics.iter().map(|t| t.to_string()).collect(),
+            cursor: Mutex::new(Cursor::default()),
+            events: broadcast::channel(REBALANCE_EVENT_CAPACITY).0,
+        }))
+    }
+}
+
+#[async_trait]
+impl MessageProducer for InMemoryBus {
+    async fn send(&self, topic: &str, message: OutgoingMessage) -> Result<DeliveryReceipt, BusError> {
+        let receipt = {
+            let mut state = self.shared.state.lock().unwrap();
+            let partitions = self.shared.partitions_of(&mut state, topic);
+            let partition = self
+                .shared
+                .select_partition(message.key.as_deref(), partitions.len());
+            let log = &mut partitions[partition];
+            let offset = log.len() as i64;
+
+            log.push(BusMessage {
+                topic: topic.to_string(),
+                partition: partition as i32,
+                offset,
+                key: message.key,
+                payload: message.payload,
+                headers: message.headers,
+                timestamp: message.timestamp.unwrap_or_else(now_millis),
+            });
+            DeliveryReceipt {
+                partition: partition as i32,
+                offset,
+            }
+        };
+        self.shared.notify.notify_waiters();
+        Ok(receipt)
+    }
+}
+
+#[derive(Default)]
+struct Cursor {
+    generation: u64,
+    /// 当前分配的分区
+    assigned: Vec<TopicPartition>,
+    /// 本成员在当前分配下的拉取位点
+    positions: HashMap<(String, i32), i64>,
+    /// 轮询起点，避免某个分区饿死其他分区
+    next: usize,
+}
+
+struct InMemoryConsumer {
+    shared: Arc<Shared>,
+    group_id: String,
+    member_id: u64,
+    topics: Vec<String>,
+    cursor: Mutex<Cursor>,
+    events: broadcast::Sender<RebalanceEvent>,
+}
+
+impl InMemoryConsumer {
+    fn poll(&self) -> Option<BusMessage> {
+        let state = self.shared.state.lock().unwrap();
+        let group = state.groups.get(&self.group_id)?;
+        let index = group.members.iter().position(|id| *id == self.member_id)?;
+        let members = group.members.len();
+
+        let assigned: Vec<(&String, usize)> = self
+            .topics
+            .iter()
+            .filter_map(|topic| state.topics.get(topic).map(|partitions| (topic, partitions.len())))
+            .flat_map(|(topic, count)| (0..count).map(move |p| (topic, p)))
+            .enumerate()
+            .filter(|(i, _)| i % members == index)
+            .map(|(_, assignment)| assignment)
+            .collect();
+
+        let mut cursor = self.cursor.lock().unwrap();
+        if cursor.generation != group.generation {
+            // 再均衡后从已提交位点重新开始
+            cursor.generation = group.generation;
+            cursor.positions.clear();
+
+            let previous = std::mem::take(&mut cursor.assigned);
+            if !previous.is_empty() {
+                let _ = self.events.send(RebalanceEvent::Revoked(previous));
+            }
+            cursor.assigned = assigned
+                .iter()
+                .map(|(topic, partition)| ((*topic).clone(), *partition as i32))
+                .collect();
+            if !cursor.assigned.is_empty() {
+                let _ = self.events.send(RebalanceEvent::Assigned(cursor.assigned.clone()));
+            }
+        }
+        if assigned.is_empty() {
+            return None;
+        }
+
+        for step in 0..assigned.len() {
+            let (topic, partition) = assigned[(cursor.next + step) % assigned.len()];
+            let key = (topic.clone(), partition as i32);
+            let committed = group.committed.get(&key).copied().unwrap_or(0);
+            let position = *cursor.positions.entry(key.clone()).or_insert(committed);
+
+            if let Some(message) = state.topics[topic][partition].get(position as usize) {
+                cursor.positions.insert(key, position + 1);
+                cursor.next = (cursor.next + step + 1) % assigned.len();
+                return Some(message.clone());
+            }
+        }
+        None
+    }
+}
+
+#[async_trait]
+impl MessageConsumer for InMemoryConsumer {
+    async fn recv(&self) -> Result<BusMessage, BusError> {
+        loop {
+            // 先注册通知再检查，避免错过检查与等待之间的新消息
+            let notified = self.shared.notify.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            if let Some(message) = self.poll() {
+                return Ok(message);
+            }
+            notified.await;
+        }
+    }
+
+    async fn commit(&self, topic: &str, partition: i32, offset: i64) -> Result<(), BusError> {
+        let mut state = self.shared.state.lock().unwrap();
+        let group = state
+            .groups
+            .get_mut(&self.group_id)
+            .ok_or(BusError::Closed)?;
+        let committed = group
+            .committed
+            .entry((topic.to_string(), partition))
+            .or_insert(0);
+        *committed = (*committed).max(offset);
+        Ok(())
+    }
+
+    fn rebalance_events(&self) -> broadcast::Receiver<RebalanceEvent> {
+        self.events.subscribe()
+    }
+}
+
+impl Drop for InMemoryConsumer {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.shared.state.lock() {
+            if let Some(group) = state.groups.get_mut(&self.group_id) {
+                group.members.retain(|id| *id != self.member_id);
+                group.generation += 1;
+            }
+        }
+        self.shared.notify.notify_waiters();
+    }
+}
+
+fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_same_key_keeps_partition_order() {
+        let bus = InMemoryBus::new(4);
+        let producer = bus.producer();
+        let consumer = bus.subscribe("group", &["topic"]).unwrap();
+
+        for i in 0..3 {
+            producer
+                .send("topic", OutgoingMessage::new(vec![i]).key("conversation-1"))
+                .await
+                .unwrap();
+        }
+
+        let payloads: Vec<u8> = recv_payloads(&*consumer, 3).await;
+        assert_eq!(payloads, vec![0, 1, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_rebalance_redelivers_uncommitted() {
+        let bus = InMemoryBus::new(1);
+        let producer = bus.producer();
+        producer.send("topic", OutgoingMessage::new(vec![1])).await.unwrap();
+        producer.send("topic", OutgoingMessage::new(vec![2])).await.unwrap();
+
+        let first = bus.subscribe("group", &["topic"]).unwrap();
+        let message = first.recv().await.unwrap();
+        first.commit_message(&message).await.unwrap();
+        let _uncommitted = first.recv().await.unwrap();
+        drop(first);
+
+        let second = bus.subscribe("group", &["topic"]).unwrap();
+        assert_eq!(second.recv().await.unwrap().payload, vec![2]);
+    }
+
+    async fn recv_payloads(consumer: &dyn MessageConsumer, count: usize) -> Vec<u8> {
+        let mut payloads = Vec::new();
+        for _ in 0..count {
+            payloads.push(consumer.recv().await.unwrap().payload[0]);
+        }
+        payloads
+    }
+}
@@ -0,0 +1,174 @@
+//! 消息总线
+//!
+//! 对 Kafka 生产/消费的抽象，业务代码只依赖 [`MessageProducer`] / [`MessageConsumer`]，
+//! 运行时可选择 [`KafkaBus`] 或进程内的 [`InMemoryBus`]。内存实现同样按分区与消费组
+//! 投递，可用于单元测试、集成测试以及不依赖 broker 的单机开发环境。
+
+mod kafka;
+mod memory;
+mod offsets;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use opentelemetry::Context;
+use tokio::sync::broadcast;
+
+use crate::telemetry::{extract_context_map, inject_context_map};
+use crate::tenant::{self, TenantContext, TenantError, TENANT_KAFKA_HEADER};
+
+pub use kafka::KafkaBus;
+pub use memory::InMemoryBus;
+pub use offsets::{OffsetTicket, OffsetTracker};
+
+/// 消息总线错误
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum BusError {
+    /// 生产者本地队列已满，可稍后重试
+    #[error("producer queue full")]
+    QueueFull,
+    /// 操作超时
+    #[error("operation timed out")]
+    Timeout,
+    /// 总线已关闭
+    #[error("message bus closed")]
+    Closed,
+    /// 后端错误
+    #[error("message bus backend error: {0}")]
+    Backend(String),
+}
+
+/// 待发送消息
+#[derive(Debug, Clone, Default)]
+pub struct OutgoingMessage {
+    /// 分区键
+    pub key: Option<String>,
+    /// 消息体
+    pub payload: Vec<u8>,
+    /// 消息头
+    pub headers: HashMap<String, String>,
+    /// 消息时间戳（毫秒），为空时由后端填充
+    pub timestamp: Option<i64>,
+}
+
+impl OutgoingMessage {
+    pub fn new(payload: Vec<u8>) -> Self {
+        Self {
+            payload,
+            ..Default::default()
+        }
+    }
+
+    /// 设置分区键
+    pub fn key(mut self, key: impl Into<String>) -> Self {
+        self.key = Some(key.into());
+        self
+    }
+
+    /// 设置消息头
+    pub fn header(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.insert(key.into(), value.into());
+        self
+    }
+
+    /// 设置时间戳
+    pub fn timestamp(mut self, timestamp: i64) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// 写入当前链路上下文与租户
+    pub fn with_current_context(mut self) -> Self {
+        inject_context_map(&mut self.headers);
+        self.headers
+            .insert(TENANT_KAFKA_HEADER.to_string(), tenant::current().tenant_id().to_string());
+        self
+    }
+}
+
+/// 已接收消息
+#[derive(Debug, Clone)]
+pub struct BusMessage {
+    pub topic: String,
+    pub partition: i32,
+    pub offset: i64,
+    pub key: Option<String>,
+    pub payload: Vec<u8>,
+    pub headers: HashMap<String, String>,
+    /// 消息时间戳（毫秒）
+    pub timestamp: i64,
+}
+
+impl BusMessage {
+    /// 生产端的链路上下文
+    pub fn trace_context(&self) -> Context {
+        extract_context_map(&self.headers)
+    }
+
+    /// 生产端的租户，缺失时为默认租户，租户头非法时返回错误
+    pub fn tenant(&self) -> Result<TenantContext, TenantError> {
+        match self.headers.get(TENANT_KAFKA_HEADER) {
+            Some(tenant) => TenantContext::new(tenant.as_str()),
+            None => Ok(TenantContext::default()),
+        }
+    }
+}
+
+/// 投递结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeliveryReceipt {
+    pub partition: i32,
+    pub offset: i64,
+}
+
+/// 消息生产者
+#[async_trait]
+pub trait MessageProducer: Send + Sync {
+    /// 发送消息到指定主题
+    async fn send(&self, topic: &str, message: OutgoingMessage) -> Result<DeliveryReceipt, BusError>;
+}
+
+/// 主题分区
+pub type TopicPartition = (String, i32);
+
+/// 分区再均衡事件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RebalanceEvent {
+    /// 新分配给本消费者的分区
+    Assigned(Vec<TopicPartition>),
+    /// 从本消费者收回的分区，收回后该分区的未提交消息会投递给新的消费者
+    Revoked(Vec<TopicPartition>),
+}
+
+/// 消息消费者
+///
+/// 同一消费组内的多个消费者分摊主题分区，每个分区同一时刻只属于一个消费者。
+#[async_trait]
+pub trait MessageConsumer: Send + Sync {
+    /// 接收下一条消息，没有消息时等待
+    async fn recv(&self) -> Result<BusMessage, BusError>;
+
+    /// 提交消费位点，`offset` 为该分区下一条待消费消息的位点
+    ///
+    /// Kafka 实现先记录位点，由客户端定期提交，并在分区被收回前提交最后记录的位点；
+    /// 分区已不属于本消费者时返回错误。
+    async fn commit(&self, topic: &str, partition: i32, offset: i64) -> Result<(), BusError>;
+
+    /// 订阅分区再均衡事件
+    fn rebalance_events(&self) -> broadcast::Receiver<RebalanceEvent>;
+
+    /// 提交单条消息之前（含）的位点
+    async fn commit_message(&self, message: &BusMessage) -> Result<(), BusError> {
+        self.commit(&message.topic, message.partition, message.offset + 1).await
+    }
+}
+
+/// 消息总线
+pub trait MessageBus: Send + Sync {
+    /// 共享的生产者
+    fn producer(&self) -> Arc<dyn MessageProducer>;
+
+    /// 以消费组订阅主题
+    fn subscribe(&self, group_id: &str, topics: &[&str]) -> Result<Arc<dyn MessageConsumer>, BusError>;
+}
@@ -0,0 +1,147 @@
+use std::collections::{BTreeSet, HashMap};
+
+use super::{BusMessage, TopicPartition};
+
+/// 正在处理的消息
+///
+/// 由 [`OffsetTracker::start`] 返回，处理完成后交回 [`OffsetTracker::complete`]。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OffsetTicket {
+    pub topic: String,
+    pub partition: i32,
+    pub offset: i64,
+    epoch: u64,
+}
+
+#[derive(Debug, Default)]
+struct PartitionState {
+    epoch: u64,
+    /// 处理中的位点
+    in_flight: BTreeSet<i64>,
+    /// 已接收的最大位点 + 1
+    next: i64,
+    /// 已提交的位点
+    committed: i64,
+}
+
+/// 分区内按序提交位点
+///
+/// 同一分区的消息可以并发处理、乱序完成，只有当某位点之前的消息全部完成后
+/// 才推进可提交位点，保证重启或再均衡后不会跳过未完成的消息。
+/// 分区被收回或重新分配时进入新的 epoch，旧 epoch 的完成通知被忽略。
+#[derive(Debug, Default)]
+pub struct OffsetTracker {
+    partitions: HashMap<TopicPartition, PartitionState>,
+    epochs: u64,
+}
+
+impl OffsetTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 登记开始处理的消息，同一分区的消息须按接收顺序登记
+    pub fn start(&mut self, message: &BusMessage) -> OffsetTicket {
+        let key = (message.topic.clone(), message.partition);
+        let epochs = &mut self.epochs;
+        let state = self.partitions.entry(key).or_insert_with(|| {
+            *epochs += 1;
+            PartitionState {
+                epoch: *epochs,
+                next: message.offset,
+                committed: message.offset,
+                ..Default::default()
+            }
+        });
+        state.in_flight.insert(message.offset);
+        state.next = state.next.max(message.offset + 1);
+
+        OffsetTicket {
+            topic: message.topic.clone(),
+            partition: message.partition,
+            offset: message.offset,
+            epoch: state.epoch,
+        }
+    }
+
+    /// 消息所属分区是否仍在当前分配中
+    pub fn is_current(&self, ticket: &OffsetTicket) -> bool {
+        self.partitions
+            .get(&(ticket.topic.clone(), ticket.partition))
+            .is_some_and(|state| state.epoch == ticket.epoch)
+    }
+
+    /// 标记消息处理完成，可提交位点推进时返回新的位点
+    pub fn complete(&mut self, ticket: &OffsetTicket) -> Option<i64> {
+        let state = self.partitions.get_mut(&(ticket.topic.clone(), ticket.partition))?;
+        if state.epoch != ticket.epoch || !state.in_flight.remove(&ticket.offset) {
+            return None;
+        }
+
+        let committable = state.in_flight.first().copied().unwrap_or(state.next);
+        if committable > state.committed {
+            state.committed = committable;
+            return Some(committable);
+        }
+        None
+    }
+
+    /// 分区被收回，丢弃该分区的处理状态
+    pub fn revoke(&mut self, partitions: &[TopicPartition]) {
+        for partition in partitions {
+            self.partitions.remove(partition);
+        }
+    }
+
+    /// 丢弃所有分区的处理状态，用于错过再均衡事件时
+    pub fn revoke_all(&mut self) {
+        self.partitions.clear();
+    }
+
+    /// 处理中的消息数量
+    pub fn in_flight(&self) -> usize {
+        self.partitions.values().map(|state| state.in_flight.len()).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(partition: i32, offset: i64) -> BusMessage {
+        BusMessage {
+            topic: "topic".to_string(),
+            partition,
+            offset,
+            key: None,
+            payload: Vec::new(),
+            headers: HashMap::new(),
+            timestamp: 0,
+        }
+    }
+
+    #[test]
+    fn test_commit_waits_for_earlier_offsets() {
+        let mut tracker = OffsetTracker::new();
+        let first = tracker.start(&message(0, 10));
+        let second = tracker.start(&message(0, 11));
+        let third = tracker.start(&message(0, 12));
+
+        assert_eq!(tracker.complete(&second), None);
+        assert_eq!(tracker.complete(&first), Some(12));
+        assert_eq!(tracker.complete(&third), Some(13));
+        assert_eq!(tracker.in_flight(), 0);
+    }
+
+    #[test]
+    fn test_revoked_partition_ignores_stale_completion() {
+        let mut tracker = OffsetTracker::new();
+        let stale = tracker.start(&message(0, 5));
+        tracker.revoke(&[("topic".to_string(), 0)]);
+        assert!(!tracker.is_current(&stale));
+
+        let fresh = tracker.start(&message(0, 5));
+        assert_eq!(tracker.complete(&stale), None);
+        assert_eq!(tracker.complete(&fresh), Some(6));
+    }
+}
@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+
+use crate::tenant::TenantSettings;
+
+/// 内容大小限制
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContentLimits {
+    /// 编码后内容最大字节数
+    pub max_content_bytes: usize,
+    /// 文本最大字符数
+    pub max_text_chars: usize,
+    /// 单个附件最大字节数
+    pub max_attachment_bytes: i64,
+    /// 自定义消息数据最大字节数
+    pub max_custom_bytes: usize,
+    /// 语音最长时长（秒）
+    pub max_voice_duration_secs: i64,
+    /// 视频最长时长（秒）
+    pub max_video_duration_secs: i64,
+}
+
+impl Default for ContentLimits {
+    fn default() -> Self {
+        Self {
+            max_content_bytes: 1024 * 1024,
+            max_text_chars: 5000,
+            max_attachment_bytes: 100 * 1024 * 1024,
+            max_custom_bytes: 64 * 1024,
+            max_voice_duration_secs: 60,
+            max_video_duration_secs: 30 * 60,
+        }
+    }
+}
+
+impl From<&TenantSettings> for ContentLimits {
+    fn from(settings: &TenantSettings) -> Self {
+        Self {
+            max_content_bytes: settings.max_content_size,
+            max_attachment_bytes: settings.max_attachment_size,
+            ..Self::default()
+        }
+    }
+}
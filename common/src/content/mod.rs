@@ -0,0 +1,440 @@
+//! 消息内容编解码
+//!
+//! `MessageData.content` 为按 `content_type` 编码的 protobuf 元素：
+//!
+//! | ContentType                          | 元素               |
+//! |--------------------------------------|--------------------|
+//! | Text / At / Tips / Reply / Quote     | `TextElem`         |
+//! | Card                                 | `CardElem`         |
+//! | Image                                | `PictureElem`      |
+//! | Voice                                | `SoundElem`        |
+//! | Video                                | `VideoElem`        |
+//! | File                                 | `FileElem`         |
+//! | Location                             | `LocationElem`     |
+//! | NotificationMsg                      | `NotificationElem` |
+//! | CustomContent / Merge / Forward / RedEnvelope | `CustomElem` |
+//!
+//! 本模块负责解码、编码、字段与大小校验，并生成会话列表/推送使用的预览文本、
+//! 搜索用文本和媒体引用。
+
+mod limits;
+
+use prost::Message;
+use proto_crate::api::im::common::{
+    CardElem, ContentType, CustomElem, FileElem, LocationElem, MessageData, NotificationElem,
+    PictureElem, SoundElem, TextElem, VideoElem,
+};
+
+pub use limits::ContentLimits;
+
+/// 内容错误
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum ContentError {
+    #[error("unknown content type: {0}")]
+    UnknownContentType(i32),
+    #[error("failed to decode {content_type:?} content: {reason}")]
+    Decode {
+        content_type: ContentType,
+        reason: String,
+    },
+    #[error("missing required field: {0}")]
+    MissingField(&'static str),
+    #[error("{field} size {size} exceeds limit {limit}")]
+    TooLarge {
+        field: &'static str,
+        size: i64,
+        limit: i64,
+    },
+    #[error("invalid field {field}: {reason}")]
+    Invalid {
+        field: &'static str,
+        reason: String,
+    },
+}
+
+impl ContentError {
+    /// 是否为超出大小限制
+    pub fn is_size_limit(&self) -> bool {
+        matches!(self, ContentError::TooLarge { .. })
+    }
+}
+
+/// 媒体类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaKind {
+    Image,
+    Thumbnail,
+    Audio,
+    Video,
+    VideoSnapshot,
+    File,
+}
+
+/// 媒体引用，用于媒体服务鉴权、生命周期管理与内容审核
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MediaRef {
+    pub kind: MediaKind,
+    pub id: String,
+    pub url: String,
+    pub size: i64,
+    pub mime_type: String,
+}
+
+/// 已解码的消息内容
+#[derive(Debug, Clone, PartialEq)]
+pub enum MessageContent {
+    Text(TextElem),
+    Card(CardElem),
+    Picture(PictureElem),
+    Sound(SoundElem),
+    Video(VideoElem),
+    File(FileElem),
+    Location(LocationElem),
+    Notification(NotificationElem),
+    Custom(CustomElem),
+}
+
+impl MessageContent {
+    /// 按内容类型解码
+    pub fn decode(content_type: i32, bytes: &[u8]) -> Result<Self, ContentError> {
+        let content_type = ContentType::try_from(content_type)
+            .map_err(|_| ContentError::UnknownContentType(content_type))?;
+        let error = |e: prost::DecodeError| ContentError::Decode {
+            content_type,
+            reason: e.to_string(),
+        };
+
+        Ok(match content_type {
+            ContentType::Text
+            | ContentType::At
+            | ContentType::Tips
+            | ContentType::Reply
+            | ContentType::Quote => MessageContent::Text(TextElem::decode(bytes).map_err(error)?),
+            ContentType::Card => MessageContent::Card(CardElem::decode(bytes).map_err(error)?),
+            ContentType::Image => MessageContent::Picture(PictureElem::decode(bytes).map_err(error)?),
+            ContentType::Voice => MessageContent::Sound(SoundElem::decode(bytes).map_err(error)?),
+            ContentType::Video => MessageContent::Video(VideoElem::decode(bytes).map_err(error)?),
+            ContentType::File => MessageContent::File(FileElem::decode(bytes).map_err(error)?),
+            ContentType::Location => MessageContent::Location(LocationElem::decode(bytes).map_err(error)?),
+            ContentType::NotificationMsg => {
+                MessageContent::Notification(NotificationElem::decode(bytes).map_err(error)?)
+            }
+            ContentType::CustomContent
+            | ContentType::Merge
+            | ContentType::Forward
+            | ContentType::RedEnvelope => MessageContent::Custom(CustomElem::decode(bytes).map_err(error)?),
+        })
+    }
+
+    /// 解码消息内容
+    pub fn from_message(message: &MessageData) -> Result<Self, ContentError> {
+        Self::decode(message.content_type, &message.content)
+    }
+
+    /// 解码并校验消息内容
+    pub fn from_message_validated(message: &MessageData, limits: &ContentLimits) -> Result<Self, ContentError> {
+        if message.content.len() > limits.max_content_bytes {
+            return Err(ContentError::TooLarge {
+                field: "content",
+                size: message.content.len() as i64,
+                limit: limits.max_content_bytes as i64,
+            });
+        }
+        let content = Self::from_message(message)?;
+        content.validate(limits)?;
+        Ok(content)
+    }
+
+    /// 编码为 `MessageData.content`
+    pub fn encode(&self) -> Vec<u8> {
+        match self {
+            MessageContent::Text(elem) => elem.encode_to_vec(),
+            MessageContent::Card(elem) => elem.encode_to_vec(),
+            MessageContent::Picture(elem) => elem.encode_to_vec(),
+            MessageContent::Sound(elem) => elem.encode_to_vec(),
+            MessageContent::Video(elem) => elem.encode_to_vec(),
+            MessageContent::File(elem) => elem.encode_to_vec(),
+            MessageContent::Location(elem) => elem.encode_to_vec(),
+            MessageContent::Notification(elem) => elem.encode_to_vec(),
+            MessageContent::Custom(elem) => elem.encode_to_vec(),
+        }
+    }
+
+    /// 元素对应的默认内容类型
+    pub fn content_type(&self) -> ContentType {
+        match self {
+            MessageContent::Text(_) => ContentType::Text,
+            MessageContent::Card(_) => ContentType::Card,
+            MessageContent::Picture(_) => ContentType::Image,
+            MessageContent::Sound(_) => ContentType::Voice,
+            MessageContent::Video(_) => ContentType::Video,
+            MessageContent::File(_) => ContentType::File,
+            MessageContent::Location(_) => ContentType::Location,
+            MessageContent::Notification(_) => ContentType::NotificationMsg,
+            MessageContent::Custom(_) => ContentType::CustomContent,
+        }
+    }
+
+    /// 校验必填字段与大小限制
+    pub fn validate(&self, limits: &ContentLimits) -> Result<(), ContentError> {
+        match self {
+            MessageContent::Text(elem) => {
+                if elem.content.trim().is_empty() {
+                    return Err(ContentError::MissingField("text.content"));
+                }
+                check_size("text.content", elem.content.chars().count() as i64, limits.max_text_chars as i64)
+            }
+            MessageContent::Card(elem) => require("card.user_id", &elem.user_id),
+            MessageContent::Picture(elem) => {
+                let source = elem.source_picture.as_ref();
+                if elem.source_path.is_empty() && source.is_none_or(|p| p.url.is_empty()) {
+                    return Err(ContentError::MissingField("picture.source_picture.url"));
+                }
+                check_size(
+                    "picture.size",
+                    source.map_or(0, |p| p.size as i64),
+                    limits.max_attachment_bytes,
+                )
+            }
+            MessageContent::Sound(elem) => {
+                require_any("sound.url", &[&elem.url, &elem.source_path])?;
+                check_duration("sound.duration", elem.duration, limits.max_voice_duration_secs)?;
+                check_size("sound.size", elem.size, limits.max_attachment_bytes)
+            }
+            MessageContent::Video(elem) => {
+                require_any("video.url", &[&elem.url, &elem.source_path])?;
+                check_duration("video.duration", elem.duration, limits.max_video_duration_secs)?;
+                check_size("video.size", elem.size, limits.max_attachment_bytes)
+            }
+            MessageContent::File(elem) => {
+                require_any("file.url", &[&elem.url, &elem.source_path])?;
+                require("file.name", &elem.name)?;
+                check_size("file.size", elem.size, limits.max_attachment_bytes)
+            }
+            MessageContent::Location(elem) => {
+                if !(-90.0..=90.0).contains(&elem.latitude) {
+                    return Err(ContentError::Invalid {
+                        field: "location.latitude",
+                        reason: elem.latitude.to_string(),
+                    });
+                }
+                if !(-180.0..=180.0).contains(&elem.longitude) {
+                    return Err(ContentError::Invalid {
+                        field: "location.longitude",
+                        reason: elem.longitude.to_string(),
+                    });
+                }
+                Ok(())
+            }
+            MessageContent::Notification(elem) => {
+                if elem.detail.is_empty() {
+                    return Err(ContentError::MissingField("notification.detail"));
+                }
+                Ok(())
+            }
+            MessageContent::Custom(elem) => {
+                if elem.data.is_empty() {
+                    return Err(ContentError::MissingField("custom.data"));
+                }
+                check_size("custom.data", elem.data.len() as i64, limits.max_custom_bytes as i64)
+            }
+        }
+    }
+
+    /// 会话列表与推送使用的预览文本，最多 `max_chars` 个字符
+    pub fn preview(&self, max_chars: usize) -> String {
+        let preview = match self {
+            MessageContent::Text(elem) => elem.content.clone(),
+            MessageContent::Card(elem) => format!("[名片] {}", elem.nickname),
+            MessageContent::Picture(_) => "[图片]".to_string(),
+            MessageContent::Sound(elem) => format!("[语音] {}\"", elem.duration),
+            MessageContent::Video(_) => "[视频]".to_string(),
+            MessageContent::File(elem) => format!("[文件] {}", elem.name),
+            MessageContent::Location(elem) => format!("[位置] {}", elem.description),
+            MessageContent::Notification(_) => "[通知]".to_string(),
+            MessageContent::Custom(elem) if !elem.desc.is_empty() => elem.desc.clone(),
+            MessageContent::Custom(_) => "[自定义消息]".to_string(),
+        };
+        truncate(preview.trim(), max_chars)
+    }
+
+    /// 用于全文检索的文本，无可检索内容时为 None
+    pub fn searchable_text(&self) -> Option<String> {
+        let text = match self {
+            MessageContent::Text(elem) => elem.content.clone(),
+            MessageContent::Card(elem) => elem.nickname.clone(),
+            MessageContent::File(elem) => elem.name.clone(),
+            MessageContent::Location(elem) => elem.description.clone(),
+            MessageContent::Custom(elem) => elem.desc.clone(),
+            _ => String::new(),
+        };
+        let text = text.trim();
+        (!text.is_empty()).then(|| text.to_string())
+    }
+
+    /// 内容引用的媒体资源
+    pub fn media_refs(&self) -> Vec<MediaRef> {
+        let media = |kind, id: &str, url: &str, size: i64, mime_type: &str| MediaRef {
+            kind,
+            id: id.to_string(),
+            url: url.to_string(),
+            size,
+            mime_type: mime_type.to_string(),
+        };
+        let url_or = |url: &str, path: &str| if url.is_empty() { path.to_string() } else { url.to_string() };
+
+        let refs = match self {
+            MessageContent::Picture(elem) => {
+                let mut refs = Vec::new();
+                match &elem.source_picture {
+                    Some(p) => refs.push(media(
+                        MediaKind::Image,
+                        &p.id,
+                        &url_or(&p.url, &elem.source_path),
+                        p.size as i64,
+                        &p.r#type,
+                    )),
+                    None => refs.push(media(MediaKind::Image, "", &elem.source_path, 0, "")),
+                }
+                if let Some(p) = &elem.snapshot_picture {
+                    refs.push(media(MediaKind::Thumbnail, &p.id, &p.url, p.size as i64, &p.r#type));
+                }
+                refs
+            }
+            MessageContent::Sound(elem) => vec![media(
+                MediaKind::Audio,
+                &elem.id,
+                &url_or(&elem.url, &elem.source_path),
+                elem.size,
+                &elem.r#type,
+            )],
+            MessageContent::Video(elem) => {
+                let mut refs = vec![media(
+                    MediaKind::Video,
+                    &elem.id,
+                    &url_or(&elem.url, &elem.source_path),
+                    elem.size,
+                    &elem.r#type,
+                )];
+                let snapshot = url_or(&elem.snapshot_url, &elem.snapshot_path);
+                if !snapshot.is_empty() {
+                    refs.push(media(
+                        MediaKind::VideoSnapshot,
+                        &elem.snapshot_id,
+                        &snapshot,
+                        elem.snapshot_size,
+                        &elem.snapshot_type,
+                    ));
+                }
+                refs
+            }
+            MessageContent::File(elem) => vec![media(
+                MediaKind::File,
+                &elem.id,
+                &url_or(&elem.url, &elem.source_path),
+                elem.size,
+                &elem.r#type,
+            )],
+            _ => Vec::new(),
+        };
+        refs.into_iter().filter(|r| !r.url.is_empty()).collect()
+    }
+
+    /// 媒体资源总大小
+    pub fn attachment_size(&self) -> i64 {
+        self.media_refs()
+            .iter()
+            .filter(|r| !matches!(r.kind, MediaKind::Thumbnail | MediaKind::VideoSnapshot))
+            .map(|r| r.size)
+            .sum()
+    }
+}
+
+fn require(field: &'static str, value: &str) -> Result<(), ContentError> {
+    if value.is_empty() {
+        Err(ContentError::MissingField(field))
+    } else {
+        Ok(())
+    }
+}
+
+fn require_any(field: &'static str, values: &[&String]) -> Result<(), ContentError> {
+    if values.iter().all(|v| v.is_empty()) {
+        Err(ContentError::MissingField(field))
+    } else {
+        Ok(())
+    }
+}
+
+fn check_size(field: &'static str, size: i64, limit: i64) -> Result<(), ContentError> {
+    if size < 0 {
+        return Err(ContentError::Invalid {
+            field,
+            reason: format!("negative size {}", size),
+        });
+    }
+    if size > limit {
+        return Err(ContentError::TooLarge { field, size, limit });
+    }
+    Ok(())
+}
+
+fn check_duration(field: &'static str, duration: i64, limit: i64) -> Result<(), ContentError> {
+    if duration <= 0 {
+        return Err(ContentError::Invalid {
+            field,
+            reason: format!("duration {} must be positive", duration),
+        });
+    }
+    check_size(field, duration, limit)
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let mut truncated: String = text.chars().take(max_chars.saturating_sub(1)).collect();
+    truncated.push('…');
+    truncated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proto_crate::api::im::common::PictureInfo;
+
+    #[test]
+    fn test_text_round_trip_and_preview() {
+        let content = MessageContent::Text(TextElem {
+            content: "你好，这是一条比较长的消息".to_string(),
+        });
+        let decoded = MessageContent::decode(ContentType::At as i32, &content.encode()).unwrap();
+        assert_eq!(decoded, content);
+        assert_eq!(decoded.preview(6), "你好，这是…");
+        assert_eq!(decoded.searchable_text().as_deref(), Some("你好，这是一条比较长的消息"));
+    }
+
+    #[test]
+    fn test_picture_validation_and_media_refs() {
+        let limits = ContentLimits::default();
+        let missing = MessageContent::Picture(PictureElem::default());
+        assert_eq!(
+            missing.validate(&limits),
+            Err(ContentError::MissingField("picture.source_picture.url"))
+        );
+
+        let picture = MessageContent::Picture(PictureElem {
+            source_path: String::new(),
+            source_picture: Some(PictureInfo {
+                id: "p1".to_string(),
+                url: "https://cdn/p1.jpg".to_string(),
+                size: 2048,
+                ..Default::default()
+            }),
+            snapshot_picture: None,
+        });
+        assert!(picture.validate(&limits).is_ok());
+        assert_eq!(picture.attachment_size(), 2048);
+        assert_eq!(picture.preview(20), "[图片]");
+    }
+}
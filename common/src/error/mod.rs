@@ -0,0 +1,236 @@
+//! 统一错误模型
+//!
+//! 以 `error.proto` 中的 [`ErrorCode`] 为唯一错误码来源，在领域错误、`tonic::Status`
+//! 与客户端响应中的 `api.im.common.Error` 之间双向转换，保证客户端拿到稳定的数字错误码。
+
+use prost::Message;
+use proto_crate::api::im::common::{Error as ProtoError, ErrorCode};
+use tonic::{Code, Status};
+
+/// 错误码分段
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCategory {
+    /// 通用错误 (0-999)
+    General,
+    /// 消息错误 (1000-1999)
+    Message,
+    /// 会话错误 (2000-2999)
+    Session,
+    /// 连接错误 (3000-3999)
+    Connection,
+    /// 路由错误 (4000-4999)
+    Router,
+    /// 过滤错误 (5000-5999)
+    Filter,
+    /// 同步错误 (6000-6999)
+    Sync,
+}
+
+impl ErrorCategory {
+    /// 根据错误码数值判断分段
+    pub fn of(code: i32) -> Self {
+        match code {
+            1000..=1999 => Self::Message,
+            2000..=2999 => Self::Session,
+            3000..=3999 => Self::Connection,
+            4000..=4999 => Self::Router,
+            5000..=5999 => Self::Filter,
+            6000..=6999 => Self::Sync,
+            _ => Self::General,
+        }
+    }
+}
+
+/// 应用错误
+///
+/// 各服务的领域错误应实现 `From<DomainError> for AppError`，在接口层统一转换。
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("{}({}): {message}", .code.as_str_name(), *.code as i32)]
+pub struct AppError {
+    /// 错误码
+    pub code: ErrorCode,
+    /// 错误信息
+    pub message: String,
+    /// 错误详情
+    pub details: String,
+}
+
+impl AppError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            details: String::new(),
+        }
+    }
+
+    /// 附加错误详情
+    pub fn with_details(mut self, details: impl Into<String>) -> Self {
+        self.details = details.into();
+        self
+    }
+
+    pub fn system(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::SystemError, message)
+    }
+
+    pub fn invalid_params(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::InvalidParams, message)
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::NotFound, message)
+    }
+
+    pub fn unavailable(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::ServiceUnavailable, message)
+    }
+
+    /// 从数值错误码构造，未知错误码视为系统错误
+    pub fn from_code(code: i32, message: impl Into<String>) -> Self {
+        Self::new(
+            ErrorCode::try_from(code).unwrap_or(ErrorCode::SystemError),
+            message,
+        )
+    }
+
+    /// 错误码数值
+    pub fn code_value(&self) -> i32 {
+        self.code as i32
+    }
+
+    /// 错误码分段
+    pub fn category(&self) -> ErrorCategory {
+        ErrorCategory::of(self.code_value())
+    }
+
+    /// 转换为客户端响应中的错误结构
+    pub fn to_proto(&self) -> ProtoError {
+        ProtoError {
+            code: self.code_value(),
+            message: self.message.clone(),
+            details: self.details.clone(),
+        }
+    }
+
+    /// 错误码对应的 gRPC 状态码
+    pub fn grpc_code(&self) -> Code {
+        grpc_code(self.code)
+    }
+}
+
+/// 错误码到 gRPC 状态码的映射
+pub fn grpc_code(code: ErrorCode) -> Code {
+    use ErrorCode::*;
+
+    match code {
+        Success => Code::Ok,
+        InvalidParams | MessageFormatError | RouterInvalidFormat | RouterInvalidMention
+        | FilterRuleInvalid => {
+            Code::InvalidArgument
+        }
+        Unauthorized => Code::Unauthenticated,
+        Forbidden | RouterNotFriend | RouterInBlacklist | RouterNotGroupMember | RouterMuted
+        | RouterGroupMuted | RouterKicked | RouterUserBanned | RouterDeviceBanned
+        | RouterAtAllNotAllowed => {
+            Code::PermissionDenied
+        }
+        NotFound | SessionNotFound | FilterRuleNotFound => Code::NotFound,
+        ServiceUnavailable | ConnectionFailed | ConnectionClosed => Code::Unavailable,
+        Timeout | ConnectionTimeout => Code::DeadlineExceeded,
+        RateLimit | RouterFrequencyLimit | RouterPrivateMessageLimit | RouterGroupMessageLimit
+        | RouterAtAllLimit => {
+            Code::ResourceExhausted
+        }
+        RouterContentLengthLimit | RouterAttachmentSizeLimit => Code::OutOfRange,
+        MessageInProgress => Code::Aborted,
+        RouterInvalidContent | RouterGroupDissolved | FilterBlocked | FilterReviewRequired
+        | SyncSequenceGap | SyncCursorExpired => Code::FailedPrecondition,
+        _ => Code::Internal,
+    }
+}
+
+/// gRPC 状态码到错误码的映射，用于对端未携带错误详情时
+fn error_code_from_grpc(code: Code) -> ErrorCode {
+    match code {
+        Code::Ok => ErrorCode::Success,
+        Code::InvalidArgument | Code::OutOfRange => ErrorCode::InvalidParams,
+        Code::Unauthenticated => ErrorCode::Unauthorized,
+        Code::PermissionDenied => ErrorCode::Forbidden,
+        Code::NotFound => ErrorCode::NotFound,
+        Code::Unavailable => ErrorCode::ServiceUnavailable,
+        Code::DeadlineExceeded => ErrorCode::Timeout,
+        Code::ResourceExhausted => ErrorCode::RateLimit,
+        _ => ErrorCode::SystemError,
+    }
+}
+
+impl From<AppError> for Status {
+    /// 错误详情以 `api.im.common.Error` 编码放入 status details，对端可无损还原
+    fn from(err: AppError) -> Self {
+        let details = err.to_proto().encode_to_vec();
+        Status::with_details(err.grpc_code(), err.message, details.into())
+    }
+}
+
+impl From<Status> for AppError {
+    fn from(status: Status) -> Self {
+        if !status.details().is_empty() {
+            if let Ok(error) = ProtoError::decode(status.details()) {
+                return error.into();
+            }
+        }
+        AppError::new(error_code_from_grpc(status.code()), status.message())
+    }
+}
+
+impl From<ProtoError> for AppError {
+    fn from(error: ProtoError) -> Self {
+        AppError::from_code(error.code, error.message).with_details(error.details)
+    }
+}
+
+impl From<AppError> for ProtoError {
+    fn from(err: AppError) -> Self {
+        err.to_proto()
+    }
+}
+
+impl From<anyhow::Error> for AppError {
+    /// 保留错误链中的 AppError / Status，其余视为系统错误
+    fn from(err: anyhow::Error) -> Self {
+        if let Some(app_error) = err.downcast_ref::<AppError>() {
+            return app_error.clone();
+        }
+        match err.downcast::<Status>() {
+            Ok(status) => status.into(),
+            Err(err) => AppError::system(err.to_string()),
+        }
+    }
+}
+
+/// 应用结果类型
+pub type AppResult<T> = Result<T, AppError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_round_trip_keeps_code() {
+        let err = AppError::new(ErrorCode::RouterInBlacklist, "blocked").with_details("user2");
+        let status: Status = err.into();
+        assert_eq!(status.code(), Code::PermissionDenied);
+
+        let back: AppError = status.into();
+        assert_eq!(back.code, ErrorCode::RouterInBlacklist);
+        assert_eq!(back.details, "user2");
+    }
+
+    #[test]
+    fn test_status_without_details_falls_back_to_grpc_code() {
+        let back: AppError = Status::unavailable("down").into();
+        assert_eq!(back.code, ErrorCode::ServiceUnavailable);
+        assert_eq!(back.category(), ErrorCategory::General);
+    }
+}
@@ -0,0 +1,262 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use log::{info, warn};
+use serde::de::DeserializeOwned;
+
+use super::flag::{FeatureFlag, FlagContext};
+use super::source::{FlagSource, StaticFlagSource};
+use crate::tenant;
+
+/// 特性开关客户端
+///
+/// 本地缓存开关并在后台监听数据源变更；数据源中不存在的开关回退到静态配置。
+/// 求值时默认取当前任务的租户上下文。
+///
+/// ```ignore
+/// let flags = FeatureFlags::new(Arc::new(ConsulFlagSource::new(&consul)))
+///     .with_fallback(StaticFlagSource::from_config(&config)?);
+/// flags.start().await;
+///
+/// if flags.is_enabled_for(FeatureFlags::READ_RECEIPT, &user_id) { ... }
+/// ```
+#[derive(Clone)]
+pub struct FeatureFlags {
+    source: Arc<dyn FlagSource>,
+    fallback: Arc<HashMap<String, FeatureFlag>>,
+    cache: Arc<RwLock<HashMap<String, FeatureFlag>>>,
+    watch_interval: Duration,
+}
+
+impl FeatureFlags {
+    /// 已读回执
+    pub const READ_RECEIPT: &'static str = "read_receipt";
+    /// 群消息扇出策略，取值见 `value`
+    pub const GROUP_FANOUT_STRATEGY: &'static str = "group_fanout_strategy";
+    /// 影子过滤规则，只记录命中不拦截
+    pub const SHADOW_FILTER_RULES: &'static str = "shadow_filter_rules";
+
+    /// 监听失败时的最长退避时间
+    const MAX_WATCH_BACKOFF: Duration = Duration::from_secs(30);
+
+    pub fn new(source: Arc<dyn FlagSource>) -> Self {
+        Self {
+            source,
+            fallback: Arc::new(HashMap::new()),
+            cache: Arc::new(RwLock::new(HashMap::new())),
+            watch_interval: Duration::from_secs(1),
+        }
+    }
+
+    /// 两次监听请求的最小间隔，阻塞查询提前返回时避免空转
+    pub fn with_watch_interval(mut self, interval: Duration) -> Self {
+        self.watch_interval = interval;
+        self
+    }
+
+    /// 仅使用静态开关，用于测试
+    pub fn from_static(source: StaticFlagSource) -> Self {
+        let flags = source.flags().clone();
+        let client = Self::new(Arc::new(source));
+        *client.cache.write().unwrap() = flags;
+        client
+    }
+
+    /// 设置静态回退开关
+    pub fn with_fallback(mut self, fallback: StaticFlagSource) -> Self {
+        self.fallback = Arc::new(fallback.flags().clone());
+        self
+    }
+
+    /// 首次加载并启动后台监听
+    ///
+    /// 首次加载失败时使用回退配置，监听任务会持续重试。
+    pub async fn start(&self) {
+        let mut index = None;
+        match self.source.fetch(None).await {
+            Ok(snapshot) => {
+                info!("Loaded {} feature flags", snapshot.flags.len());
+                index = Some(snapshot.index.max(1));
+                *self.cache.write().unwrap() = snapshot.flags;
+            }
+            Err(e) => warn!("Failed to load feature flags, using fallback: {}", e),
+        }
+
+        if self.source.watchable() {
+            self.spawn_watch(index);
+        }
+    }
+
+    fn spawn_watch(&self, mut index: Option<u64>) {
+        let source = self.source.clone();
+        let cache = Arc::downgrade(&self.cache);
+        let interval = self.watch_interval;
+        tokio::spawn(async move {
+            let mut backoff = Duration::from_secs(1);
+            loop {
+                let started = tokio::time::Instant::now();
+                let result = source.fetch(index).await;
+                let Some(cache) = cache.upgrade() else {
+                    break;
+                };
+                match result {
+                    Ok(snapshot) => {
+                        backoff = Duration::from_secs(1);
+                        // 版本为 0 的阻塞查询会立即返回，按 1 处理
+                        let next = snapshot.index.max(1);
+                        if index.is_some_and(|i| next < i) {
+                            // 版本回退时（如 Consul 快照恢复）从头重新监听
+                            index = None;
+                        } else {
+                            if index != Some(next) {
+                                info!("Feature flags updated, index {}", next);
+                                *cache.write().unwrap() = snapshot.flags;
+                            }
+                            index = Some(next);
+                        }
+                        drop(cache);
+                        tokio::time::sleep_until(started + interval).await;
+                    }
+                    Err(e) => {
+                        drop(cache);
+                        warn!("Failed to watch feature flags: {}", e);
+                        tokio::time::sleep(backoff).await;
+                        backoff = (backoff * 2).min(Self::MAX_WATCH_BACKOFF);
+                    }
+                }
+            }
+        });
+    }
+
+    /// 获取开关定义
+    pub fn get(&self, name: &str) -> Option<FeatureFlag> {
+        self.cache
+            .read()
+            .unwrap()
+            .get(name)
+            .or_else(|| self.fallback.get(name))
+            .cloned()
+    }
+
+    /// 按显式上下文求值，未定义的开关视为关闭
+    pub fn evaluate(&self, name: &str, ctx: &FlagContext<'_>) -> bool {
+        self.get(name).is_some_and(|flag| flag.evaluate(name, ctx))
+    }
+
+    /// 对当前租户是否开启
+    pub fn is_enabled(&self, name: &str) -> bool {
+        let tenant = tenant::try_current();
+        self.evaluate(name, &FlagContext::new(tenant.as_ref().map(|t| t.tenant_id()), None))
+    }
+
+    /// 对当前租户下的用户是否开启，灰度开关按 user_id 分桶
+    pub fn is_enabled_for(&self, name: &str, user_id: &str) -> bool {
+        let tenant = tenant::try_current();
+        self.evaluate(
+            name,
+            &FlagContext::new(tenant.as_ref().map(|t| t.tenant_id()), Some(user_id)),
+        )
+    }
+
+    /// 开关对当前租户开启时返回其取值
+    pub fn value<T: DeserializeOwned>(&self, name: &str) -> Option<T> {
+        if !self.is_enabled(name) {
+            return None;
+        }
+        let value = self.get(name)?.value?;
+        match serde_json::from_value(value) {
+            Ok(value) => Some(value),
+            Err(e) => {
+                warn!("Invalid value of feature flag {}: {}", name, e);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use std::sync::Mutex;
+
+    use anyhow::{anyhow, Result};
+    use async_trait::async_trait;
+
+    use super::super::FlagSnapshot;
+    use super::*;
+
+    /// 按脚本返回快照并记录请求的版本，脚本用完后一直阻塞
+    #[derive(Default)]
+    struct ScriptedSource {
+        responses: Mutex<VecDeque<Result<FlagSnapshot>>>,
+        requests: Mutex<Vec<Option<u64>>>,
+    }
+
+    impl ScriptedSource {
+        fn new(responses: Vec<Result<FlagSnapshot>>) -> Arc<Self> {
+            Arc::new(Self {
+                responses: Mutex::new(responses.into()),
+                requests: Mutex::default(),
+            })
+        }
+
+        async fn wait_requests(&self, count: usize) -> Vec<Option<u64>> {
+            for _ in 0..500 {
+                if self.requests.lock().unwrap().len() >= count {
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            self.requests.lock().unwrap().iter().take(count).copied().collect()
+        }
+    }
+
+    #[async_trait]
+    impl FlagSource for ScriptedSource {
+        async fn fetch(&self, index: Option<u64>) -> Result<FlagSnapshot> {
+            self.requests.lock().unwrap().push(index);
+            let response = self.responses.lock().unwrap().pop_front();
+            match response {
+                Some(response) => response,
+                None => std::future::pending().await,
+            }
+        }
+    }
+
+    fn snapshot(flag: &str, index: u64) -> Result<FlagSnapshot> {
+        Ok(FlagSnapshot {
+            flags: HashMap::from([(flag.to_string(), FeatureFlag::on())]),
+            index,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_watch_resets_only_when_index_goes_backwards() {
+        let source = ScriptedSource::new(vec![
+            snapshot("a", 5),
+            snapshot("a", 5),
+            snapshot("b", 3),
+            snapshot("b", 3),
+            Err(anyhow!("consul unavailable")),
+        ]);
+        let flags = FeatureFlags::new(source.clone()).with_watch_interval(Duration::from_millis(10));
+        flags.start().await;
+
+        let requests = source.wait_requests(6).await;
+        assert_eq!(requests, vec![None, Some(5), Some(5), None, Some(3), Some(3)]);
+        assert!(flags.get("b").is_some());
+        assert!(flags.get("a").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_watch_never_blocks_on_index_zero() {
+        let source = ScriptedSource::new(vec![snapshot("a", 0), snapshot("a", 0), snapshot("a", 0)]);
+        let flags = FeatureFlags::new(source.clone()).with_watch_interval(Duration::from_millis(10));
+        flags.start().await;
+
+        let requests = source.wait_requests(4).await;
+        assert_eq!(requests, vec![None, Some(1), Some(1), Some(1)]);
+        assert!(flags.get("a").is_some());
+    }
+}
@@ -0,0 +1,133 @@
+use serde::{Deserialize, Serialize};
+
+/// 特性开关
+///
+/// ```json
+/// {
+///   "enabled": true,
+///   "tenants": ["acme"],
+///   "rollout_percentage": 20,
+///   "value": "read_diffusion"
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FeatureFlag {
+    /// 总开关
+    #[serde(default)]
+    pub enabled: bool,
+    /// 生效租户，为空表示所有租户
+    #[serde(default)]
+    pub tenants: Vec<String>,
+    /// 排除的租户
+    #[serde(default)]
+    pub excluded_tenants: Vec<String>,
+    /// 始终生效的用户，不受灰度比例限制
+    #[serde(default)]
+    pub users: Vec<String>,
+    /// 按 user_id 哈希的灰度比例 (0-100)，未设置表示全量
+    #[serde(default)]
+    pub rollout_percentage: Option<u8>,
+    /// 开关附带的取值，如扇出策略名
+    #[serde(default)]
+    pub value: Option<serde_json::Value>,
+}
+
+/// 开关求值上下文
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FlagContext<'a> {
+    pub tenant_id: Option<&'a str>,
+    pub user_id: Option<&'a str>,
+}
+
+impl<'a> FlagContext<'a> {
+    pub fn new(tenant_id: Option<&'a str>, user_id: Option<&'a str>) -> Self {
+        Self { tenant_id, user_id }
+    }
+}
+
+impl FeatureFlag {
+    /// 全量开启的开关
+    pub fn on() -> Self {
+        Self {
+            enabled: true,
+            ..Self::default()
+        }
+    }
+
+    /// 对给定上下文是否生效
+    pub fn evaluate(&self, name: &str, ctx: &FlagContext<'_>) -> bool {
+        if !self.enabled {
+            return false;
+        }
+
+        if let Some(tenant_id) = ctx.tenant_id {
+            if self.excluded_tenants.iter().any(|t| t == tenant_id) {
+                return false;
+            }
+        }
+        if !self.tenants.is_empty() && !ctx.tenant_id.is_some_and(|t| self.tenants.iter().any(|x| x == t)) {
+            return false;
+        }
+
+        if let Some(user_id) = ctx.user_id {
+            if self.users.iter().any(|u| u == user_id) {
+                return true;
+            }
+        }
+
+        match self.rollout_percentage {
+            None => true,
+            Some(p) if p >= 100 => true,
+            // 灰度中的开关需要用户维度才能分桶
+            Some(p) => ctx.user_id.is_some_and(|user_id| rollout_bucket(name, user_id) < p as u64),
+        }
+    }
+}
+
+/// 用户所在灰度桶 (0-99)
+///
+/// 使用 FNV-1a 保证跨进程、跨版本稳定；开关名参与哈希，使不同开关的灰度用户相互独立。
+pub fn rollout_bucket(name: &str, user_id: &str) -> u64 {
+    const OFFSET: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+
+    let mut hash = OFFSET;
+    for byte in name.bytes().chain(std::iter::once(b':')).chain(user_id.bytes()) {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(PRIME);
+    }
+    hash % 100
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tenant_scoping() {
+        let flag = FeatureFlag {
+            tenants: vec!["acme".to_string()],
+            ..FeatureFlag::on()
+        };
+        assert!(flag.evaluate("read_receipt", &FlagContext::new(Some("acme"), None)));
+        assert!(!flag.evaluate("read_receipt", &FlagContext::new(Some("other"), None)));
+        assert!(!flag.evaluate("read_receipt", &FlagContext::new(None, None)));
+    }
+
+    #[test]
+    fn test_percentage_rollout() {
+        let flag = FeatureFlag {
+            rollout_percentage: Some(30),
+            ..FeatureFlag::on()
+        };
+        let enabled = (0..10_000)
+            .filter(|i| flag.evaluate("shadow_rules", &FlagContext::new(None, Some(&format!("user_{}", i)))))
+            .count();
+        assert!((2_500..3_500).contains(&enabled), "enabled: {}", enabled);
+
+        // 同一用户结果稳定
+        let ctx = FlagContext::new(None, Some("user_42"));
+        assert_eq!(flag.evaluate("shadow_rules", &ctx), flag.evaluate("shadow_rules", &ctx));
+        assert!(!flag.evaluate("shadow_rules", &FlagContext::default()));
+    }
+}
@@ -0,0 +1,13 @@
+//! 动态特性开关
+//!
+//! 开关存储在 Consul KV（`flare/feature-flags/{name}`），本地缓存并通过阻塞查询监听变更，
+//! 支持按租户生效和按 user_id 哈希灰度。Consul 不可用或未定义的开关回退到配置文件
+//! `extensions.feature_flags` 中的静态开关。
+
+mod client;
+mod flag;
+mod source;
+
+pub use client::FeatureFlags;
+pub use flag::{rollout_bucket, FeatureFlag, FlagContext};
+pub use source::{ConsulFlagSource, FlagSnapshot, FlagSource, StaticFlagSource};
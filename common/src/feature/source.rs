@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use base64::Engine;
+use log::warn;
+use serde::Deserialize;
+
+use super::flag::FeatureFlag;
+use crate::config::{Config, ConsulConfig};
+
+/// 开关快照
+#[derive(Debug, Clone, Default)]
+pub struct FlagSnapshot {
+    pub flags: HashMap<String, FeatureFlag>,
+    /// 数据版本，用于阻塞查询
+    pub index: u64,
+}
+
+/// 开关数据源
+#[async_trait]
+pub trait FlagSource: Send + Sync {
+    /// 拉取开关
+    ///
+    /// `index` 为上次快照的版本，数据源支持时阻塞到数据变化或等待超时后返回。
+    async fn fetch(&self, index: Option<u64>) -> Result<FlagSnapshot>;
+
+    /// 是否支持变更监听
+    fn watchable(&self) -> bool {
+        true
+    }
+}
+
+/// 静态开关，来自配置文件 `extensions.feature_flags` 或代码构造，用于测试与降级
+#[derive(Debug, Clone, Default)]
+pub struct StaticFlagSource {
+    flags: HashMap<String, FeatureFlag>,
+}
+
+impl StaticFlagSource {
+    pub fn new(flags: HashMap<String, FeatureFlag>) -> Self {
+        Self { flags }
+    }
+
+    /// 读取配置文件 `extensions.feature_flags`
+    pub fn from_config(config: &Config) -> Result<Self> {
+        let flags = match config.extensions.get("feature_flags") {
+            Some(value) => serde_json::from_value(value.clone())?,
+            None => HashMap::new(),
+        };
+        Ok(Self { flags })
+    }
+
+    pub fn with_flag(mut self, name: impl Into<String>, flag: FeatureFlag) -> Self {
+        self.flags.insert(name.into(), flag);
+        self
+    }
+
+    pub fn flags(&self) -> &HashMap<String, FeatureFlag> {
+        &self.flags
+    }
+}
+
+#[async_trait]
+impl FlagSource for StaticFlagSource {
+    async fn fetch(&self, _index: Option<u64>) -> Result<FlagSnapshot> {
+        Ok(FlagSnapshot {
+            flags: self.flags.clone(),
+            index: 0,
+        })
+    }
+
+    fn watchable(&self) -> bool {
+        false
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct KvEntry {
+    key: String,
+    #[serde(default)]
+    value: Option<String>,
+}
+
+/// Consul KV 开关
+///
+/// 每个开关一个键 `{prefix}/{name}`，值为 JSON 格式的 [`FeatureFlag`]，
+/// 通过阻塞查询 (`?index=&wait=`) 监听前缀下的变更。
+pub struct ConsulFlagSource {
+    client: reqwest::Client,
+    base_url: String,
+    prefix: String,
+    wait: Duration,
+}
+
+impl ConsulFlagSource {
+    /// 默认键前缀
+    pub const DEFAULT_PREFIX: &'static str = "flare/feature-flags";
+
+    pub fn new(config: &ConsulConfig) -> Self {
+        Self::with_prefix(config, Self::DEFAULT_PREFIX)
+    }
+
+    pub fn with_prefix(config: &ConsulConfig, prefix: &str) -> Self {
+        let wait = Duration::from_secs(55);
+        Self {
+            // 客户端超时需大于阻塞查询的等待时间
+            client: reqwest::Client::builder()
+                .timeout(wait + Duration::from_secs(10))
+                .build()
+                .unwrap_or_default(),
+            base_url: format!("http://{}:{}", config.host, config.port),
+            prefix: prefix.trim_matches('/').to_string(),
+            wait,
+        }
+    }
+
+    fn parse(&self, entries: Vec<KvEntry>) -> HashMap<String, FeatureFlag> {
+        let mut flags = HashMap::new();
+        for entry in entries {
+            let name = entry
+                .key
+                .strip_prefix(&self.prefix)
+                .unwrap_or(&entry.key)
+                .trim_start_matches('/');
+            // 跳过目录键
+            let Some(value) = entry.value.as_deref().filter(|_| !name.is_empty()) else {
+                continue;
+            };
+            let parsed = base64::engine::general_purpose::STANDARD
+                .decode(value)
+                .map_err(anyhow::Error::from)
+                .and_then(|raw| serde_json::from_slice::<FeatureFlag>(&raw).map_err(Into::into));
+            match parsed {
+                Ok(flag) => {
+                    flags.insert(name.to_string(), flag);
+                }
+                Err(e) => warn!("Ignore invalid feature flag {}: {}", entry.key, e),
+            }
+        }
+        flags
+    }
+}
+
+#[async_trait]
+impl FlagSource for ConsulFlagSource {
+    async fn fetch(&self, index: Option<u64>) -> Result<FlagSnapshot> {
+        let mut request = self
+            .client
+            .get(format!("{}/v1/kv/{}", self.base_url, self.prefix))
+            .query(&[("recurse", "true")]);
+        if let Some(index) = index {
+            request = request.query(&[
+                ("index", index.to_string()),
+                ("wait", format!("{}s", self.wait.as_secs())),
+            ]);
+        }
+
+        let response = request.send().await?;
+        // 没有版本号时无法阻塞查询，按失败处理由调用方退避重试
+        let consul_index = response
+            .headers()
+            .get("X-Consul-Index")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<u64>().ok())
+            .ok_or_else(|| anyhow!("consul kv {} returned no X-Consul-Index", self.prefix))?;
+
+        // 前缀下没有键时返回 404
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(FlagSnapshot {
+                flags: HashMap::new(),
+                index: consul_index,
+            });
+        }
+        if !response.status().is_success() {
+            return Err(anyhow!("consul kv {} failed: {}", self.prefix, response.status()));
+        }
+
+        let entries: Vec<KvEntry> = response.json().await?;
+        Ok(FlagSnapshot {
+            flags: self.parse(entries),
+            index: consul_index,
+        })
+    }
+}
@@ -0,0 +1,153 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use rdkafka::producer::{FutureProducer, Producer};
+use redis::aio::ConnectionManager;
+use sqlx::PgPool;
+
+use super::HealthCheck;
+
+/// Redis 连通性检查 (PING)
+pub struct RedisHealthCheck {
+    redis: ConnectionManager,
+}
+
+impl RedisHealthCheck {
+    pub fn new(redis: ConnectionManager) -> Self {
+        Self { redis }
+    }
+}
+
+#[async_trait]
+impl HealthCheck for RedisHealthCheck {
+    fn name(&self) -> &str {
+        "redis"
+    }
+
+    async fn check(&self) -> Result<(), String> {
+        let mut conn = self.redis.clone();
+        let _: String = redis::cmd("PING")
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+}
+
+/// Postgres 连通性检查 (SELECT 1)
+pub struct PostgresHealthCheck {
+    pool: PgPool,
+}
+
+impl PostgresHealthCheck {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl HealthCheck for PostgresHealthCheck {
+    fn name(&self) -> &str {
+        "postgres"
+    }
+
+    async fn check(&self) -> Result<(), String> {
+        sqlx::query("SELECT 1")
+            .execute(&self.pool)
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Kafka 生产者检查，通过拉取集群元数据确认 broker 可达
+pub struct KafkaProducerHealthCheck {
+    producer: FutureProducer,
+    timeout: Duration,
+}
+
+impl KafkaProducerHealthCheck {
+    pub fn new(producer: FutureProducer) -> Self {
+        Self {
+            producer,
+            timeout: Duration::from_secs(2),
+        }
+    }
+}
+
+#[async_trait]
+impl HealthCheck for KafkaProducerHealthCheck {
+    fn name(&self) -> &str {
+        "kafka"
+    }
+
+    async fn check(&self) -> Result<(), String> {
+        let producer = self.producer.clone();
+        let timeout = self.timeout;
+        // fetch_metadata 是阻塞调用
+        let metadata = tokio::task::spawn_blocking(move || {
+            producer
+                .client()
+                .fetch_metadata(None, timeout)
+                .map(|m| m.brokers().len())
+        })
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())?;
+
+        if metadata == 0 {
+            return Err("no kafka broker available".to_string());
+        }
+        Ok(())
+    }
+}
+
+type CheckFuture = Pin<Box<dyn Future<Output = Result<(), String>> + Send>>;
+
+/// 基于闭包的检查项
+///
+/// 用于 Mongo 等在 common 中没有直接依赖的组件，例如:
+///
+/// ```ignore
+/// FnHealthCheck::new("mongodb", move || {
+///     let mongo = mongo.clone();
+///     Box::pin(async move {
+///         mongo.database("admin").run_command(doc! { "ping": 1 }).await.map(|_| ()).map_err(|e| e.to_string())
+///     })
+/// })
+/// ```
+pub struct FnHealthCheck<F>
+where
+    F: Fn() -> CheckFuture + Send + Sync,
+{
+    name: String,
+    check: F,
+}
+
+impl<F> FnHealthCheck<F>
+where
+    F: Fn() -> CheckFuture + Send + Sync,
+{
+    pub fn new(name: impl Into<String>, check: F) -> Self {
+        Self {
+            name: name.into(),
+            check,
+        }
+    }
+}
+
+#[async_trait]
+impl<F> HealthCheck for FnHealthCheck<F>
+where
+    F: Fn() -> CheckFuture + Send + Sync,
+{
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn check(&self) -> Result<(), String> {
+        (self.check)().await
+    }
+}
@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use log::{info, warn};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::config::ConsulConfig;
+
+/// 等待服务实例出现时的最长重试间隔
+const MAX_LOOKUP_BACKOFF: Duration = Duration::from_secs(30);
+
+/// agent 中已注册的服务实例
+#[derive(Debug, Deserialize)]
+struct AgentService {
+    #[serde(rename = "ID")]
+    id: String,
+    #[serde(rename = "Service")]
+    service: String,
+    #[serde(rename = "Address", default)]
+    address: String,
+    #[serde(rename = "Port", default)]
+    port: u16,
+}
+
+/// Consul 健康检查注册
+///
+/// 为已注册的服务实例追加一个指向 `/readyz` 的 HTTP 检查。检查失败时 Consul 将实例
+/// 标记为 critical，从服务发现结果中剔除；持续失败超过 `deregister_after` 后自动注销。
+#[derive(Clone)]
+pub struct ConsulHealthRegistrar {
+    client: reqwest::Client,
+    consul_addr: String,
+    interval: Duration,
+    timeout: Duration,
+    deregister_after: Duration,
+}
+
+impl ConsulHealthRegistrar {
+    pub fn new(config: &ConsulConfig) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            consul_addr: format!("http://{}:{}", config.host, config.port),
+            interval: Duration::from_secs(config.heartbeat_interval),
+            timeout: Duration::from_secs(2),
+            deregister_after: Duration::from_secs(60),
+        }
+    }
+
+    /// 自动注销时间
+    pub fn with_deregister_after(mut self, deregister_after: Duration) -> Self {
+        self.deregister_after = deregister_after;
+        self
+    }
+
+    /// 注册就绪检查
+    ///
+    /// # 参数
+    /// * `service_id` - Consul 中的服务实例ID
+    /// * `ready_url` - 就绪探针地址，例如 http://10.0.0.1:9052/readyz
+    pub async fn register(&self, service_id: &str, ready_url: &str) -> anyhow::Result<()> {
+        let body = json!({
+            "ID": format!("{}-readyz", service_id),
+            "Name": format!("{} readiness", service_id),
+            "ServiceID": service_id,
+            "HTTP": ready_url,
+            "Method": "GET",
+            "Interval": format!("{}s", self.interval.as_secs().max(1)),
+            "Timeout": format!("{}s", self.timeout.as_secs().max(1)),
+            "DeregisterCriticalServiceAfter": format!("{}s", self.deregister_after.as_secs()),
+        });
+
+        self.client
+            .put(format!("{}/v1/agent/check/register", self.consul_addr))
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        info!("Registered consul readiness check for {} -> {}", service_id, ready_url);
+        Ok(())
+    }
+
+    /// 服务实例注册到 Consul 后为其注册就绪检查
+    ///
+    /// 实例由 `AppBuilder::run` 启动时注册，实例ID由注册方生成：后台按服务名与监听地址在本地 agent
+    /// 中查找实例，找到后把检查挂到该实例上；未找到或注册失败时退避重试。
+    pub fn spawn_register(
+        &self,
+        service_name: impl Into<String>,
+        service_addr: SocketAddr,
+        ready_url: impl Into<String>,
+    ) -> tokio::task::JoinHandle<()> {
+        let registrar = self.clone();
+        let service_name = service_name.into();
+        let ready_url = ready_url.into();
+        tokio::spawn(async move {
+            let mut backoff = Duration::from_secs(1);
+            loop {
+                match registrar.find_instance(&service_name, service_addr).await {
+                    Ok(Some(service_id)) => match registrar.register(&service_id, &ready_url).await {
+                        Ok(()) => return,
+                        Err(e) => warn!("Failed to register consul health check for {}: {}", service_id, e),
+                    },
+                    Ok(None) => info!("Waiting for {} at {} to register in consul", service_name, service_addr),
+                    Err(e) => warn!("Failed to look up {} in consul: {}", service_name, e),
+                }
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_LOOKUP_BACKOFF);
+            }
+        })
+    }
+
+    /// 在本地 agent 中查找服务实例ID
+    async fn find_instance(&self, service_name: &str, service_addr: SocketAddr) -> anyhow::Result<Option<String>> {
+        let services: HashMap<String, AgentService> = self
+            .client
+            .get(format!("{}/v1/agent/services", self.consul_addr))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(matching_instance(services.into_values(), service_name, service_addr))
+    }
+
+    /// 注销就绪检查
+    pub async fn deregister(&self, service_id: &str) -> anyhow::Result<()> {
+        self.client
+            .put(format!(
+                "{}/v1/agent/check/deregister/{}-readyz",
+                self.consul_addr, service_id
+            ))
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+/// 服务名与端口一致、地址一致(或任一方未指定具体地址)的实例
+fn matching_instance(
+    services: impl IntoIterator<Item = AgentService>,
+    service_name: &str,
+    service_addr: SocketAddr,
+) -> Option<String> {
+    let ip = service_addr.ip().to_string();
+    services
+        .into_iter()
+        .find(|s| {
+            s.service == service_name
+                && s.port == service_addr.port()
+                && (s.address.is_empty() || s.address == ip || service_addr.ip().is_unspecified())
+        })
+        .map(|s| s.id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn service(id: &str, name: &str, address: &str, port: u16) -> AgentService {
+        AgentService {
+            id: id.to_string(),
+            service: name.to_string(),
+            address: address.to_string(),
+            port,
+        }
+    }
+
+    #[test]
+    fn test_matching_instance() {
+        let services = || {
+            vec![
+                service("router-a", "message_router", "10.0.0.1", 50052),
+                service("router-b", "message_router", "10.0.0.2", 50052),
+                service("filter", "message-filter", "10.0.0.2", 50054),
+            ]
+        };
+        let addr = |s: &str| s.parse::<SocketAddr>().unwrap();
+
+        assert_eq!(
+            matching_instance(services(), "message_router", addr("10.0.0.2:50052")),
+            Some("router-b".to_string())
+        );
+        assert_eq!(matching_instance(services(), "message_router", addr("10.0.0.3:50052")), None);
+        assert_eq!(matching_instance(services(), "message_router", addr("10.0.0.2:50053")), None);
+        assert_eq!(
+            matching_instance(services(), "message-filter", addr("0.0.0.0:50054")),
+            Some("filter".to_string())
+        );
+        assert_eq!(
+            matching_instance(vec![service("media-1", "media", "", 50056)], "media", addr("10.0.0.1:50056")),
+            Some("media-1".to_string())
+        );
+    }
+}
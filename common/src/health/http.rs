@@ -0,0 +1,85 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use log::{error, info};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use super::HealthRegistry;
+
+/// 启动 HTTP 探针服务
+///
+/// - `GET /healthz`: 存活探针，进程存活即返回 200
+/// - `GET /readyz`: 就绪探针，全部依赖检查通过返回 200，否则 503，响应体包含各检查项结果
+pub async fn serve_http(registry: Arc<HealthRegistry>, addr: SocketAddr) -> anyhow::Result<()> {
+    let listener = TcpListener::bind(addr).await?;
+    info!("Health endpoint listening on {}", addr);
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let registry = registry.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(stream, registry).await {
+                error!("Health endpoint error: {}", e);
+            }
+        });
+    }
+}
+
+async fn handle_connection(mut stream: TcpStream, registry: Arc<HealthRegistry>) -> anyhow::Result<()> {
+    let mut buf = [0u8; 1024];
+    let n = stream.read(&mut buf).await?;
+    let request = String::from_utf8_lossy(&buf[..n]);
+    let path = request
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .unwrap_or("/");
+
+    let (status, body) = match path {
+        "/healthz" | "/livez" => {
+            if registry.is_alive() {
+                ("200 OK", r#"{"status":"alive"}"#.to_string())
+            } else {
+                ("503 Service Unavailable", r#"{"status":"dead"}"#.to_string())
+            }
+        }
+        "/readyz" => {
+            let checks: Vec<serde_json::Value> = registry
+                .last_results()
+                .await
+                .into_iter()
+                .map(|r| {
+                    serde_json::json!({
+                        "name": r.name,
+                        "healthy": r.healthy,
+                        "error": r.error,
+                    })
+                })
+                .collect();
+            let ready = registry.is_ready();
+            let body = serde_json::json!({
+                "status": if ready { "ready" } else { "not_ready" },
+                "service": registry.service_name(),
+                "checks": checks,
+            })
+            .to_string();
+            if ready {
+                ("200 OK", body)
+            } else {
+                ("503 Service Unavailable", body)
+            }
+        }
+        _ => ("404 Not Found", r#"{"status":"not_found"}"#.to_string()),
+    };
+
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
@@ -0,0 +1,213 @@
+//! 健康检查
+//!
+//! 同时提供 `grpc.health.v1` 协议与 HTTP `/healthz`、`/readyz` 两种探针，
+//! 就绪状态由注册的依赖检查(Kafka、Redis、Postgres、Mongo 等)实时决定。
+
+mod checks;
+mod consul;
+mod http;
+
+pub use checks::{FnHealthCheck, KafkaProducerHealthCheck, PostgresHealthCheck, RedisHealthCheck};
+pub use consul::ConsulHealthRegistrar;
+pub use http::serve_http;
+
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use log::{error, info, warn};
+use tokio::sync::RwLock;
+use tonic_health::server::HealthReporter;
+use tonic_health::ServingStatus;
+
+/// 依赖健康检查
+#[async_trait]
+pub trait HealthCheck: Send + Sync {
+    /// 检查项名称
+    fn name(&self) -> &str;
+
+    /// 执行检查，失败时返回原因
+    async fn check(&self) -> Result<(), String>;
+}
+
+/// 单个检查项结果
+#[derive(Debug, Clone)]
+pub struct CheckResult {
+    /// 检查项名称
+    pub name: String,
+    /// 是否健康
+    pub healthy: bool,
+    /// 失败原因
+    pub error: Option<String>,
+}
+
+/// 健康状态注册表
+///
+/// 持有所有依赖检查，后台定期执行并同步到 gRPC health 服务。
+pub struct HealthRegistry {
+    service_name: String,
+    checks: Vec<Arc<dyn HealthCheck>>,
+    check_timeout: Duration,
+    alive: AtomicBool,
+    ready: AtomicBool,
+    last_results: RwLock<Vec<CheckResult>>,
+    reporter: RwLock<Option<HealthReporter>>,
+}
+
+impl HealthRegistry {
+    pub fn new(service_name: impl Into<String>) -> Self {
+        Self {
+            service_name: service_name.into(),
+            checks: Vec::new(),
+            check_timeout: Duration::from_secs(2),
+            alive: AtomicBool::new(true),
+            ready: AtomicBool::new(false),
+            last_results: RwLock::new(Vec::new()),
+            reporter: RwLock::new(None),
+        }
+    }
+
+    /// 注册依赖检查
+    pub fn with_check(mut self, check: Arc<dyn HealthCheck>) -> Self {
+        self.checks.push(check);
+        self
+    }
+
+    /// 单项检查超时时间
+    pub fn with_check_timeout(mut self, timeout: Duration) -> Self {
+        self.check_timeout = timeout;
+        self
+    }
+
+    /// 服务名称
+    pub fn service_name(&self) -> &str {
+        &self.service_name
+    }
+
+    /// 存活状态(进程是否需要重启)
+    pub fn is_alive(&self) -> bool {
+        self.alive.load(Ordering::Acquire)
+    }
+
+    /// 就绪状态(是否可以接收流量)
+    pub fn is_ready(&self) -> bool {
+        self.ready.load(Ordering::Acquire)
+    }
+
+    /// 标记进程不再存活，例如关键后台任务退出
+    pub fn mark_dead(&self) {
+        self.alive.store(false, Ordering::Release);
+        self.ready.store(false, Ordering::Release);
+    }
+
+    /// 最近一次检查结果
+    pub async fn last_results(&self) -> Vec<CheckResult> {
+        self.last_results.read().await.clone()
+    }
+
+    /// 创建 `grpc.health.v1` 服务，并绑定到本注册表
+    pub async fn grpc_service(
+        &self,
+    ) -> tonic_health::pb::health_server::HealthServer<impl tonic_health::pb::health_server::Health> {
+        let (reporter, service) = tonic_health::server::health_reporter();
+        *self.reporter.write().await = Some(reporter);
+        self.publish().await;
+        service
+    }
+
+    /// 启动健康检查
+    ///
+    /// 执行首轮检查后按 `check_interval` 定期检查，并在 `http_addr` 上提供 HTTP 探针；
+    /// 返回绑定到本注册表的 `grpc.health.v1` 服务，由调用方挂到 gRPC 服务端。
+    pub async fn start(
+        self: &Arc<Self>,
+        http_addr: &str,
+        check_interval: Duration,
+    ) -> anyhow::Result<tonic_health::pb::health_server::HealthServer<impl tonic_health::pb::health_server::Health>>
+    {
+        let http_addr: SocketAddr = http_addr
+            .parse()
+            .map_err(|e| anyhow::anyhow!("invalid health address {}: {}", http_addr, e))?;
+        self.run_checks().await;
+        self.spawn_checker(check_interval);
+        let service = self.grpc_service().await;
+
+        let registry = self.clone();
+        tokio::spawn(async move {
+            if let Err(e) = serve_http(registry, http_addr).await {
+                error!("Health endpoint error: {}", e);
+            }
+        });
+        Ok(service)
+    }
+
+    /// 执行一轮全部检查
+    pub async fn run_checks(&self) -> bool {
+        let mut results = Vec::with_capacity(self.checks.len());
+        for check in &self.checks {
+            let result = match tokio::time::timeout(self.check_timeout, check.check()).await {
+                Ok(Ok(())) => CheckResult {
+                    name: check.name().to_string(),
+                    healthy: true,
+                    error: None,
+                },
+                Ok(Err(e)) => CheckResult {
+                    name: check.name().to_string(),
+                    healthy: false,
+                    error: Some(e),
+                },
+                Err(_) => CheckResult {
+                    name: check.name().to_string(),
+                    healthy: false,
+                    error: Some("check timed out".to_string()),
+                },
+            };
+            if let Some(error) = &result.error {
+                warn!("Health check {} failed: {}", result.name, error);
+            }
+            results.push(result);
+        }
+
+        let ready = self.is_alive() && results.iter().all(|r| r.healthy);
+        let changed = self.ready.swap(ready, Ordering::AcqRel) != ready;
+        *self.last_results.write().await = results;
+
+        if changed {
+            info!(
+                "Service {} readiness changed to {}",
+                self.service_name,
+                if ready { "ready" } else { "not ready" }
+            );
+            self.publish().await;
+        }
+        ready
+    }
+
+    /// 启动后台定期检查
+    pub fn spawn_checker(self: &Arc<Self>, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let registry = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                registry.run_checks().await;
+            }
+        })
+    }
+
+    /// 同步就绪状态到 gRPC health 服务
+    async fn publish(&self) {
+        if let Some(reporter) = self.reporter.write().await.as_mut() {
+            let status = if self.is_ready() {
+                ServingStatus::Serving
+            } else {
+                ServingStatus::NotServing
+            };
+            // 空服务名表示整体状态
+            reporter.set_service_status("", status).await;
+            reporter.set_service_status(&self.service_name, status).await;
+        }
+    }
+}
@@ -0,0 +1,9 @@
+//! 分布式唯一ID生成
+//!
+//! 基于 snowflake 的 64 位有序ID，Worker ID 通过 Redis 租约分配。
+
+mod snowflake;
+mod worker;
+
+pub use snowflake::{IdError, SnowflakeGenerator, SnowflakeId};
+pub use worker::{RedisWorkerLease, WorkerLeaseConfig};
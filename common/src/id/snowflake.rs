@@ -0,0 +1,270 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// 自定义纪元 (2024-01-01 00:00:00 UTC)，单位毫秒
+pub const EPOCH_MS: i64 = 1_704_067_200_000;
+
+const WORKER_ID_BITS: u32 = 10;
+const SEQUENCE_BITS: u32 = 12;
+
+/// 最大 Worker ID
+pub const MAX_WORKER_ID: u16 = (1 << WORKER_ID_BITS) - 1;
+const MAX_SEQUENCE: u16 = (1 << SEQUENCE_BITS) - 1;
+
+const WORKER_ID_SHIFT: u32 = SEQUENCE_BITS;
+const TIMESTAMP_SHIFT: u32 = SEQUENCE_BITS + WORKER_ID_BITS;
+
+/// 允许等待的最大时钟回拨(毫秒)，超过则直接报错
+const MAX_BACKWARD_MS: i64 = 5;
+
+/// ID 生成错误
+#[derive(Debug, thiserror::Error)]
+pub enum IdError {
+    #[error("Clock moved backwards by {0}ms")]
+    ClockMovedBackwards(i64),
+
+    #[error("Invalid worker id: {0}")]
+    InvalidWorkerId(u16),
+
+    #[error("Worker lease lost")]
+    WorkerLeaseLost,
+
+    #[error("Worker lease error: {0}")]
+    Lease(String),
+}
+
+/// 解析后的 snowflake ID
+///
+/// 布局: 1 位符号位(恒为0) | 41 位毫秒时间戳 | 10 位 Worker ID | 12 位序列号
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SnowflakeId(pub i64);
+
+impl SnowflakeId {
+    /// 生成时间(Unix 毫秒)
+    pub fn timestamp_ms(&self) -> i64 {
+        (self.0 >> TIMESTAMP_SHIFT) + EPOCH_MS
+    }
+
+    /// Worker ID
+    pub fn worker_id(&self) -> u16 {
+        ((self.0 >> WORKER_ID_SHIFT) & MAX_WORKER_ID as i64) as u16
+    }
+
+    /// 序列号
+    pub fn sequence(&self) -> u16 {
+        (self.0 & MAX_SEQUENCE as i64) as u16
+    }
+
+    /// 定长字符串形式(左补零到19位)
+    ///
+    /// 字典序与数值序一致，可直接作为 `server_msg_id`、Kafka key 和 Mongo `_id` 使用。
+    pub fn to_sortable_string(&self) -> String {
+        format!("{:019}", self.0)
+    }
+
+    /// 从字符串解析
+    pub fn parse(s: &str) -> Option<Self> {
+        s.parse::<i64>().ok().filter(|v| *v >= 0).map(SnowflakeId)
+    }
+}
+
+impl std::fmt::Display for SnowflakeId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_sortable_string())
+    }
+}
+
+impl serde::Serialize for SnowflakeId {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_sortable_string())
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for SnowflakeId {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Self::parse(&s).ok_or_else(|| serde::de::Error::custom(format!("invalid snowflake id: {}", s)))
+    }
+}
+
+struct State {
+    last_timestamp: i64,
+    /// 当前毫秒内下一个可用的序列号，超过 MAX_SEQUENCE 时需等待下一毫秒
+    next_sequence: u16,
+}
+
+/// Snowflake ID 生成器
+///
+/// 线程安全，可通过 `Arc` 在服务内共享。Worker ID 由 [`RedisWorkerLease`](super::RedisWorkerLease)
+/// 租约分配，租约失效后生成器拒绝继续发号，避免与其他实例冲突。
+pub struct SnowflakeGenerator {
+    worker_id: u16,
+    state: Mutex<State>,
+    lease_valid: Arc<AtomicBool>,
+}
+
+impl SnowflakeGenerator {
+    /// 使用固定 Worker ID 创建生成器(测试或单实例部署)
+    pub fn new(worker_id: u16) -> Result<Self, IdError> {
+        Self::with_lease_flag(worker_id, Arc::new(AtomicBool::new(true)))
+    }
+
+    /// 使用租约状态创建生成器
+    pub(crate) fn with_lease_flag(worker_id: u16, lease_valid: Arc<AtomicBool>) -> Result<Self, IdError> {
+        if worker_id > MAX_WORKER_ID {
+            return Err(IdError::InvalidWorkerId(worker_id));
+        }
+        Ok(Self {
+            worker_id,
+            state: Mutex::new(State {
+                last_timestamp: 0,
+                next_sequence: 0,
+            }),
+            lease_valid,
+        })
+    }
+
+    /// 当前 Worker ID
+    pub fn worker_id(&self) -> u16 {
+        self.worker_id
+    }
+
+    /// 生成下一个ID
+    pub async fn next_id(&self) -> Result<SnowflakeId, IdError> {
+        let mut ids = self.next_ids(1).await?;
+        Ok(ids.remove(0))
+    }
+
+    /// 生成下一个ID的字符串形式
+    pub async fn next_id_string(&self) -> Result<String, IdError> {
+        self.next_id().await.map(|id| id.to_sortable_string())
+    }
+
+    /// 批量分配ID
+    ///
+    /// 返回的ID严格递增。每次加锁分配当前毫秒内可用的序列号，时钟回拨或序列号耗尽时
+    /// 释放锁后异步等待，不阻塞其他任务发号，也不占用 tokio 工作线程。
+    pub async fn next_ids(&self, count: usize) -> Result<Vec<SnowflakeId>, IdError> {
+        if !self.lease_valid.load(Ordering::Acquire) {
+            return Err(IdError::WorkerLeaseLost);
+        }
+
+        let mut ids = Vec::with_capacity(count);
+        while ids.len() < count {
+            match self.allocate(count, &mut ids)? {
+                Some(wait) if wait.is_zero() => tokio::task::yield_now().await,
+                Some(wait) => tokio::time::sleep(wait).await,
+                None => {}
+            }
+        }
+        Ok(ids)
+    }
+
+    /// 在当前毫秒内分配ID直到满足 `count` 或序列号耗尽
+    ///
+    /// 未分配完时返回需要在锁外等待的时间：小幅回拨(<= MAX_BACKWARD_MS)时等待时钟追上，
+    /// 序列号耗尽时让出线程等待下一毫秒；回拨超过上限时返回错误。
+    fn allocate(&self, count: usize, ids: &mut Vec<SnowflakeId>) -> Result<Option<Duration>, IdError> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let now = current_millis();
+        if now < state.last_timestamp {
+            let offset = state.last_timestamp - now;
+            if offset > MAX_BACKWARD_MS {
+                return Err(IdError::ClockMovedBackwards(offset));
+            }
+            return Ok(Some(Duration::from_millis(offset as u64)));
+        }
+        if now > state.last_timestamp {
+            state.last_timestamp = now;
+            state.next_sequence = 0;
+        }
+
+        while ids.len() < count && state.next_sequence <= MAX_SEQUENCE {
+            let id = ((now - EPOCH_MS) << TIMESTAMP_SHIFT)
+                | ((self.worker_id as i64) << WORKER_ID_SHIFT)
+                | state.next_sequence as i64;
+            ids.push(SnowflakeId(id));
+            state.next_sequence += 1;
+        }
+
+        if ids.len() < count {
+            // 当前毫秒序列号耗尽，等待下一毫秒
+            return Ok(Some(Duration::ZERO));
+        }
+        Ok(None)
+    }
+}
+
+fn current_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_ids_are_monotonic_and_sortable() {
+        let generator = SnowflakeGenerator::new(7).unwrap();
+        let ids = generator.next_ids(10_000).await.unwrap();
+
+        for pair in ids.windows(2) {
+            assert!(pair[0] < pair[1]);
+            assert!(pair[0].to_sortable_string() < pair[1].to_sortable_string());
+        }
+        assert!(ids.iter().all(|id| id.worker_id() == 7));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_allocation_is_unique() {
+        let generator = Arc::new(SnowflakeGenerator::new(3).unwrap());
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let generator = generator.clone();
+                tokio::spawn(async move {
+                    let mut ids = Vec::new();
+                    for _ in 0..500 {
+                        ids.extend(generator.next_ids(10).await.unwrap());
+                    }
+                    ids
+                })
+            })
+            .collect();
+
+        let mut ids = std::collections::HashSet::new();
+        for handle in handles {
+            let batch = handle.await.unwrap();
+            assert!(batch.windows(2).all(|pair| pair[0] < pair[1]));
+            ids.extend(batch);
+        }
+        assert_eq!(ids.len(), 4 * 500 * 10);
+    }
+
+    #[tokio::test]
+    async fn test_serde_as_sortable_string() {
+        let id = SnowflakeGenerator::new(1).unwrap().next_id().await.unwrap();
+        let json = serde_json::to_string(&id).unwrap();
+        assert_eq!(json, format!("\"{}\"", id.to_sortable_string()));
+        assert_eq!(serde_json::from_str::<SnowflakeId>(&json).unwrap(), id);
+        assert!(serde_json::from_str::<SnowflakeId>("\"abc\"").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_lease_lost_rejects_allocation() {
+        let flag = Arc::new(AtomicBool::new(true));
+        let generator = SnowflakeGenerator::with_lease_flag(1, flag.clone()).unwrap();
+        assert!(generator.next_id().await.is_ok());
+
+        flag.store(false, Ordering::Release);
+        assert!(matches!(generator.next_id().await, Err(IdError::WorkerLeaseLost)));
+    }
+
+    #[test]
+    fn test_invalid_worker_id() {
+        assert!(SnowflakeGenerator::new(MAX_WORKER_ID + 1).is_err());
+    }
+}
@@ -0,0 +1,203 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use log::{error, info, warn};
+use redis::aio::ConnectionManager;
+use tokio::task::JoinHandle;
+
+use super::snowflake::{IdError, SnowflakeGenerator, MAX_WORKER_ID};
+
+/// 续约脚本: 仅当租约仍属于当前实例时刷新过期时间
+const RENEW_SCRIPT: &str = r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    return redis.call('PEXPIRE', KEYS[1], ARGV[2])
+end
+return 0
+"#;
+
+/// 释放脚本: 仅当租约仍属于当前实例时删除
+const RELEASE_SCRIPT: &str = r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    return redis.call('DEL', KEYS[1])
+end
+return 0
+"#;
+
+/// Worker 租约配置
+#[derive(Debug, Clone)]
+pub struct WorkerLeaseConfig {
+    /// 租约 key 前缀
+    pub key_prefix: String,
+    /// 租约有效期
+    pub lease_ttl: Duration,
+    /// 心跳间隔，应明显小于 lease_ttl
+    pub heartbeat_interval: Duration,
+    /// 服务名称，用于区分不同服务的 worker 空间
+    pub service_name: String,
+}
+
+impl Default for WorkerLeaseConfig {
+    fn default() -> Self {
+        Self {
+            key_prefix: "snowflake:worker".to_string(),
+            lease_ttl: Duration::from_secs(30),
+            heartbeat_interval: Duration::from_secs(10),
+            service_name: "default".to_string(),
+        }
+    }
+}
+
+/// 基于 Redis 的 Worker ID 租约
+///
+/// 启动时通过 `SET NX PX` 抢占一个空闲的 Worker ID，并在后台定期续约。
+/// 续约失败(租约被他人占用或过期)时将生成器置为失效，停止发号。
+pub struct RedisWorkerLease {
+    redis: ConnectionManager,
+    config: WorkerLeaseConfig,
+    worker_id: u16,
+    token: String,
+    valid: Arc<AtomicBool>,
+    heartbeat: Option<JoinHandle<()>>,
+}
+
+impl RedisWorkerLease {
+    /// 抢占 Worker ID
+    pub async fn acquire(redis: ConnectionManager, config: WorkerLeaseConfig) -> Result<Self, IdError> {
+        let token = format!(
+            "{}:{}:{}",
+            config.service_name,
+            std::process::id(),
+            chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()
+        );
+
+        // 从随机位置开始探测，降低多实例同时启动时的冲突
+        let start = rand::random_range(0..=MAX_WORKER_ID);
+        let mut conn = redis.clone();
+
+        for offset in 0..=MAX_WORKER_ID {
+            let worker_id = (start + offset) % (MAX_WORKER_ID + 1);
+            let key = Self::lease_key(&config, worker_id);
+
+            let acquired: Option<String> = redis::cmd("SET")
+                .arg(&key)
+                .arg(&token)
+                .arg("NX")
+                .arg("PX")
+                .arg(config.lease_ttl.as_millis() as u64)
+                .query_async(&mut conn)
+                .await
+                .map_err(|e| IdError::Lease(e.to_string()))?;
+
+            if acquired.is_some() {
+                info!("Acquired snowflake worker id {} for {}", worker_id, config.service_name);
+                let mut lease = Self {
+                    redis,
+                    config,
+                    worker_id,
+                    token,
+                    valid: Arc::new(AtomicBool::new(true)),
+                    heartbeat: None,
+                };
+                lease.start_heartbeat();
+                return Ok(lease);
+            }
+        }
+
+        Err(IdError::Lease("no free worker id available".to_string()))
+    }
+
+    /// 租约对应的 Worker ID
+    pub fn worker_id(&self) -> u16 {
+        self.worker_id
+    }
+
+    /// 租约是否仍然有效
+    pub fn is_valid(&self) -> bool {
+        self.valid.load(Ordering::Acquire)
+    }
+
+    /// 基于当前租约创建生成器
+    pub fn generator(&self) -> Result<SnowflakeGenerator, IdError> {
+        SnowflakeGenerator::with_lease_flag(self.worker_id, self.valid.clone())
+    }
+
+    /// 主动释放租约
+    pub async fn release(mut self) -> Result<(), IdError> {
+        if let Some(handle) = self.heartbeat.take() {
+            handle.abort();
+        }
+        self.valid.store(false, Ordering::Release);
+
+        let mut conn = self.redis.clone();
+        let _: i32 = redis::Script::new(RELEASE_SCRIPT)
+            .key(Self::lease_key(&self.config, self.worker_id))
+            .arg(&self.token)
+            .invoke_async(&mut conn)
+            .await
+            .map_err(|e| IdError::Lease(e.to_string()))?;
+
+        info!("Released snowflake worker id {}", self.worker_id);
+        Ok(())
+    }
+
+    fn lease_key(config: &WorkerLeaseConfig, worker_id: u16) -> String {
+        format!("{}:{}:{}", config.key_prefix, config.service_name, worker_id)
+    }
+
+    fn start_heartbeat(&mut self) {
+        let mut conn = self.redis.clone();
+        let key = Self::lease_key(&self.config, self.worker_id);
+        let token = self.token.clone();
+        let ttl_ms = self.config.lease_ttl.as_millis() as u64;
+        let interval = self.config.heartbeat_interval;
+        let lease_ttl = self.config.lease_ttl;
+        let valid = self.valid.clone();
+        let worker_id = self.worker_id;
+
+        self.heartbeat = Some(tokio::spawn(async move {
+            let script = redis::Script::new(RENEW_SCRIPT);
+            let mut ticker = tokio::time::interval(interval);
+            let mut last_renewed = tokio::time::Instant::now();
+
+            loop {
+                ticker.tick().await;
+                let renewed: Result<i32, _> = script
+                    .key(&key)
+                    .arg(&token)
+                    .arg(ttl_ms)
+                    .invoke_async(&mut conn)
+                    .await;
+
+                match renewed {
+                    Ok(1) => {
+                        last_renewed = tokio::time::Instant::now();
+                    }
+                    Ok(_) => {
+                        error!("Snowflake worker id {} lease taken over, stop allocating", worker_id);
+                        valid.store(false, Ordering::Release);
+                        return;
+                    }
+                    Err(e) => {
+                        warn!("Failed to renew snowflake worker id {}: {}", worker_id, e);
+                        // Redis 不可达超过租期时，其他实例可能已拿到同一个 ID
+                        if last_renewed.elapsed() >= lease_ttl {
+                            error!("Snowflake worker id {} lease expired, stop allocating", worker_id);
+                            valid.store(false, Ordering::Release);
+                            return;
+                        }
+                    }
+                }
+            }
+        }));
+    }
+}
+
+impl Drop for RedisWorkerLease {
+    fn drop(&mut self) {
+        if let Some(handle) = self.heartbeat.take() {
+            handle.abort();
+        }
+        self.valid.store(false, Ordering::Release);
+    }
+}
@@ -1,3 +1,13 @@
 pub mod config;
 pub mod utils;
-pub mod topic;
\ No newline at end of file
+pub mod topic;
+pub mod id;
+pub mod telemetry;
+pub mod health;
+pub mod error;
+pub mod tenant;
+pub mod bus;
+pub mod rpc;
+pub mod content;
+pub mod feature;
+pub mod route;
@@ -0,0 +1,297 @@
+//! 用户连接路由
+//!
+//! 网关在连接建立时登记 用户/设备 → 网关 路由，心跳时续期，连接关闭时删除；
+//! 路由服务据此查找用户所在网关。网关同时定期上报负载，用于填充路由的权重与负载。
+//!
+//! 存储结构:
+//! - `route:user:{user_id}`: Hash，field 为 device_id，value 为 [`UserRoute`] JSON，
+//!   value 中带过期时间，读取时忽略过期设备；key 本身的 TTL 随每次登记/续期刷新
+//! - `route:gateway:{gateway_id}`: [`GatewayLoad`] JSON，带 TTL，网关停止上报后自动消失
+//!
+//! 用户路由按租户隔离，网关负载为集群共享。
+
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use anyhow::Result;
+use log::warn;
+use redis::aio::ConnectionManager;
+use serde::{Deserialize, Serialize};
+
+use crate::tenant;
+
+/// 删除脚本: 仅当设备路由仍属于该网关时删除，避免重连到其他网关后被旧连接的关闭事件误删
+const REMOVE_SCRIPT: &str = r#"
+local value = redis.call('HGET', KEYS[1], ARGV[1])
+if not value then
+    return 0
+end
+if cjson.decode(value)['gateway_id'] == ARGV[2] then
+    return redis.call('HDEL', KEYS[1], ARGV[1])
+end
+return 0
+"#;
+
+/// 用户设备路由
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UserRoute {
+    pub user_id: String,
+    pub device_id: String,
+    /// 客户端平台
+    pub platform: i32,
+    /// 网关实例ID
+    pub gateway_id: String,
+    /// 网关 gRPC 地址 (`host:port`)
+    pub gateway_addr: String,
+    /// 连接建立时间 (毫秒)
+    pub connected_at: i64,
+    /// 路由过期时间 (毫秒)，由存储写入
+    #[serde(default)]
+    pub expires_at: i64,
+}
+
+/// 用户上下线事件
+///
+/// 网关在设备连接建立与关闭时写入 [`KafkaTopics::USER_PRESENCE`](crate::topic::KafkaTopics::USER_PRESENCE)，
+/// 以用户ID为键，JSON 编码。同一用户的多个设备各自产生事件，消费方需结合路由判断用户是否仍在线。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PresenceEvent {
+    pub user_id: String,
+    pub device_id: String,
+    /// 网关实例ID
+    pub gateway_id: String,
+    /// true 为上线，false 为下线
+    pub online: bool,
+    /// 事件时间 (毫秒)
+    pub time: i64,
+}
+
+/// 群成员变更事件
+///
+/// 群组服务在成员加入、退出或被移出时写入 [`KafkaTopics::GROUP_MEMBERSHIP`](crate::topic::KafkaTopics::GROUP_MEMBERSHIP)，
+/// 以用户ID为键，JSON 编码，与上下线事件一起维护群在线成员索引。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GroupMemberEvent {
+    pub user_id: String,
+    pub group_id: String,
+    /// true 为加入，false 为退出
+    pub joined: bool,
+    /// 事件时间 (毫秒)
+    pub time: i64,
+}
+
+/// 网关负载
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GatewayLoad {
+    pub gateway_id: String,
+    pub gateway_addr: String,
+    /// 调度权重
+    pub weight: u32,
+    /// 当前连接数
+    pub connections: u64,
+    /// 连接容量
+    pub capacity: u64,
+    /// 上报时间 (毫秒)
+    pub updated_at: i64,
+}
+
+impl GatewayLoad {
+    /// 负载百分比 (0-100)
+    pub fn load_percent(&self) -> u32 {
+        if self.capacity == 0 {
+            return 0;
+        }
+        (self.connections.saturating_mul(100) / self.capacity).min(100) as u32
+    }
+}
+
+/// 带网关负载的路由
+#[derive(Debug, Clone)]
+pub struct ResolvedRoute {
+    pub route: UserRoute,
+    /// 网关未上报负载时为 None
+    pub gateway: Option<GatewayLoad>,
+}
+
+/// 路由存储配置
+#[derive(Debug, Clone)]
+pub struct RouteStoreConfig {
+    /// 设备路由有效期，应为网关心跳间隔的数倍
+    pub route_ttl: Duration,
+    /// 网关负载有效期
+    pub gateway_ttl: Duration,
+    /// 批量查询时单个 pipeline 的用户数
+    pub batch_size: usize,
+}
+
+impl Default for RouteStoreConfig {
+    fn default() -> Self {
+        Self {
+            route_ttl: Duration::from_secs(90),
+            gateway_ttl: Duration::from_secs(30),
+            batch_size: 500,
+        }
+    }
+}
+
+/// 基于 Redis 的路由存储
+#[derive(Clone)]
+pub struct RouteStore {
+    redis: ConnectionManager,
+    config: RouteStoreConfig,
+}
+
+impl RouteStore {
+    pub fn new(redis: ConnectionManager) -> Self {
+        Self::with_config(redis, RouteStoreConfig::default())
+    }
+
+    pub fn with_config(redis: ConnectionManager, config: RouteStoreConfig) -> Self {
+        Self { redis, config }
+    }
+
+    fn user_key(user_id: &str) -> String {
+        tenant::current().redis_key(&format!("route:user:{}", user_id))
+    }
+
+    fn gateway_key(gateway_id: &str) -> String {
+        format!("route:gateway:{}", gateway_id)
+    }
+
+    /// 登记设备路由，同一设备重复登记时覆盖
+    pub async fn register(&self, route: &UserRoute) -> Result<()> {
+        self.refresh_many(std::slice::from_ref(route)).await
+    }
+
+    /// 心跳续期
+    pub async fn refresh(&self, route: &UserRoute) -> Result<()> {
+        self.refresh_many(std::slice::from_ref(route)).await
+    }
+
+    /// 批量续期，网关按心跳周期批量刷新本机连接
+    pub async fn refresh_many(&self, routes: &[UserRoute]) -> Result<()> {
+        if routes.is_empty() {
+            return Ok(());
+        }
+        let expires_at = chrono::Utc::now().timestamp_millis() + self.config.route_ttl.as_millis() as i64;
+        let ttl = self.config.route_ttl.as_secs() as i64;
+
+        let mut pipe = redis::pipe();
+        for route in routes {
+            let key = Self::user_key(&route.user_id);
+            let route = UserRoute {
+                expires_at,
+                ..route.clone()
+            };
+            pipe.hset(&key, &route.device_id, serde_json::to_string(&route)?)
+                .ignore()
+                .expire(&key, ttl)
+                .ignore();
+        }
+        let mut conn = self.redis.clone();
+        pipe.query_async::<()>(&mut conn).await?;
+        Ok(())
+    }
+
+    /// 删除设备路由，仅当路由仍属于 `gateway_id` 时生效
+    pub async fn remove(&self, user_id: &str, device_id: &str, gateway_id: &str) -> Result<bool> {
+        let mut conn = self.redis.clone();
+        let removed: i32 = redis::Script::new(REMOVE_SCRIPT)
+            .key(Self::user_key(user_id))
+            .arg(device_id)
+            .arg(gateway_id)
+            .invoke_async(&mut conn)
+            .await?;
+        Ok(removed > 0)
+    }
+
+    /// 上报网关负载
+    pub async fn report_load(&self, load: &GatewayLoad) -> Result<()> {
+        let mut conn = self.redis.clone();
+        redis::cmd("SET")
+            .arg(Self::gateway_key(&load.gateway_id))
+            .arg(serde_json::to_string(load)?)
+            .arg("EX")
+            .arg(self.config.gateway_ttl.as_secs())
+            .query_async::<()>(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    /// 查询用户所有在线设备路由
+    pub async fn get_routes(&self, user_id: &str) -> Result<Vec<ResolvedRoute>> {
+        let mut routes = self.get_routes_batch(&[user_id.to_string()]).await?;
+        Ok(routes.remove(user_id).unwrap_or_default())
+    }
+
+    /// 批量查询用户路由，按 `batch_size` 分批以 pipeline 查询；
+    /// 结果包含所有请求的用户，离线用户对应空列表
+    pub async fn get_routes_batch(&self, user_ids: &[String]) -> Result<HashMap<String, Vec<ResolvedRoute>>> {
+        let now = chrono::Utc::now().timestamp_millis();
+        let mut conn = self.redis.clone();
+        let mut result: HashMap<String, Vec<UserRoute>> = HashMap::with_capacity(user_ids.len());
+
+        for chunk in user_ids.chunks(self.config.batch_size.max(1)) {
+            let mut pipe = redis::pipe();
+            for user_id in chunk {
+                pipe.hgetall(Self::user_key(user_id));
+            }
+            let devices: Vec<HashMap<String, String>> = pipe.query_async(&mut conn).await?;
+
+            for (user_id, devices) in chunk.iter().zip(devices) {
+                let routes = result.entry(user_id.clone()).or_default();
+                for (device_id, value) in devices {
+                    // 过期设备的 field 随 key 过期一并清除
+                    match serde_json::from_str::<UserRoute>(&value) {
+                        Ok(route) if route.expires_at > now => routes.push(route),
+                        Ok(_) => {}
+                        Err(e) => warn!("Invalid route of {}/{}: {}", user_id, device_id, e),
+                    }
+                }
+            }
+        }
+
+        let gateway_ids: HashSet<&str> = result
+            .values()
+            .flatten()
+            .map(|r| r.gateway_id.as_str())
+            .collect();
+        let gateways = self.gateway_loads(gateway_ids.into_iter()).await?;
+
+        Ok(result
+            .into_iter()
+            .map(|(user_id, routes)| {
+                let routes = routes
+                    .into_iter()
+                    .map(|route| ResolvedRoute {
+                        gateway: gateways.get(&route.gateway_id).cloned(),
+                        route,
+                    })
+                    .collect();
+                (user_id, routes)
+            })
+            .collect())
+    }
+
+    /// 查询网关负载
+    pub async fn gateway_loads<'a>(
+        &self,
+        gateway_ids: impl Iterator<Item = &'a str>,
+    ) -> Result<HashMap<String, GatewayLoad>> {
+        let gateway_ids: Vec<&str> = gateway_ids.collect();
+        if gateway_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let keys: Vec<String> = gateway_ids.iter().map(|id| Self::gateway_key(id)).collect();
+        let mut conn = self.redis.clone();
+        let values: Vec<Option<String>> = redis::cmd("MGET").arg(&keys).query_async(&mut conn).await?;
+
+        Ok(values
+            .into_iter()
+            .flatten()
+            .filter_map(|value| serde_json::from_str::<GatewayLoad>(&value).ok())
+            .map(|load| (load.gateway_id.clone(), load))
+            .collect())
+    }
+}
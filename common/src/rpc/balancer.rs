@@ -0,0 +1,141 @@
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use super::breaker::BreakerState;
+use super::channel::PooledEndpoint;
+
+/// 负载均衡策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LoadBalancePolicy {
+    /// 轮询
+    #[default]
+    RoundRobin,
+    /// 平滑加权轮询，权重来自服务注册信息
+    Weighted,
+}
+
+/// 负载均衡器
+///
+/// 只在熔断器未打开的实例间选择；所有实例都熔断时返回 None。
+#[derive(Debug, Default)]
+pub(crate) struct Balancer {
+    policy: LoadBalancePolicy,
+    next: AtomicUsize,
+    /// 平滑加权轮询的当前权重，与实例列表一一对应
+    current_weights: Mutex<Vec<i64>>,
+}
+
+impl Balancer {
+    pub(crate) fn new(policy: LoadBalancePolicy) -> Self {
+        Self {
+            policy,
+            ..Default::default()
+        }
+    }
+
+    /// 实例列表变化后重置状态
+    pub(crate) fn reset(&self, len: usize) {
+        *self.current_weights.lock().unwrap() = vec![0; len];
+    }
+
+    pub(crate) fn pick(&self, endpoints: &[std::sync::Arc<PooledEndpoint>], exclude: &HashSet<String>) -> Option<usize> {
+        let available: Vec<usize> = endpoints
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| !exclude.contains(&e.instance.address))
+            .filter(|(_, e)| e.breaker.state() != BreakerState::Open)
+            .map(|(i, _)| i)
+            .collect();
+        if available.is_empty() {
+            return None;
+        }
+
+        match self.policy {
+            LoadBalancePolicy::RoundRobin => {
+                let n = self.next.fetch_add(1, Ordering::Relaxed);
+                Some(available[n % available.len()])
+            }
+            LoadBalancePolicy::Weighted => {
+                let mut current = self.current_weights.lock().unwrap();
+                if current.len() != endpoints.len() {
+                    *current = vec![0; endpoints.len()];
+                }
+                let total: i64 = available
+                    .iter()
+                    .map(|&i| endpoints[i].instance.weight as i64)
+                    .sum();
+                let mut best = available[0];
+                for &i in &available {
+                    current[i] += endpoints[i].instance.weight as i64;
+                    if current[i] > current[best] {
+                        best = i;
+                    }
+                }
+                current[best] -= total;
+                Some(best)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tonic::transport::Endpoint;
+
+    use super::*;
+    use crate::rpc::breaker::{CircuitBreaker, CircuitBreakerConfig};
+    use crate::rpc::discovery::ServiceInstance;
+
+    fn endpoints(weights: &[u32]) -> Vec<Arc<PooledEndpoint>> {
+        weights
+            .iter()
+            .enumerate()
+            .map(|(i, &weight)| {
+                Arc::new(PooledEndpoint {
+                    instance: ServiceInstance {
+                        id: format!("instance-{}", i),
+                        address: format!("127.0.0.1:{}", 9000 + i),
+                        weight,
+                    },
+                    channel: Endpoint::from_static("http://127.0.0.1:9000").connect_lazy(),
+                    breaker: CircuitBreaker::new(CircuitBreakerConfig {
+                        failure_threshold: 1,
+                        ..Default::default()
+                    }),
+                })
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_round_robin_skips_excluded_and_open() {
+        let endpoints = endpoints(&[1, 1, 1]);
+        let balancer = Balancer::new(LoadBalancePolicy::RoundRobin);
+
+        let picks: Vec<_> = (0..3).map(|_| balancer.pick(&endpoints, &HashSet::new()).unwrap()).collect();
+        assert_eq!(picks, vec![0, 1, 2]);
+
+        endpoints[1].breaker.on_failure();
+        let exclude = HashSet::from([endpoints[0].instance.address.clone()]);
+        for _ in 0..3 {
+            assert_eq!(balancer.pick(&endpoints, &exclude), Some(2));
+        }
+
+        endpoints[2].breaker.on_failure();
+        assert_eq!(balancer.pick(&endpoints, &exclude), None);
+    }
+
+    #[tokio::test]
+    async fn test_weighted_is_smooth_and_proportional() {
+        let endpoints = endpoints(&[5, 1, 1]);
+        let balancer = Balancer::new(LoadBalancePolicy::Weighted);
+        balancer.reset(endpoints.len());
+
+        let picks: Vec<_> = (0..7).map(|_| balancer.pick(&endpoints, &HashSet::new()).unwrap()).collect();
+        // 高权重实例的请求分散在整个周期内，而不是连续命中
+        assert_eq!(picks, vec![0, 0, 1, 0, 2, 0, 0]);
+    }
+}
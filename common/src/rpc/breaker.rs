@@ -0,0 +1,143 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// 熔断器配置
+#[derive(Debug, Clone)]
+pub struct CircuitBreakerConfig {
+    /// 连续失败多少次后熔断
+    pub failure_threshold: u32,
+    /// 熔断持续时长，之后进入半开状态
+    pub open_duration: Duration,
+    /// 半开状态下允许的探测请求数
+    pub half_open_requests: u32,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            open_duration: Duration::from_secs(10),
+            half_open_requests: 1,
+        }
+    }
+}
+
+/// 熔断状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakerState {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+#[derive(Debug)]
+struct Inner {
+    state: BreakerState,
+    failures: u32,
+    opened_at: Option<Instant>,
+    half_open_inflight: u32,
+}
+
+/// 实例级熔断器
+///
+/// 连续失败达到阈值后熔断，熔断期内不再选择该实例；到期后放行少量探测请求，
+/// 探测成功则恢复，失败则重新熔断。
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    config: CircuitBreakerConfig,
+    inner: Mutex<Inner>,
+}
+
+impl CircuitBreaker {
+    pub fn new(config: CircuitBreakerConfig) -> Self {
+        Self {
+            config,
+            inner: Mutex::new(Inner {
+                state: BreakerState::Closed,
+                failures: 0,
+                opened_at: None,
+                half_open_inflight: 0,
+            }),
+        }
+    }
+
+    /// 当前状态
+    pub fn state(&self) -> BreakerState {
+        let mut inner = self.inner.lock().unwrap();
+        self.refresh(&mut inner);
+        inner.state
+    }
+
+    /// 尝试获取调用许可
+    pub fn try_acquire(&self) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        self.refresh(&mut inner);
+        match inner.state {
+            BreakerState::Closed => true,
+            BreakerState::Open => false,
+            BreakerState::HalfOpen => {
+                if inner.half_open_inflight < self.config.half_open_requests {
+                    inner.half_open_inflight += 1;
+                    true
+                } else {
+                    false
+                }
+            }
+        }
+    }
+
+    /// 记录调用成功
+    pub fn on_success(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.state = BreakerState::Closed;
+        inner.failures = 0;
+        inner.opened_at = None;
+        inner.half_open_inflight = 0;
+    }
+
+    /// 记录调用失败
+    pub fn on_failure(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.failures += 1;
+        if inner.state == BreakerState::HalfOpen || inner.failures >= self.config.failure_threshold {
+            inner.state = BreakerState::Open;
+            inner.opened_at = Some(Instant::now());
+            inner.half_open_inflight = 0;
+        }
+    }
+
+    fn refresh(&self, inner: &mut Inner) {
+        if inner.state == BreakerState::Open {
+            if let Some(opened_at) = inner.opened_at {
+                if opened_at.elapsed() >= self.config.open_duration {
+                    inner.state = BreakerState::HalfOpen;
+                    inner.half_open_inflight = 0;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_opens_after_threshold_and_recovers() {
+        let breaker = CircuitBreaker::new(CircuitBreakerConfig {
+            failure_threshold: 2,
+            open_duration: Duration::from_millis(0),
+            half_open_requests: 1,
+        });
+
+        breaker.on_failure();
+        assert_eq!(breaker.state(), BreakerState::Closed);
+        breaker.on_failure();
+
+        // 熔断时长为 0，立即进入半开，只放行一个探测请求
+        assert!(breaker.try_acquire());
+        assert!(!breaker.try_acquire());
+        breaker.on_success();
+        assert_eq!(breaker.state(), BreakerState::Closed);
+    }
+}
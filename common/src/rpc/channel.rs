@@ -0,0 +1,196 @@
+use std::collections::HashSet;
+use std::future::Future;
+use std::sync::{Arc, RwLock};
+
+use anyhow::Result;
+use log::{debug, warn};
+use tonic::transport::{Channel, Endpoint};
+use tonic::Status;
+
+use super::balancer::Balancer;
+use super::breaker::CircuitBreaker;
+use super::discovery::ServiceInstance;
+use super::factory::ClientOptions;
+use super::retry::{RetryBudget, RetryPolicy};
+
+/// 连接池中的实例
+#[derive(Debug)]
+pub(crate) struct PooledEndpoint {
+    pub(crate) instance: ServiceInstance,
+    pub(crate) channel: Channel,
+    pub(crate) breaker: CircuitBreaker,
+}
+
+/// 服务通道
+///
+/// 持有某个服务所有健康实例的连接，按负载均衡策略选择实例，
+/// 并对每次调用应用超时、重试预算与实例级熔断。
+pub struct ServiceChannel {
+    name: String,
+    options: ClientOptions,
+    endpoints: RwLock<Vec<Arc<PooledEndpoint>>>,
+    balancer: Balancer,
+    budget: RetryBudget,
+}
+
+impl ServiceChannel {
+    pub(crate) fn new(name: &str, options: ClientOptions) -> Self {
+        Self {
+            name: name.to_string(),
+            balancer: Balancer::new(options.policy),
+            budget: RetryBudget::new(&options.retry),
+            options,
+            endpoints: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// 服务名
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// 当前实例列表
+    pub fn instances(&self) -> Vec<ServiceInstance> {
+        self.endpoints
+            .read()
+            .unwrap()
+            .iter()
+            .map(|e| e.instance.clone())
+            .collect()
+    }
+
+    /// 用服务发现结果更新连接池，已有实例复用原连接
+    pub(crate) fn update(&self, instances: Vec<ServiceInstance>) -> Result<()> {
+        let current = self.endpoints.read().unwrap().clone();
+        let mut endpoints = Vec::with_capacity(instances.len());
+        for instance in instances {
+            match current.iter().find(|e| e.instance == instance) {
+                Some(existing) => endpoints.push(existing.clone()),
+                None => {
+                    debug!("Add {} instance {}", self.name, instance.address);
+                    endpoints.push(Arc::new(PooledEndpoint {
+                        channel: self.connect(&instance.address)?,
+                        breaker: CircuitBreaker::new(self.options.breaker.clone()),
+                        instance,
+                    }));
+                }
+            }
+        }
+
+        let len = endpoints.len();
+        *self.endpoints.write().unwrap() = endpoints;
+        self.balancer.reset(len);
+        Ok(())
+    }
+
+    fn connect(&self, address: &str) -> Result<Channel> {
+        let endpoint = Endpoint::from_shared(format!("http://{}", address))?
+            .connect_timeout(self.options.connect_timeout)
+            .timeout(self.options.deadline)
+            .tcp_keepalive(Some(self.options.keepalive))
+            .http2_keep_alive_interval(self.options.keepalive);
+        Ok(endpoint.connect_lazy())
+    }
+
+    /// 调用服务
+    ///
+    /// `f` 以选中实例的 [`Channel`] 构造客户端并发起请求，可能被调用多次：
+    /// 失败且错误可重试时，在预算允许的情况下换一个实例重试。
+    pub async fn call<T, F, Fut>(&self, mut f: F) -> Result<T, Status>
+    where
+        F: FnMut(Channel) -> Fut,
+        Fut: Future<Output = Result<T, Status>>,
+    {
+        self.budget.deposit();
+        let mut tried = HashSet::new();
+        let mut attempt = 0;
+
+        loop {
+            attempt += 1;
+            let endpoint = self.pick(&tried).ok_or_else(|| {
+                Status::unavailable(format!("no available instance for {}", self.name))
+            })?;
+            tried.insert(endpoint.instance.address.clone());
+
+            match self.invoke(&endpoint, &mut f).await {
+                Ok(response) => return Ok(response),
+                Err(status) => {
+                    let retry = attempt < self.options.retry.max_attempts
+                        && RetryPolicy::is_retryable(status.code())
+                        && self.budget.withdraw();
+                    if !retry {
+                        return Err(status);
+                    }
+                    warn!(
+                        "Call {} on {} failed: {}, retrying",
+                        self.name, endpoint.instance.address, status
+                    );
+                    tokio::time::sleep(self.options.retry.backoff * attempt).await;
+                }
+            }
+        }
+    }
+
+    /// 调用指定实例，不做负载均衡与重试，用于按连接所在网关定向推送等场景
+    pub async fn call_instance<T, F, Fut>(&self, address: &str, mut f: F) -> Result<T, Status>
+    where
+        F: FnMut(Channel) -> Fut,
+        Fut: Future<Output = Result<T, Status>>,
+    {
+        let endpoint = self
+            .endpoints
+            .read()
+            .unwrap()
+            .iter()
+            .find(|e| e.instance.address == address)
+            .cloned()
+            .ok_or_else(|| Status::unavailable(format!("{} instance {} not found", self.name, address)))?;
+        if !endpoint.breaker.try_acquire() {
+            return Err(Status::unavailable(format!("{} instance {} circuit open", self.name, address)));
+        }
+        self.invoke(&endpoint, &mut f).await
+    }
+
+    fn pick(&self, tried: &HashSet<String>) -> Option<Arc<PooledEndpoint>> {
+        let endpoints = self.endpoints.read().unwrap();
+        // 半开状态的实例可能拒绝许可，最多尝试全部实例
+        let mut exclude = tried.clone();
+        for _ in 0..endpoints.len() {
+            let index = self.balancer.pick(&endpoints, &exclude)?;
+            let endpoint = &endpoints[index];
+            if endpoint.breaker.try_acquire() {
+                return Some(endpoint.clone());
+            }
+            exclude.insert(endpoint.instance.address.clone());
+        }
+        None
+    }
+
+    async fn invoke<T, F, Fut>(&self, endpoint: &PooledEndpoint, f: &mut F) -> Result<T, Status>
+    where
+        F: FnMut(Channel) -> Fut,
+        Fut: Future<Output = Result<T, Status>>,
+    {
+        let result = match tokio::time::timeout(self.options.deadline, f(endpoint.channel.clone())).await {
+            Ok(result) => result,
+            Err(_) => Err(Status::deadline_exceeded(format!(
+                "{} call exceeded {:?}",
+                self.name, self.options.deadline
+            ))),
+        };
+
+        match &result {
+            Err(status) if is_instance_failure(status) => endpoint.breaker.on_failure(),
+            _ => endpoint.breaker.on_success(),
+        }
+        result
+    }
+}
+
+/// 只有实例本身的故障计入熔断，业务错误不计入
+fn is_instance_failure(status: &Status) -> bool {
+    matches!(
+        status.code(),
+        tonic::Code::Unavailable | tonic::Code::DeadlineExceeded | tonic::Code::Unknown
+    )
+}
@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use serde::Deserialize;
+
+use crate::config::ConsulConfig;
+
+/// 服务实例
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ServiceInstance {
+    /// 实例ID
+    pub id: String,
+    /// `host:port`
+    pub address: String,
+    /// 负载均衡权重
+    pub weight: u32,
+}
+
+/// 服务发现
+#[async_trait]
+pub trait ServiceDiscovery: Send + Sync {
+    /// 解析服务名为健康实例列表
+    async fn resolve(&self, service: &str) -> Result<Vec<ServiceInstance>>;
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct HealthEntry {
+    service: AgentService,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct AgentService {
+    #[serde(rename = "ID")]
+    id: String,
+    address: String,
+    port: u16,
+    #[serde(default)]
+    meta: Option<HashMap<String, String>>,
+    #[serde(default)]
+    weights: Option<Weights>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Weights {
+    passing: u32,
+}
+
+/// 基于 Consul 健康检查的服务发现，只返回检查通过的实例
+pub struct ConsulDiscovery {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl ConsulDiscovery {
+    pub fn new(config: &ConsulConfig) -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(3))
+                .build()
+                .unwrap_or_default(),
+            base_url: format!("http://{}:{}", config.host, config.port),
+        }
+    }
+}
+
+#[async_trait]
+impl ServiceDiscovery for ConsulDiscovery {
+    async fn resolve(&self, service: &str) -> Result<Vec<ServiceInstance>> {
+        let url = format!("{}/v1/health/service/{}?passing=true", self.base_url, service);
+        let response = self.client.get(&url).send().await?;
+        if !response.status().is_success() {
+            return Err(anyhow!("consul resolve {} failed: {}", service, response.status()));
+        }
+
+        let entries: Vec<HealthEntry> = response.json().await?;
+        Ok(entries
+            .into_iter()
+            .map(|entry| {
+                let service = entry.service;
+                // 权重优先取注册元数据，其次取 Consul Weights
+                let weight = service
+                    .meta
+                    .as_ref()
+                    .and_then(|meta| meta.get("weight"))
+                    .and_then(|w| w.parse().ok())
+                    .or_else(|| service.weights.as_ref().map(|w| w.passing))
+                    .unwrap_or(1)
+                    .max(1);
+                ServiceInstance {
+                    id: service.id,
+                    address: format!("{}:{}", service.address, service.port),
+                    weight,
+                }
+            })
+            .collect())
+    }
+}
+
+/// 静态服务列表，用于本地开发与测试
+#[derive(Default)]
+pub struct StaticDiscovery {
+    services: HashMap<String, Vec<ServiceInstance>>,
+}
+
+impl StaticDiscovery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 添加服务实例
+    pub fn with_instance(mut self, service: &str, address: &str, weight: u32) -> Self {
+        self.services
+            .entry(service.to_string())
+            .or_default()
+            .push(ServiceInstance {
+                id: format!("{}-{}", service, address),
+                address: address.to_string(),
+                weight: weight.max(1),
+            });
+        self
+    }
+}
+
+#[async_trait]
+impl ServiceDiscovery for StaticDiscovery {
+    async fn resolve(&self, service: &str) -> Result<Vec<ServiceInstance>> {
+        Ok(self.services.get(service).cloned().unwrap_or_default())
+    }
+}
@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+use anyhow::Result;
+use log::{info, warn};
+use tokio::sync::Mutex;
+
+use super::balancer::LoadBalancePolicy;
+use super::breaker::CircuitBreakerConfig;
+use super::channel::ServiceChannel;
+use super::discovery::ServiceDiscovery;
+use super::retry::RetryPolicy;
+
+/// 客户端选项
+#[derive(Debug, Clone)]
+pub struct ClientOptions {
+    /// 负载均衡策略
+    pub policy: LoadBalancePolicy,
+    /// 单次调用超时
+    pub deadline: Duration,
+    /// 建连超时
+    pub connect_timeout: Duration,
+    /// TCP / HTTP2 保活间隔
+    pub keepalive: Duration,
+    /// 服务发现刷新间隔
+    pub refresh_interval: Duration,
+    /// 熔断配置
+    pub breaker: CircuitBreakerConfig,
+    /// 重试策略
+    pub retry: RetryPolicy,
+}
+
+impl Default for ClientOptions {
+    fn default() -> Self {
+        Self {
+            policy: LoadBalancePolicy::RoundRobin,
+            deadline: Duration::from_secs(3),
+            connect_timeout: Duration::from_secs(1),
+            keepalive: Duration::from_secs(30),
+            refresh_interval: Duration::from_secs(10),
+            breaker: CircuitBreakerConfig::default(),
+            retry: RetryPolicy::default(),
+        }
+    }
+}
+
+/// gRPC 客户端工厂
+///
+/// 同一服务名共享一个 [`ServiceChannel`]，首次获取时解析实例并启动后台刷新。
+///
+/// ```ignore
+/// let store = factory.service(ServiceNames::MESSAGE_STORE).await?;
+/// let response = store
+///     .call(|channel| {
+///         let request = request.clone();
+///         async move {
+///             MessageStoreClient::with_interceptor(channel, context_interceptor)
+///                 .store_message(request)
+///                 .await
+///         }
+///     })
+///     .await?;
+/// ```
+pub struct GrpcClientFactory {
+    discovery: Arc<dyn ServiceDiscovery>,
+    default_options: ClientOptions,
+    options: HashMap<String, ClientOptions>,
+    services: Mutex<HashMap<String, Arc<ServiceChannel>>>,
+}
+
+impl GrpcClientFactory {
+    pub fn new(discovery: Arc<dyn ServiceDiscovery>, default_options: ClientOptions) -> Self {
+        Self {
+            discovery,
+            default_options,
+            options: HashMap::new(),
+            services: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 为指定服务设置独立选项
+    pub fn with_service_options(mut self, service: &str, options: ClientOptions) -> Self {
+        self.options.insert(service.to_string(), options);
+        self
+    }
+
+    /// 获取服务通道
+    pub async fn service(&self, name: &str) -> Result<Arc<ServiceChannel>> {
+        let mut services = self.services.lock().await;
+        if let Some(channel) = services.get(name) {
+            return Ok(channel.clone());
+        }
+
+        let options = self
+            .options
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| self.default_options.clone());
+        let refresh_interval = options.refresh_interval;
+        let channel = Arc::new(ServiceChannel::new(name, options));
+
+        // 首次解析失败不阻止创建，后台刷新会继续尝试
+        match self.discovery.resolve(name).await {
+            Ok(instances) => {
+                info!("Resolved {} instances of {}", instances.len(), name);
+                channel.update(instances)?;
+            }
+            Err(e) => warn!("Failed to resolve {}: {}", name, e),
+        }
+
+        Self::spawn_refresh(self.discovery.clone(), Arc::downgrade(&channel), refresh_interval);
+        services.insert(name.to_string(), channel.clone());
+        Ok(channel)
+    }
+
+    fn spawn_refresh(discovery: Arc<dyn ServiceDiscovery>, channel: Weak<ServiceChannel>, interval: Duration) {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                let Some(channel) = channel.upgrade() else {
+                    break;
+                };
+                // 服务发现暂时不可用时保留现有实例
+                match discovery.resolve(channel.name()).await {
+                    Ok(instances) => {
+                        if let Err(e) = channel.update(instances) {
+                            warn!("Failed to update {} instances: {}", channel.name(), e);
+                        }
+                    }
+                    Err(e) => warn!("Failed to refresh {}: {}", channel.name(), e),
+                }
+            }
+        });
+    }
+}
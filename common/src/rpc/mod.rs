@@ -0,0 +1,47 @@
+//! 服务间 gRPC 调用
+//!
+//! 通过 Consul 解析服务名，维护实例连接池，按轮询或权重选择健康实例，
+//! 并为每次调用提供超时、重试预算与实例级熔断。
+
+mod balancer;
+mod breaker;
+mod channel;
+mod discovery;
+mod factory;
+mod retry;
+
+use tonic::{Request, Status};
+
+pub use balancer::LoadBalancePolicy;
+pub use breaker::{BreakerState, CircuitBreaker, CircuitBreakerConfig};
+pub use channel::ServiceChannel;
+pub use discovery::{ConsulDiscovery, ServiceDiscovery, ServiceInstance, StaticDiscovery};
+pub use factory::{ClientOptions, GrpcClientFactory};
+pub use retry::{RetryBudget, RetryPolicy};
+
+/// 服务注册名
+pub struct ServiceNames;
+
+impl ServiceNames {
+    /// 消息存储服务
+    pub const MESSAGE_STORE: &'static str = "message_store";
+
+    /// 消息过滤服务
+    pub const MESSAGE_FILTER: &'static str = "message-filter";
+
+    /// 消息网关
+    pub const MESSAGE_GATEWAY: &'static str = "message-gateway";
+
+    /// 会话服务
+    pub const SESSION: &'static str = "session";
+
+    /// 群组服务
+    pub const GROUP: &'static str = "group";
+}
+
+/// 服务间调用的客户端拦截器，传递链路上下文与租户
+#[allow(clippy::result_large_err)]
+pub fn context_interceptor(request: Request<()>) -> Result<Request<()>, Status> {
+    let request = crate::telemetry::client_interceptor(request)?;
+    crate::tenant::tenant_client_interceptor(request)
+}
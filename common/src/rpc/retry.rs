@@ -0,0 +1,127 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use tonic::Code;
+
+/// 重试策略
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// 最大尝试次数（含首次）
+    pub max_attempts: u32,
+    /// 重试基础退避
+    pub backoff: Duration,
+    /// 重试预算：每个正常请求为预算存入的比例
+    pub budget_ratio: f64,
+    /// 预算的最低保障，每秒允许的重试次数
+    pub min_retries_per_sec: u32,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            backoff: Duration::from_millis(50),
+            budget_ratio: 0.2,
+            min_retries_per_sec: 10,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// 可安全重试的错误：请求未被对端处理
+    pub fn is_retryable(code: Code) -> bool {
+        matches!(code, Code::Unavailable | Code::ResourceExhausted | Code::Aborted)
+    }
+}
+
+/// 重试预算
+///
+/// 限制重试请求占总请求的比例，避免下游故障时重试放大流量。每个请求存入
+/// `budget_ratio` 个令牌，每次重试取出一个；另外每秒补充 `min_retries_per_sec`
+/// 个令牌，保证低流量时仍可重试。
+#[derive(Debug)]
+pub struct RetryBudget {
+    ratio: f64,
+    min_per_sec: f64,
+    inner: Mutex<(f64, Instant)>,
+}
+
+impl RetryBudget {
+    pub fn new(policy: &RetryPolicy) -> Self {
+        let min_per_sec = policy.min_retries_per_sec as f64;
+        Self {
+            ratio: policy.budget_ratio,
+            min_per_sec,
+            inner: Mutex::new((min_per_sec, Instant::now())),
+        }
+    }
+
+    fn capacity(&self) -> f64 {
+        // 预算上限为 10 秒的保障额度
+        (self.min_per_sec * 10.0).max(1.0)
+    }
+
+    /// 记录一次请求
+    pub fn deposit(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.0 = (inner.0 + self.ratio).min(self.capacity());
+    }
+
+    /// 申请一次重试
+    pub fn withdraw(&self) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        let elapsed = inner.1.elapsed().as_secs_f64();
+        inner.1 = Instant::now();
+        inner.0 = (inner.0 + elapsed * self.min_per_sec).min(self.capacity());
+
+        if inner.0 >= 1.0 {
+            inner.0 -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn budget(ratio: f64, min_retries_per_sec: u32) -> RetryBudget {
+        RetryBudget::new(&RetryPolicy {
+            budget_ratio: ratio,
+            min_retries_per_sec,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_retries_limited_by_deposits() {
+        let budget = budget(0.5, 0);
+        assert!(!budget.withdraw());
+
+        budget.deposit();
+        assert!(!budget.withdraw());
+        budget.deposit();
+        assert!(budget.withdraw());
+        assert!(!budget.withdraw());
+    }
+
+    #[test]
+    fn test_min_retries_available_without_traffic() {
+        let budget = budget(0.2, 2);
+        assert!(budget.withdraw());
+        assert!(budget.withdraw());
+        assert!(!budget.withdraw());
+    }
+
+    #[test]
+    fn test_deposits_capped() {
+        let budget = budget(1.0, 0);
+        for _ in 0..5 {
+            budget.deposit();
+        }
+        assert!(budget.withdraw());
+        assert!(!budget.withdraw());
+    }
+}
@@ -0,0 +1,204 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context as TaskContext, Poll};
+
+use opentelemetry::propagation::{Extractor, Injector};
+use opentelemetry::{global, Context};
+use tonic::metadata::{KeyRef, MetadataKey, MetadataMap, MetadataValue};
+use tonic::server::NamedService;
+use tonic::service::Interceptor;
+use tonic::{Request, Status};
+use tower::{Layer, Service};
+use tracing::Instrument;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+struct MetadataInjector<'a>(&'a mut MetadataMap);
+
+impl Injector for MetadataInjector<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        if let (Ok(key), Ok(value)) = (
+            MetadataKey::from_bytes(key.as_bytes()),
+            MetadataValue::try_from(&value),
+        ) {
+            self.0.insert(key, value);
+        }
+    }
+}
+
+struct MetadataExtractor<'a>(&'a MetadataMap);
+
+impl Extractor for MetadataExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|v| v.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0
+            .keys()
+            .map(|key| match key {
+                KeyRef::Ascii(k) => k.as_str(),
+                KeyRef::Binary(k) => k.as_str(),
+            })
+            .collect()
+    }
+}
+
+struct HeaderExtractor<'a>(&'a http::HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|v| v.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|key| key.as_str()).collect()
+    }
+}
+
+/// gRPC 客户端拦截器
+///
+/// 将当前 span 的上下文写入请求 metadata (traceparent/tracestate)。
+#[allow(clippy::result_large_err)]
+pub fn client_interceptor(mut request: Request<()>) -> Result<Request<()>, Status> {
+    let context = tracing::Span::current().context();
+    global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(&context, &mut MetadataInjector(request.metadata_mut()))
+    });
+    Ok(request)
+}
+
+/// gRPC 服务端拦截器
+///
+/// 从请求 metadata 中解析上游上下文，并放入请求扩展，供 [`set_request_parent`] 使用。
+/// 服务端 span 的父上下文由 [`TraceContextLayer`] 统一设置，处理方法无需再调用。
+#[allow(clippy::result_large_err)]
+pub fn server_interceptor(mut request: Request<()>) -> Result<Request<()>, Status> {
+    let context = global::get_text_map_propagator(|propagator| {
+        propagator.extract(&MetadataExtractor(request.metadata()))
+    });
+    request.extensions_mut().insert(context);
+    Ok(request)
+}
+
+/// 将当前 span 的父上下文设置为上游调用方
+///
+/// 用于未挂载 [`TraceContextLayer`] 的服务端，父上下文取自 [`server_interceptor`] 解析结果。
+pub fn set_request_parent<T>(request: &Request<T>) {
+    if let Some(context) = request.extensions().get::<Context>() {
+        tracing::Span::current().set_parent(context.clone());
+    }
+}
+
+/// 可用于 `with_interceptor` 的客户端拦截器类型
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TraceInterceptor;
+
+impl Interceptor for TraceInterceptor {
+    fn call(&mut self, request: Request<()>) -> Result<Request<()>, Status> {
+        client_interceptor(request)
+    }
+}
+
+/// 服务端链路上下文中间件
+///
+/// 从请求头解析上游上下文，为每个 gRPC 请求创建以上游调用方为父节点的服务端 span，
+/// 处理方法中 `#[instrument]` 创建的 span 都挂在该 span 下。
+/// 通过 `Server::builder().layer(TraceContextLayer)` 挂载，对所有 gRPC 服务生效。
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TraceContextLayer;
+
+impl<S> Layer<S> for TraceContextLayer {
+    type Service = TraceContextService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        TraceContextService { inner }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TraceContextService<S> {
+    inner: S,
+}
+
+impl<S: NamedService> NamedService for TraceContextService<S> {
+    const NAME: &'static str = S::NAME;
+}
+
+impl<S, B> Service<http::Request<B>> for TraceContextService<S>
+where
+    S: Service<http::Request<B>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<S::Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut TaskContext<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        let parent = global::get_text_map_propagator(|propagator| {
+            propagator.extract(&HeaderExtractor(request.headers()))
+        });
+        let span = tracing::info_span!(
+            "grpc.server",
+            otel.name = %request.uri().path(),
+            otel.kind = "server",
+        );
+        span.set_parent(parent);
+
+        let future = span.in_scope(|| self.inner.call(request));
+        Box::pin(future.instrument(span))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use opentelemetry::trace::{TraceContextExt, TracerProvider as _};
+    use opentelemetry_sdk::propagation::TraceContextPropagator;
+    use opentelemetry_sdk::trace::TracerProvider;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+
+    /// 返回处理请求时所在链路的 trace id
+    #[derive(Clone)]
+    struct EchoTraceId;
+
+    impl Service<http::Request<()>> for EchoTraceId {
+        type Response = String;
+        type Error = Infallible;
+        type Future = Pin<Box<dyn Future<Output = Result<String, Infallible>> + Send>>;
+
+        fn poll_ready(&mut self, _cx: &mut TaskContext<'_>) -> Poll<Result<(), Infallible>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _request: http::Request<()>) -> Self::Future {
+            Box::pin(async {
+                let context = tracing::Span::current().context();
+                Ok(context.span().span_context().trace_id().to_string())
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_layer_continues_upstream_trace() {
+        global::set_text_map_propagator(TraceContextPropagator::new());
+        let provider = TracerProvider::builder().build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("trace-layer-test")));
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let mut request = http::Request::new(());
+        request.headers_mut().insert(
+            "traceparent",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01".parse().unwrap(),
+        );
+        let trace_id = TraceContextLayer.layer(EchoTraceId).call(request).await.unwrap();
+        assert_eq!(trace_id, "4bf92f3577b34da6a3ce929d0e0e4736");
+    }
+}
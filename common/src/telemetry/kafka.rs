@@ -0,0 +1,48 @@
+use std::collections::HashMap;
+
+use opentelemetry::{global, Context};
+use rdkafka::message::{Header, Headers, OwnedHeaders};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+/// 将当前 span 的上下文写入消息头集合
+pub fn inject_context_map(carrier: &mut HashMap<String, String>) {
+    let context = tracing::Span::current().context();
+    global::get_text_map_propagator(|propagator| propagator.inject_context(&context, carrier));
+}
+
+/// 从消息头集合解析上游上下文
+pub fn extract_context_map(carrier: &HashMap<String, String>) -> Context {
+    global::get_text_map_propagator(|propagator| propagator.extract(carrier))
+}
+
+/// 将当前 span 的上下文写入 Kafka 消息头
+pub fn inject_kafka_headers(headers: OwnedHeaders) -> OwnedHeaders {
+    let mut carrier: HashMap<String, String> = HashMap::new();
+    inject_context_map(&mut carrier);
+
+    carrier.iter().fold(headers, |headers, (key, value)| {
+        headers.insert(Header {
+            key: key.as_str(),
+            value: Some(value.as_bytes()),
+        })
+    })
+}
+
+/// 从 Kafka 消息头解析上游上下文
+///
+/// 没有消息头或不含追踪信息时返回空上下文，调用方的 span 会成为新的根。
+pub fn extract_kafka_context<H: Headers>(headers: Option<&H>) -> Context {
+    let carrier: HashMap<String, String> = headers
+        .map(|headers| {
+            headers
+                .iter()
+                .filter_map(|header| {
+                    let value = std::str::from_utf8(header.value?).ok()?;
+                    Some((header.key.to_string(), value.to_string()))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    extract_context_map(&carrier)
+}
@@ -0,0 +1,200 @@
+//! 分布式链路追踪与指标
+//!
+//! 基于 OpenTelemetry 的上下文传播，覆盖 gRPC 调用和 Kafka 消息两类跨进程链路。
+//! 配置 OTLP 导出时同时注册全局 MeterProvider，业务代码通过 `opentelemetry::global::meter` 记录指标。
+
+mod grpc;
+mod kafka;
+
+pub use grpc::{
+    client_interceptor, server_interceptor, set_request_parent, TraceContextLayer, TraceContextService,
+    TraceInterceptor,
+};
+pub use kafka::{extract_context_map, extract_kafka_context, inject_context_map, inject_kafka_headers};
+
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry::{global, KeyValue};
+use opentelemetry_sdk::metrics::{PeriodicReader, SdkMeterProvider};
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::TracerProvider;
+use opentelemetry_sdk::{runtime, Resource};
+use serde::{Deserialize, Serialize};
+use log::warn;
+use tracing::Subscriber;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::EnvFilter;
+
+/// 追踪数据导出方式
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum TraceExporter {
+    /// 导出到 OTLP 端点 (如 otel-collector、Jaeger、Tempo)
+    Otlp {
+        /// gRPC 端点地址，例如 http://localhost:4317
+        endpoint: String,
+    },
+    /// 输出到标准输出，用于本地调试
+    #[default]
+    Stdout,
+    /// 不导出，仅做上下文传播
+    None,
+}
+
+/// 链路追踪配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TelemetryConfig {
+    /// 服务名称，作为 service.name 上报
+    pub service_name: String,
+    /// 导出方式
+    #[serde(default)]
+    pub exporter: TraceExporter,
+    /// 日志过滤规则 (EnvFilter 语法)
+    #[serde(default = "default_filter")]
+    pub filter: String,
+}
+
+fn default_filter() -> String {
+    "info".to_string()
+}
+
+impl TelemetryConfig {
+    /// 按环境变量构造服务的默认配置
+    ///
+    /// 设置了 `OTEL_EXPORTER_OTLP_ENDPOINT` 时导出到该端点，否则输出到标准输出。
+    pub fn from_env(service_name: impl Into<String>) -> Self {
+        Self {
+            service_name: service_name.into(),
+            exporter: match std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT") {
+                Ok(endpoint) if !endpoint.is_empty() => TraceExporter::Otlp { endpoint },
+                _ => TraceExporter::Stdout,
+            },
+            filter: default_filter(),
+        }
+    }
+}
+
+/// 链路追踪守卫，drop 时刷新并关闭导出器
+pub struct TelemetryGuard {
+    provider: Option<TracerProvider>,
+    meter_provider: Option<SdkMeterProvider>,
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        if let Some(provider) = self.provider.take() {
+            if let Err(e) = provider.shutdown() {
+                warn!("Failed to shutdown tracer provider: {}", e);
+            }
+        }
+        if let Some(provider) = self.meter_provider.take() {
+            if let Err(e) = provider.shutdown() {
+                warn!("Failed to shutdown meter provider: {}", e);
+            }
+        }
+    }
+}
+
+/// 初始化链路追踪
+///
+/// 注册 W3C TraceContext 传播器，并将 tracing span 通过 OpenTelemetry 导出；
+/// OTLP 导出时指标也导出到同一端点，其他方式下指标为空操作。
+/// 返回的守卫需要在 main 中持有到进程退出。
+///
+/// 全局 tracing subscriber 已被设置时（例如日志组件或测试已初始化）只记录警告，
+/// 传播器与 provider 仍然生效，不影响服务启动。
+pub fn init_telemetry(config: &TelemetryConfig) -> anyhow::Result<TelemetryGuard> {
+    global::set_text_map_propagator(TraceContextPropagator::new());
+
+    let resource = Resource::new(vec![KeyValue::new(
+        "service.name",
+        config.service_name.clone(),
+    )]);
+
+    let provider = match &config.exporter {
+        TraceExporter::Otlp { endpoint } => {
+            use opentelemetry_otlp::WithExportConfig;
+
+            let exporter = opentelemetry_otlp::SpanExporter::builder()
+                .with_tonic()
+                .with_endpoint(endpoint.clone())
+                .build()?;
+            Some(
+                TracerProvider::builder()
+                    .with_batch_exporter(exporter, runtime::Tokio)
+                    .with_resource(resource.clone())
+                    .build(),
+            )
+        }
+        TraceExporter::Stdout => Some(
+            TracerProvider::builder()
+                .with_simple_exporter(opentelemetry_stdout::SpanExporter::default())
+                .with_resource(resource.clone())
+                .build(),
+        ),
+        TraceExporter::None => None,
+    };
+
+    let meter_provider = match &config.exporter {
+        TraceExporter::Otlp { endpoint } => {
+            use opentelemetry_otlp::WithExportConfig;
+
+            let exporter = opentelemetry_otlp::MetricExporter::builder()
+                .with_tonic()
+                .with_endpoint(endpoint.clone())
+                .build()?;
+            let provider = SdkMeterProvider::builder()
+                .with_reader(PeriodicReader::builder(exporter, runtime::Tokio).build())
+                .with_resource(resource)
+                .build();
+            global::set_meter_provider(provider.clone());
+            Some(provider)
+        }
+        _ => None,
+    };
+
+    let filter = EnvFilter::try_new(&config.filter).unwrap_or_else(|_| EnvFilter::new("info"));
+
+    match &provider {
+        Some(provider) => {
+            let tracer = provider.tracer(config.service_name.clone());
+            global::set_tracer_provider(provider.clone());
+            install_subscriber(
+                tracing_subscriber::registry()
+                    .with(filter)
+                    .with(tracing_opentelemetry::layer().with_tracer(tracer)),
+            );
+        }
+        None => install_subscriber(tracing_subscriber::registry().with(filter)),
+    }
+
+    Ok(TelemetryGuard {
+        provider,
+        meter_provider,
+    })
+}
+
+/// 设置全局 subscriber，不接管 `log` 门面，避免与已初始化的日志组件冲突
+fn install_subscriber<S>(subscriber: S)
+where
+    S: Subscriber + Send + Sync + 'static,
+{
+    if let Err(e) = tracing::subscriber::set_global_default(subscriber) {
+        warn!("Tracing subscriber is already set, telemetry layer is not installed: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_init_twice_does_not_fail() {
+        let config = TelemetryConfig {
+            service_name: "telemetry-test".to_string(),
+            exporter: TraceExporter::None,
+            filter: "info".to_string(),
+        };
+        let _first = init_telemetry(&config).unwrap();
+        let _second = init_telemetry(&config).unwrap();
+    }
+}
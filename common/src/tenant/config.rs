@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::context::TenantContext;
+use crate::config::Config;
+
+/// 租户生效配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TenantSettings {
+    /// 每用户每分钟发送上限
+    pub rate_limit_per_minute: u32,
+    /// 群聊每日消息上限
+    pub group_daily_limit: u32,
+    /// 单聊每日消息上限
+    pub private_daily_limit: u32,
+    /// 每个群每日 @所有人 上限
+    #[serde(default = "default_at_all_daily_limit")]
+    pub at_all_daily_limit: u32,
+    /// 消息内容最大字节数
+    pub max_content_size: usize,
+    /// 附件最大字节数
+    pub max_attachment_size: i64,
+    /// 关闭的预处理检查项
+    pub disabled_checks: Vec<String>,
+    /// 扩展配置
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+impl Default for TenantSettings {
+    fn default() -> Self {
+        Self {
+            rate_limit_per_minute: 10,
+            group_daily_limit: 1000,
+            private_daily_limit: 200,
+            at_all_daily_limit: default_at_all_daily_limit(),
+            max_content_size: 1024 * 1024,
+            max_attachment_size: 100 * 1024 * 1024,
+            disabled_checks: Vec::new(),
+            extra: HashMap::new(),
+        }
+    }
+}
+
+fn default_at_all_daily_limit() -> u32 {
+    10
+}
+
+/// 租户覆盖配置，未设置的项沿用默认值
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TenantOverride {
+    #[serde(default)]
+    pub rate_limit_per_minute: Option<u32>,
+    #[serde(default)]
+    pub group_daily_limit: Option<u32>,
+    #[serde(default)]
+    pub private_daily_limit: Option<u32>,
+    #[serde(default)]
+    pub at_all_daily_limit: Option<u32>,
+    #[serde(default)]
+    pub max_content_size: Option<usize>,
+    #[serde(default)]
+    pub max_attachment_size: Option<i64>,
+    #[serde(default)]
+    pub disabled_checks: Option<Vec<String>>,
+    #[serde(default)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+impl TenantOverride {
+    fn apply(&self, base: &TenantSettings) -> TenantSettings {
+        let mut settings = base.clone();
+        if let Some(v) = self.rate_limit_per_minute {
+            settings.rate_limit_per_minute = v;
+        }
+        if let Some(v) = self.group_daily_limit {
+            settings.group_daily_limit = v;
+        }
+        if let Some(v) = self.private_daily_limit {
+            settings.private_daily_limit = v;
+        }
+        if let Some(v) = self.at_all_daily_limit {
+            settings.at_all_daily_limit = v;
+        }
+        if let Some(v) = self.max_content_size {
+            settings.max_content_size = v;
+        }
+        if let Some(v) = self.max_attachment_size {
+            settings.max_attachment_size = v;
+        }
+        if let Some(v) = &self.disabled_checks {
+            settings.disabled_checks = v.clone();
+        }
+        settings.extra.extend(self.extra.clone());
+        settings
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct TenantsSection {
+    #[serde(default)]
+    defaults: Option<TenantSettings>,
+    #[serde(default)]
+    overrides: HashMap<String, TenantOverride>,
+}
+
+/// 租户配置注册表
+///
+/// 读取配置文件 `extensions.tenants`:
+///
+/// ```yaml
+/// extensions:
+///   tenants:
+///     defaults:
+///       rate_limit_per_minute: 10
+///       ...
+///     overrides:
+///       acme:
+///         rate_limit_per_minute: 60
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TenantConfigRegistry {
+    defaults: TenantSettings,
+    overrides: HashMap<String, TenantOverride>,
+}
+
+impl TenantConfigRegistry {
+    pub fn new(defaults: TenantSettings) -> Self {
+        Self {
+            defaults,
+            overrides: HashMap::new(),
+        }
+    }
+
+    /// 设置租户覆盖配置
+    pub fn with_override(mut self, tenant_id: impl Into<String>, override_: TenantOverride) -> Self {
+        self.overrides.insert(tenant_id.into(), override_);
+        self
+    }
+
+    /// 从应用配置加载
+    pub fn from_config(config: &Config) -> anyhow::Result<Self> {
+        let section: TenantsSection = match config.extensions.get("tenants") {
+            Some(value) => serde_json::from_value(value.clone())?,
+            None => TenantsSection::default(),
+        };
+        Ok(Self {
+            defaults: section.defaults.unwrap_or_default(),
+            overrides: section.overrides,
+        })
+    }
+
+    /// 获取租户生效配置
+    pub fn settings(&self, tenant: &TenantContext) -> TenantSettings {
+        match self.overrides.get(tenant.tenant_id()) {
+            Some(override_) => override_.apply(&self.defaults),
+            None => self.defaults.clone(),
+        }
+    }
+
+    /// 获取当前租户生效配置
+    pub fn current(&self) -> TenantSettings {
+        self.settings(&super::current())
+    }
+}
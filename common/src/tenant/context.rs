@@ -0,0 +1,239 @@
+use std::future::Future;
+
+use rdkafka::message::{Header, Headers, OwnedHeaders};
+use tonic::metadata::MetadataValue;
+use tonic::{Request, Status};
+
+/// 默认租户，单租户部署及历史数据使用
+pub const DEFAULT_TENANT: &str = "default";
+
+/// gRPC metadata 中的租户键
+pub const TENANT_METADATA_KEY: &str = "x-tenant-id";
+
+/// Kafka 消息头中的租户键
+pub const TENANT_KAFKA_HEADER: &str = "tenant-id";
+
+/// 租户解析错误
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum TenantError {
+    /// 租户ID不合法
+    #[error("invalid tenant id: {0}")]
+    InvalidTenant(String),
+    /// 租户头不是合法的 UTF-8/ASCII 字符串
+    #[error("invalid tenant metadata")]
+    InvalidMetadata,
+}
+
+impl From<TenantError> for Status {
+    fn from(error: TenantError) -> Self {
+        Status::invalid_argument(error.to_string())
+    }
+}
+
+tokio::task_local! {
+    static CURRENT_TENANT: TenantContext;
+}
+
+/// 租户上下文
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TenantContext {
+    tenant_id: String,
+}
+
+impl Default for TenantContext {
+    fn default() -> Self {
+        Self {
+            tenant_id: DEFAULT_TENANT.to_string(),
+        }
+    }
+}
+
+impl TenantContext {
+    /// 创建租户上下文，空租户ID视为默认租户
+    ///
+    /// 租户ID只允许小写字母、数字和 `-`，且以字母或数字开头。
+    /// 存储名由租户ID单射得到：Postgres 不区分未加引号标识符的大小写，
+    /// schema 名中的 `-` 替换为 `_`，集合名以 `_` 分隔租户与集合。
+    pub fn new(tenant_id: impl Into<String>) -> Result<Self, TenantError> {
+        let tenant_id = tenant_id.into();
+        if tenant_id.is_empty() {
+            return Ok(Self::default());
+        }
+        if tenant_id.len() > 64
+            || tenant_id.starts_with('-')
+            || !tenant_id
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        {
+            return Err(TenantError::InvalidTenant(tenant_id));
+        }
+        Ok(Self { tenant_id })
+    }
+
+    /// 租户ID
+    pub fn tenant_id(&self) -> &str {
+        &self.tenant_id
+    }
+
+    /// 是否默认租户
+    pub fn is_default(&self) -> bool {
+        self.tenant_id == DEFAULT_TENANT
+    }
+
+    /// Redis 键
+    ///
+    /// 默认租户沿用原有键，其他租户加 `t:{tenant}:` 前缀。
+    pub fn redis_key(&self, key: &str) -> String {
+        if self.is_default() {
+            key.to_string()
+        } else {
+            format!("t:{}:{}", self.tenant_id, key)
+        }
+    }
+
+    /// Mongo 集合名
+    pub fn collection_name(&self, name: &str) -> String {
+        if self.is_default() {
+            name.to_string()
+        } else {
+            format!("{}_{}", self.tenant_id, name)
+        }
+    }
+
+    /// Postgres schema，每个租户一个 schema
+    pub fn pg_schema(&self) -> String {
+        if self.is_default() {
+            "public".to_string()
+        } else {
+            format!("tenant_{}", self.tenant_id.replace('-', "_"))
+        }
+    }
+
+    /// 带 schema 的 Postgres 表名
+    pub fn pg_table(&self, table: &str) -> String {
+        format!("{}.{}", self.pg_schema(), table)
+    }
+
+    /// Kafka 消息键，保证不同租户的相同业务键不会落到同一有序流
+    pub fn kafka_key(&self, key: &str) -> String {
+        self.redis_key(key)
+    }
+
+    /// 从 gRPC 请求中解析租户
+    pub fn from_request<T>(request: &Request<T>) -> Result<Self, TenantError> {
+        match request.metadata().get(TENANT_METADATA_KEY) {
+            Some(value) => Self::new(value.to_str().map_err(|_| TenantError::InvalidMetadata)?),
+            None => Ok(Self::default()),
+        }
+    }
+}
+
+impl std::fmt::Display for TenantContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.tenant_id)
+    }
+}
+
+/// 在指定租户上下文中执行 future
+pub async fn scope<F: Future>(tenant: TenantContext, f: F) -> F::Output {
+    CURRENT_TENANT.scope(tenant, f).await
+}
+
+/// 当前租户，未设置时返回默认租户
+pub fn current() -> TenantContext {
+    try_current().unwrap_or_default()
+}
+
+/// 当前租户，未设置时返回 None
+pub fn try_current() -> Option<TenantContext> {
+    CURRENT_TENANT.try_with(|tenant| tenant.clone()).ok()
+}
+
+/// gRPC 客户端拦截器，将当前租户写入请求 metadata
+#[allow(clippy::result_large_err)]
+pub fn tenant_client_interceptor(mut request: Request<()>) -> Result<Request<()>, Status> {
+    let tenant = current();
+    let value = MetadataValue::try_from(tenant.tenant_id())
+        .map_err(|_| Status::internal("invalid tenant id"))?;
+    request.metadata_mut().insert(TENANT_METADATA_KEY, value);
+    Ok(request)
+}
+
+/// 将当前租户写入 Kafka 消息头
+pub fn inject_kafka_tenant(headers: OwnedHeaders) -> OwnedHeaders {
+    let tenant = current();
+    headers.insert(Header {
+        key: TENANT_KAFKA_HEADER,
+        value: Some(tenant.tenant_id().as_bytes()),
+    })
+}
+
+/// 从 Kafka 消息头解析租户，缺失时视为默认租户
+///
+/// 租户头非法时返回错误，与 [`TenantLayer`](super::TenantLayer) 一致不回落到默认租户，
+/// 由消费者转入死信。
+pub fn extract_kafka_tenant<H: Headers>(headers: Option<&H>) -> Result<TenantContext, TenantError> {
+    let value = headers.and_then(|headers| {
+        headers
+            .iter()
+            .find(|header| header.key == TENANT_KAFKA_HEADER)
+            .and_then(|header| header.value)
+    });
+    match value {
+        Some(value) => TenantContext::new(std::str::from_utf8(value).map_err(|_| TenantError::InvalidMetadata)?),
+        None => Ok(TenantContext::default()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_tenant_keeps_legacy_keys() {
+        let tenant = TenantContext::default();
+        assert_eq!(tenant.redis_key("session:1"), "session:1");
+        assert_eq!(tenant.collection_name("messages"), "messages");
+        assert_eq!(tenant.pg_table("filter_rules"), "public.filter_rules");
+    }
+
+    #[test]
+    fn test_tenant_keys_are_prefixed() {
+        let tenant = TenantContext::new("acme-1").unwrap();
+        assert_eq!(tenant.redis_key("session:1"), "t:acme-1:session:1");
+        assert_eq!(tenant.collection_name("messages"), "acme-1_messages");
+        assert_eq!(tenant.pg_table("filter_rules"), "tenant_acme_1.filter_rules");
+    }
+
+    #[test]
+    fn test_invalid_tenant_rejected() {
+        assert!(TenantContext::new("a:b").is_err());
+        // 与 `acme-1` 的 schema 和 `acme` 的集合名冲突
+        assert!(TenantContext::new("acme_1").is_err());
+        // 与 `acme` 的 schema 冲突
+        assert!(TenantContext::new("Acme").is_err());
+        assert!(TenantContext::new("-acme").is_err());
+        assert!(TenantContext::new("").unwrap().is_default());
+    }
+
+    #[test]
+    fn test_invalid_kafka_tenant_rejected() {
+        let headers = OwnedHeaders::new().insert(Header {
+            key: TENANT_KAFKA_HEADER,
+            value: Some("acme_1"),
+        });
+        assert_eq!(
+            extract_kafka_tenant(Some(&headers)),
+            Err(TenantError::InvalidTenant("acme_1".to_string()))
+        );
+        assert!(extract_kafka_tenant::<OwnedHeaders>(None).unwrap().is_default());
+    }
+
+    #[tokio::test]
+    async fn test_scope_sets_current_tenant() {
+        let tenant = TenantContext::new("acme").unwrap();
+        let seen = scope(tenant.clone(), async { current() }).await;
+        assert_eq!(seen, tenant);
+        assert!(current().is_default());
+    }
+}
@@ -0,0 +1,137 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tonic::body::BoxBody;
+use tonic::server::NamedService;
+use tonic::Status;
+use tower::{Layer, Service};
+
+use super::context::{scope, TenantContext, TenantError, TENANT_METADATA_KEY};
+
+/// 服务端租户中间件
+///
+/// 从请求头 `x-tenant-id` 解析租户，并在整个请求处理期间设置 task-local 租户上下文。
+/// 未携带租户头的请求使用默认租户，租户ID非法时直接返回 `INVALID_ARGUMENT`。
+/// 通过 `Server::builder().layer(TenantLayer)` 挂载，对所有 gRPC 服务生效；
+/// 也可用 `TenantLayer.layer(server)` 包装单个服务，由服务库自行保证租户隔离。
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TenantLayer;
+
+impl<S> Layer<S> for TenantLayer {
+    type Service = TenantService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        TenantService { inner }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TenantService<S> {
+    inner: S,
+}
+
+impl<S: NamedService> NamedService for TenantService<S> {
+    const NAME: &'static str = S::NAME;
+}
+
+impl<S> TenantService<S> {
+    /// 包装单个服务，服务库以此对外提供租户隔离的服务端
+    pub fn new(inner: S) -> Self {
+        Self { inner }
+    }
+
+    fn tenant<B>(request: &http::Request<B>) -> Result<TenantContext, TenantError> {
+        match request.headers().get(TENANT_METADATA_KEY) {
+            Some(value) => TenantContext::new(value.to_str().map_err(|_| TenantError::InvalidMetadata)?),
+            None => Ok(TenantContext::default()),
+        }
+    }
+}
+
+impl<S, B> Service<http::Request<B>> for TenantService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<S::Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        // 非法租户ID不能回落到默认租户，否则请求会读写默认租户的数据
+        let tenant = match Self::tenant(&request) {
+            Ok(tenant) => tenant,
+            Err(e) => {
+                let status = Status::from(e);
+                return Box::pin(async move { Ok(status.into_http()) });
+            }
+        };
+
+        let future = self.inner.call(request);
+        Box::pin(scope(tenant, future))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use tonic::Code;
+
+    use super::super::context::current;
+    use super::*;
+
+    /// 响应头中回显处理请求时的租户
+    #[derive(Clone)]
+    struct EchoTenant;
+
+    impl Service<http::Request<()>> for EchoTenant {
+        type Response = http::Response<BoxBody>;
+        type Error = Infallible;
+        type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Infallible>> + Send>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _request: http::Request<()>) -> Self::Future {
+            Box::pin(async {
+                let mut response = http::Response::new(tonic::body::empty_body());
+                response
+                    .headers_mut()
+                    .insert("x-seen-tenant", current().tenant_id().parse().unwrap());
+                Ok(response)
+            })
+        }
+    }
+
+    async fn call(tenant: Option<&str>) -> http::Response<BoxBody> {
+        let mut request = http::Request::new(());
+        if let Some(tenant) = tenant {
+            request.headers_mut().insert(TENANT_METADATA_KEY, tenant.parse().unwrap());
+        }
+        TenantLayer.layer(EchoTenant).call(request).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_request_runs_in_header_tenant() {
+        let response = call(Some("acme")).await;
+        assert_eq!(response.headers()["x-seen-tenant"], "acme");
+
+        let response = call(None).await;
+        assert_eq!(response.headers()["x-seen-tenant"], "default");
+    }
+
+    #[tokio::test]
+    async fn test_invalid_tenant_is_rejected() {
+        let response = call(Some("acme_1")).await;
+        assert!(response.headers().get("x-seen-tenant").is_none());
+        let status = Status::from_header_map(response.headers()).unwrap();
+        assert_eq!(status.code(), Code::InvalidArgument);
+    }
+}
@@ -0,0 +1,17 @@
+//! 多租户支持
+//!
+//! 租户上下文随 gRPC metadata (`x-tenant-id`) 与 Kafka 消息头 (`tenant-id`) 传递，
+//! 在服务内部通过 task-local 获取，仓储层据此生成租户隔离的存储键、集合名和表名。
+
+mod config;
+mod context;
+mod layer;
+mod schema;
+
+pub use config::{TenantConfigRegistry, TenantOverride, TenantSettings};
+pub use context::{
+    current, extract_kafka_tenant, inject_kafka_tenant, scope, tenant_client_interceptor,
+    try_current, TenantContext, TenantError, DEFAULT_TENANT, TENANT_KAFKA_HEADER, TENANT_METADATA_KEY,
+};
+pub use layer::{TenantLayer, TenantService};
+pub use schema::TenantSchemas;
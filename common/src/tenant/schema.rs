@@ -0,0 +1,76 @@
+use std::collections::HashSet;
+use std::future::Future;
+use std::sync::Mutex;
+
+use super::context::{current, TenantContext};
+
+/// 租户存储结构的初始化记录
+///
+/// 租户随请求出现，不能只在启动时为默认租户建表。仓储在访问存储前调用
+/// [`ensure`](Self::ensure)，每个租户首次访问时执行一次初始化，失败时下次访问重试。
+#[derive(Debug, Default)]
+pub struct TenantSchemas {
+    ready: Mutex<HashSet<TenantContext>>,
+    /// 同一时间只有一个初始化在执行，避免并发建表
+    init_lock: tokio::sync::Mutex<()>,
+}
+
+impl TenantSchemas {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 确保当前租户已初始化
+    pub async fn ensure<F, Fut, E>(&self, init: F) -> Result<(), E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<(), E>>,
+    {
+        let tenant = current();
+        if self.ready.lock().unwrap().contains(&tenant) {
+            return Ok(());
+        }
+
+        let _guard = self.init_lock.lock().await;
+        if self.ready.lock().unwrap().contains(&tenant) {
+            return Ok(());
+        }
+        init().await?;
+        self.ready.lock().unwrap().insert(tenant);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::super::context::scope;
+    use super::*;
+
+    #[tokio::test]
+    async fn test_init_once_per_tenant() {
+        let schemas = TenantSchemas::new();
+        let inits = AtomicUsize::new(0);
+        let init = || async {
+            inits.fetch_add(1, Ordering::SeqCst);
+            Ok::<_, ()>(())
+        };
+
+        schemas.ensure(init).await.unwrap();
+        schemas.ensure(init).await.unwrap();
+        assert_eq!(inits.load(Ordering::SeqCst), 1);
+
+        scope(TenantContext::new("acme").unwrap(), schemas.ensure(init)).await.unwrap();
+        assert_eq!(inits.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_failed_init_is_retried() {
+        let schemas = TenantSchemas::new();
+        assert!(schemas.ensure(|| async { Err::<(), _>("unavailable") }).await.is_err());
+        assert!(schemas.ensure(|| async { Ok::<_, &str>(()) }).await.is_ok());
+        // 已初始化，不再执行
+        assert!(schemas.ensure(|| async { Err::<(), _>("unavailable") }).await.is_ok());
+    }
+}
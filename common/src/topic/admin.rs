@@ -0,0 +1,260 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use rdkafka::admin::{
+    AdminClient, AdminOptions, AlterConfig, ConfigResource, ConfigSource, NewPartitions, NewTopic,
+    ResourceSpecifier, TopicReplication,
+};
+use rdkafka::client::DefaultClientContext;
+use rdkafka::error::RDKafkaErrorCode;
+use rdkafka::ClientConfig;
+use log::{info, warn};
+
+use super::registry::{TopicRegistry, TopicSpec};
+
+/// 主题初始化结果
+#[derive(Debug, Default, Clone)]
+pub struct TopicProvisionReport {
+    /// 新建的主题
+    pub created: Vec<String>,
+    /// 扩容分区的主题
+    pub expanded: Vec<String>,
+    /// 更新配置的主题
+    pub reconfigured: Vec<String>,
+    /// 无法自动修正的差异，例如分区数多于规格（分区不能缩容）或副本数不一致
+    pub mismatches: Vec<String>,
+}
+
+/// 主题初始化器
+///
+/// 服务启动时按 [`TopicRegistry`] 创建缺失主题、扩容分区并修正主题级配置。
+/// 多个服务同时执行是安全的，主题已存在的错误会被忽略。
+pub struct TopicProvisioner {
+    admin: Arc<AdminClient<DefaultClientContext>>,
+    timeout: Duration,
+}
+
+impl TopicProvisioner {
+    pub fn new(brokers: &str) -> Result<Self> {
+        let admin = ClientConfig::new()
+            .set("bootstrap.servers", brokers)
+            .create()?;
+        Ok(Self {
+            admin: Arc::new(admin),
+            timeout: Duration::from_secs(10),
+        })
+    }
+
+    /// 设置管理操作超时
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    fn options(&self) -> AdminOptions {
+        AdminOptions::new().operation_timeout(Some(self.timeout))
+    }
+
+    /// 应用注册表中的所有主题
+    pub async fn apply(&self, registry: &TopicRegistry) -> Result<TopicProvisionReport> {
+        let mut report = TopicProvisionReport::default();
+
+        let existing = self.existing_topics().await?;
+
+        let mut missing = Vec::new();
+        for spec in registry.topics() {
+            match existing.get(spec.name).copied() {
+                None => missing.push(spec),
+                Some((partitions, replicas)) => {
+                    if partitions < spec.partitions {
+                        self.expand_partitions(spec).await?;
+                        report.expanded.push(spec.name.to_string());
+                    } else if partitions > spec.partitions {
+                        report.mismatches.push(format!(
+                            "{}: has {} partitions, registry expects {}",
+                            spec.name, partitions, spec.partitions
+                        ));
+                    }
+                    if let Some(replicas) = replicas {
+                        if replicas != spec.replication_factor {
+                            report.mismatches.push(format!(
+                                "{}: has replication factor {}, registry expects {}",
+                                spec.name, replicas, spec.replication_factor
+                            ));
+                        }
+                    }
+                    if self.reconcile_configs(spec).await? {
+                        report.reconfigured.push(spec.name.to_string());
+                    }
+                }
+            }
+        }
+
+        if !missing.is_empty() {
+            report.created = self.create_topics(&missing).await?;
+        }
+
+        for mismatch in &report.mismatches {
+            warn!("Kafka topic mismatch: {}", mismatch);
+        }
+        info!(
+            "Kafka topics provisioned: created={:?}, expanded={:?}, reconfigured={:?}",
+            report.created, report.expanded, report.reconfigured
+        );
+        Ok(report)
+    }
+
+    /// 现有主题的分区数与副本数
+    ///
+    /// `fetch_metadata` 是阻塞调用，放到阻塞线程池执行，避免占用运行时线程
+    async fn existing_topics(&self) -> Result<HashMap<String, (i32, Option<i32>)>> {
+        let admin = self.admin.clone();
+        let timeout = self.timeout;
+        tokio::task::spawn_blocking(move || {
+            let metadata = admin.inner().fetch_metadata(None, timeout)?;
+            Ok(metadata
+                .topics()
+                .iter()
+                .filter(|t| t.error().is_none())
+                .map(|t| {
+                    let replicas = t.partitions().first().map(|p| p.replicas().len() as i32);
+                    (t.name().to_string(), (t.partitions().len() as i32, replicas))
+                })
+                .collect())
+        })
+        .await?
+    }
+
+    async fn create_topics(&self, specs: &[&TopicSpec]) -> Result<Vec<String>> {
+        let configs: Vec<_> = specs.iter().map(|spec| spec.configs()).collect();
+        let new_topics: Vec<_> = specs
+            .iter()
+            .zip(configs.iter())
+            .map(|(spec, configs)| {
+                configs.iter().fold(
+                    NewTopic::new(
+                        spec.name,
+                        spec.partitions,
+                        TopicReplication::Fixed(spec.replication_factor),
+                    ),
+                    |topic, (key, value)| topic.set(key, value),
+                )
+            })
+            .collect();
+
+        let mut created = Vec::new();
+        for result in self.admin.create_topics(&new_topics, &self.options()).await? {
+            match result {
+                Ok(name) => created.push(name),
+                // 其他实例已创建
+                Err((_, RDKafkaErrorCode::TopicAlreadyExists)) => {}
+                Err((name, code)) => {
+                    return Err(anyhow!("failed to create topic {}: {}", name, code));
+                }
+            }
+        }
+        Ok(created)
+    }
+
+    async fn expand_partitions(&self, spec: &TopicSpec) -> Result<()> {
+        let partitions = NewPartitions::new(spec.name, spec.partitions as usize);
+        for result in self.admin.create_partitions(&[partitions], &self.options()).await? {
+            if let Err((name, code)) = result {
+                if code != RDKafkaErrorCode::InvalidPartitions {
+                    return Err(anyhow!("failed to expand partitions of {}: {}", name, code));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 比较并修正主题级配置，返回是否有修改
+    async fn reconcile_configs(&self, spec: &TopicSpec) -> Result<bool> {
+        let expected = spec.configs();
+        let resource = ResourceSpecifier::Topic(spec.name);
+
+        let described = self.admin.describe_configs([&resource], &self.options()).await?;
+        let current = match described.into_iter().next() {
+            Some(Ok(current)) => current,
+            Some(Err(code)) => return Err(anyhow!("failed to describe topic {}: {}", spec.name, code)),
+            None => return Ok(false),
+        };
+
+        let changed = expected.iter().any(|(key, value)| {
+            current.get(key).and_then(|entry| entry.value.as_deref()) != Some(value.as_str())
+        });
+        if !changed {
+            return Ok(false);
+        }
+
+        // AlterConfigs 会整体替换主题的动态配置，未受管的已有配置需要一并提交才能保留
+        let merged = merge_configs(&current, &expected);
+        let alter = merged
+            .iter()
+            .fold(AlterConfig::new(resource), |alter, (key, value)| alter.set(key, value));
+        for result in self.admin.alter_configs(&[alter], &self.options()).await? {
+            if let Err((_, code)) = result {
+                return Err(anyhow!("failed to alter configs of {}: {}", spec.name, code));
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// 合并主题上已有的动态配置与受管配置，受管配置优先
+///
+/// 敏感配置读取不到明文，无法原样回写，因此跳过。
+fn merge_configs(current: &ConfigResource, expected: &BTreeMap<&'static str, String>) -> BTreeMap<String, String> {
+    let mut merged: BTreeMap<String, String> = current
+        .entries
+        .iter()
+        .filter(|entry| entry.source == ConfigSource::DynamicTopic && !entry.is_sensitive)
+        .filter_map(|entry| entry.value.clone().map(|value| (entry.name.clone(), value)))
+        .collect();
+    for (key, value) in expected {
+        merged.insert(key.to_string(), value.clone());
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use rdkafka::admin::{ConfigEntry, OwnedResourceSpecifier};
+
+    use super::*;
+
+    fn entry(name: &str, value: &str, source: ConfigSource) -> ConfigEntry {
+        let is_default = source == ConfigSource::Default;
+        ConfigEntry {
+            name: name.to_string(),
+            value: Some(value.to_string()),
+            source,
+            is_read_only: false,
+            is_default,
+            is_sensitive: false,
+        }
+    }
+
+    #[test]
+    fn test_merge_keeps_unmanaged_dynamic_configs() {
+        let current = ConfigResource {
+            specifier: OwnedResourceSpecifier::Topic("im-messages".to_string()),
+            entries: vec![
+                entry("retention.ms", "1000", ConfigSource::DynamicTopic),
+                entry("segment.ms", "60000", ConfigSource::DynamicTopic),
+                entry("compression.type", "producer", ConfigSource::Default),
+                entry("log.retention.hours", "168", ConfigSource::StaticBroker),
+            ],
+        };
+        let mut expected = BTreeMap::new();
+        expected.insert("retention.ms", "86400000".to_string());
+
+        let merged = merge_configs(&current, &expected);
+        assert_eq!(merged.get("retention.ms").map(String::as_str), Some("86400000"));
+        assert_eq!(merged.get("segment.ms").map(String::as_str), Some("60000"));
+        assert!(!merged.contains_key("compression.type"));
+        assert!(!merged.contains_key("log.retention.hours"));
+    }
+}
@@ -0,0 +1,141 @@
+use serde_yaml::Value;
+
+use super::registry::TopicRegistry;
+
+/// 开发环境 compose 配置与主题注册表的差异
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComposeMismatch {
+    /// compose 服务名
+    pub service: String,
+    /// 差异说明
+    pub reason: String,
+}
+
+impl std::fmt::Display for ComposeMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.service, self.reason)
+    }
+}
+
+/// 检查 docker compose 中的 Kafka 配置是否满足主题注册表
+///
+/// - broker 数量不少于注册表要求的副本数
+/// - 关闭自动建主题，避免服务先于初始化写入时以 broker 默认参数建出主题
+/// - `min.insync.replicas` 不大于副本数
+/// - `message.max.bytes` 不小于主题允许的最大消息
+pub fn check_compose(compose_yaml: &str, registry: &TopicRegistry) -> anyhow::Result<Vec<ComposeMismatch>> {
+    let compose: Value = serde_yaml::from_str(compose_yaml)?;
+    let services = compose
+        .get("services")
+        .and_then(Value::as_mapping)
+        .ok_or_else(|| anyhow::anyhow!("compose file has no services"))?;
+
+    let brokers: Vec<(String, &Value)> = services
+        .iter()
+        .filter_map(|(name, service)| {
+            let env = service.get("environment")?;
+            let roles = env_value(env, "KAFKA_PROCESS_ROLES")?;
+            roles
+                .contains("broker")
+                .then(|| (name.as_str().unwrap_or_default().to_string(), env))
+        })
+        .collect();
+
+    let mut mismatches = Vec::new();
+    if brokers.is_empty() {
+        mismatches.push(ComposeMismatch {
+            service: "kafka".to_string(),
+            reason: "no kafka broker found".to_string(),
+        });
+        return Ok(mismatches);
+    }
+
+    let replication_factor = registry.max_replication_factor();
+    if (brokers.len() as i32) < replication_factor {
+        mismatches.push(ComposeMismatch {
+            service: brokers[0].0.clone(),
+            reason: format!(
+                "{} broker(s) cannot host replication factor {}",
+                brokers.len(),
+                replication_factor
+            ),
+        });
+    }
+
+    let max_message_bytes = registry
+        .topics()
+        .iter()
+        .map(|t| t.max_message_bytes)
+        .max()
+        .unwrap_or_default();
+
+    for (service, env) in &brokers {
+        let mut mismatch = |reason: String| {
+            mismatches.push(ComposeMismatch {
+                service: service.clone(),
+                reason,
+            })
+        };
+
+        if env_value(env, "KAFKA_AUTO_CREATE_TOPICS_ENABLE").as_deref() != Some("false") {
+            mismatch("KAFKA_AUTO_CREATE_TOPICS_ENABLE must be false".to_string());
+        }
+
+        if let Some(min_isr) = env_value(env, "KAFKA_MIN_INSYNC_REPLICAS").and_then(|v| v.parse::<i32>().ok()) {
+            if min_isr > replication_factor {
+                mismatch(format!(
+                    "KAFKA_MIN_INSYNC_REPLICAS {} exceeds replication factor {}",
+                    min_isr, replication_factor
+                ));
+            }
+        }
+
+        if let Some(bytes) = env_value(env, "KAFKA_MESSAGE_MAX_BYTES").and_then(|v| v.parse::<usize>().ok()) {
+            if bytes < max_message_bytes {
+                mismatch(format!(
+                    "KAFKA_MESSAGE_MAX_BYTES {} is below topic max.message.bytes {}",
+                    bytes, max_message_bytes
+                ));
+            }
+        }
+    }
+
+    Ok(mismatches)
+}
+
+/// 读取环境变量，兼容 map 与 `KEY=VALUE` 列表两种写法
+fn env_value(env: &Value, key: &str) -> Option<String> {
+    match env {
+        Value::Mapping(map) => map.get(key).map(|v| match v {
+            Value::String(s) => s.clone(),
+            Value::Bool(b) => b.to_string(),
+            Value::Number(n) => n.to_string(),
+            _ => String::new(),
+        }),
+        Value::Sequence(items) => items.iter().filter_map(Value::as_str).find_map(|item| {
+            item.split_once('=')
+                .filter(|(k, _)| *k == key)
+                .map(|(_, v)| v.to_string())
+        }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dev_compose_matches_registry() {
+        let compose = include_str!("../../../deploy/docker-compose.yaml");
+        let mismatches = check_compose(compose, &TopicRegistry::default()).unwrap();
+        assert!(mismatches.is_empty(), "{:?}", mismatches);
+    }
+
+    #[test]
+    fn test_detects_replication_mismatch() {
+        let compose = include_str!("../../../deploy/docker-compose.yaml");
+        let mismatches = check_compose(compose, &TopicRegistry::standard(3)).unwrap();
+        assert!(mismatches.iter().any(|m| m.reason.contains("replication factor 3")));
+    }
+}
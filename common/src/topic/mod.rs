@@ -0,0 +1,116 @@
+//! Kafka 主题定义与管理
+//!
+//! [`KafkaTopics`] 为主题名常量，[`TopicRegistry`] 描述每个主题的分区数、保留时长、
+//! 清理策略与消息键语义，服务启动时由 [`TopicProvisioner`] 通过 AdminClient 应用到集群。
+
+mod admin;
+mod compose;
+mod registry;
+
+pub use admin::{TopicProvisionReport, TopicProvisioner};
+pub use compose::{check_compose, ComposeMismatch};
+pub use registry::{CleanupPolicy, KeySemantics, TopicRegistry, TopicSpec};
+
+use proto_crate::api::im::common::MessagePriority;
+
+/// Kafka 主题常量定义
+pub struct KafkaTopics;
+
+impl KafkaTopics {
+    /// 消息存储主题
+    pub const MESSAGE_STORE: &'static str = "message_store";
+
+    /// 已存储消息事件主题，由存储服务在消息落库后写入
+    pub const MESSAGE_STORED: &'static str = "message_stored";
+
+    /// 消息分发主题，同时作为普通优先级通道
+    pub const MESSAGE_DISTRIBUTION: &'static str = "message_distribution";
+
+    /// 紧急优先级消息分发通道
+    pub const MESSAGE_DISTRIBUTION_URGENT: &'static str = "message_distribution_urgent";
+
+    /// 高优先级消息分发通道
+    pub const MESSAGE_DISTRIBUTION_HIGH: &'static str = "message_distribution_high";
+
+    /// 低优先级消息分发通道
+    pub const MESSAGE_DISTRIBUTION_LOW: &'static str = "message_distribution_low";
+
+    /// 1 秒延迟重试主题
+    pub const MESSAGE_RETRY_1S: &'static str = "message_retry_1s";
+
+    /// 10 秒延迟重试主题
+    pub const MESSAGE_RETRY_10S: &'static str = "message_retry_10s";
+
+    /// 60 秒延迟重试主题
+    pub const MESSAGE_RETRY_60S: &'static str = "message_retry_60s";
+
+    /// 离线通知主题
+    pub const OFFLINE_NOTIFICATIONS: &'static str = "offline_notifications";
+
+    /// 消息状态主题
+    pub const MESSAGE_STATUS: &'static str = "message_status";
+
+    /// 死信队列主题
+    pub const DEAD_LETTER: &'static str = "dead_letter";
+
+    /// 用户上下线事件主题，由网关在连接建立与关闭时写入
+    pub const USER_PRESENCE: &'static str = "user_presence";
+
+    /// 群成员变更事件主题，由群组服务在成员加入与退出时写入
+    pub const GROUP_MEMBERSHIP: &'static str = "group_membership";
+
+    /// 群消息离线推送任务主题
+    pub const OFFLINE_GROUP_PUSH: &'static str = "offline_group_push";
+
+    /// 消息分发通道，按优先级从高到低排列
+    pub const DISTRIBUTION_LANES: [&'static str; 4] = [
+        Self::MESSAGE_DISTRIBUTION_URGENT,
+        Self::MESSAGE_DISTRIBUTION_HIGH,
+        Self::MESSAGE_DISTRIBUTION,
+        Self::MESSAGE_DISTRIBUTION_LOW,
+    ];
+
+    /// 延迟重试主题及其延迟(毫秒)，按延迟从小到大排列
+    ///
+    /// 同一主题内每条消息的延迟相同，消费端按写入时间依次等待即可保证到期顺序。
+    pub const RETRY_TIERS: [(&'static str, i64); 3] = [
+        (Self::MESSAGE_RETRY_1S, 1_000),
+        (Self::MESSAGE_RETRY_10S, 10_000),
+        (Self::MESSAGE_RETRY_60S, 60_000),
+    ];
+
+    /// 延迟对应的重试主题：不超过该延迟的最大档位，不足最小档位时使用最小档位
+    ///
+    /// 档位延迟到期后剩余的延迟由重试消费者转入下一档位。
+    pub fn retry_topic(delay_ms: i64) -> (&'static str, i64) {
+        Self::RETRY_TIERS
+            .iter()
+            .rev()
+            .find(|(_, tier)| *tier <= delay_ms)
+            .copied()
+            .unwrap_or(Self::RETRY_TIERS[0])
+    }
+
+    /// 优先级对应的消息分发主题
+    pub fn distribution_topic(priority: MessagePriority) -> &'static str {
+        match priority {
+            MessagePriority::MsgPriorityUrgent => Self::MESSAGE_DISTRIBUTION_URGENT,
+            MessagePriority::MsgPriorityHigh => Self::MESSAGE_DISTRIBUTION_HIGH,
+            MessagePriority::MsgPriorityNormal => Self::MESSAGE_DISTRIBUTION,
+            MessagePriority::MsgPriorityLow => Self::MESSAGE_DISTRIBUTION_LOW,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_topic_tiers() {
+        assert_eq!(KafkaTopics::retry_topic(200).0, KafkaTopics::MESSAGE_RETRY_1S);
+        assert_eq!(KafkaTopics::retry_topic(4_000).0, KafkaTopics::MESSAGE_RETRY_1S);
+        assert_eq!(KafkaTopics::retry_topic(10_000).0, KafkaTopics::MESSAGE_RETRY_10S);
+        assert_eq!(KafkaTopics::retry_topic(300_000).0, KafkaTopics::MESSAGE_RETRY_60S);
+    }
+}
@@ -0,0 +1,169 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use super::KafkaTopics;
+
+const DAY: Duration = Duration::from_secs(24 * 3600);
+
+/// 清理策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CleanupPolicy {
+    /// 按保留时长删除
+    Delete,
+    /// 按键压缩，只保留每个键的最新值
+    Compact,
+    /// 压缩并按保留时长删除
+    CompactDelete,
+}
+
+impl CleanupPolicy {
+    /// `cleanup.policy` 配置值
+    pub fn as_config(&self) -> &'static str {
+        match self {
+            CleanupPolicy::Delete => "delete",
+            CleanupPolicy::Compact => "compact",
+            CleanupPolicy::CompactDelete => "compact,delete",
+        }
+    }
+}
+
+/// 消息键语义，决定分区与有序性范围
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeySemantics {
+    /// 以 server_msg_id 为键，同一消息的事件有序
+    MessageId,
+    /// 以会话为键（单聊双方/群ID），同一会话内有序
+    Conversation,
+    /// 以用户ID为键，同一用户的事件有序
+    UserId,
+}
+
+/// 主题规格
+#[derive(Debug, Clone)]
+pub struct TopicSpec {
+    /// 主题名
+    pub name: &'static str,
+    /// 分区数
+    pub partitions: i32,
+    /// 副本数
+    pub replication_factor: i32,
+    /// 保留时长
+    pub retention: Duration,
+    /// 清理策略
+    pub cleanup_policy: CleanupPolicy,
+    /// 消息键语义
+    pub key: KeySemantics,
+    /// 单条消息最大字节数
+    pub max_message_bytes: usize,
+}
+
+impl TopicSpec {
+    fn new(name: &'static str, partitions: i32, retention: Duration, key: KeySemantics) -> Self {
+        Self {
+            name,
+            partitions,
+            replication_factor: 1,
+            retention,
+            cleanup_policy: CleanupPolicy::Delete,
+            key,
+            max_message_bytes: 1024 * 1024,
+        }
+    }
+
+    fn cleanup(mut self, policy: CleanupPolicy) -> Self {
+        self.cleanup_policy = policy;
+        self
+    }
+
+    /// 由本注册表管理的主题级配置
+    pub fn configs(&self) -> BTreeMap<&'static str, String> {
+        let mut configs = BTreeMap::new();
+        configs.insert("retention.ms", self.retention.as_millis().to_string());
+        configs.insert("cleanup.policy", self.cleanup_policy.as_config().to_string());
+        configs.insert("max.message.bytes", self.max_message_bytes.to_string());
+        if self.replication_factor >= 3 {
+            configs.insert("min.insync.replicas", "2".to_string());
+        }
+        configs
+    }
+}
+
+/// 主题注册表
+#[derive(Debug, Clone)]
+pub struct TopicRegistry {
+    topics: Vec<TopicSpec>,
+}
+
+impl Default for TopicRegistry {
+    fn default() -> Self {
+        Self::standard(1)
+    }
+}
+
+impl TopicRegistry {
+    /// 标准主题集合
+    ///
+    /// `replication_factor` 随部署环境变化，开发环境单 broker 为 1，生产环境建议 3。
+    pub fn standard(replication_factor: i32) -> Self {
+        let topics = vec![
+            TopicSpec::new(KafkaTopics::MESSAGE_STORE, 16, 7 * DAY, KeySemantics::Conversation),
+            TopicSpec::new(KafkaTopics::MESSAGE_STORED, 16, 3 * DAY, KeySemantics::MessageId),
+            TopicSpec::new(KafkaTopics::MESSAGE_DISTRIBUTION_URGENT, 8, 3 * DAY, KeySemantics::Conversation),
+            TopicSpec::new(KafkaTopics::MESSAGE_DISTRIBUTION_HIGH, 16, 3 * DAY, KeySemantics::Conversation),
+            TopicSpec::new(KafkaTopics::MESSAGE_DISTRIBUTION, 32, 3 * DAY, KeySemantics::Conversation),
+            TopicSpec::new(KafkaTopics::MESSAGE_DISTRIBUTION_LOW, 16, 3 * DAY, KeySemantics::Conversation),
+            TopicSpec::new(KafkaTopics::MESSAGE_RETRY_1S, 8, DAY, KeySemantics::Conversation),
+            TopicSpec::new(KafkaTopics::MESSAGE_RETRY_10S, 8, DAY, KeySemantics::Conversation),
+            TopicSpec::new(KafkaTopics::MESSAGE_RETRY_60S, 8, DAY, KeySemantics::Conversation),
+            TopicSpec::new(KafkaTopics::OFFLINE_NOTIFICATIONS, 8, DAY, KeySemantics::Conversation),
+            // 状态主题只关心每条消息的最新状态
+            TopicSpec::new(KafkaTopics::MESSAGE_STATUS, 16, 3 * DAY, KeySemantics::MessageId)
+                .cleanup(CleanupPolicy::CompactDelete),
+            TopicSpec::new(KafkaTopics::DEAD_LETTER, 4, 30 * DAY, KeySemantics::MessageId),
+            TopicSpec::new(KafkaTopics::USER_PRESENCE, 16, DAY, KeySemantics::UserId),
+            TopicSpec::new(KafkaTopics::GROUP_MEMBERSHIP, 16, DAY, KeySemantics::UserId),
+            TopicSpec::new(KafkaTopics::OFFLINE_GROUP_PUSH, 16, DAY, KeySemantics::Conversation),
+        ];
+
+        Self {
+            topics: topics
+                .into_iter()
+                .map(|mut spec| {
+                    spec.replication_factor = replication_factor;
+                    spec
+                })
+                .collect(),
+        }
+    }
+
+    /// 从环境变量 `KAFKA_REPLICATION_FACTOR` 读取副本数，默认 1
+    pub fn from_env() -> Self {
+        let replication_factor = std::env::var("KAFKA_REPLICATION_FACTOR")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(1);
+        Self::standard(replication_factor)
+    }
+
+    /// 注册或替换主题规格
+    pub fn with_topic(mut self, spec: TopicSpec) -> Self {
+        self.topics.retain(|t| t.name != spec.name);
+        self.topics.push(spec);
+        self
+    }
+
+    /// 所有主题规格
+    pub fn topics(&self) -> &[TopicSpec] {
+        &self.topics
+    }
+
+    /// 按名称查找主题规格
+    pub fn get(&self, name: &str) -> Option<&TopicSpec> {
+        self.topics.iter().find(|t| t.name == name)
+    }
+
+    /// 最大副本数
+    pub fn max_replication_factor(&self) -> i32 {
+        self.topics.iter().map(|t| t.replication_factor).max().unwrap_or(1)
+    }
+}
@@ -1,4 +1,4 @@
-use proto_crate::api::im::common::{MessageData, SessionType};
+use proto_crate::api::im::common::{AtType, MessageData, MessagePriority, SessionType};
 
 /// 判断消息是否是群消息
 pub fn is_group_message(message: &MessageData) -> bool {
@@ -7,4 +7,155 @@ pub fn is_group_message(message: &MessageData) -> bool {
         message.session_type == SessionType::SuperGroup as i32
         || message.session_type == SessionType::WorkGroup as i32
     )
-}
\ No newline at end of file
+}
+/// 发送设备ID在 options 中的键，由路由入口按上行调用方(网关)的元数据写入，不信任客户端填写的值
+pub const DEVICE_ID_OPTION: &str = "device_id";
+/// 消息优先级在 options 中的键，只由服务端写入，上行消息中的同名键会被移除
+pub const PRIORITY_OPTION: &str = "priority";
+/// 离线推送忽略接收者的会话免打扰设置，用于 @ 提醒
+pub const FORCE_PUSH_OPTION: &str = "force_push";
+/// 人工审核已通过，投递时不再做内容检查；只由服务端写入，上行消息中的同名键会被移除
+pub const REVIEW_APPROVED_OPTION: &str = "review_approved";
+/// 定时消息的投递时间（毫秒时间戳）在 options 中的键
+pub const SCHEDULED_TIME_OPTION: &str = "scheduled_time";
+/// 阅后即焚 / 定时销毁的存活时间（秒）在 options 中的键
+pub const DESTRUCT_AFTER_OPTION: &str = "destruct_after";
+/// 销毁计时起点在 options 中的键，`send` 或 `read`，默认 `send`
+pub const DESTRUCT_MODE_OPTION: &str = "destruct_mode";
+
+/// 消息销毁策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DestructPolicy {
+    /// 发送后经过指定秒数销毁
+    AfterSend(u64),
+    /// 首次阅读后经过指定秒数销毁
+    AfterRead(u64),
+}
+
+/// 会话ID
+///
+/// 群聊为 `sg_{group_id}`；单聊按双方ID排序拼接为 `si_{a}_{b}`，收发双方得到相同的会话ID。
+pub fn conversation_id(message: &MessageData) -> String {
+    if is_group_message(message) {
+        return format!("sg_{}", message.group_id);
+    }
+    let (a, b) = if message.send_id <= message.recv_id {
+        (&message.send_id, &message.recv_id)
+    } else {
+        (&message.recv_id, &message.send_id)
+    };
+    format!("si_{}_{}", a, b)
+}
+
+/// 用户的会话 @ 未读计数键（不含租户前缀）
+///
+/// HASH，field 为 [`conversation_id`]，value 为 @ 未读数；由路由服务写入，会话服务读取与清零。
+pub fn mention_unread_key(user_id: &str) -> String {
+    format!("mention:unread:{}", user_id)
+}
+
+/// 路由时写入 options 的消息优先级，未写入或无法识别时为普通优先级
+pub fn message_priority(message: &MessageData) -> MessagePriority {
+    message
+        .options
+        .get(PRIORITY_OPTION)
+        .and_then(|v| v.parse::<i32>().ok())
+        .and_then(|v| MessagePriority::try_from(v).ok())
+        .unwrap_or(MessagePriority::MsgPriorityNormal)
+}
+
+/// 消息的 @ 类型
+///
+/// @所有人 以 `at_type` 字段为准；其余情况按 `at_user_list` 判断，不信任客户端填写的 AtYou。
+pub fn at_type(message: &MessageData) -> AtType {
+    if message.at_type == AtType::AtAll as i32 {
+        AtType::AtAll
+    } else if mentioned_users(message).is_empty() {
+        AtType::None
+    } else {
+        AtType::AtUser
+    }
+}
+
+/// 被 @ 的用户，去掉发送者自己、空ID与重复项
+pub fn mentioned_users(message: &MessageData) -> Vec<String> {
+    let mut users: Vec<String> = message
+        .at_user_list
+        .iter()
+        .filter(|id| !id.is_empty() && **id != message.send_id)
+        .cloned()
+        .collect();
+    users.sort();
+    users.dedup();
+    users
+}
+
+/// 定时消息的投递时间（毫秒时间戳），未设置或无法解析时为 None
+pub fn scheduled_time(message: &MessageData) -> Option<i64> {
+    message
+        .options
+        .get(SCHEDULED_TIME_OPTION)
+        .and_then(|v| v.parse::<i64>().ok())
+        .filter(|t| *t > 0)
+}
+
+/// 消息是否已通过人工审核
+pub fn is_review_approved(message: &MessageData) -> bool {
+    message.options.get(REVIEW_APPROVED_OPTION).is_some_and(|v| v == "true")
+}
+
+/// 消息销毁策略，存活时间未设置或为 0 时不销毁
+pub fn destruct_policy(message: &MessageData) -> Option<DestructPolicy> {
+    let secs = message
+        .options
+        .get(DESTRUCT_AFTER_OPTION)
+        .and_then(|v| v.parse::<u64>().ok())
+        .filter(|secs| *secs > 0)?;
+    match message.options.get(DESTRUCT_MODE_OPTION).map(String::as_str) {
+        Some("read") => Some(DestructPolicy::AfterRead(secs)),
+        _ => Some(DestructPolicy::AfterSend(secs)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(send_id: &str, at_type: AtType, at: &[&str]) -> MessageData {
+        MessageData {
+            send_id: send_id.to_string(),
+            at_type: at_type as i32,
+            at_user_list: at.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn at_type_and_mentioned_users() {
+        assert_eq!(at_type(&message("u1", AtType::None, &[])), AtType::None);
+        assert_eq!(at_type(&message("u1", AtType::None, &["u2"])), AtType::AtUser);
+        // 只 @ 了自己不算提醒，客户端填写的 AtYou 按列表判断
+        assert_eq!(at_type(&message("u1", AtType::AtUser, &["u1", ""])), AtType::None);
+        assert_eq!(at_type(&message("u1", AtType::AtYou, &["u2"])), AtType::AtUser);
+
+        let msg = message("u1", AtType::AtAll, &["u3", "u1", "u2", "u3", ""]);
+        assert_eq!(at_type(&msg), AtType::AtAll);
+        assert_eq!(mentioned_users(&msg), vec!["u2".to_string(), "u3".to_string()]);
+        assert_eq!(at_type(&message("u1", AtType::AtAll, &[])), AtType::AtAll);
+    }
+
+    #[test]
+    fn destruct_policy_from_options() {
+        let mut msg = MessageData::default();
+        assert_eq!(destruct_policy(&msg), None);
+
+        msg.options.insert(DESTRUCT_AFTER_OPTION.to_string(), "30".to_string());
+        assert_eq!(destruct_policy(&msg), Some(DestructPolicy::AfterSend(30)));
+
+        msg.options.insert(DESTRUCT_MODE_OPTION.to_string(), "read".to_string());
+        assert_eq!(destruct_policy(&msg), Some(DestructPolicy::AfterRead(30)));
+
+        msg.options.insert(DESTRUCT_AFTER_OPTION.to_string(), "0".to_string());
+        assert_eq!(destruct_policy(&msg), None);
+    }
+}
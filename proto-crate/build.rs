@@ -15,6 +15,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 "proto/service/sync.proto",
                 "proto/service/session.proto",
                 "proto/service/notification.proto",
+                "proto/service/dead_letter.proto",
                 "proto/business/user.proto",
                 "proto/business/group.proto",
                 "proto/business/friend.proto",
@@ -32,6 +32,10 @@ pub mod api {
             pub mod notification {
                 tonic::include_proto!("api.im.service.notification");
             }
+
+            pub mod deadletter {
+                tonic::include_proto!("api.im.service.deadletter");
+            }
         }
 
         pub mod business {
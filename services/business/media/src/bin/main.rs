@@ -1,6 +1,6 @@
 use anyhow::Result;
 use flare_core::logs::Logger;
-use log::{info, error};
+use log::info;
 use sqlx::postgres::PgPoolOptions;
 use std::sync::Arc;
 use media::{
@@ -17,6 +17,7 @@ use flare_rpc_core::{
 };
 use std::time::Duration;
 use tonic::transport::Server;
+use common::health::{ConsulHealthRegistrar, HealthRegistry, PostgresHealthCheck};
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -32,6 +33,13 @@ async fn main() -> Result<()> {
         .connect(&database_url)
         .await?;
 
+    // 健康检查
+    let health = Arc::new(
+        HealthRegistry::new("media").with_check(Arc::new(PostgresHealthCheck::new(pool.clone()))),
+    );
+    let health_addr = std::env::var("HEALTH_ADDR").unwrap_or_else(|_| "127.0.0.1:9056".to_string());
+    let health_service = health.start(&health_addr, Duration::from_secs(5)).await?;
+
     // 初始化存储库
     let repository = Arc::new(PostgresMediaRepository::new(pool.clone()));
     repository.create_tables().await?;
@@ -84,6 +92,15 @@ async fn main() -> Result<()> {
         .parse::<u16>()?;
     let addr = format!("{}:{}", service_host, service_port).parse()?;
 
+    // 就绪检查失败时由 Consul 将实例摘除
+    let consul_health = ConsulHealthRegistrar::new(&common::config::ConsulConfig {
+        host: consul_host.clone(),
+        port: consul_port,
+        register_interval: 10,
+        heartbeat_interval: 5,
+    });
+    consul_health.spawn_register("media", addr, format!("http://{}/readyz", health_addr));
+
     // 创建应用构建器
     let app = AppBuilder::new("media")
         .version("1.0.0")
@@ -98,6 +115,7 @@ async fn main() -> Result<()> {
     // 运行服务
     app.run(service_host.as_str(), service_port, |mut server, addr| async move {
         server
+            .add_service(health_service)
             .add_service(MediaServer::new(grpc_service))
             .serve(addr)
             .await
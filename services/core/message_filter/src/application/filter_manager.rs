@@ -6,6 +6,8 @@ use crate::domain::{
     services::filter_service::FilterService,
 };
 use chrono::Utc;
+use common::error::AppError;
+use proto_crate::api::im::common::ErrorCode;
 use uuid::Uuid;
 
 pub struct FilterManager<R: FilterRepository, S: FilterService> {
@@ -166,4 +168,43 @@ pub enum Error {
     
     #[error("Not found: {0}")]
     NotFound(String),
-} 
\ No newline at end of file
+} 
+
+impl From<Error> for AppError {
+    fn from(err: Error) -> Self {
+        use crate::domain::repositories::filter_repository::Error as RepositoryError;
+        use crate::domain::services::filter_service::Error as ServiceError;
+
+        let code = match &err {
+            Error::NotFound(_)
+            | Error::Repository(RepositoryError::NotFound(_))
+            | Error::Service(ServiceError::NotFound(_)) => ErrorCode::FilterRuleNotFound,
+            Error::Repository(RepositoryError::InvalidData(_) | RepositoryError::Duplicate(_))
+            | Error::Service(ServiceError::Rule(_)) => ErrorCode::FilterRuleInvalid,
+            Error::ValidationError(_) | Error::Service(ServiceError::InvalidRequest(_)) => {
+                ErrorCode::InvalidParams
+            }
+            Error::Repository(RepositoryError::Repository(_))
+            | Error::Service(ServiceError::Service(_) | ServiceError::Filter(_)) => ErrorCode::SystemError,
+        };
+        AppError::new(code, err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_maps_to_filter_codes() {
+        let err: AppError = Error::NotFound("rule-1".to_string()).into();
+        assert_eq!(err.code, ErrorCode::FilterRuleNotFound);
+
+        let err: AppError =
+            Error::Repository(crate::domain::repositories::filter_repository::Error::Duplicate("rule-1".to_string())).into();
+        assert_eq!(err.code, ErrorCode::FilterRuleInvalid);
+
+        let err: AppError = Error::ValidationError("empty content".to_string()).into();
+        assert_eq!(err.code, ErrorCode::InvalidParams);
+    }
+}
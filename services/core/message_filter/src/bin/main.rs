@@ -1,6 +1,7 @@
 use anyhow::Result;
 use flare_core::logs::Logger;
-use log::{info, error};
+use log::info;
+use std::sync::Arc;
 use sqlx::postgres::PgPoolOptions;
 use message_filter::{
     application::filter_manager::FilterManager,
@@ -17,11 +18,17 @@ use flare_rpc_core::{
 };
 use std::time::Duration;
 use tonic::transport::Server;
+use common::health::{ConsulHealthRegistrar, HealthRegistry, PostgresHealthCheck};
+use common::tenant::TenantLayer;
+use common::telemetry::{init_telemetry, server_interceptor, TelemetryConfig, TraceContextLayer};
 
 #[tokio::main]
 async fn main() -> Result<()> {
     // 初始化日志
     Logger::init("message_filter", "debug")?;
+
+    // 初始化链路追踪
+    let _telemetry = init_telemetry(&TelemetryConfig::from_env("message_filter"))?;
     info!("Starting Message Filter Service...");
 
     // 创建数据库连接池
@@ -32,6 +39,13 @@ async fn main() -> Result<()> {
         .connect(&database_url)
         .await?;
 
+    // 健康检查
+    let health = Arc::new(
+        HealthRegistry::new("message-filter").with_check(Arc::new(PostgresHealthCheck::new(pool.clone()))),
+    );
+    let health_addr = std::env::var("HEALTH_ADDR").unwrap_or_else(|_| "127.0.0.1:9054".to_string());
+    let health_service = health.start(&health_addr, Duration::from_secs(5)).await?;
+
     // 创建过滤服务组件
     let filter_repository = PostgresFilterRepository::new(pool);
     let filter_service = FilterServiceImpl::new();
@@ -67,6 +81,15 @@ async fn main() -> Result<()> {
         .parse::<u16>()?;
     let addr = format!("{}:{}", service_host, service_port).parse()?;
 
+    // 就绪检查失败时由 Consul 将实例摘除
+    let consul_health = ConsulHealthRegistrar::new(&common::config::ConsulConfig {
+        host: consul_host.clone(),
+        port: consul_port,
+        register_interval: 10,
+        heartbeat_interval: 5,
+    });
+    consul_health.spawn_register("message-filter", addr, format!("http://{}/readyz", health_addr));
+
     // 创建应用构建器
     let app = AppBuilder::new("message-filter")
         .version("1.0.0")
@@ -81,7 +104,10 @@ async fn main() -> Result<()> {
     // 运行服务
     app.run(service_host.as_str(), service_port, |mut server, addr| async move {
         server
-            .add_service(FilterServer::new(grpc_service))
+            .layer(TenantLayer)
+            .layer(TraceContextLayer)
+            .add_service(health_service)
+            .add_service(FilterServer::with_interceptor(grpc_service, server_interceptor))
             .serve(addr)
             .await
             .map_err(|e| e.into())
@@ -5,21 +5,41 @@ use crate::domain::{
     repositories::filter_repository::{FilterRepository, Error as RepoError},
 };
 use chrono::Utc;
+use common::tenant::{self, TenantSchemas};
 use uuid::Uuid;
 
 pub struct PostgresFilterRepository {
     pool: PgPool,
+    schemas: TenantSchemas,
 }
 
 impl PostgresFilterRepository {
     pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+        Self {
+            pool,
+            schemas: TenantSchemas::new(),
+        }
+    }
+
+    // 租户首次访问时创建其 schema 与规则表
+    async fn ensure_tables(&self) -> Result<(), RepoError> {
+        self.schemas.ensure(|| self.create_tables()).await
+    }
+
+    // 将 SQL 中的 `{filter_rules}` 占位符替换为当前租户 schema 下的表名
+    fn sql(sql: &str) -> String {
+        sql.replace("{filter_rules}", &tenant::current().pg_table("filter_rules"))
     }
 
-    // 构建规则表 SQL
+    // 构建当前租户的规则表 SQL
     async fn create_tables(&self) -> Result<(), RepoError> {
-        sqlx::query(r#"
-            CREATE TABLE IF NOT EXISTS filter_rules (
+        sqlx::query(&format!("CREATE SCHEMA IF NOT EXISTS {}", tenant::current().pg_schema()))
+            .execute(&self.pool)
+            .await
+            .map_err(|e| RepoError::Repository(e.to_string()))?;
+
+        sqlx::query(&Self::sql(r#"
+            CREATE TABLE IF NOT EXISTS {filter_rules} (
                 id UUID PRIMARY KEY,
                 name VARCHAR(255) NOT NULL,
                 rule_type VARCHAR(50) NOT NULL,
@@ -34,7 +54,7 @@ impl PostgresFilterRepository {
                 created_at TIMESTAMP WITH TIME ZONE NOT NULL,
                 updated_at TIMESTAMP WITH TIME ZONE NOT NULL
             )
-        "#)
+        "#))
         .execute(&self.pool)
         .await
         .map_err(|e| RepoError::Repository(e.to_string()))?;
@@ -69,8 +89,9 @@ impl PostgresFilterRepository {
 #[async_trait]
 impl FilterRepository for PostgresFilterRepository {
     async fn save_rule(&self, rule: FilterRule) -> Result<(), RepoError> {
-        sqlx::query(r#"
-            INSERT INTO filter_rules (
+        self.ensure_tables().await?;
+        sqlx::query(&Self::sql(r#"
+            INSERT INTO {filter_rules} (
                 id, name, rule_type, pattern, action, priority, is_enabled,
                 description, category, replacement, custom_config,
                 created_at, updated_at
@@ -81,7 +102,7 @@ impl FilterRepository for PostgresFilterRepository {
                 priority = $6, is_enabled = $7, description = $8,
                 category = $9, replacement = $10, custom_config = $11,
                 updated_at = $13
-        "#)
+        "#))
         .bind(rule.id)
         .bind(&rule.name)
         .bind(format!("{:?}", rule.rule_type))
@@ -103,16 +124,17 @@ impl FilterRepository for PostgresFilterRepository {
     }
 
     async fn get_rule(&self, rule_id: &str) -> Result<Option<FilterRule>, RepoError> {
+        self.ensure_tables().await?;
         let uuid = Uuid::parse_str(rule_id)
             .map_err(|e| RepoError::InvalidData(e.to_string()))?;
 
-        let row = sqlx::query(r#"
+        let row = sqlx::query(&Self::sql(r#"
             SELECT id, name, rule_type, pattern, action, priority, is_enabled,
                    description, category, replacement, custom_config,
                    created_at, updated_at
-            FROM filter_rules
+            FROM {filter_rules}
             WHERE id = $1
-        "#)
+        "#))
         .bind(uuid)
         .fetch_optional(&self.pool)
         .await
@@ -145,10 +167,11 @@ impl FilterRepository for PostgresFilterRepository {
     }
 
     async fn delete_rule(&self, rule_id: &str) -> Result<(), RepoError> {
+        self.ensure_tables().await?;
         let uuid = Uuid::parse_str(rule_id)
             .map_err(|e| RepoError::InvalidData(e.to_string()))?;
 
-        sqlx::query("DELETE FROM filter_rules WHERE id = $1")
+        sqlx::query(&Self::sql("DELETE FROM {filter_rules} WHERE id = $1"))
             .bind(uuid)
             .execute(&self.pool)
             .await
@@ -158,14 +181,15 @@ impl FilterRepository for PostgresFilterRepository {
     }
 
     async fn get_rules_by_type(&self, rule_type: RuleType) -> Result<Vec<FilterRule>, RepoError> {
-        let rows = sqlx::query(r#"
+        self.ensure_tables().await?;
+        let rows = sqlx::query(&Self::sql(r#"
             SELECT id, name, rule_type, pattern, action, priority, is_enabled,
                    description, category, replacement, custom_config,
                    created_at, updated_at
-            FROM filter_rules
+            FROM {filter_rules}
             WHERE rule_type = $1
             ORDER BY priority DESC
-        "#)
+        "#))
         .bind(format!("{:?}", rule_type))
         .fetch_all(&self.pool)
         .await
@@ -198,14 +222,15 @@ impl FilterRepository for PostgresFilterRepository {
     }
 
     async fn get_enabled_rules(&self) -> Result<Vec<FilterRule>, RepoError> {
-        let rows = sqlx::query(r#"
+        self.ensure_tables().await?;
+        let rows = sqlx::query(&Self::sql(r#"
             SELECT id, name, rule_type, pattern, action, priority, is_enabled,
                    description, category, replacement, custom_config,
                    created_at, updated_at
-            FROM filter_rules
+            FROM {filter_rules}
             WHERE is_enabled = true
             ORDER BY priority DESC
-        "#)
+        "#))
         .fetch_all(&self.pool)
         .await
         .map_err(|e| RepoError::Repository(e.to_string()))?;
@@ -237,14 +262,15 @@ impl FilterRepository for PostgresFilterRepository {
     }
 
     async fn get_rules_by_category(&self, category: &str) -> Result<Vec<FilterRule>, RepoError> {
-        let rows = sqlx::query(r#"
+        self.ensure_tables().await?;
+        let rows = sqlx::query(&Self::sql(r#"
             SELECT id, name, rule_type, pattern, action, priority, is_enabled,
                    description, category, replacement, custom_config,
                    created_at, updated_at
-            FROM filter_rules
+            FROM {filter_rules}
             WHERE category = $1
             ORDER BY priority DESC
-        "#)
+        "#))
         .bind(category)
         .fetch_all(&self.pool)
         .await
@@ -277,12 +303,13 @@ impl FilterRepository for PostgresFilterRepository {
     }
 
     async fn batch_save_rules(&self, rules: Vec<FilterRule>) -> Result<(), RepoError> {
+        self.ensure_tables().await?;
         let mut tx = self.pool.begin().await
             .map_err(|e| RepoError::Repository(e.to_string()))?;
 
         for rule in rules {
-            sqlx::query(r#"
-                INSERT INTO filter_rules (
+            sqlx::query(&Self::sql(r#"
+                INSERT INTO {filter_rules} (
                     id, name, rule_type, pattern, action, priority, is_enabled,
                     description, category, replacement, custom_config,
                     created_at, updated_at
@@ -293,7 +320,7 @@ impl FilterRepository for PostgresFilterRepository {
                     priority = $6, is_enabled = $7, description = $8,
                     category = $9, replacement = $10, custom_config = $11,
                     updated_at = $13
-            "#)
+            "#))
             .bind(rule.id)
             .bind(&rule.name)
             .bind(format!("{:?}", rule.rule_type))
@@ -319,13 +346,14 @@ impl FilterRepository for PostgresFilterRepository {
     }
 
     async fn batch_delete_rules(&self, rule_ids: Vec<String>) -> Result<(), RepoError> {
+        self.ensure_tables().await?;
         let uuids: Result<Vec<Uuid>, _> = rule_ids.iter()
             .map(|id| Uuid::parse_str(id))
             .collect();
 
         let uuids = uuids.map_err(|e| RepoError::InvalidData(e.to_string()))?;
 
-        sqlx::query("DELETE FROM filter_rules WHERE id = ANY($1)")
+        sqlx::query(&Self::sql("DELETE FROM {filter_rules} WHERE id = ANY($1)"))
             .bind(&uuids)
             .execute(&self.pool)
             .await
@@ -335,15 +363,16 @@ impl FilterRepository for PostgresFilterRepository {
     }
 
     async fn enable_rule(&self, rule_id: &str) -> Result<(), RepoError> {
+        self.ensure_tables().await?;
         let uuid = Uuid::parse_str(rule_id)
             .map_err(|e| RepoError::InvalidData(e.to_string()))?;
 
-        sqlx::query(r#"
-            UPDATE filter_rules
+        sqlx::query(&Self::sql(r#"
+            UPDATE {filter_rules}
             SET is_enabled = true,
                 updated_at = $2
             WHERE id = $1
-        "#)
+        "#))
         .bind(uuid)
         .bind(Utc::now())
         .execute(&self.pool)
@@ -354,15 +383,16 @@ impl FilterRepository for PostgresFilterRepository {
     }
 
     async fn disable_rule(&self, rule_id: &str) -> Result<(), RepoError> {
+        self.ensure_tables().await?;
         let uuid = Uuid::parse_str(rule_id)
             .map_err(|e| RepoError::InvalidData(e.to_string()))?;
 
-        sqlx::query(r#"
-            UPDATE filter_rules
+        sqlx::query(&Self::sql(r#"
+            UPDATE {filter_rules}
             SET is_enabled = false,
                 updated_at = $2
             WHERE id = $1
-        "#)
+        "#))
         .bind(uuid)
         .bind(Utc::now())
         .execute(&self.pool)
@@ -373,7 +403,8 @@ impl FilterRepository for PostgresFilterRepository {
     }
 
     async fn count_rules(&self) -> Result<u64, RepoError> {
-        let row = sqlx::query("SELECT COUNT(*) as count FROM filter_rules")
+        self.ensure_tables().await?;
+        let row = sqlx::query(&Self::sql("SELECT COUNT(*) as count FROM {filter_rules}"))
             .fetch_one(&self.pool)
             .await
             .map_err(|e| RepoError::Repository(e.to_string()))?;
@@ -382,7 +413,8 @@ impl FilterRepository for PostgresFilterRepository {
     }
 
     async fn count_rules_by_type(&self, rule_type: RuleType) -> Result<u64, RepoError> {
-        let row = sqlx::query("SELECT COUNT(*) as count FROM filter_rules WHERE rule_type = $1")
+        self.ensure_tables().await?;
+        let row = sqlx::query(&Self::sql("SELECT COUNT(*) as count FROM {filter_rules} WHERE rule_type = $1"))
             .bind(format!("{:?}", rule_type))
             .fetch_one(&self.pool)
             .await
@@ -1,6 +1,7 @@
 use tonic::{Request, Response, Status};
 use uuid::Uuid;
 use chrono::Utc;
+use common::error::AppError;
 use crate::{
     application::filter_manager::FilterManager,
     domain::{
@@ -152,7 +153,7 @@ impl<R: FilterRepository + Send + Sync + 'static, S: FilterService + Send + Sync
 
         let result = self.filter_manager.filter_content(filter_request)
             .await
-            .map_err(|e| Status::internal(e.to_string()))?;
+            .map_err(|e| Status::from(AppError::from(e)))?;
 
         Ok(Response::new(FilterContentResponse {
             is_blocked: result.is_blocked,
@@ -214,7 +215,7 @@ impl<R: FilterRepository + Send + Sync + 'static, S: FilterService + Send + Sync
 
         let results = self.filter_manager.batch_filter_content(filter_requests)
             .await
-            .map_err(|e| Status::internal(e.to_string()))?;
+            .map_err(|e| Status::from(AppError::from(e)))?;
 
         Ok(Response::new(BatchFilterContentResponse {
             results: results.into_iter().map(|r| api::im::service::filter::FilterContentResponse {
@@ -267,7 +268,7 @@ impl<R: FilterRepository + Send + Sync + 'static, S: FilterService + Send + Sync
 
         let added_rule = self.filter_manager.add_rule(rule)
             .await
-            .map_err(|e| Status::internal(e.to_string()))?;
+            .map_err(|e| Status::from(AppError::from(e)))?;
 
         Ok(Response::new(AddRuleResponse {
             rule: Some(Self::to_grpc_rule(&added_rule)),
@@ -304,7 +305,7 @@ impl<R: FilterRepository + Send + Sync + 'static, S: FilterService + Send + Sync
 
         let updated_rule = self.filter_manager.update_rule(rule)
             .await
-            .map_err(|e| Status::internal(e.to_string()))?;
+            .map_err(|e| Status::from(AppError::from(e)))?;
 
         Ok(Response::new(UpdateRuleResponse {
             rule: Some(Self::to_grpc_rule(&updated_rule)),
@@ -320,7 +321,7 @@ impl<R: FilterRepository + Send + Sync + 'static, S: FilterService + Send + Sync
         
         self.filter_manager.delete_rule(&req.id)
             .await
-            .map_err(|e| Status::internal(e.to_string()))?;
+            .map_err(|e| Status::from(AppError::from(e)))?;
 
         Ok(Response::new(DeleteRuleResponse {
             success: true,
@@ -336,7 +337,7 @@ impl<R: FilterRepository + Send + Sync + 'static, S: FilterService + Send + Sync
         
         let rule = self.filter_manager.get_rule(&req.id)
             .await
-            .map_err(|e| Status::internal(e.to_string()))?;
+            .map_err(|e| Status::from(AppError::from(e)))?;
 
         Ok(Response::new(GetRuleResponse {
             rule: rule.map(|r| Self::to_grpc_rule(&r)),
@@ -352,7 +353,7 @@ impl<R: FilterRepository + Send + Sync + 'static, S: FilterService + Send + Sync
         
         let rules = self.filter_manager.get_rules_by_type(Self::convert_rule_type(req.rule_type))
             .await
-            .map_err(|e| Status::internal(e.to_string()))?;
+            .map_err(|e| Status::from(AppError::from(e)))?;
 
         Ok(Response::new(GetRulesByTypeResponse {
             rules: rules.into_iter().map(|r| Self::to_grpc_rule(&r)).collect(),
@@ -368,7 +369,7 @@ impl<R: FilterRepository + Send + Sync + 'static, S: FilterService + Send + Sync
         
         self.filter_manager.enable_rule(&req.id)
             .await
-            .map_err(|e| Status::internal(e.to_string()))?;
+            .map_err(|e| Status::from(AppError::from(e)))?;
 
         Ok(Response::new(EnableRuleResponse {
             success: true,
@@ -384,7 +385,7 @@ impl<R: FilterRepository + Send + Sync + 'static, S: FilterService + Send + Sync
         
         self.filter_manager.disable_rule(&req.id)
             .await
-            .map_err(|e| Status::internal(e.to_string()))?;
+            .map_err(|e| Status::from(AppError::from(e)))?;
 
         Ok(Response::new(DisableRuleResponse {
             success: true,
@@ -424,7 +425,7 @@ impl<R: FilterRepository + Send + Sync + 'static, S: FilterService + Send + Sync
 
         self.filter_manager.import_rules(rules)
             .await
-            .map_err(|e| Status::internal(e.to_string()))?;
+            .map_err(|e| Status::from(AppError::from(e)))?;
 
         Ok(Response::new(ImportRulesResponse {
             success: true,
@@ -446,7 +447,7 @@ impl<R: FilterRepository + Send + Sync + 'static, S: FilterService + Send + Sync
 
         let rules = self.filter_manager.export_rules(rule_type)
             .await
-            .map_err(|e| Status::internal(e.to_string()))?;
+            .map_err(|e| Status::from(AppError::from(e)))?;
 
         Ok(Response::new(ExportRulesResponse {
             rules: rules.into_iter().map(|r| Self::to_grpc_rule(&r)).collect(),
@@ -0,0 +1,373 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::Result;
+use common::error::AppError;
+use log::{error, info};
+use proto_crate::api::im::common::{ErrorCode, MessageData};
+
+use crate::domain::entities::{
+    AuditAction, AuditRecord, DeadLetterPage, DeadLetterQuery, DeadLetterRecord, MessageStatus, PreProcessCode,
+};
+use crate::domain::repositories::{DeadLetterRepository, MessageRepository};
+use crate::domain::services::MessageService;
+
+/// 重放时清除的重试状态，重放后的消息重新计算重试次数
+const RETRY_OPTIONS: [&str; 3] = ["retry_count", "next_retry_time", "last_retry_time"];
+/// 清除死信时每批处理的数量
+const PURGE_BATCH: usize = 500;
+/// 审计记录中最多保留的消息ID数量，完整数量见 `succeeded`
+const MAX_AUDIT_MESSAGE_IDS: usize = 1000;
+
+/// 死信管理配置
+#[derive(Debug, Clone)]
+pub struct DeadLetterConfig {
+    /// 每分钟允许重放的消息数量（按租户）
+    pub replay_per_minute: u32,
+    /// 单次重放的最大消息数量
+    pub max_replay_batch: usize,
+    /// 分页查询的最大每页数量
+    pub max_page_size: usize,
+}
+
+impl Default for DeadLetterConfig {
+    fn default() -> Self {
+        Self {
+            replay_per_minute: 600,
+            max_replay_batch: 100,
+            max_page_size: 500,
+        }
+    }
+}
+
+/// 重放请求
+#[derive(Debug, Clone, Default)]
+pub struct ReplayRequest {
+    pub message_ids: Vec<String>,
+    /// 覆盖或新增的消息选项
+    pub set_options: HashMap<String, String>,
+    /// 删除的消息选项
+    pub remove_options: Vec<String>,
+    pub operator: String,
+    pub comment: String,
+    /// 重放成功后从死信索引中移除
+    pub remove_after_replay: bool,
+}
+
+/// 清除请求，`message_ids` 与 `before_time` 至少指定一项
+#[derive(Debug, Clone, Default)]
+pub struct PurgeRequest {
+    pub message_ids: Vec<String>,
+    /// 清除该时间（秒，不含）之前进入的死信
+    pub before_time: Option<i64>,
+    /// 与 `before_time` 同时使用，只清除该原因分类
+    pub reason: Option<String>,
+    pub operator: String,
+    pub comment: String,
+}
+
+/// 死信管理
+///
+/// 死信由 `DeadLetterConsumer` 从死信主题写入索引，这里提供查询、重放与清除；
+/// 重放与清除均写入审计记录，重放按租户每分钟限量。
+/// 重放的消息按修改后的内容重新执行预处理检查，未通过时不投递。
+pub struct DeadLetterService {
+    repository: Arc<dyn DeadLetterRepository>,
+    message_repository: Arc<dyn MessageRepository>,
+    message_service: Arc<dyn MessageService>,
+    config: DeadLetterConfig,
+}
+
+impl DeadLetterService {
+    pub fn new(
+        repository: Arc<dyn DeadLetterRepository>,
+        message_repository: Arc<dyn MessageRepository>,
+        message_service: Arc<dyn MessageService>,
+    ) -> Self {
+        Self {
+            repository,
+            message_repository,
+            message_service,
+            config: DeadLetterConfig::default(),
+        }
+    }
+
+    pub fn with_config(mut self, config: DeadLetterConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// 分页查询死信，同时返回已知的原因分类
+    pub async fn list(&self, mut query: DeadLetterQuery) -> Result<(DeadLetterPage, Vec<String>)> {
+        query.limit = match query.limit {
+            0 => 50,
+            limit => limit.min(self.config.max_page_size),
+        };
+        let page = self.repository.list(&query).await?;
+        let reasons = self.repository.reasons().await?;
+        Ok((page, reasons))
+    }
+
+    /// 查看单条死信
+    pub async fn get(&self, message_id: &str) -> Result<DeadLetterRecord> {
+        self.repository
+            .get(message_id)
+            .await?
+            .ok_or_else(|| AppError::not_found(format!("dead letter {} not found", message_id)).into())
+    }
+
+    /// 重放死信，返回每条消息的结果
+    pub async fn replay(&self, request: ReplayRequest) -> Result<Vec<(String, Option<AppError>)>> {
+        if request.operator.is_empty() {
+            return Err(AppError::invalid_params("operator is required").into());
+        }
+        if request.message_ids.is_empty() {
+            return Err(AppError::invalid_params("message_ids is required").into());
+        }
+        if request.message_ids.len() > self.config.max_replay_batch {
+            return Err(AppError::invalid_params(format!(
+                "at most {} messages per replay",
+                self.config.max_replay_batch
+            ))
+            .into());
+        }
+        if !self
+            .repository
+            .try_acquire_replay_quota(request.message_ids.len() as u32, self.config.replay_per_minute)
+            .await?
+        {
+            return Err(AppError::new(
+                ErrorCode::RateLimit,
+                format!("replay limit of {} messages per minute exceeded", self.config.replay_per_minute),
+            )
+            .into());
+        }
+
+        let mut results = Vec::with_capacity(request.message_ids.len());
+        for message_id in &request.message_ids {
+            let result = self.replay_one(message_id, &request).await.err().map(|e| {
+                error!("Failed to replay dead letter {}: {}", message_id, e);
+                AppError::from(e)
+            });
+            results.push((message_id.clone(), result));
+        }
+
+        let failed = results.iter().filter(|(_, e)| e.is_some()).count() as i32;
+        let audit = AuditRecord {
+            action: AuditAction::Replay,
+            operator: request.operator,
+            comment: request.comment,
+            message_ids: request.message_ids,
+            set_options: request.set_options,
+            remove_options: request.remove_options,
+            succeeded: results.len() as i32 - failed,
+            failed,
+            time: chrono::Utc::now().timestamp_millis(),
+        };
+        self.audit(&audit).await?;
+        Ok(results)
+    }
+
+    async fn replay_one(&self, message_id: &str, request: &ReplayRequest) -> Result<()> {
+        let mut record = self.get(message_id).await?;
+        let mut message: MessageData = record
+            .dead_letter
+            .original_message
+            .as_ref()
+            .and_then(|payload| payload.msg.clone())
+            .ok_or_else(|| AppError::new(ErrorCode::MessageFormatError, "dead letter has no original message"))?;
+
+        for key in RETRY_OPTIONS.iter().copied().chain(request.remove_options.iter().map(String::as_str)) {
+            message.options.remove(key);
+        }
+        message.options.extend(request.set_options.clone());
+        message
+            .options
+            .insert("replay_count".to_string(), (record.replay_count + 1).to_string());
+        message.status = MessageStatus::Pending as i32;
+
+        // 死信期间好友关系、禁言或内容规则可能已变化
+        let code = self.message_service.pre_process(&mut message).await?;
+        if code != PreProcessCode::Ok {
+            return Err(AppError::from(code).into());
+        }
+
+        // 按消息中的优先级回到原分发通道
+        self.message_repository.handle_message_distribution(&message).await?;
+
+        if request.remove_after_replay {
+            self.repository.remove(&[message_id.to_string()]).await?;
+        } else {
+            record.replay_count += 1;
+            record.last_replay_time = chrono::Utc::now().timestamp();
+            self.repository.save(&record).await?;
+        }
+        Ok(())
+    }
+
+    /// 清除死信，返回清除数量
+    pub async fn purge(&self, request: PurgeRequest) -> Result<u64> {
+        if request.operator.is_empty() {
+            return Err(AppError::invalid_params("operator is required").into());
+        }
+        if request.message_ids.is_empty() && request.before_time.is_none() {
+            return Err(AppError::invalid_params("message_ids or before_time is required").into());
+        }
+
+        let mut purged = self.repository.remove(&request.message_ids).await?;
+        let mut purged_ids = request.message_ids.clone();
+        if let Some(before_time) = request.before_time {
+            let query = DeadLetterQuery {
+                reason: request.reason.clone(),
+                end_time: Some(before_time - 1),
+                limit: PURGE_BATCH,
+                ..Default::default()
+            };
+            loop {
+                let page = self.repository.list(&query).await?;
+                if page.entries.is_empty() {
+                    break;
+                }
+                let ids: Vec<String> = page.entries.into_iter().map(|e| e.message_id).collect();
+                purged += self.repository.remove(&ids).await?;
+                let room = MAX_AUDIT_MESSAGE_IDS.saturating_sub(purged_ids.len());
+                purged_ids.extend(ids.into_iter().take(room));
+            }
+        }
+
+        let audit = AuditRecord {
+            action: AuditAction::Purge,
+            operator: request.operator,
+            comment: request.comment,
+            message_ids: purged_ids,
+            set_options: HashMap::new(),
+            remove_options: Vec::new(),
+            succeeded: purged as i32,
+            failed: 0,
+            time: chrono::Utc::now().timestamp_millis(),
+        };
+        self.audit(&audit).await?;
+        Ok(purged)
+    }
+
+    /// 按时间倒序查询审计记录
+    pub async fn audit_records(&self, offset: usize, limit: usize) -> Result<Vec<AuditRecord>> {
+        let limit = match limit {
+            0 => 50,
+            limit => limit.min(self.config.max_page_size),
+        };
+        self.repository.list_audit(offset, limit).await
+    }
+
+    async fn audit(&self, record: &AuditRecord) -> Result<()> {
+        info!(
+            "Dead letter {} by {}: {} succeeded, {} failed, messages {:?}, comment: {}",
+            record.action.as_str(),
+            record.operator,
+            record.succeeded,
+            record.failed,
+            record.message_ids,
+            record.comment
+        );
+        self.repository.append_audit(record).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proto_crate::api::im::common::{DeadLetterMessage, MessagePayload};
+
+    use super::*;
+    use crate::domain::repositories::{MockDeadLetterRepository, MockMessageRepository};
+    use crate::domain::services::MockMessageService;
+
+    fn record(message_id: &str) -> DeadLetterRecord {
+        let message = MessageData {
+            server_msg_id: message_id.to_string(),
+            send_id: "user1".to_string(),
+            recv_id: "user2".to_string(),
+            options: HashMap::from([("retry_count".to_string(), "5".to_string())]),
+            ..Default::default()
+        };
+        DeadLetterRecord::from_dead_letter(DeadLetterMessage {
+            original_message: Some(MessagePayload {
+                msg_id: message_id.to_string(),
+                msg: Some(message),
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    fn dead_letters(audited_failures: i32) -> MockDeadLetterRepository {
+        let mut repository = MockDeadLetterRepository::new();
+        repository.expect_get().returning(|id| Ok(Some(record(id))));
+        repository.expect_try_acquire_replay_quota().returning(|_, _| Ok(true));
+        repository
+            .expect_append_audit()
+            .withf(move |audit| audit.operator == "alice" && audit.failed == audited_failures)
+            .times(1)
+            .returning(|_| Ok(()));
+        repository
+    }
+
+    fn replay_request() -> ReplayRequest {
+        ReplayRequest {
+            message_ids: vec!["1001".to_string()],
+            operator: "alice".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replay_runs_pre_check_before_redelivery() {
+        let mut repository = dead_letters(0);
+        repository
+            .expect_save()
+            .withf(|record| record.replay_count == 1)
+            .times(1)
+            .returning(|_| Ok(()));
+        let mut service = MockMessageService::new();
+        service.expect_pre_process().times(1).returning(|_| Ok(PreProcessCode::Ok));
+        let mut messages = MockMessageRepository::new();
+        messages
+            .expect_handle_message_distribution()
+            .withf(|m| {
+                m.options.get("replay_count").map(String::as_str) == Some("1") && !m.options.contains_key("retry_count")
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let dead_letters = DeadLetterService::new(Arc::new(repository), Arc::new(messages), Arc::new(service));
+        let results = dead_letters.replay(replay_request()).await.unwrap();
+        assert!(results[0].1.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_replay_rejected_by_pre_check_is_not_redelivered() {
+        let mut service = MockMessageService::new();
+        service.expect_pre_process().times(1).returning(|_| Ok(PreProcessCode::NotFriend));
+        // 未设置分发期望，投递即失败
+        let messages = MockMessageRepository::new();
+
+        let dead_letters = DeadLetterService::new(Arc::new(dead_letters(1)), Arc::new(messages), Arc::new(service));
+        let results = dead_letters.replay(replay_request()).await.unwrap();
+        let error = results[0].1.as_ref().unwrap();
+        assert_eq!(error.code, AppError::from(PreProcessCode::NotFriend).code);
+    }
+
+    #[tokio::test]
+    async fn test_replay_requires_operator() {
+        let dead_letters = DeadLetterService::new(
+            Arc::new(MockDeadLetterRepository::new()),
+            Arc::new(MockMessageRepository::new()),
+            Arc::new(MockMessageService::new()),
+        );
+        let request = ReplayRequest {
+            operator: String::new(),
+            ..replay_request()
+        };
+        assert!(dead_letters.replay(request).await.is_err());
+    }
+}
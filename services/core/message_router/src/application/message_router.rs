@@ -1,45 +1,115 @@
 use crate::domain::{
-    entities::MessageStatus,
+    entities::{MessageStatus, PreProcessCode, ScheduledTask, ScheduledTaskKind},
     services::MessageService,
 };
 use anyhow::Result;
-use log::{error, info, warn};
-use proto_crate::api::im::common::MessageData;
+use common::error::AppError;
+use crate::domain::repositories::{DedupClaim, GroupTimelinePage, MessageDedupRepository, MessageScheduleRepository};
+use chrono::Utc;
+use common::id::SnowflakeGenerator;
+use common::utils::msg_utils::{
+    destruct_policy, scheduled_time, DestructPolicy, DEVICE_ID_OPTION, FORCE_PUSH_OPTION, PRIORITY_OPTION,
+    REVIEW_APPROVED_OPTION,
+};
+use proto_crate::api::im::common::ErrorCode;
+use log::{debug, error, info, warn};
+use proto_crate::api::im::common::{MessageData, MessagePriority};
+use proto_crate::api::im::service::router::PriorityRules;
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
+
+/// 默认去重窗口，覆盖客户端断线重连后的重发
+pub const DEFAULT_DEDUP_WINDOW: Duration = Duration::from_secs(24 * 3600);
+/// 去重键处理中状态的有效期，需大于单条消息的路由耗时
+const DEDUP_PENDING_TTL: Duration = Duration::from_secs(60);
+/// 定时消息最远可提前的时间
+pub const MAX_SCHEDULE_AHEAD: Duration = Duration::from_secs(30 * 24 * 3600);
+/// 单次上报已读的最大消息数
+const MAX_READ_REPORT: usize = 500;
 
 pub struct MessageRouterService {
     message_service: Arc<dyn MessageService>,
+    id_generator: Arc<SnowflakeGenerator>,
+    dedup: Option<(Arc<dyn MessageDedupRepository>, Duration)>,
+    /// 定时投递、到期销毁与待审核消息，未配置时不支持定时消息与销毁，待审核的消息直接拒绝
+    scheduler: Option<Arc<dyn MessageScheduleRepository>>,
 }
 
 impl MessageRouterService {
     pub fn new(
         message_service: Arc<dyn MessageService>,
+        id_generator: Arc<SnowflakeGenerator>,
     ) -> Self {
         Self {
             message_service,
+            id_generator,
+            dedup: None,
+            scheduler: None,
         }
     }
 
-    /// 消息预处理和校验
-    pub async fn pre_process(&self, message: &MessageData) -> Result<i32> {
-        // 1. 基础格式校验
-        let pre_process_code = self.message_service.pre_process(message).await?;
-        Ok(pre_process_code as i32)
+    /// 启用基于 client_msg_id 的上行去重，`window` 内的重发只路由一次
+    pub fn with_dedup(mut self, repository: Arc<dyn MessageDedupRepository>, window: Duration) -> Self {
+        self.dedup = Some((repository, window));
+        self
+    }
+
+    /// 启用定时消息与定时销毁
+    pub fn with_scheduler(mut self, repository: Arc<dyn MessageScheduleRepository>) -> Self {
+        self.scheduler = Some(repository);
+        self
+    }
+
+    /// 为上行消息分配服务端消息ID
+    ///
+    /// 客户端流量一律重新分配 server_msg_id，客户端携带的值可能与其他消息的存储主键、去重键冲突或打乱顺序。
+    /// 审核通过标记、优先级与强制推送标记只由服务端写入，上行时移除。
+    pub async fn assign_server_msg_id(&self, mut message: MessageData) -> Result<MessageData> {
+        Self::strip_server_options(&mut message);
+        message.server_msg_id = self.id_generator.next_id_string().await?;
+        Ok(message)
+    }
+
+    /// 为内部服务投递的消息补齐服务端消息ID
+    ///
+    /// 只用于服务间调用的可信路径，已携带 server_msg_id 的消息(如重试、转发)保持不变。
+    pub async fn ensure_server_msg_id(&self, mut message: MessageData) -> Result<MessageData> {
+        Self::strip_server_options(&mut message);
+        if message.server_msg_id.is_empty() {
+            message.server_msg_id = self.id_generator.next_id_string().await?;
+        }
+        Ok(message)
+    }
+
+    fn strip_server_options(message: &mut MessageData) {
+        message.options.remove(REVIEW_APPROVED_OPTION);
+        message.options.remove(PRIORITY_OPTION);
+        message.options.remove(FORCE_PUSH_OPTION);
+    }
+
+    /// 按调用方携带的优先级规则确定消息的分发通道
+    pub fn prioritize(&self, message: &mut MessageData, rules: &PriorityRules) -> MessagePriority {
+        self.message_service.prioritize(message, rules)
+    }
+
+    /// 消息预处理和校验，命中替换规则时改写消息内容
+    pub async fn pre_process(&self, message: &mut MessageData) -> Result<PreProcessCode> {
+        self.message_service.pre_process(message).await
     }
 
     /// 消息路由处理
-    pub async fn route_message(&self, message: &MessageData) -> Result<(bool, Option<String>, Vec<String>)> {
+    pub async fn route_message(&self, message: &MessageData) -> Result<(bool, Option<AppError>, Vec<String>)> {
         // 1. 先存储消息
         if let Err(e) = self.message_service.handle_message_storage(message).await {
             error!("Failed to store message {}: {}", message.server_msg_id, e);
-            return Ok((false, Some(e.to_string()), vec![]));
+            return Ok((false, Some(AppError::new(ErrorCode::MessageStoreFailed, e.to_string())), vec![]));
         }
         
         // 2. 下发消息
         if let Err(e) = self.message_service.handle_message_distribution(message).await {
             error!("Failed to route message {}: {}", message.server_msg_id, e);
-            return Ok((false, Some(e.to_string()), vec![]));
+            return Ok((false, Some(AppError::new(ErrorCode::MessageSendFailed, e.to_string())), vec![]));
         }
 
         // 3. 下发成功，处理消息同步
@@ -47,9 +117,303 @@ impl MessageRouterService {
             warn!("Failed to sync message {}: {}", message.server_msg_id, e);
         }
 
+        // 4. 登记定时销毁，失败时消息不会自动销毁
+        if let Err(e) = self.register_destruct(message).await {
+            error!("Failed to register destruct timer for {}: {}", message.server_msg_id, e);
+        }
+
         Ok((true, None, vec![]))
     }
 
+    /// 上行消息路由，同一发送者设备的重发消息只路由一次
+    ///
+    /// 消息先经过预处理，命中替换规则时路由改写后的内容；待审核的消息暂存，视为成功并返回 `FilterReviewRequired`，
+    /// 审核通过后投递，见 [`resolve_review`](Self::resolve_review)。
+    ///
+    /// 返回 (服务端消息ID, 是否成功, 错误)。首次发送成功后的重复消息返回首次分配的服务端消息ID并视为成功，
+    /// 客户端据此确认，配合网关的确认重发实现端到端的 `QOS_LEVEL_EXACTLY_ONCE`；
+    /// 首次发送仍在处理中时返回 `MessageInProgress`，客户端稍后重发。
+    /// 路由失败或出错时释放去重键，客户端可用同一 client_msg_id 重发。
+    pub async fn route_upstream(&self, message: &MessageData) -> Result<(String, bool, Option<AppError>)> {
+        let dedup = match (&self.dedup, Self::dedup_key(message)) {
+            (Some((repository, window)), Some(key)) => Some((repository, *window, key)),
+            _ => None,
+        };
+        if let Some((repository, _, key)) = &dedup {
+            // 去重存储不可用时放行，宁可重复也不拒绝发送
+            match repository.claim(key, &message.server_msg_id, DEDUP_PENDING_TTL.as_secs()).await {
+                Ok(DedupClaim::Claimed) => {}
+                Ok(DedupClaim::Completed(original)) => {
+                    debug!(
+                        "Duplicate upstream message {} from {}, original {}",
+                        message.client_msg_id, message.send_id, original
+                    );
+                    return Ok((original, true, None));
+                }
+                Ok(DedupClaim::InFlight(original)) => {
+                    debug!(
+                        "Upstream message {} from {} is still in flight as {}",
+                        message.client_msg_id, message.send_id, original
+                    );
+                    let error = AppError::new(ErrorCode::MessageInProgress, "message is being processed, retry later");
+                    return Ok((original, false, Some(error)));
+                }
+                Err(e) => warn!("Failed to check duplicate for {}: {}", message.client_msg_id, e),
+            }
+        }
+
+        let result = self.check_and_route(message).await;
+        if let Some((repository, window, key)) = &dedup {
+            let outcome = match &result {
+                Ok((true, _)) => repository.confirm(key, &message.server_msg_id, window.as_secs()).await,
+                _ => repository.release(key, &message.server_msg_id).await,
+            };
+            if let Err(e) = outcome {
+                warn!("Failed to update dedup key for {}: {}", message.client_msg_id, e);
+            }
+        }
+        let (success, error) = result?;
+        Ok((message.server_msg_id.clone(), success, error))
+    }
+
+    /// 预处理后路由
+    async fn check_and_route(&self, message: &MessageData) -> Result<(bool, Option<AppError>)> {
+        let mut message = message.clone();
+        let code = self.pre_process(&mut message).await?;
+        if code != PreProcessCode::Ok {
+            return self.reject_or_hold(&message, code).await;
+        }
+        let result = self.route_or_schedule(&message).await;
+        if !matches!(result, Ok((true, _))) {
+            self.release_quota(&message).await;
+        }
+        result
+    }
+
+    /// 退回消息在预处理时占用的频率与配额
+    ///
+    /// 未送达的消息不计数，客户端重发时重新计数；只做检查不发送的消息同样退回。
+    pub async fn release_quota(&self, message: &MessageData) {
+        if let Err(e) = self.message_service.release_quota(message).await {
+            warn!("Failed to release send quota of {}: {}", message.server_msg_id, e);
+        }
+    }
+
+    /// 未通过预处理的消息，待审核的暂存等待审核，其他直接拒绝
+    ///
+    /// 返回 (是否已暂存, 错误)。
+    async fn reject_or_hold(&self, message: &MessageData, code: PreProcessCode) -> Result<(bool, Option<AppError>)> {
+        if code == PreProcessCode::UnderReview && self.scheduler.is_some() {
+            self.hold_for_review(message).await?;
+            return Ok((true, Some(code.into())));
+        }
+        Ok((false, Some(code.into())))
+    }
+
+    /// 暂存待审核的消息，审核通过前不存储也不下发
+    async fn hold_for_review(&self, message: &MessageData) -> Result<()> {
+        self.scheduler()?.schedule(ScheduledTaskKind::Review, message, None).await?;
+        info!("Message {} held for review", message.server_msg_id);
+        Ok(())
+    }
+
+    /// 处理人工审核结果
+    ///
+    /// 通过的消息按原定投递时间(未定时则立即)由调度器投递，投递前不再做内容检查；驳回的消息直接丢弃。
+    pub async fn resolve_review(&self, message_id: &str, approved: bool) -> Result<()> {
+        let scheduler = self.scheduler()?;
+        let message = scheduler
+            .get(ScheduledTaskKind::Review, message_id)
+            .await?
+            .ok_or_else(|| AppError::not_found(format!("message {} is not under review", message_id)))?;
+
+        if !approved {
+            if !scheduler.cancel(ScheduledTaskKind::Review, message_id).await? {
+                return Err(AppError::not_found(format!("message {} is not under review", message_id)).into());
+            }
+            info!("Message {} rejected by review", message_id);
+            return Ok(());
+        }
+
+        let fire_at = scheduled_time(&message)
+            .unwrap_or_default()
+            .max(Utc::now().timestamp_millis());
+        if !scheduler.arm(ScheduledTaskKind::Review, message_id, fire_at).await? {
+            return Err(AppError::invalid_params(format!("message {} has already been approved", message_id)).into());
+        }
+        info!("Message {} approved by review, delivers at {}", message_id, fire_at);
+        Ok(())
+    }
+
+    /// 定时消息暂不存储与下发，到期后由调度器路由；其他消息直接路由
+    async fn route_or_schedule(&self, message: &MessageData) -> Result<(bool, Option<AppError>)> {
+        match scheduled_time(message) {
+            Some(fire_at) if fire_at > Utc::now().timestamp_millis() => {
+                Ok(match self.schedule_delivery(message, fire_at).await {
+                    Ok(()) => (true, None),
+                    Err(e) => (false, Some(AppError::from(e))),
+                })
+            }
+            _ => {
+                let (success, error, _) = self.route_message(message).await?;
+                Ok((success, error))
+            }
+        }
+    }
+
+    fn scheduler(&self) -> Result<&Arc<dyn MessageScheduleRepository>> {
+        self.scheduler
+            .as_ref()
+            .ok_or_else(|| AppError::unavailable("message scheduler is not enabled").into())
+    }
+
+    /// 保存定时消息，到期后由调度器路由
+    async fn schedule_delivery(&self, message: &MessageData, fire_at: i64) -> Result<()> {
+        let scheduler = self.scheduler()?;
+        if fire_at - Utc::now().timestamp_millis() > MAX_SCHEDULE_AHEAD.as_millis() as i64 {
+            return Err(AppError::invalid_params(format!(
+                "scheduled_time must be within {} days",
+                MAX_SCHEDULE_AHEAD.as_secs() / 86_400
+            ))
+            .into());
+        }
+        scheduler.schedule(ScheduledTaskKind::Deliver, message, Some(fire_at)).await?;
+        info!("Message {} scheduled at {}", message.server_msg_id, fire_at);
+        Ok(())
+    }
+
+    /// 登记消息的定时销毁
+    ///
+    /// 发送后计时的消息直接设置触发时间；阅读后计时的消息先保存，首次阅读时设置触发时间。
+    async fn register_destruct(&self, message: &MessageData) -> Result<()> {
+        let Some(policy) = destruct_policy(message) else {
+            return Ok(());
+        };
+        let scheduler = self.scheduler()?;
+        match policy {
+            DestructPolicy::AfterSend(secs) => {
+                let fire_at = Utc::now().timestamp_millis() + secs as i64 * 1000;
+                scheduler.schedule(ScheduledTaskKind::Expire, message, Some(fire_at)).await
+            }
+            DestructPolicy::AfterRead(_) => scheduler.schedule(ScheduledTaskKind::Expire, message, None).await,
+        }
+    }
+
+    /// 取消定时消息，只有发送者可以取消
+    pub async fn cancel_scheduled(&self, user_id: &str, message_id: &str) -> Result<()> {
+        let scheduler = self.scheduler()?;
+        let message = scheduler
+            .get(ScheduledTaskKind::Deliver, message_id)
+            .await?
+            .ok_or_else(|| AppError::not_found(format!("scheduled message {} not found", message_id)))?;
+        if message.send_id != user_id {
+            return Err(AppError::new(ErrorCode::Forbidden, "only the sender can cancel a scheduled message").into());
+        }
+        // 已被调度器取出时仍可能送达
+        if !scheduler.cancel(ScheduledTaskKind::Deliver, message_id).await? {
+            return Err(AppError::not_found(format!("scheduled message {} not found", message_id)).into());
+        }
+        info!("Scheduled message {} cancelled by {}", message_id, user_id);
+        Ok(())
+    }
+
+    /// 上报消息已读，阅读后计时销毁的消息从首次阅读开始计时
+    ///
+    /// 返回本次开始计时的消息数量。
+    pub async fn report_read(&self, user_id: &str, message_ids: &[String]) -> Result<usize> {
+        if message_ids.len() > MAX_READ_REPORT {
+            return Err(AppError::invalid_params(format!("at most {} message_ids per report", MAX_READ_REPORT)).into());
+        }
+        let scheduler = self.scheduler()?;
+        let mut armed = 0;
+        for message_id in message_ids {
+            let Some(message) = scheduler.get(ScheduledTaskKind::Expire, message_id).await? else {
+                continue;
+            };
+            let Some(DestructPolicy::AfterRead(secs)) = destruct_policy(&message) else {
+                continue;
+            };
+            // 发送者自己阅读不计时
+            if message.send_id == user_id || !self.message_service.is_recipient(&message, user_id).await? {
+                continue;
+            }
+            let fire_at = Utc::now().timestamp_millis() + secs as i64 * 1000;
+            if scheduler.arm(ScheduledTaskKind::Expire, message_id, fire_at).await? {
+                debug!("Message {} read by {}, expires at {}", message_id, user_id, fire_at);
+                armed += 1;
+            }
+        }
+        Ok(armed)
+    }
+
+    /// 执行到期的定时任务
+    pub async fn fire_scheduled(&self, task: &ScheduledTask) -> Result<()> {
+        match task.kind {
+            ScheduledTaskKind::Deliver | ScheduledTaskKind::Review => {
+                // 发送时的检查结果到送达时可能已失效(好友关系、禁言、封禁等)，送达前重新检查
+                let mut message = task.message.clone();
+                if task.kind == ScheduledTaskKind::Review {
+                    message.options.insert(REVIEW_APPROVED_OPTION.to_string(), "true".to_string());
+                }
+                let code = self.pre_process(&mut message).await?;
+                if code.is_system_error() {
+                    return Err(anyhow::anyhow!(
+                        "pre-check of scheduled message {} failed: {}",
+                        message.server_msg_id,
+                        code.description()
+                    ));
+                }
+                if code != PreProcessCode::Ok {
+                    let (held, _) = self.reject_or_hold(&message, code).await?;
+                    if !held {
+                        warn!(
+                            "Scheduled message {} rejected at delivery: {}",
+                            message.server_msg_id,
+                            code.description()
+                        );
+                    }
+                    return Ok(());
+                }
+
+                message.options.remove(REVIEW_APPROVED_OPTION);
+                let (success, error, _) = self.route_message(&message).await?;
+                if !success {
+                    return Err(anyhow::anyhow!(
+                        "failed to route scheduled message {}: {:?}",
+                        message.server_msg_id,
+                        error
+                    ));
+                }
+                Ok(())
+            }
+            ScheduledTaskKind::Expire => self.message_service.expire_message(&task.message).await,
+        }
+    }
+
+    /// 去重键：发送者 + 设备 + client_msg_id，未携带 client_msg_id 的消息不去重
+    fn dedup_key(message: &MessageData) -> Option<String> {
+        if message.client_msg_id.is_empty() {
+            return None;
+        }
+        let device = message
+            .options
+            .get(DEVICE_ID_OPTION)
+            .cloned()
+            .unwrap_or_else(|| format!("platform_{}", message.send_platform_id));
+        Some(format!("{}:{}:{}", message.send_id, device, message.client_msg_id))
+    }
+
+    /// 拉取读扩散群的消息时间线
+    pub async fn pull_group_messages(
+        &self,
+        group_id: &str,
+        user_id: &str,
+        after_seq: i64,
+        limit: usize,
+    ) -> Result<GroupTimelinePage> {
+        self.message_service.pull_group_messages(group_id, user_id, after_seq, limit).await
+    }
+
     /// 消息重试处理
     pub async fn retry_message(&self, message: &MessageData) -> Result<()> {
         // 1. 检查是否需要重试
@@ -61,31 +425,35 @@ impl MessageRouterService {
     }
 
     /// 批量消息处理
-    pub async fn process_messages(&self, messages: Vec<MessageData>) -> Result<HashMap<String, (bool, Option<String>, Vec<String>)>> {
+    pub async fn process_messages(&self, messages: Vec<MessageData>) -> Result<HashMap<String, (bool, Option<AppError>, Vec<String>)>> {
         let mut results = HashMap::new();
 
-        for message in messages {
+        for mut message in messages {
             // 1. 预处理
-            let pre_process_code = self.pre_process(&message).await?;
-            if pre_process_code != 0 { // 0 表示 Ok
-                results.insert(message.server_msg_id.clone(), (
-                    false,
-                    Some(format!("Pre-process failed with code: {}", pre_process_code)),
-                    vec![],
-                ));
+            let pre_process_code = self.pre_process(&mut message).await?;
+            if pre_process_code != PreProcessCode::Ok {
+                let (held, error) = match self.reject_or_hold(&message, pre_process_code).await {
+                    Ok(result) => result,
+                    Err(e) => (false, Some(AppError::from(e))),
+                };
+                results.insert(message.server_msg_id.clone(), (held, error, vec![]));
                 continue;
             }
 
             // 2. 路由处理
             match self.route_message(&message).await {
                 Ok(result) => {
+                    if !result.0 {
+                        self.release_quota(&message).await;
+                    }
                     results.insert(message.server_msg_id.clone(), result);
                 }
                 Err(e) => {
                     error!("Failed to route message {}: {}", message.server_msg_id, e);
+                    self.release_quota(&message).await;
                     results.insert(message.server_msg_id.clone(), (
                         false,
-                        Some(e.to_string()),
+                        Some(AppError::from(e)),
                         vec![],
                     ));
                 }
@@ -96,3 +464,269 @@ impl MessageRouterService {
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::domain::repositories::MockMessageScheduleRepository;
+    use crate::domain::services::MockMessageService;
+
+    /// 内存中的去重记录
+    #[derive(Default)]
+    struct MemoryDedupRepository {
+        keys: Mutex<HashMap<String, DedupClaim>>,
+    }
+
+    #[async_trait]
+    impl MessageDedupRepository for MemoryDedupRepository {
+        async fn claim(&self, key: &str, server_msg_id: &str, _pending_ttl_secs: u64) -> Result<DedupClaim> {
+            let mut keys = self.keys.lock().unwrap();
+            match keys.get(key) {
+                Some(existing) => Ok(existing.clone()),
+                None => {
+                    keys.insert(key.to_string(), DedupClaim::InFlight(server_msg_id.to_string()));
+                    Ok(DedupClaim::Claimed)
+                }
+            }
+        }
+
+        async fn confirm(&self, key: &str, server_msg_id: &str, _ttl_secs: u64) -> Result<()> {
+            let mut keys = self.keys.lock().unwrap();
+            if keys.get(key) == Some(&DedupClaim::InFlight(server_msg_id.to_string())) {
+                keys.insert(key.to_string(), DedupClaim::Completed(server_msg_id.to_string()));
+            }
+            Ok(())
+        }
+
+        async fn release(&self, key: &str, server_msg_id: &str) -> Result<()> {
+            let mut keys = self.keys.lock().unwrap();
+            if keys.get(key) == Some(&DedupClaim::InFlight(server_msg_id.to_string())) {
+                keys.remove(key);
+            }
+            Ok(())
+        }
+    }
+
+    fn upstream(server_msg_id: &str) -> MessageData {
+        MessageData {
+            server_msg_id: server_msg_id.to_string(),
+            client_msg_id: "c1".to_string(),
+            send_id: "user1".to_string(),
+            recv_id: "user2".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn router(service: MockMessageService, dedup: Arc<MemoryDedupRepository>) -> MessageRouterService {
+        MessageRouterService::new(Arc::new(service), Arc::new(SnowflakeGenerator::new(1).unwrap()))
+            .with_dedup(dedup, DEFAULT_DEDUP_WINDOW)
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_after_success_returns_original() {
+        let mut service = MockMessageService::new();
+        service.expect_pre_process().times(1).returning(|_| Ok(PreProcessCode::Ok));
+        service.expect_handle_message_storage().times(1).returning(|_| Ok(()));
+        service.expect_handle_message_distribution().times(1).returning(|_| Ok(()));
+        service.expect_handle_message_sync().times(1).returning(|_| Ok(()));
+        let router = router(service, Arc::new(MemoryDedupRepository::default()));
+
+        let (id, success, error) = router.route_upstream(&upstream("1001")).await.unwrap();
+        assert_eq!((id.as_str(), success, error.is_none()), ("1001", true, true));
+
+        // 重发分配了新的服务端消息ID，仍返回首次的ID且不再路由
+        let (id, success, error) = router.route_upstream(&upstream("1002")).await.unwrap();
+        assert_eq!((id.as_str(), success, error.is_none()), ("1001", true, true));
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_in_flight_is_retryable() {
+        let mut service = MockMessageService::new();
+        service.expect_pre_process().never();
+        service.expect_handle_message_storage().never();
+        let dedup = Arc::new(MemoryDedupRepository::default());
+        dedup
+            .claim("user1:platform_0:c1", "1001", 60)
+            .await
+            .unwrap();
+        let router = router(service, dedup);
+
+        let (id, success, error) = router.route_upstream(&upstream("1002")).await.unwrap();
+        assert_eq!(id, "1001");
+        assert!(!success);
+        assert_eq!(error.unwrap().code, ErrorCode::MessageInProgress);
+    }
+
+    #[tokio::test]
+    async fn test_failed_route_releases_key() {
+        let mut service = MockMessageService::new();
+        service.expect_pre_process().times(2).returning(|_| Ok(PreProcessCode::Ok));
+        // 未送达的消息退回配额
+        service
+            .expect_release_quota()
+            .withf(|message| message.server_msg_id == "1001")
+            .times(1)
+            .returning(|_| Ok(()));
+        let mut calls = 0;
+        service.expect_handle_message_storage().times(2).returning(move |_| {
+            calls += 1;
+            if calls == 1 {
+                Err(anyhow::anyhow!("store unavailable"))
+            } else {
+                Ok(())
+            }
+        });
+        service.expect_handle_message_distribution().times(1).returning(|_| Ok(()));
+        service.expect_handle_message_sync().times(1).returning(|_| Ok(()));
+        let router = router(service, Arc::new(MemoryDedupRepository::default()));
+
+        let (_, success, error) = router.route_upstream(&upstream("1001")).await.unwrap();
+        assert!(!success);
+        assert_eq!(error.unwrap().code, ErrorCode::MessageStoreFailed);
+
+        // 重发重新路由
+        let (id, success, _) = router.route_upstream(&upstream("1002")).await.unwrap();
+        assert_eq!(id, "1002");
+        assert!(success);
+    }
+
+    #[tokio::test]
+    async fn test_upstream_routes_rewritten_content() {
+        let mut service = MockMessageService::new();
+        service.expect_pre_process().times(1).returning(|message| {
+            message.content = b"***".to_vec();
+            Ok(PreProcessCode::Ok)
+        });
+        service
+            .expect_handle_message_storage()
+            .withf(|message| message.content == b"***")
+            .times(1)
+            .returning(|_| Ok(()));
+        service.expect_handle_message_distribution().times(1).returning(|_| Ok(()));
+        service.expect_handle_message_sync().times(1).returning(|_| Ok(()));
+        let router = router(service, Arc::new(MemoryDedupRepository::default()));
+
+        let (_, success, error) = router.route_upstream(&upstream("1001")).await.unwrap();
+        assert!(success && error.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_upstream_under_review_is_held() {
+        let mut service = MockMessageService::new();
+        service.expect_pre_process().times(1).returning(|_| Ok(PreProcessCode::UnderReview));
+        service.expect_handle_message_storage().never();
+        let mut scheduler = MockMessageScheduleRepository::new();
+        scheduler
+            .expect_schedule()
+            .withf(|kind, message, fire_at| {
+                *kind == ScheduledTaskKind::Review && message.server_msg_id == "1001" && fire_at.is_none()
+            })
+            .times(1)
+            .returning(|_, _, _| Ok(()));
+        let dedup = Arc::new(MemoryDedupRepository::default());
+        let router = router(service, dedup.clone()).with_scheduler(Arc::new(scheduler));
+
+        let (id, success, error) = router.route_upstream(&upstream("1001")).await.unwrap();
+        assert_eq!(id, "1001");
+        assert!(success);
+        assert_eq!(error.unwrap().code, ErrorCode::FilterReviewRequired);
+
+        // 暂存即确认，重发不再重复送审
+        let (id, success, _) = router.route_upstream(&upstream("1002")).await.unwrap();
+        assert_eq!((id.as_str(), success), ("1001", true));
+    }
+
+    #[tokio::test]
+    async fn test_under_review_without_scheduler_is_rejected() {
+        let mut service = MockMessageService::new();
+        service.expect_pre_process().times(1).returning(|_| Ok(PreProcessCode::UnderReview));
+        service.expect_handle_message_storage().never();
+        let router = router(service, Arc::new(MemoryDedupRepository::default()));
+
+        let (_, success, error) = router.route_upstream(&upstream("1001")).await.unwrap();
+        assert!(!success);
+        assert_eq!(error.unwrap().code, ErrorCode::FilterReviewRequired);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_review() {
+        let mut scheduler = MockMessageScheduleRepository::new();
+        scheduler
+            .expect_get()
+            .returning(|_, id| Ok((id != "missing").then(|| upstream(id))));
+        let mut armed = false;
+        scheduler
+            .expect_arm()
+            .withf(|kind, id, _| *kind == ScheduledTaskKind::Review && id == "1001")
+            .times(2)
+            .returning(move |_, _, _| Ok(!std::mem::replace(&mut armed, true)));
+        scheduler
+            .expect_cancel()
+            .withf(|kind, id| *kind == ScheduledTaskKind::Review && id == "1002")
+            .times(1)
+            .returning(|_, _| Ok(true));
+        let router = router(MockMessageService::new(), Arc::new(MemoryDedupRepository::default()))
+            .with_scheduler(Arc::new(scheduler));
+
+        router.resolve_review("1001", true).await.unwrap();
+        let error = AppError::from(router.resolve_review("1001", true).await.unwrap_err());
+        assert_eq!(error.code, ErrorCode::InvalidParams);
+
+        router.resolve_review("1002", false).await.unwrap();
+        let error = AppError::from(router.resolve_review("missing", true).await.unwrap_err());
+        assert_eq!(error.code, ErrorCode::NotFound);
+    }
+
+    #[tokio::test]
+    async fn test_approved_review_skips_content_check_and_routes() {
+        let mut service = MockMessageService::new();
+        service
+            .expect_pre_process()
+            .withf(|message| message.options.get(REVIEW_APPROVED_OPTION).map(String::as_str) == Some("true"))
+            .times(1)
+            .returning(|_| Ok(PreProcessCode::Ok));
+        service
+            .expect_handle_message_storage()
+            .withf(|message| !message.options.contains_key(REVIEW_APPROVED_OPTION))
+            .times(1)
+            .returning(|_| Ok(()));
+        service.expect_handle_message_distribution().times(1).returning(|_| Ok(()));
+        service.expect_handle_message_sync().times(1).returning(|_| Ok(()));
+        let router = router(service, Arc::new(MemoryDedupRepository::default()));
+
+        let task = ScheduledTask {
+            tenant: common::tenant::TenantContext::default(),
+            kind: ScheduledTaskKind::Review,
+            message: upstream("1001"),
+        };
+        router.fire_scheduled(&task).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_client_cannot_set_server_options() {
+        let router = router(MockMessageService::new(), Arc::new(MemoryDedupRepository::default()));
+        let mut message = upstream("0000000000000000001");
+        message.options.insert(REVIEW_APPROVED_OPTION.to_string(), "true".to_string());
+        message.options.insert(PRIORITY_OPTION.to_string(), "3".to_string());
+        message.options.insert(FORCE_PUSH_OPTION.to_string(), "true".to_string());
+        let message = router.assign_server_msg_id(message).await.unwrap();
+        assert!(!message.server_msg_id.is_empty());
+        assert_ne!(message.server_msg_id, "0000000000000000001");
+        assert!(!message.options.contains_key(REVIEW_APPROVED_OPTION));
+        assert!(!message.options.contains_key(PRIORITY_OPTION));
+        assert!(!message.options.contains_key(FORCE_PUSH_OPTION));
+    }
+
+    #[tokio::test]
+    async fn test_internal_path_keeps_server_msg_id() {
+        let router = router(MockMessageService::new(), Arc::new(MemoryDedupRepository::default()));
+        let message = router.ensure_server_msg_id(upstream("0000000000000000001")).await.unwrap();
+        assert_eq!(message.server_msg_id, "0000000000000000001");
+        let message = router.ensure_server_msg_id(upstream("")).await.unwrap();
+        assert!(!message.server_msg_id.is_empty());
+    }
+}
@@ -1,3 +1,4 @@
+pub mod dead_letter;
 pub mod message_router;
 
-pub use message_router::*;
\ No newline at end of file
+pub use message_router::*;
@@ -1,29 +1,64 @@
 use anyhow::Result;
 use flare_core::logs::{LogConfig, Logger};
-use log::{info, error};
+use log::{info, error, warn};
 use std::sync::Arc;
 use flare_rpc_core::{
     discover::consul::{ConsulConfig, ConsulRegistry},
     AppBuilder,
 };
 use std::time::Duration;
+use std::path::PathBuf;
 use tonic::transport::Server;
 use message_router::{
-    domain::services::{MessageService, MessageServiceImpl},
-    application::message_router::MessageRouterService,
+    domain::services::{
+        FanoutPolicy, MessageService, MessageServiceImpl, OfflinePushConfig, OfflinePushScheduler,
+        OfflinePushWorker, PriorityResolver,
+    },
+    application::{
+        dead_letter::DeadLetterService,
+        message_router::{MessageRouterService, DEFAULT_DEDUP_WINDOW},
+    },
     infrastructure::repositories::{
+        DeadLetterRepositoryImpl,
+        GatewayPusher,
+        MentionRepositoryImpl,
+        MessageCounter,
+        MessageDedupRepositoryImpl,
         MessageRepositoryImpl,
+        MessageScheduleRepositoryImpl,
         RouteRepositoryImpl,
+        StoreClient,
         FriendRepositoryImpl,
         GroupRepositoryImpl,
+        GroupTimelineRepositoryImpl,
         ContentFilterRepositoryImpl,
+        FilterFailurePolicy,
+        OnlineMemberIndex,
     },
     interfaces::{
-        grpc::message_router_service::MessageRouterGrpcService,
-        consumers::MessageDistributionConsumer,
+        grpc::{
+            admin_auth::AdminAuth, dead_letter_service::DeadLetterGrpcService,
+            message_router_service::MessageRouterGrpcService, review_service::MessageReviewGrpcService,
+        },
+        consumers::{
+            DeadLetterConsumer, MessageDistributionConsumer, OfflinePushConsumer, PresenceConsumer, RetryConsumer,
+            ScheduleDispatcher,
+        },
     },
 };
+use proto_crate::api::im::service::router::message_review_server::MessageReviewServer;
 use proto_crate::api::im::service::router::message_router_server::MessageRouterServer;
+use proto_crate::api::im::service::deadletter::dead_letter_service_server::DeadLetterServiceServer;
+use common::id::{RedisWorkerLease, WorkerLeaseConfig};
+use common::config::{Config, Environment};
+use common::feature::{ConsulFlagSource, FeatureFlags, StaticFlagSource};
+use common::tenant::{TenantConfigRegistry, TenantLayer};
+use common::topic::{TopicProvisioner, TopicRegistry};
+use common::bus::{InMemoryBus, KafkaBus, MessageBus};
+use common::route::RouteStore;
+use common::rpc::{ClientOptions, ConsulDiscovery, GrpcClientFactory, ServiceNames};
+use common::telemetry::{init_telemetry, server_interceptor, TelemetryConfig, TraceContextLayer};
+use common::health::{ConsulHealthRegistrar, HealthRegistry, KafkaProducerHealthCheck, RedisHealthCheck};
 use std::error::Error;
 
 #[tokio::main]
@@ -31,20 +66,187 @@ async fn main() -> Result<()> {
     // 初始化日志
     Logger::init(Some(LogConfig::default()))?;
 
+    // 初始化链路追踪
+    let _telemetry = init_telemetry(&TelemetryConfig::from_env("message_router"))?;
+
     info!("Starting Message Router Service...");
 
+    // 消息总线：MESSAGE_BUS=memory 时使用进程内总线，用于不依赖 Kafka 的单机开发
+    let topic_registry = TopicRegistry::from_env();
+    let (message_bus, kafka_bus): (Arc<dyn MessageBus>, Option<Arc<KafkaBus>>) =
+        match std::env::var("MESSAGE_BUS").as_deref() {
+            Ok("memory") => {
+                info!("Using in-memory message bus");
+                (Arc::new(InMemoryBus::with_registry(&topic_registry)), None)
+            }
+            _ => {
+                let kafka_brokers = std::env::var("KAFKA_BROKERS").unwrap_or_else(|_| "localhost:9092".to_string());
+                // 按主题注册表初始化 Kafka 主题，失败时继续启动，由就绪检查反映 Kafka 状态
+                match TopicProvisioner::new(&kafka_brokers) {
+                    Ok(provisioner) => {
+                        if let Err(e) = provisioner.apply(&topic_registry).await {
+                            error!("Failed to provision kafka topics: {}", e);
+                        }
+                    }
+                    Err(e) => error!("Failed to create kafka admin client: {}", e),
+                }
+                let bus = Arc::new(KafkaBus::new(&kafka_brokers, "message-router")?);
+                (bus.clone(), Some(bus))
+            }
+        };
+
+    // 申请 snowflake worker 租约
+    let redis_client = redis::Client::open("redis://127.0.0.1:6379/")?;
+    let redis_conn = redis::aio::ConnectionManager::new(redis_client).await?;
+    let worker_lease = RedisWorkerLease::acquire(redis_conn.clone(), WorkerLeaseConfig {
+        service_name: "message_router".to_string(),
+        ..Default::default()
+    }).await?;
+    let id_generator = Arc::new(worker_lease.generator()?);
+
+    // 服务间调用：通过 Consul 解析并预热下游服务连接
+    let client_factory = Arc::new(GrpcClientFactory::new(
+        Arc::new(ConsulDiscovery::new(&consul_settings())),
+        ClientOptions::default(),
+    ));
+    for service in [ServiceNames::MESSAGE_STORE, ServiceNames::MESSAGE_FILTER] {
+        client_factory.service(service).await?;
+    }
+    let gateway_pusher = GatewayPusher::new(client_factory.service(ServiceNames::MESSAGE_GATEWAY).await?);
+    // 内容过滤：CONTENT_FILTER_FAILURE_POLICY=closed 时过滤服务不可用即拒绝消息，默认放行
+    let filter_failure_policy = match std::env::var("CONTENT_FILTER_FAILURE_POLICY").as_deref() {
+        Ok("closed") => FilterFailurePolicy::FailClosed,
+        _ => FilterFailurePolicy::FailOpen,
+    };
+    let content_filter_repo = Arc::new(
+        ContentFilterRepositoryImpl::new(client_factory.service(ServiceNames::MESSAGE_FILTER).await?)
+            .with_failure_policy(filter_failure_policy),
+    );
+
     // 初始化消息服务
-    let message_service = init_message_service()?;
-    let message_router_service = Arc::new(MessageRouterService::new(message_service.clone()));
-    let grpc_service = MessageRouterGrpcService::new(message_router_service);
+    let message_repo = Arc::new(MessageRepositoryImpl::new(
+        message_bus.producer(),
+        gateway_pusher,
+        MessageCounter::new(redis_conn.clone()),
+        StoreClient::new(client_factory.service(ServiceNames::MESSAGE_STORE).await?),
+    ));
+    let feature_flags = feature_flags();
+    feature_flags.start().await;
+    let route_repo = Arc::new(RouteRepositoryImpl::new(RouteStore::new(redis_conn.clone())));
+    let group_repo = Arc::new(
+        GroupRepositoryImpl::new()
+            .with_online_index(OnlineMemberIndex::new(redis_conn.clone()))
+            .with_group_service(client_factory.service(ServiceNames::GROUP).await?),
+    );
+    let message_service = init_message_service(
+        message_repo.clone(),
+        route_repo.clone(),
+        group_repo.clone(),
+        Arc::new(GroupTimelineRepositoryImpl::new(redis_conn.clone())),
+        Arc::new(MentionRepositoryImpl::new(redis_conn.clone())),
+        content_filter_repo,
+        FanoutPolicy::default().with_flags(feature_flags),
+    )?;
+    let schedule_repo = Arc::new(MessageScheduleRepositoryImpl::new(redis_conn.clone()));
+    let message_router_service = Arc::new(
+        MessageRouterService::new(message_service.clone(), id_generator)
+            .with_dedup(
+                Arc::new(MessageDedupRepositoryImpl::new(redis_conn.clone())),
+                DEFAULT_DEDUP_WINDOW,
+            )
+            .with_scheduler(schedule_repo.clone()),
+    );
+    let grpc_service = MessageRouterGrpcService::new(message_router_service.clone());
+    let review_grpc = MessageReviewGrpcService::new(message_router_service.clone());
+
+    // 定时投递与到期销毁
+    let schedule_dispatcher = ScheduleDispatcher::new(schedule_repo, message_router_service);
+    tokio::spawn(async move {
+        if let Err(e) = schedule_dispatcher.start().await {
+            error!("Schedule dispatcher error: {}", e);
+        }
+    });
 
     // 初始化并启动 Kafka 消费者
-    let consumer = MessageDistributionConsumer::new(message_service)?;
+    let consumer = MessageDistributionConsumer::new(message_service.clone(), message_bus.as_ref())?;
     tokio::spawn(async move {
         if let Err(e) = consumer.start().await {
             error!("Kafka consumer error: {}", e);
         }
     });
+    // 群消息离线推送任务
+    let offline_push_worker = Arc::new(OfflinePushWorker::new(
+        message_repo.clone(),
+        group_repo.clone(),
+        OfflinePushConfig::default(),
+    ));
+    let offline_push_consumer = OfflinePushConsumer::new(offline_push_worker, message_bus.as_ref())?;
+    tokio::spawn(async move {
+        if let Err(e) = offline_push_consumer.start().await {
+            error!("Offline push consumer error: {}", e);
+        }
+    });
+    // 根据上下线与群成员变更事件维护群在线成员索引
+    let presence_consumer = PresenceConsumer::new(group_repo, route_repo, message_bus.as_ref())?;
+    tokio::spawn(async move {
+        if let Err(e) = presence_consumer.start().await {
+            error!("Presence consumer error: {}", e);
+        }
+    });
+    let retry_consumer = RetryConsumer::new(message_bus.as_ref())?;
+    tokio::spawn(async move {
+        if let Err(e) = retry_consumer.start().await {
+            error!("Retry consumer error: {}", e);
+        }
+    });
+
+    // 死信索引与管理接口
+    let dead_letter_repo = Arc::new(DeadLetterRepositoryImpl::new(redis_conn.clone()));
+    let dead_letter_consumer = DeadLetterConsumer::new(dead_letter_repo.clone(), message_bus.as_ref())?;
+    tokio::spawn(async move {
+        if let Err(e) = dead_letter_consumer.start().await {
+            error!("Dead letter consumer error: {}", e);
+        }
+    });
+    let dead_letter_grpc = DeadLetterGrpcService::new(Arc::new(DeadLetterService::new(
+        dead_letter_repo,
+        message_repo,
+        message_service,
+    )));
+    // 管理接口使用独立端口并以令牌鉴权，不随业务端口注册到服务发现
+    let admin_auth = AdminAuth::from_env()?;
+    if admin_auth.is_empty() {
+        warn!("ADMIN_TOKENS is not set, all admin requests will be rejected");
+    }
+    let admin_addr = get_admin_addr()?.parse()?;
+    let review_auth = admin_auth.clone();
+    tokio::spawn(async move {
+        info!("Message Router admin listening on {}", admin_addr);
+        let result = Server::builder()
+            .layer(TenantLayer)
+            .layer(TraceContextLayer)
+            .add_service(DeadLetterServiceServer::with_interceptor(dead_letter_grpc, move |request| {
+                admin_auth.check(server_interceptor(request)?)
+            }))
+            .add_service(MessageReviewServer::with_interceptor(review_grpc, move |request| {
+                review_auth.check(server_interceptor(request)?)
+            }))
+            .serve(admin_addr)
+            .await;
+        if let Err(e) = result {
+            error!("Admin server error: {}", e);
+        }
+    });
+
+    // 健康检查
+    let mut health = HealthRegistry::new("message_router")
+        .with_check(Arc::new(RedisHealthCheck::new(redis_conn)));
+    if let Some(kafka_bus) = &kafka_bus {
+        health = health.with_check(Arc::new(KafkaProducerHealthCheck::new(kafka_bus.kafka_producer())));
+    }
+    let health = Arc::new(health);
+    let health_addr = get_health_addr()?;
+    let health_service = health.start(&health_addr, Duration::from_secs(5)).await?;
 
     // 启动 gRPC 服务
     let addr = get_service_addr()?;
@@ -63,25 +265,50 @@ async fn main() -> Result<()> {
         .register(registry)
         .build();
 
+    // 就绪检查失败时由 Consul 将实例摘除
+    ConsulHealthRegistrar::new(&consul_settings()).spawn_register(
+        "message_router",
+        addr.parse()?,
+        format!("http://{}/readyz", health_addr),
+    );
+
     info!("Message Router Service listening on {}", addr);
 
     app.run("127.0.0.1", 50052, |mut server, addr| async move {
         server
-            .add_service(MessageRouterServer::new(grpc_service))
+            .layer(TenantLayer)
+            .layer(TraceContextLayer)
+            .add_service(health_service)
+            .add_service(MessageRouterServer::with_interceptor(grpc_service, server_interceptor))
             .serve(addr)
             .await
             .map_err(|e| e.into())
     }).await.expect("server start filed");
 
+    worker_lease.release().await?;
     Ok(())
 }
 
-fn init_message_service() -> Result<Arc<MessageServiceImpl>> {
-    let message_repo = Arc::new(MessageRepositoryImpl::new()?);
-    let route_repo = Arc::new(RouteRepositoryImpl::new());
+fn consul_settings() -> common::config::ConsulConfig {
+    common::config::ConsulConfig {
+        host: "127.0.0.1".to_string(),
+        port: 8500,
+        register_interval: 10,
+        heartbeat_interval: 5,
+    }
+}
+
+fn init_message_service(
+    message_repo: Arc<MessageRepositoryImpl>,
+    route_repo: Arc<RouteRepositoryImpl>,
+    group_repo: Arc<GroupRepositoryImpl>,
+    timeline_repo: Arc<GroupTimelineRepositoryImpl>,
+    mention_repo: Arc<MentionRepositoryImpl>,
+    content_filter_repo: Arc<ContentFilterRepositoryImpl>,
+    fanout: FanoutPolicy,
+) -> Result<Arc<MessageServiceImpl>> {
     let friend_repo = Arc::new(FriendRepositoryImpl::new());
-    let group_repo = Arc::new(GroupRepositoryImpl::new());
-    let content_filter_repo = Arc::new(ContentFilterRepositoryImpl::new());
+    let offline_push = OfflinePushScheduler::new(message_repo.clone());
 
     Ok(Arc::new(MessageServiceImpl::new(
         message_repo,
@@ -89,9 +316,59 @@ fn init_message_service() -> Result<Arc<MessageServiceImpl>> {
         friend_repo,
         group_repo,
         content_filter_repo,
-    )))
+        tenant_registry()?,
+    ).with_read_diffusion(timeline_repo, fanout)
+        .with_offline_push(offline_push)
+        .with_mentions(mention_repo)
+        .with_priority(priority_resolver()?)))
+}
+
+/// 特性开关，Consul 不可用时回退到配置文件 `extensions.feature_flags`
+fn feature_flags() -> FeatureFlags {
+    let flags = FeatureFlags::new(Arc::new(ConsulFlagSource::new(&consul_settings())));
+    let fallback = Config::from_env_file::<PathBuf>(Environment::Development)
+        .and_then(|config| StaticFlagSource::from_config(&config));
+    match fallback {
+        Ok(fallback) => flags.with_fallback(fallback),
+        Err(e) => {
+            warn!("Failed to load fallback feature flags: {}", e);
+            flags
+        }
+    }
+}
+
+/// 租户配置，无配置文件时使用默认限额
+///
+/// 租户配置无效时拒绝启动，避免租户的检查开关与限额静默回退为默认值。
+fn tenant_registry() -> Result<Arc<TenantConfigRegistry>> {
+    match Config::from_env_file::<PathBuf>(Environment::Development) {
+        Ok(config) => Ok(Arc::new(TenantConfigRegistry::from_config(&config)?)),
+        Err(e) => {
+            warn!("Failed to load config, using default tenant settings: {}", e);
+            Ok(Arc::new(TenantConfigRegistry::default()))
+        }
+    }
+}
+
+/// 消息优先级规则，配置文件 `extensions.priority_rules` 无效时启动失败
+fn priority_resolver() -> Result<PriorityResolver> {
+    match Config::from_env_file::<PathBuf>(Environment::Development) {
+        Ok(config) => PriorityResolver::from_config(&config),
+        Err(e) => {
+            warn!("Failed to load config, using default priority rules: {}", e);
+            Ok(PriorityResolver::default())
+        }
+    }
 }
 
 fn get_service_addr() -> Result<String> {
     Ok("127.0.0.1:50052".to_string())
+}
+
+fn get_admin_addr() -> Result<String> {
+    Ok(std::env::var("ADMIN_ADDR").unwrap_or_else(|_| "127.0.0.1:50152".to_string()))
+}
+
+fn get_health_addr() -> Result<String> {
+    Ok(std::env::var("HEALTH_ADDR").unwrap_or_else(|_| "127.0.0.1:9052".to_string()))
 }
\ No newline at end of file
@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+
+use proto_crate::api::im::common::{DeadLetterMessage, MessagePayload};
+use serde::{Deserialize, Serialize};
+
+/// 死信原因分类在 `error_metadata` 中的键
+pub const DEAD_LETTER_REASON_KEY: &str = "reason";
+/// 超过最大重试次数
+pub const REASON_RETRY_EXHAUSTED: &str = "retry_exhausted";
+/// 未达到最大重试次数即判定无法处理
+pub const REASON_PROCESSING_FAILED: &str = "processing_failed";
+/// 未标注原因分类的死信
+pub const REASON_UNKNOWN: &str = "unknown";
+/// 消息体无法解码
+pub const REASON_UNDECODABLE: &str = "undecodable";
+/// 无法解码的消息体(十六进制)在 `error_metadata` 中的键
+pub const RAW_PAYLOAD_KEY: &str = "raw_payload";
+
+/// 无法解码的消息构建死信
+///
+/// 以 `{topic}/{partition}/{offset}` 作为消息ID，原始字节以十六进制保存在 `error_metadata` 中，
+/// 不含原始消息，不可重放。
+pub fn undecodable_dead_letter(source: &str, payload: &[u8], error: String, now: i64) -> DeadLetterMessage {
+    let raw_payload: String = payload.iter().map(|b| format!("{:02x}", b)).collect();
+    DeadLetterMessage {
+        original_message: Some(MessagePayload {
+            msg_id: source.to_string(),
+            msg: None,
+            timestamp: now,
+            metadata: HashMap::new(),
+        }),
+        error_reason: error,
+        retry_count: 0,
+        max_retry_count: 0,
+        last_retry_time: now,
+        dead_time: now,
+        error_metadata: HashMap::from([
+            (DEAD_LETTER_REASON_KEY.to_string(), REASON_UNDECODABLE.to_string()),
+            (RAW_PAYLOAD_KEY.to_string(), raw_payload),
+        ]),
+    }
+}
+
+/// 死信记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadLetterRecord {
+    /// 消息ID
+    pub message_id: String,
+    /// 原因分类
+    pub reason: String,
+    /// 死信内容
+    pub dead_letter: DeadLetterMessage,
+    /// 进入死信队列的时间（秒）
+    pub dead_time: i64,
+    /// 已重放次数
+    #[serde(default)]
+    pub replay_count: i32,
+    /// 最后一次重放时间（秒）
+    #[serde(default)]
+    pub last_replay_time: i64,
+}
+
+impl DeadLetterRecord {
+    /// 从死信消息构建记录，缺少原始消息时返回 None
+    pub fn from_dead_letter(dead_letter: DeadLetterMessage) -> Option<Self> {
+        let message_id = dead_letter.original_message.as_ref()?.msg_id.clone();
+        if message_id.is_empty() {
+            return None;
+        }
+        let reason = dead_letter
+            .error_metadata
+            .get(DEAD_LETTER_REASON_KEY)
+            .filter(|r| !r.is_empty())
+            .cloned()
+            .unwrap_or_else(|| REASON_UNKNOWN.to_string());
+
+        Some(Self {
+            message_id,
+            reason,
+            dead_time: dead_letter.dead_time,
+            dead_letter,
+            replay_count: 0,
+            last_replay_time: 0,
+        })
+    }
+}
+
+/// 死信查询条件
+#[derive(Debug, Clone, Default)]
+pub struct DeadLetterQuery {
+    /// 原因分类
+    pub reason: Option<String>,
+    /// 起始时间（秒，含）
+    pub start_time: Option<i64>,
+    /// 结束时间（秒，含）
+    pub end_time: Option<i64>,
+    pub offset: usize,
+    pub limit: usize,
+}
+
+/// 死信分页结果
+#[derive(Debug, Clone, Default)]
+pub struct DeadLetterPage {
+    /// 按进入时间倒序
+    pub entries: Vec<DeadLetterRecord>,
+    /// 满足条件的总数
+    pub total: u64,
+}
+
+/// 审计操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditAction {
+    Replay,
+    Purge,
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::Replay => "replay",
+            AuditAction::Purge => "purge",
+        }
+    }
+}
+
+/// 死信操作审计记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub action: AuditAction,
+    pub operator: String,
+    pub comment: String,
+    pub message_ids: Vec<String>,
+    /// 重放时修改的选项
+    #[serde(default)]
+    pub set_options: HashMap<String, String>,
+    /// 重放时删除的选项
+    #[serde(default)]
+    pub remove_options: Vec<String>,
+    pub succeeded: i32,
+    pub failed: i32,
+    /// 操作时间（毫秒）
+    pub time: i64,
+}
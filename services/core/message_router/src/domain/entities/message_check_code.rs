@@ -1,3 +1,7 @@
+use common::error::AppError;
+use super::RecipientPushResult;
+use proto_crate::api::im::common::ErrorCode;
+
 /// 预处理状态码
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PreProcessCode {
@@ -7,6 +11,10 @@ pub enum PreProcessCode {
     InvalidFormat = 1,
     /// 内容违规
     InvalidContent = 2,
+    /// @ 的用户不是群成员
+    InvalidMention = 3,
+    /// 内容待审核
+    UnderReview = 4,
 
     // 权限相关错误 (10-29)
     /// 非好友关系
@@ -27,6 +35,8 @@ pub enum PreProcessCode {
     UserBanned = 17,
     /// 设备已被封禁
     DeviceBanned = 18,
+    /// 无权 @所有人
+    AtAllNotAllowed = 19,
 
     // 业务规则限制 (30-49)
     /// 发送频率超限
@@ -39,6 +49,8 @@ pub enum PreProcessCode {
     ContentLengthLimit = 33,
     /// 附件大小超限
     AttachmentSizeLimit = 34,
+    /// @所有人次数超限
+    AtAllLimit = 35,
 
     // 系统错误 (50+)
     /// 系统内部错误
@@ -62,6 +74,8 @@ impl PreProcessCode {
             Self::Ok => "成功",
             Self::InvalidFormat => "消息格式错误",
             Self::InvalidContent => "内容违规",
+            Self::InvalidMention => "@ 的用户不是群成员",
+            Self::UnderReview => "内容待审核",
             Self::NotFriend => "不是好友关系",
             Self::InBlacklist => "在对方黑名单中",
             Self::NotGroupMember => "不是群成员",
@@ -71,11 +85,13 @@ impl PreProcessCode {
             Self::GroupDissolved => "群已解散",
             Self::UserBanned => "用户已被封禁",
             Self::DeviceBanned => "设备已被封禁",
+            Self::AtAllNotAllowed => "无权 @所有人",
             Self::FrequencyLimit => "发送频率超限",
             Self::PrivateMessageLimit => "单聊消息数量超限",
             Self::GroupMessageLimit => "群消息数量超限",
             Self::ContentLengthLimit => "消息长度超限",
             Self::AttachmentSizeLimit => "附件大小超限",
+            Self::AtAllLimit => "@所有人次数超限",
             Self::SystemError => "系统内部错误",
             Self::ServiceUnavailable => "服务暂时不可用",
             Self::DatabaseError => "数据库错误",
@@ -83,6 +99,37 @@ impl PreProcessCode {
         }
     }
 
+    /// 映射为统一错误码
+    ///
+    /// 校验类错误落在 `ROUTER_*` (4000 + 校验码) 区间，系统类错误复用通用错误码。
+    pub fn error_code(&self) -> ErrorCode {
+        match self {
+            Self::Ok => ErrorCode::Success,
+            Self::InvalidFormat => ErrorCode::RouterInvalidFormat,
+            Self::InvalidContent => ErrorCode::RouterInvalidContent,
+            Self::InvalidMention => ErrorCode::RouterInvalidMention,
+            Self::UnderReview => ErrorCode::FilterReviewRequired,
+            Self::NotFriend => ErrorCode::RouterNotFriend,
+            Self::InBlacklist => ErrorCode::RouterInBlacklist,
+            Self::NotGroupMember => ErrorCode::RouterNotGroupMember,
+            Self::Muted => ErrorCode::RouterMuted,
+            Self::GroupMuted => ErrorCode::RouterGroupMuted,
+            Self::Kicked => ErrorCode::RouterKicked,
+            Self::GroupDissolved => ErrorCode::RouterGroupDissolved,
+            Self::UserBanned => ErrorCode::RouterUserBanned,
+            Self::DeviceBanned => ErrorCode::RouterDeviceBanned,
+            Self::AtAllNotAllowed => ErrorCode::RouterAtAllNotAllowed,
+            Self::FrequencyLimit => ErrorCode::RouterFrequencyLimit,
+            Self::PrivateMessageLimit => ErrorCode::RouterPrivateMessageLimit,
+            Self::GroupMessageLimit => ErrorCode::RouterGroupMessageLimit,
+            Self::ContentLengthLimit => ErrorCode::RouterContentLengthLimit,
+            Self::AttachmentSizeLimit => ErrorCode::RouterAttachmentSizeLimit,
+            Self::AtAllLimit => ErrorCode::RouterAtAllLimit,
+            Self::SystemError | Self::DatabaseError | Self::CacheError => ErrorCode::SystemError,
+            Self::ServiceUnavailable => ErrorCode::ServiceUnavailable,
+        }
+    }
+
     /// 是否是权限相关错误
     pub fn is_permission_error(&self) -> bool {
         matches!(
@@ -96,6 +143,7 @@ impl PreProcessCode {
                 | Self::GroupDissolved
                 | Self::UserBanned
                 | Self::DeviceBanned
+                | Self::AtAllNotAllowed
         )
     }
 
@@ -108,6 +156,7 @@ impl PreProcessCode {
                 | Self::GroupMessageLimit
                 | Self::ContentLengthLimit
                 | Self::AttachmentSizeLimit
+                | Self::AtAllLimit
         )
     }
 
@@ -123,6 +172,12 @@ impl PreProcessCode {
     }
 }
 
+impl From<PreProcessCode> for AppError {
+    fn from(code: PreProcessCode) -> Self {
+        AppError::new(code.error_code(), code.description())
+    }
+}
+
 /// 消息处理结果
 #[derive(Debug)]
 pub struct MessageProcessResult {
@@ -134,4 +189,6 @@ pub struct MessageProcessResult {
     pub error: Option<String>,
     /// 路由信息
     pub routes: Vec<String>,
+    /// 各接收者推送结果
+    pub push_results: Vec<RecipientPushResult>,
 }
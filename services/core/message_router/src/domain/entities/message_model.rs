@@ -18,6 +18,30 @@ pub struct UserStatus {
     pub last_active_time: i64,
 }
 
+/// 发送配额上限
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SendQuota {
+    /// 每分钟发送上限
+    pub rate_per_minute: u32,
+    /// 会话每日消息上限，群聊与单聊分别取租户配置
+    pub daily: u32,
+    /// 群每日 @所有人 上限，只对 @所有人 消息生效
+    pub at_all_daily: u32,
+}
+
+/// 发送配额预占结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuotaVerdict {
+    /// 已计入配额，或该消息此前已计入
+    Reserved,
+    /// 超出发送频率
+    RateLimited,
+    /// 超出会话每日上限
+    DailyLimited,
+    /// 超出群每日 @所有人 上限
+    AtAllLimited,
+}
+
 /// 设备状态
 #[derive(Debug, Clone)]
 pub struct DeviceStatus {
@@ -106,3 +130,60 @@ pub struct Friendship {
     /// 更新时间
     pub update_time: Option<i64>,
 }
+
+/// 单个接收者的推送结果
+#[derive(Debug, Clone)]
+pub struct RecipientPushResult {
+    /// 接收者ID
+    pub user_id: String,
+    /// 推送所经网关地址
+    pub gateway: String,
+    /// 是否推送成功
+    pub success: bool,
+    /// 失败原因
+    pub error: Option<String>,
+    /// 已送达的设备
+    pub device_ids: Vec<String>,
+}
+
+/// 一次推送的全部结果
+#[derive(Debug, Clone, Default)]
+pub struct PushOutcome {
+    pub results: Vec<RecipientPushResult>,
+}
+
+impl PushOutcome {
+    /// 推送成功的接收者数量
+    pub fn delivered_count(&self) -> usize {
+        self.results.iter().filter(|r| r.success).count()
+    }
+
+    /// 至少一个网关推送成功的接收者
+    pub fn delivered_users(&self) -> std::collections::HashSet<&str> {
+        self.results
+            .iter()
+            .filter(|r| r.success)
+            .map(|r| r.user_id.as_str())
+            .collect()
+    }
+
+    /// 所有设备都推送失败的接收者
+    ///
+    /// 同一用户可能经多个网关推送，任一网关成功即视为已送达。
+    pub fn failed_users(&self) -> Vec<String> {
+        let delivered = self.delivered_users();
+        let mut failed: Vec<String> = self
+            .results
+            .iter()
+            .filter(|r| !r.success && !delivered.contains(r.user_id.as_str()))
+            .map(|r| r.user_id.clone())
+            .collect();
+        failed.sort();
+        failed.dedup();
+        failed
+    }
+
+    pub fn merge(&mut self, other: PushOutcome) {
+        self.results.extend(other.results);
+    }
+}
@@ -1,8 +1,11 @@
-
 mod message_status;
 mod message_check_code;
 mod message_model;
+mod dead_letter;
+mod scheduled_task;
 
 pub use message_status::*;
 pub use message_check_code::*;
-pub use message_model::*;
\ No newline at end of file
+pub use message_model::*;
+pub use dead_letter::*;
+pub use scheduled_task::*;
\ No newline at end of file
@@ -0,0 +1,43 @@
+use common::tenant::TenantContext;
+use proto_crate::api::im::common::MessageData;
+
+/// 定时任务类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ScheduledTaskKind {
+    /// 定时投递消息
+    Deliver,
+    /// 销毁到期消息
+    Expire,
+    /// 待人工审核的消息，审核通过后投递
+    Review,
+}
+
+impl ScheduledTaskKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Deliver => "deliver",
+            Self::Expire => "expire",
+            Self::Review => "review",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "deliver" => Some(Self::Deliver),
+            "expire" => Some(Self::Expire),
+            "review" => Some(Self::Review),
+            _ => None,
+        }
+    }
+}
+
+/// 到期的定时任务
+#[derive(Debug, Clone)]
+pub struct ScheduledTask {
+    /// 任务所属租户
+    pub tenant: TenantContext,
+    /// 任务类型
+    pub kind: ScheduledTaskKind,
+    /// 任务关联的消息
+    pub message: MessageData,
+}
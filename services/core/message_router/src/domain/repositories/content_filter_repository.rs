@@ -1,8 +1,10 @@
+use std::collections::HashMap;
+
 use async_trait::async_trait;
 use anyhow::Result;
 use proto_crate::api::im::common::MessageData;
 /// 内容过滤结果
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct FilterResult {
     /// 是否通过过滤
     pub passed: bool,
@@ -12,17 +14,43 @@ pub struct FilterResult {
     pub sensitive_words: Vec<String>,
     /// 风险等级
     pub risk_level: i32,
+    /// 替换敏感词后的消息内容，已按内容类型编码，可直接写回 `MessageData.content`
+    pub replaced_content: Option<Vec<u8>>,
+    /// 命中审核规则，消息暂缓投递等待人工审核
+    pub held: bool,
+    /// 过滤服务不可用且策略为拒绝
+    pub unavailable: bool,
+}
+
+impl FilterResult {
+    /// 直接放行
+    pub fn pass() -> Self {
+        Self {
+            passed: true,
+            ..Default::default()
+        }
+    }
 }
 
 /// 内容过滤仓储接口
+#[cfg_attr(test, mockall::automock)]
 #[async_trait]
 pub trait ContentFilterRepository: Send + Sync {
     /// 检查文本内容安全性
-    /// 
+    ///
     /// # 参数
     /// * `content` - 要检查的文本内容
-    /// 
+    ///
     /// # 返回
     /// * `Result<FilterResult, Error>` - 过滤结果
     async fn check(&self, message: &MessageData) -> Result<FilterResult>;
-}
\ No newline at end of file
+
+    /// 批量检查，结果按 server_msg_id 索引
+    async fn check_batch(&self, messages: &[MessageData]) -> Result<HashMap<String, FilterResult>> {
+        let mut results = HashMap::with_capacity(messages.len());
+        for message in messages {
+            results.insert(message.server_msg_id.clone(), self.check(message).await?);
+        }
+        Ok(results)
+    }
+}
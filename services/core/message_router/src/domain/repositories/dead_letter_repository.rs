@@ -0,0 +1,42 @@
+use async_trait::async_trait;
+use anyhow::Result;
+
+use crate::domain::entities::{AuditRecord, DeadLetterPage, DeadLetterQuery, DeadLetterRecord};
+
+/// 死信仓储接口
+///
+/// 按消息ID、原因分类与进入时间索引死信，记录按租户隔离。
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait DeadLetterRepository: Send + Sync {
+    /// 保存或更新死信记录
+    async fn save(&self, record: &DeadLetterRecord) -> Result<()>;
+
+    /// 按消息ID查询
+    async fn get(&self, message_id: &str) -> Result<Option<DeadLetterRecord>>;
+
+    /// 按原因分类与时间范围分页查询
+    async fn list(&self, query: &DeadLetterQuery) -> Result<DeadLetterPage>;
+
+    /// 已出现过的原因分类
+    async fn reasons(&self) -> Result<Vec<String>>;
+
+    /// 删除死信记录，返回实际删除的数量
+    async fn remove(&self, message_ids: &[String]) -> Result<u64>;
+
+    /// 申请重放配额
+    ///
+    /// # 参数
+    /// * `count` - 本次重放的消息数量
+    /// * `limit_per_minute` - 每分钟允许重放的消息数量
+    ///
+    /// # 返回
+    /// * `Result<bool, Error>` - 配额不足时返回 false，且不占用配额
+    async fn try_acquire_replay_quota(&self, count: u32, limit_per_minute: u32) -> Result<bool>;
+
+    /// 追加审计记录
+    async fn append_audit(&self, record: &AuditRecord) -> Result<()>;
+
+    /// 按时间倒序查询审计记录
+    async fn list_audit(&self, offset: usize, limit: usize) -> Result<Vec<AuditRecord>>;
+}
@@ -0,0 +1,34 @@
+use async_trait::async_trait;
+use anyhow::Result;
+
+/// 占用去重键的结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DedupClaim {
+    /// 首次发送，本次请求负责路由
+    Claimed,
+    /// 首次发送仍在处理中，值为首次分配的服务端消息ID
+    InFlight(String),
+    /// 首次发送已成功，值为首次分配的服务端消息ID
+    Completed(String),
+}
+
+/// 上行消息去重仓储
+///
+/// 以发送者、设备与客户端消息ID为键记录首次分配的服务端消息ID，记录按租户隔离。
+/// 键先以处理中状态短期占用，路由成功后确认并保留整个去重窗口，路由失败时释放。
+#[async_trait]
+pub trait MessageDedupRepository: Send + Sync {
+    /// 占用去重键
+    ///
+    /// # 参数
+    /// * `key` - 去重键
+    /// * `server_msg_id` - 本次分配的服务端消息ID
+    /// * `pending_ttl_secs` - 处理中状态的有效期（秒），实例异常退出后键在此之后可重新占用
+    async fn claim(&self, key: &str, server_msg_id: &str, pending_ttl_secs: u64) -> Result<DedupClaim>;
+
+    /// 确认路由成功，键在 `ttl_secs` 内保留
+    async fn confirm(&self, key: &str, server_msg_id: &str, ttl_secs: u64) -> Result<()>;
+
+    /// 释放去重键，仅当键仍属于该服务端消息ID且未确认时删除
+    async fn release(&self, key: &str, server_msg_id: &str) -> Result<()>;
+}
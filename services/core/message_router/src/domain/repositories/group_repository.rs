@@ -20,6 +20,7 @@ pub struct GroupMemberQuery {
 }
 
 /// 群仓储接口
+#[cfg_attr(test, mockall::automock)]
 #[async_trait]
 pub trait GroupRepository: Send + Sync {
     /// 检查群成员状态
@@ -93,71 +94,34 @@ pub trait GroupRepository: Send + Sync {
 
     /// 获取群成员数量
     async fn get_member_count(&self, group_id: &str) -> Result<i64>;
-}
-
-// 模拟实现
-pub struct MockGroupRepository;
-
-#[async_trait]
-impl GroupRepository for MockGroupRepository {
-    async fn check_member_status(&self, _group_id: &str, _user_id: &str) -> Result<MemberStatus> {
-        Ok(MemberStatus {
-            is_member: true,
-            is_muted: false,
-            mute_expire_time: None,
-            role: 0,
-            join_time: None,
-            last_speak_time: None,
-            nickname: None,
-        })
-    }
-
-    async fn get_group_members_paged(&self, _group_id: &str, query: GroupMemberQuery) -> Result<GroupMemberPage> {
-        Ok(GroupMemberPage {
-            members: vec!["user1".to_string(), "user2".to_string()],
-            total: 2,
-            cursor: None,
-            has_more: false,
-        })
-    }
 
-    async fn get_online_members_paged(&self, _group_id: &str, _page_size: i32, _cursor: Option<String>) -> Result<GroupMemberPage> {
-        Ok(GroupMemberPage {
-            members: vec!["user1".to_string()],
-            total: 1,
-            cursor: None,
-            has_more: false,
-        })
-    }
-
-    async fn get_group_status(&self, _group_id: &str) -> Result<GroupStatus> {
-        Ok(GroupStatus {
-            group_id: "group1".to_string(),
-            name: "Group 1".to_string(),
-            avatar: None,
-            description: None,
-            owner_id: "user1".to_string(),
-            is_active: true,
-            is_muted: false,
-            mute_expire_time: None,
-            create_time: 1633072800,
-            update_time: 1633072800,
-        })
-    }
-
-    async fn update_last_message(&self, _group_id: &str, _message: &MessageData) -> Result<()> {
-        Ok(())
-    }
-
-    async fn increment_unread_count(&self, _group_id: &str, _user_id: &str) -> Result<()> {
-        Ok(())
-    }
+    /// 获取用户加入的群
+    ///
+    /// # 参数
+    /// * `user_id` - 用户ID
+    ///
+    /// # 返回
+    /// * `Result<Vec<String>, Error>` - 群ID列表
+    async fn get_joined_groups(&self, user_id: &str) -> Result<Vec<String>>;
 
-    async fn dissolve_group(&self, _group_id: &str) -> Result<()> {
-        Ok(())
-    }
+    /// 更新用户在所在群的在线状态
+    ///
+    /// # 参数
+    /// * `user_id` - 用户ID
+    /// * `group_ids` - 用户所在的群
+    /// * `online` - 是否在线
+    ///
+    /// # 返回
+    /// * `Result<(), Error>` - 操作成功返回Ok(()),失败返回具体错误
+    async fn set_member_online(&self, user_id: &str, group_ids: &[String], online: bool) -> Result<()>;
 
-    async fn get_member_count(&self, _group_id: &str) -> Result<i64> {
-        Ok(2)
-    }
-} 
\ No newline at end of file
+    /// 从群在线成员中移除已无路由的成员
+    ///
+    /// # 参数
+    /// * `group_id` - 群ID
+    /// * `user_ids` - 用户ID列表
+    ///
+    /// # 返回
+    /// * `Result<(), Error>` - 操作成功返回Ok(()),失败返回具体错误
+    async fn remove_online_members(&self, group_id: &str, user_ids: &[String]) -> Result<()>;
+}
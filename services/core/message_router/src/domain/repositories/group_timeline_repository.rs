@@ -0,0 +1,49 @@
+use async_trait::async_trait;
+use anyhow::Result;
+use proto_crate::api::im::common::MessageData;
+
+/// 群时间线分页结果
+pub struct GroupTimelinePage {
+    pub messages: Vec<MessageData>, // 按序号升序
+    pub latest_seq: i64,            // 群最新序号
+    pub min_seq: i64,               // 时间线中最早可拉取的序号，更早的消息已裁剪或过期
+    pub has_more: bool,             // 是否还有更多
+}
+
+/// 群消息时间线仓储接口
+///
+/// 读扩散的群只写一份时间线，序号在群内单调递增，客户端按序号增量拉取。
+/// 时间线只保留最近的消息，拉取起点早于 `min_seq` 时其间的消息需从消息存储拉取。
+#[async_trait]
+pub trait GroupTimelineRepository: Send + Sync {
+    /// 追加消息，同一消息重复追加时返回首次分配的序号
+    ///
+    /// # 参数
+    /// * `group_id` - 群ID
+    /// * `message` - 群消息
+    ///
+    /// # 返回
+    /// * `Result<i64, Error>` - 分配的群内序号
+    async fn append(&self, group_id: &str, message: &MessageData) -> Result<i64>;
+
+    /// 拉取序号之后的消息
+    ///
+    /// # 参数
+    /// * `group_id` - 群ID
+    /// * `after_seq` - 从该序号（不含）之后开始
+    /// * `limit` - 最大返回数量
+    ///
+    /// # 返回
+    /// * `Result<GroupTimelinePage, Error>` - 时间线分页结果
+    async fn pull(&self, group_id: &str, after_seq: i64, limit: usize) -> Result<GroupTimelinePage>;
+
+    /// 从时间线中删除消息，序号不回收
+    ///
+    /// # 参数
+    /// * `group_id` - 群ID
+    /// * `message_id` - 服务端消息ID
+    ///
+    /// # 返回
+    /// * `Result<bool, Error>` - 消息在时间线中并已删除时返回 true
+    async fn remove(&self, group_id: &str, message_id: &str) -> Result<bool>;
+}
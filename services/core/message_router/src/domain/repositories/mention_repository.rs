@@ -0,0 +1,21 @@
+use async_trait::async_trait;
+use anyhow::Result;
+
+/// 会话 @ 未读计数仓储
+///
+/// 按用户记录每个会话中未读的 @ 提醒数量，计数按租户隔离；读取与清零由会话服务负责。
+#[async_trait]
+pub trait MentionRepository: Send + Sync {
+    /// 为被 @ 的用户增加会话的 @ 未读计数
+    ///
+    /// 同一 `record_id` 只计数一次，分发重试时不会重复累加。
+    ///
+    /// # 参数
+    /// * `record_id` - 计数批次ID，通常为消息ID
+    /// * `conversation_id` - 会话ID
+    /// * `user_ids` - 被 @ 的用户
+    ///
+    /// # 返回
+    /// 本次是否计入，批次已计入过时为 false
+    async fn increment(&self, record_id: &str, conversation_id: &str, user_ids: &[String]) -> Result<bool>;
+}
@@ -1,11 +1,12 @@
 use crate::domain::entities::MessageStatus;
 use crate::domain::repositories::RouteInfo;
-use crate::entities::{DeviceStatus, UserStatus};
+use crate::entities::{DeviceStatus, PushOutcome, QuotaVerdict, SendQuota, UserStatus};
 use anyhow::Result;
 use async_trait::async_trait;
 use proto_crate::api::im::common::MessageData;
 
 /// 消息仓储接口
+#[cfg_attr(test, mockall::automock)]
 #[async_trait]
 pub trait MessageRepository: Send + Sync {
     /// 保存消息
@@ -18,18 +19,31 @@ pub trait MessageRepository: Send + Sync {
     async fn save_message(&self, message: &MessageData) -> Result<()>;
     /// 处理消息分发
     ///
-    /// 包含:
+    /// 按 `options["priority"]` 投递到对应优先级的分发通道，包含:
     /// - 消息分发到各个网关
     /// - 消息分发到各个设备
     async fn handle_message_distribution(&self, message: &MessageData) -> Result<()>;
+
+    /// 延迟重试
+    ///
+    /// 消息进入与 `delay_ms` 对应的延迟重试主题，到达 `options["next_retry_time"]` 后
+    /// 由重试消费者重新投递到原优先级的分发通道。
+    async fn schedule_retry(&self, message: &MessageData, delay_ms: i64) -> Result<()>;
     /// 推送消息到网关
     ///
-    async fn push_message(&self, message: &MessageData,routers:Vec<RouteInfo>) -> Result<()>;
+    /// 按网关分组批量推送，返回每个接收者的推送结果；
+    /// 网关不可达或超时时，该网关上的接收者均记为失败。
+    async fn push_message(&self, message: &MessageData, routers: Vec<RouteInfo>) -> Result<PushOutcome>;
 
     /// 发送离线通知
     ///
     async fn send_offline_notification(&self,userid :&str, message: &MessageData) -> Result<()>;
 
+    /// 提交群消息的离线推送任务
+    ///
+    /// 写入离线推送任务主题，以群为键，由离线推送消费者限速通知离线成员。
+    async fn enqueue_offline_push(&self, message: &MessageData) -> Result<()>;
+
     /// 更新消息状态
     /// 
     /// # 参数
@@ -40,6 +54,11 @@ pub trait MessageRepository: Send + Sync {
     /// * `Result<(), Error>` - 更新成功返回Ok(()),失败返回具体错误
     async fn update_message_status(&self, message_id: &str, status: MessageStatus) -> Result<()>;
 
+    /// 从存储中删除消息，并以 `status` 发布状态事件
+    ///
+    /// 状态主题只通知关注方，存储不消费状态事件，销毁/删除必须经由此方法。
+    async fn delete_message(&self, message: &MessageData, status: MessageStatus) -> Result<()>;
+
     /// 获取用户最近的消息数量
     /// 
     /// # 参数
@@ -59,6 +78,15 @@ pub trait MessageRepository: Send + Sync {
     /// * `Result<i32, Error>` - 今日消息数量
     async fn get_group_daily_message_count(&self, group_id: &str) -> Result<i32>;
 
+    /// 获取群聊每日 @所有人 次数
+    /// 
+    /// # 参数
+    /// * `group_id` - 群ID
+    /// 
+    /// # 返回
+    /// * `Result<i32, Error>` - 今日 @所有人 次数
+    async fn get_group_at_all_daily_count(&self, group_id: &str) -> Result<i32>;
+
     /// 获取私聊每日消息数量
     /// 
     /// # 参数
@@ -69,6 +97,25 @@ pub trait MessageRepository: Send + Sync {
     /// * `Result<i32, Error>` - 今日消息数量
     async fn get_private_daily_message_count(&self, sender_id: &str, receiver_id: &str) -> Result<i32>;
 
+    /// 检查并计入发送配额，更新发送频率、每日数量与 @所有人 计数
+    ///
+    /// 检查与计数原子执行，超出任一上限时不计数。按 server_msg_id 只计一次，
+    /// 定时投递、审核放行与死信重放时再次检查不重复计数。
+    ///
+    /// # 参数
+    /// * `message` - 通过其他检查的消息
+    /// * `quota` - 租户的配额上限
+    ///
+    /// # 返回
+    /// * `Result<QuotaVerdict, Error>` - 预占结果
+    async fn reserve_send_quota(&self, message: &MessageData, quota: SendQuota) -> Result<QuotaVerdict>;
+
+    /// 退回未送达消息占用的配额，未计入的消息忽略
+    ///
+    /// # 参数
+    /// * `message` - 路由失败的消息
+    async fn release_send_quota(&self, message: &MessageData) -> Result<()>;
+
     /// 获取用户状态
     /// 
     /// # 参数
@@ -2,10 +2,20 @@ mod message_repository;
 mod route_repository;
 mod friend_repository;
 mod group_repository;
+mod group_timeline_repository;
 mod content_filter_repository;
+mod dead_letter_repository;
+mod dedup_repository;
+mod mention_repository;
+mod schedule_repository;
 
 pub use message_repository::*;
 pub use route_repository::*;
 pub use friend_repository::*;
 pub use group_repository::*;
+pub use group_timeline_repository::*;
 pub use content_filter_repository::*;
+pub use dead_letter_repository::*;
+pub use dedup_repository::*;
+pub use mention_repository::*;
+pub use schedule_repository::*;
@@ -5,6 +5,14 @@ use std::collections::HashMap;
 /// 路由信息
 #[derive(Debug, Clone)]
 pub struct RouteInfo {
+    /// 用户ID
+    pub user_id: String,
+    /// 设备ID
+    pub device_id: String,
+    /// 客户端平台
+    pub platform: i32,
+    /// 网关实例ID
+    pub gateway_id: String,
     /// 网关地址
     pub address: String,
     /// 权重
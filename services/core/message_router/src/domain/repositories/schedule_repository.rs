@@ -0,0 +1,42 @@
+use async_trait::async_trait;
+use anyhow::Result;
+use proto_crate::api::im::common::MessageData;
+
+use crate::domain::entities::{ScheduledTask, ScheduledTaskKind};
+
+/// 定时任务仓储
+///
+/// 保存定时投递、到期销毁与待审核任务，按触发时间取出到期任务。任务按租户隔离，
+/// 到期索引跨租户共享，由调度器统一取出后在任务所属租户下执行。
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait MessageScheduleRepository: Send + Sync {
+    /// 保存任务
+    ///
+    /// # 参数
+    /// * `kind` - 任务类型
+    /// * `message` - 任务关联的消息
+    /// * `fire_at` - 触发时间（毫秒），为 None 时只保存，由 [`arm`](Self::arm) 设置触发时间
+    async fn schedule(&self, kind: ScheduledTaskKind, message: &MessageData, fire_at: Option<i64>) -> Result<()>;
+
+    /// 为已保存的任务设置触发时间，已设置过的任务保持原触发时间
+    ///
+    /// # 返回
+    /// 任务存在且本次设置了触发时间时返回 true
+    async fn arm(&self, kind: ScheduledTaskKind, message_id: &str, fire_at: i64) -> Result<bool>;
+
+    /// 获取任务关联的消息
+    async fn get(&self, kind: ScheduledTaskKind, message_id: &str) -> Result<Option<MessageData>>;
+
+    /// 取消任务，任务不存在时返回 false
+    async fn cancel(&self, kind: ScheduledTaskKind, message_id: &str) -> Result<bool>;
+
+    /// 取出到期任务
+    ///
+    /// 取出的任务在 `lease_ms` 内不会被再次取出，执行完成后需调用 [`complete`](Self::complete)，
+    /// 未完成的任务租约到期后重新触发。
+    async fn claim_due(&self, now: i64, lease_ms: i64, limit: usize) -> Result<Vec<ScheduledTask>>;
+
+    /// 完成任务
+    async fn complete(&self, task: &ScheduledTask) -> Result<()>;
+}
@@ -0,0 +1,31 @@
+//! 消息销毁
+//!
+//! 定时销毁的消息到期后经存储服务删除，并发布 `Expired` 状态；
+//! 同时向会话各方下发销毁通知，客户端收到后删除本地消息。
+
+use std::collections::HashMap;
+
+use proto_crate::api::im::common::{ContentType, MessageData};
+
+/// 销毁通知中被销毁消息ID的 options 键
+pub const EXPIRED_MSG_OPTION: &str = "expired_msg_id";
+
+/// 销毁通知
+///
+/// 沿用原消息的会话信息，不含消息内容；通知本身不离线推送，离线设备同步时按消息状态删除。
+pub fn expire_notification(message: &MessageData) -> MessageData {
+    MessageData {
+        send_id: message.send_id.clone(),
+        recv_id: message.recv_id.clone(),
+        group_id: message.group_id.clone(),
+        server_msg_id: format!("{}_expired", message.server_msg_id),
+        session_type: message.session_type,
+        content_type: ContentType::NotificationMsg as i32,
+        send_time: chrono::Utc::now().timestamp_millis() as u64,
+        options: HashMap::from([
+            (EXPIRED_MSG_OPTION.to_string(), message.server_msg_id.clone()),
+            ("need_offline_push".to_string(), "false".to_string()),
+        ]),
+        ..Default::default()
+    }
+}
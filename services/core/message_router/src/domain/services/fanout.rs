@@ -0,0 +1,112 @@
+//! 群消息扇出策略
+//!
+//! 写扩散把消息逐一推送给每个成员的在线路由，适合小群；
+//! 读扩散只写一份群时间线，向在线成员推送"新消息序号"通知，由客户端拉取，
+//! 适合超级群等成员众多的群。策略按会话类型与成员数选择，
+//! 可通过特性开关 `group_fanout_strategy` 按租户强制指定或调整阈值。
+
+use std::collections::HashMap;
+
+use common::feature::FeatureFlags;
+use proto_crate::api::im::common::{ContentType, MessageData, SessionType};
+use serde::{Deserialize, Serialize};
+
+/// 读扩散通知中携带新消息序号的 options 键
+pub const NEW_SEQ_OPTION: &str = "new_seq";
+
+/// 读扩散的新消息通知
+///
+/// 只携带群ID、消息ID与群内序号，不含消息内容，客户端收到后按序号拉取。
+pub fn seq_notification(message: &MessageData, seq: i64) -> MessageData {
+    MessageData {
+        send_id: message.send_id.clone(),
+        group_id: message.group_id.clone(),
+        server_msg_id: message.server_msg_id.clone(),
+        session_type: message.session_type,
+        content_type: ContentType::NotificationMsg as i32,
+        send_time: message.send_time,
+        create_time: message.create_time,
+        seq,
+        options: HashMap::from([(NEW_SEQ_OPTION.to_string(), seq.to_string())]),
+        ..Default::default()
+    }
+}
+
+/// 扇出策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FanoutStrategy {
+    /// 写扩散
+    WriteDiffusion,
+    /// 读扩散
+    ReadDiffusion,
+}
+
+/// 扇出策略配置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FanoutConfig {
+    /// 成员数达到该值的群使用读扩散
+    pub read_diffusion_min_members: i64,
+    /// 始终使用读扩散的会话类型
+    pub read_diffusion_session_types: Vec<i32>,
+}
+
+impl Default for FanoutConfig {
+    fn default() -> Self {
+        Self {
+            read_diffusion_min_members: 2000,
+            read_diffusion_session_types: vec![SessionType::SuperGroup as i32],
+        }
+    }
+}
+
+/// 特性开关取值：策略名强制指定策略，对象则替换配置
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum FanoutFlagValue {
+    Strategy(FanoutStrategy),
+    Config(FanoutConfig),
+}
+
+/// 扇出策略选择
+#[derive(Clone, Default)]
+pub struct FanoutPolicy {
+    config: FanoutConfig,
+    flags: Option<FeatureFlags>,
+}
+
+impl FanoutPolicy {
+    pub fn new(config: FanoutConfig) -> Self {
+        Self { config, flags: None }
+    }
+
+    /// 按租户读取 `group_fanout_strategy` 开关
+    pub fn with_flags(mut self, flags: FeatureFlags) -> Self {
+        self.flags = Some(flags);
+        self
+    }
+
+    /// 为群消息选择扇出策略
+    pub fn choose(&self, message: &MessageData, member_count: i64) -> FanoutStrategy {
+        let flag = self
+            .flags
+            .as_ref()
+            .and_then(|flags| flags.value::<FanoutFlagValue>(FeatureFlags::GROUP_FANOUT_STRATEGY));
+        match flag {
+            Some(FanoutFlagValue::Strategy(strategy)) => strategy,
+            Some(FanoutFlagValue::Config(config)) => Self::choose_with(&config, message, member_count),
+            None => Self::choose_with(&self.config, message, member_count),
+        }
+    }
+
+    fn choose_with(config: &FanoutConfig, message: &MessageData, member_count: i64) -> FanoutStrategy {
+        if config.read_diffusion_session_types.contains(&message.session_type)
+            || member_count >= config.read_diffusion_min_members
+        {
+            FanoutStrategy::ReadDiffusion
+        } else {
+            FanoutStrategy::WriteDiffusion
+        }
+    }
+}
@@ -2,27 +2,49 @@ use crate::domain::{
     entities::MessageStatus,
     repositories::{
         MessageRepository, RouteRepository, FriendRepository, GroupRepository,
-        ContentFilterRepository,
+        ContentFilterRepository, GroupMemberQuery, GroupTimelinePage, GroupTimelineRepository,
+        MentionRepository,
     },
 };
 use async_trait::async_trait;
 use std::sync::Arc;
 use anyhow::Result;
-use log::{info, error, warn};
-use common::utils::msg_utils::is_group_message;
-use proto_crate::api::im::common::MessageData;
-use crate::domain::repositories::GroupMemberQuery;
-use crate::entities::{MessageProcessResult, PreProcessCode};
+use log::{debug, info, error, warn};
+use common::error::AppError;
+use common::tenant::{self, TenantConfigRegistry};
+use common::utils::msg_utils::{at_type, conversation_id, is_group_message, mentioned_users};
+use proto_crate::api::im::common::{AtType, ErrorCode, MessageData, MessagePriority};
+use proto_crate::api::im::service::router::PriorityRules;
+use crate::entities::{MessageProcessResult, PreProcessCode, PushOutcome};
 use crate::services::MessageService;
+use super::pre_check::{
+    BanStatusStage, ContentSecurityStage, FormatStage, FriendshipStage, GroupPermissionStage,
+    MentionStage, PreCheckPipeline, RateLimitStage,
+};
+use super::expiry::expire_notification;
+use super::fanout::{seq_notification, FanoutPolicy, FanoutStrategy};
+use super::offline_push::{force_push, OfflinePushScheduler};
+use super::priority::PriorityResolver;
+
+/// 拉取群时间线的默认与最大数量
+const DEFAULT_PULL_LIMIT: usize = 50;
+const MAX_PULL_LIMIT: usize = 200;
 
 
 
 pub struct MessageServiceImpl {
     message_repository: Arc<dyn MessageRepository>,
     route_repository: Arc<dyn RouteRepository>,
-    friend_repository: Arc<dyn FriendRepository>,
     group_repository: Arc<dyn GroupRepository>,
-    content_filter_repository: Arc<dyn ContentFilterRepository>,
+    pre_check: PreCheckPipeline,
+    priority: PriorityResolver,
+    /// 读扩散的群时间线，未配置时群消息均使用写扩散
+    timeline: Option<Arc<dyn GroupTimelineRepository>>,
+    fanout: FanoutPolicy,
+    /// 群消息离线推送任务，未配置时写扩散只推送在线成员
+    offline_push: Option<OfflinePushScheduler>,
+    /// 会话 @ 未读计数，未配置时不记录
+    mentions: Option<Arc<dyn MentionRepository>>,
 }
 
 impl MessageServiceImpl {
@@ -32,219 +54,266 @@ impl MessageServiceImpl {
         friend_repository: Arc<dyn FriendRepository>,
         group_repository: Arc<dyn GroupRepository>,
         content_filter_repository: Arc<dyn ContentFilterRepository>,
+        tenants: Arc<TenantConfigRegistry>,
     ) -> Self {
+        let pre_check = PreCheckPipeline::new(tenants)
+            .with_stage(Arc::new(FormatStage))
+            .with_stage(Arc::new(ContentSecurityStage::new(content_filter_repository)))
+            .with_stage(Arc::new(FriendshipStage::new(friend_repository)))
+            .with_stage(Arc::new(GroupPermissionStage::new(group_repository.clone())))
+            .with_stage(Arc::new(MentionStage::new(group_repository.clone(), message_repository.clone())))
+            .with_stage(Arc::new(BanStatusStage::new(message_repository.clone())))
+            // 计入配额，须在最后
+            .with_stage(Arc::new(RateLimitStage::new(message_repository.clone())));
+
         Self {
             message_repository,
             route_repository,
-            friend_repository,
             group_repository,
-            content_filter_repository,
+            pre_check,
+            priority: PriorityResolver::default(),
+            timeline: None,
+            fanout: FanoutPolicy::default(),
+            offline_push: None,
+            mentions: None,
         }
     }
 
-    /// 检查是否需要离线推送
-    fn need_offline_push(&self, message: &MessageData) -> bool {
-        // 检查消息配置
-        message.options.get("need_offline_push")
-            .map(|v| v == "true")
-            .unwrap_or(true) // 默认开启离线推送
+    /// 替换预处理检查链
+    pub fn with_pre_check(mut self, pre_check: PreCheckPipeline) -> Self {
+        self.pre_check = pre_check;
+        self
     }
 
-    /// 检查消息是否需要重试
-    fn need_retry(&self, message: &MessageData) -> bool {
-        message.options.get("need_retry")
-            .map(|v| v == "true")
-            .unwrap_or(true) // 默认开启重试
+    /// 替换优先级计算器
+    pub fn with_priority(mut self, priority: PriorityResolver) -> Self {
+        self.priority = priority;
+        self
     }
 
-    /// 检查消息格式
-    fn check_message_format(&self, message: &MessageData) -> bool {
-        // 检查必要字段
-        if message.server_msg_id.is_empty() || message.send_id.is_empty() {
-            return false;
-        }
-
-        // 检查消息内容
-        if message.content.is_empty() {
-            return false;
-        }
-
-        true
+    /// 启用读扩散，按扇出策略为大群写入时间线并只推送序号通知
+    pub fn with_read_diffusion(mut self, timeline: Arc<dyn GroupTimelineRepository>, fanout: FanoutPolicy) -> Self {
+        self.timeline = Some(timeline);
+        self.fanout = fanout;
+        self
     }
-    async fn check_content_security(&self, message: &MessageData) -> Result<bool> {
-        // 1. 检查文本内容
-        let text_result = self.content_filter_repository
-            .check(message)
-            .await?;
 
-        if !text_result.passed {
-            warn!(
-                "Message content security check failed: {:?}, reason: {:?}",
-                message.server_msg_id,
-                text_result.reason
-            );
-            return Ok(false);
-        }
-
-        Ok(true)
+    /// 启用群消息离线推送任务
+    pub fn with_offline_push(mut self, scheduler: OfflinePushScheduler) -> Self {
+        self.offline_push = Some(scheduler);
+        self
     }
 
-    async fn check_permission(&self, message: &MessageData) -> Result<PreProcessCode> {
-        if is_group_message(message){
-            // 检查群成员权限
-            let member_status = self.group_repository
-                .check_member_status(&message.group_id, &message.send_id)
-                .await?;
-
-            if !member_status.is_member {
-                return Ok(PreProcessCode::NotGroupMember);
-            }
-
-            if member_status.is_muted {
-                return Ok(PreProcessCode::Muted);
-            }
-
-        } else {
-            // 检查好友关系
-            let friendship = self.friend_repository
-                .check_friendship(&message.send_id, &message.recv_id)
-                .await?;
-
-            if !friendship.is_friend {
-                return Ok(PreProcessCode::NotFriend);
-            }
-
-            if friendship.in_blacklist {
-                return Ok(PreProcessCode::InBlacklist);
-            }
-        }
-
-        Ok(PreProcessCode::Ok)
+    /// 启用会话 @ 未读计数
+    pub fn with_mentions(mut self, mentions: Arc<dyn MentionRepository>) -> Self {
+        self.mentions = Some(mentions);
+        self
     }
 
-    async fn check_business_rules(&self, message: &MessageData) -> Result<PreProcessCode> {
-        // 1. 检查消息频率限制
-        if let Some(limit) = message.options.get("rate_limit") {
-            let rate_limit = limit.parse::<i32>().unwrap_or(10); // 默认每分钟10条
-            let count = self.message_repository
-                .get_recent_message_count(&message.send_id, 60)
-                .await?;
-            
-            if count >= rate_limit {
-                return Ok(PreProcessCode::FrequencyLimit);
-            }
-        }
+    /// 记录被 @ 用户的会话 @ 未读计数
+    ///
+    /// 按消息ID去重，分发重试不会重复计数；@所有人 需要遍历全部群成员，
+    /// 在后台分批写入不阻塞路由，每批以 `{server_msg_id}:{批次}` 去重。
+    async fn record_mentions(&self, message: &MessageData) -> Result<()> {
+        const BATCH_SIZE: i32 = 1000;
 
-        // 2. 检查消息大小限制
-        let content_size = message.content.len();
-        if content_size > 1024 * 1024 { // 1MB
-            return Ok(PreProcessCode::ContentLengthLimit);
+        let Some(mentions) = &self.mentions else {
+            return Ok(());
+        };
+        if !is_group_message(message) {
+            return Ok(());
         }
-
-        // 3. 检查附件大小
-        if let Some(attachment_size) = message.options.get("attachment_size") {
-            let size = attachment_size.parse::<i64>().unwrap_or(0);
-            if size > 100 * 1024 * 1024 { // 100MB
-                return Ok(PreProcessCode::AttachmentSizeLimit);
+        let conversation = conversation_id(message);
+        match at_type(message) {
+            AtType::AtAll => {
+                let mentions = mentions.clone();
+                let group_repository = self.group_repository.clone();
+                let message = message.clone();
+                tokio::spawn(tenant::scope(tenant::current(), async move {
+                    let mut cursor = None;
+                    for batch in 0.. {
+                        let query = GroupMemberQuery {
+                            page_size: BATCH_SIZE,
+                            cursor,
+                            role_filter: None,
+                            active_only: true,
+                        };
+                        let page = match group_repository.get_group_members_paged(&message.group_id, query).await {
+                            Ok(page) => page,
+                            Err(e) => {
+                                error!("Failed to page members of group {}: {}", message.group_id, e);
+                                break;
+                            }
+                        };
+                        let members: Vec<String> = page.members
+                            .into_iter()
+                            .filter(|id| id != &message.send_id)
+                            .collect();
+                        let record_id = format!("{}:{}", message.server_msg_id, batch);
+                        if let Err(e) = mentions.increment(&record_id, &conversation, &members).await {
+                            error!("Failed to record @all for group {}: {}", message.group_id, e);
+                            break;
+                        }
+                        if !page.has_more {
+                            break;
+                        }
+                        cursor = page.cursor;
+                    }
+                }));
+                Ok(())
             }
+            AtType::AtUser | AtType::AtYou => mentions
+                .increment(&message.server_msg_id, &conversation, &mentioned_users(message))
+                .await
+                .map(|_| ()),
+            AtType::None => Ok(()),
         }
+    }
 
-        // 4. 检查会话消息数量限制
-        if is_group_message(message) {
-            // 群聊消息限制
-            let daily_limit = message.options.get("group_daily_limit")
-                .and_then(|v| v.parse::<i32>().ok())
-                .unwrap_or(1000); // 默认群聊每天1000条
-
-            let daily_count = self.message_repository
-                .get_group_daily_message_count(&message.group_id)
-                .await?;
-
-            if daily_count >= daily_limit {
-                return Ok(PreProcessCode::GroupMessageLimit);
+    /// @ 提醒的强制离线推送
+    ///
+    /// 未在线送达的被 @ 用户直接推送，不受群冷却限制并忽略会话免打扰；
+    /// @所有人 交给离线推送任务强制推送全部离线成员，任务提交失败时返回错误由分发重试。
+    async fn push_mentions(&self, message: &MessageData, outcome: &PushOutcome) -> Result<()> {
+        match at_type(message) {
+            AtType::AtAll => match &self.offline_push {
+                Some(scheduler) => scheduler.enqueue_forced(message).await?,
+                None => debug!("Offline push is not configured, skipping @all of {}", message.server_msg_id),
+            },
+            AtType::AtUser | AtType::AtYou => {
+                let delivered = outcome.delivered_users();
+                let pending: Vec<String> = mentioned_users(message)
+                    .into_iter()
+                    .filter(|id| !delivered.contains(id.as_str()))
+                    .collect();
+                if pending.is_empty() {
+                    return Ok(());
+                }
+                let message_repo = self.message_repository.clone();
+                let message = force_push(message);
+                tokio::spawn(tenant::scope(tenant::current(), async move {
+                    for user_id in &pending {
+                        if let Err(e) = message_repo.send_offline_notification(user_id, &message).await {
+                            error!("Failed to send mention notification to {}: {}", user_id, e);
+                        }
+                    }
+                }));
             }
+            AtType::None => {}
+        }
+        Ok(())
+    }
 
-            // 检查群状态
-            let group_status = self.group_repository
-                .get_group_status(&message.group_id)
+    /// 读扩散群消息处理
+    ///
+    /// 消息只写入一次群时间线，向在线成员推送携带序号的轻量通知；
+    /// 离线成员上线后按序号拉取，不逐一离线推送。
+    async fn handle_group_read_diffusion(
+        &self,
+        message: &MessageData,
+        timeline: &Arc<dyn GroupTimelineRepository>,
+    ) -> Result<MessageProcessResult> {
+        const BATCH_SIZE: i32 = 1000;
+
+        // 1. 写入群时间线，失败时交由重试；后续步骤失败重试时沿用首次分配的序号
+        let seq = timeline.append(&message.group_id, message).await?;
+        let notification = seq_notification(message, seq);
+
+        // 2. 分批向在线成员推送序号通知
+        let mut outcome = PushOutcome::default();
+        let mut addresses = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = self.group_repository
+                .get_online_members_paged(&message.group_id, BATCH_SIZE, cursor)
                 .await?;
-            
-            if !group_status.is_active {
-                return Ok(PreProcessCode::GroupDissolved);
-            }
+            let members: Vec<String> = page.members
+                .into_iter()
+                .filter(|id| id != &message.send_id)
+                .collect();
 
-            if group_status.is_muted {
-                return Ok(PreProcessCode::GroupMuted);
+            if !members.is_empty() {
+                match self.route_repository.get_routes_with_weight_batch(&members).await {
+                    Ok(routes_map) => {
+                        let routes: Vec<_> = routes_map.into_values().flatten().collect();
+                        addresses.extend(routes.iter().map(|r| r.address.clone()));
+                        if !routes.is_empty() {
+                            match self.message_repository.push_message(&notification, routes).await {
+                                Ok(batch_outcome) => outcome.merge(batch_outcome),
+                                Err(e) => error!("Failed to push seq notification batch: {}", e),
+                            }
+                        }
+                    }
+                    Err(e) => error!("Failed to get routes for batch: {}", e),
+                }
             }
 
-        } else {
-            // 单聊消息限制
-            let daily_limit = message.options.get("private_daily_limit")
-                .and_then(|v| v.parse::<i32>().ok())
-                .unwrap_or(200); // 默认私聊每天200条
-
-            let daily_count = self.message_repository
-                .get_private_daily_message_count(&message.send_id, &message.recv_id)
-                .await?;
-
-            if daily_count >= daily_limit {
-                return Ok(PreProcessCode::PrivateMessageLimit);
+            if !page.has_more {
+                break;
             }
+            cursor = page.cursor;
         }
 
-        // 5. 检查用户状态
-        let user_status = self.message_repository
-            .get_user_status(&message.send_id)
-            .await?;
-
-        if user_status.is_banned {
-            return Ok(PreProcessCode::UserBanned);
+        // 大群不逐一离线推送，@ 提醒仍需送达
+        if self.need_offline_push(message) {
+            self.push_mentions(message, &outcome).await?;
         }
 
-        // 6. 检查设备状态
-        if let Some(device_id) = message.options.get("device_id") {
-            let device_status = self.message_repository
-                .get_device_status(device_id)
-                .await?;
+        // 通知失败不重试，消息已在时间线中，客户端下次拉取时补齐
+        debug!(
+            "Group message {} appended at seq {}, notified {} routes",
+            message.server_msg_id,
+            seq,
+            outcome.delivered_count()
+        );
+        Ok(MessageProcessResult {
+            message_id: message.server_msg_id.clone(),
+            success: true,
+            error: None,
+            routes: addresses,
+            push_results: outcome.results,
+        })
+    }
 
-            if device_status.is_banned {
-                return Ok(PreProcessCode::DeviceBanned);
-            }
-        }
-        Ok(PreProcessCode::Ok)
+    /// 检查是否需要离线推送
+    fn need_offline_push(&self, message: &MessageData) -> bool {
+        // 检查消息配置
+        message.options.get("need_offline_push")
+            .map(|v| v == "true")
+            .unwrap_or(true) // 默认开启离线推送
     }
 
+    /// 检查消息是否需要重试
+    fn need_retry(&self, message: &MessageData) -> bool {
+        message.options.get("need_retry")
+            .map(|v| v == "true")
+            .unwrap_or(true) // 默认开启重试
+    }
 }
 
 #[async_trait]
 impl MessageService for MessageServiceImpl {
-    async fn pre_process(&self, message: &MessageData) -> Result<PreProcessCode> {
-        // 1. 基础格式校验
-        if !self.check_message_format(message) {
-            return Ok(PreProcessCode::InvalidFormat);
-        }
-
-        // 2. 内容安全检查
-        if !self.check_content_security(message).await? {
-            return Ok(PreProcessCode::InvalidContent);
+    async fn pre_process(&self, message: &mut MessageData) -> Result<PreProcessCode> {
+        let report = self.pre_check.run(message).await?;
+        if let Some(stage) = report.rejected_by {
+            info!(
+                "Message {} rejected by pre-check stage {}: {:?}",
+                message.server_msg_id, stage, report.code
+            );
         }
-
-        // 3. 权限校验
-        self.check_permission(message).await?;
-
-        // 4. 业务规则校验
-        self.check_business_rules(message).await?;
-
-        Ok(PreProcessCode::Ok)
+        Ok(report.code)
     }
 
 
     async fn handle_message(&self, message: &MessageData) -> Result<MessageProcessResult> {
         // 1. 获取接收方路由信息
         let routes = self.route_repository.get_routes_with_weight(&message.recv_id).await?;
-        
+        let addresses: Vec<String> = routes.iter().map(|r| r.address.clone()).collect();
+
         // 2. 处理消息推送
         let mut error_msg = None;
+        let mut success = true;
+        let mut push_results = Vec::new();
         if routes.is_empty() {
             // 如果用户离线，发送离线通知
             if self.need_offline_push(message) {
@@ -253,15 +322,27 @@ impl MessageService for MessageServiceImpl {
             }
         } else {
             // 如果用户在线，推送消息到网关
-            self.message_repository.push_message(message, routes.clone()).await?;
+            let outcome = self.message_repository.push_message(message, routes).await?;
+            if !outcome.failed_users().is_empty() {
+                // 推送失败时转离线推送，未开启离线推送则交由重试
+                if self.need_offline_push(message) {
+                    self.message_repository.send_offline_notification(&message.recv_id, message).await?;
+                    error_msg = Some("Push to gateway failed, message queued for offline push".to_string());
+                } else {
+                    success = false;
+                    error_msg = Some("Push to gateway failed".to_string());
+                }
+            }
+            push_results = outcome.results;
         }
 
         // 3. 构建处理结果
         Ok(MessageProcessResult {
             message_id: message.server_msg_id.clone(),
-            success: true,
+            success,
             error: error_msg,
-            routes: routes.into_iter().map(|r| r.address).collect(),
+            routes: addresses,
+            push_results,
         })
     }
 
@@ -270,24 +351,21 @@ impl MessageService for MessageServiceImpl {
         
         // 1. 获取群成员总数
         let total_members = self.group_repository.get_member_count(&message.group_id).await?;
+
+        // 大群与超级群使用读扩散
+        if let Some(timeline) = &self.timeline {
+            if self.fanout.choose(message, total_members) == FanoutStrategy::ReadDiffusion {
+                return self.handle_group_read_diffusion(message, timeline).await;
+            }
+        }
         
-        let mut all_routes = Vec::new();
-        let mut offline_members = Vec::new();
+        // 2. 分批获取在线成员并推送，离线成员交给离线推送任务
+        let mut outcome = PushOutcome::default();
+        let mut addresses = Vec::new();
         let mut cursor = None;
-        let mut success = false;
-
-        // 2. 分批获取并处理群成员
-        while {
-            // 获取一批群成员
-            let query = GroupMemberQuery {
-                page_size: BATCH_SIZE,
-                cursor: cursor.clone(),
-                role_filter: None,
-                active_only: true, // 只获取活跃成员
-            };
-            
+        loop {
             let page = self.group_repository
-                .get_group_members_paged(&message.group_id, query)
+                .get_online_members_paged(&message.group_id, BATCH_SIZE, cursor)
                 .await?;
 
             // 过滤掉发送者自己
@@ -300,11 +378,29 @@ impl MessageService for MessageServiceImpl {
                 // 批量获取路由信息
                 match self.route_repository.get_routes_with_weight_batch(&current_batch).await {
                     Ok(routes_map) => {
-                        for (member_id, routes) in routes_map {
-                            if routes.is_empty() {
-                                offline_members.push(member_id);
+                        let mut routes = Vec::new();
+                        let mut stale = Vec::new();
+                        for (member_id, member_routes) in routes_map {
+                            if member_routes.is_empty() {
+                                stale.push(member_id);
                             } else {
-                                all_routes.extend(routes);
+                                routes.extend(member_routes);
+                            }
+                        }
+
+                        // 网关异常退出时没有下线事件，索引中已无路由的成员在此清理
+                        if !stale.is_empty() {
+                            debug!("Removing {} stale online members of group {}", stale.len(), message.group_id);
+                            if let Err(e) = self.group_repository.remove_online_members(&message.group_id, &stale).await {
+                                warn!("Failed to remove stale online members: {}", e);
+                            }
+                        }
+
+                        if !routes.is_empty() {
+                            addresses.extend(routes.iter().map(|r| r.address.clone()));
+                            match self.message_repository.push_message(message, routes).await {
+                                Ok(batch_outcome) => outcome.merge(batch_outcome),
+                                Err(e) => error!("Failed to push message to batch: {}", e),
                             }
                         }
                     }
@@ -314,63 +410,109 @@ impl MessageService for MessageServiceImpl {
                 }
             }
 
-            // 更新游标，继续处理下一批
-            cursor = page.cursor;
-            page.has_more
-        } {}
-
-        // 3. 批量推送在线消息
-        if !all_routes.is_empty() {
-            // 分批推送消息
-            for routes_chunk in all_routes.chunks(BATCH_SIZE as usize) {
-                match self.message_repository.push_message(message, routes_chunk.to_vec()).await {
-                    Ok(_) => success = true,
-                    Err(e) => {
-                        error!("Failed to push message to chunk: {}", e);
-                    }
-                }
+            if !page.has_more {
+                break;
             }
+            cursor = page.cursor;
         }
 
-        // 4. 异步处理离线消息
-        if !offline_members.is_empty() && self.need_offline_push(message) {
-            let message_repo = self.message_repository.clone();
-            let message = message.clone();
-            let offline_members = offline_members.clone();
-            
-            tokio::spawn(async move {
-                for member_chunk in offline_members.chunks(BATCH_SIZE as usize) {
-                    for member_id in member_chunk {
-                        if let Err(e) = message_repo
-                            .send_offline_notification(member_id, &message)
-                            .await
-                        {
+        // 3. 推送失败的在线成员直接转离线通知，其余离线成员由后台任务限速推送
+        let failed_members = outcome.failed_users();
+        let offline_push = self.need_offline_push(message);
+        if !failed_members.is_empty() {
+            warn!(
+                "Failed to push group message {} to {} members",
+                message.server_msg_id,
+                failed_members.len()
+            );
+        }
+        if offline_push {
+            // 被 @ 的成员由 push_mentions 强制推送
+            let mentioned = mentioned_users(message);
+            let failed_unmentioned: Vec<String> = failed_members
+                .iter()
+                .filter(|id| !mentioned.contains(id))
+                .cloned()
+                .collect();
+            if !failed_unmentioned.is_empty() {
+                let message_repo = self.message_repository.clone();
+                let message = message.clone();
+                tokio::spawn(tenant::scope(tenant::current(), async move {
+                    for member_id in &failed_unmentioned {
+                        if let Err(e) = message_repo.send_offline_notification(member_id, &message).await {
                             error!("Failed to send offline notification to {}: {}", member_id, e);
                         }
                     }
-                }
-            });
+                }));
+            }
+            match &self.offline_push {
+                // @所有人 由 push_mentions 强制推送
+                Some(_) if at_type(message) == AtType::AtAll => {}
+                // 任务提交失败时返回错误，由分发重试
+                Some(scheduler) => scheduler.enqueue(message).await?,
+                None => debug!("Offline push is not configured, skipping group message {}", message.server_msg_id),
+            }
+            self.push_mentions(message, &outcome).await?;
         }
 
-        // 5. 构建处理结果
+        // 4. 构建处理结果，推送失败且无法转离线时交由重试
+        let success = failed_members.is_empty() || offline_push;
         Ok(MessageProcessResult {
             message_id: message.server_msg_id.clone(),
             success,
             error: if !success {
                 Some(format!(
-                    "Message processed: {} online members, {} offline members",
-                    all_routes.len() / 2, // 每个成员可能有多个路由
-                    offline_members.len()
+                    "Message processed: {} delivered, {} failed",
+                    outcome.delivered_count(),
+                    failed_members.len()
                 ))
             } else {
                 None
             },
-            routes: all_routes.into_iter().map(|r| r.address).collect(),
+            routes: addresses,
+            push_results: outcome.results,
         })
     }
 
+    async fn pull_group_messages(
+        &self,
+        group_id: &str,
+        user_id: &str,
+        after_seq: i64,
+        limit: usize,
+    ) -> Result<GroupTimelinePage> {
+        let timeline = self.timeline.as_ref()
+            .ok_or_else(|| AppError::unavailable("group timeline is not enabled"))?;
+        let member = self.group_repository.check_member_status(group_id, user_id).await?;
+        if !member.is_member {
+            return Err(AppError::new(ErrorCode::RouterNotGroupMember, "not a group member").into());
+        }
+
+        let limit = match limit {
+            0 => DEFAULT_PULL_LIMIT,
+            limit => limit.min(MAX_PULL_LIMIT),
+        };
+        timeline.pull(group_id, after_seq.max(0), limit).await
+    }
+
     async fn handle_message_distribution(&self, message: &MessageData) -> Result<()> {
-        self.message_repository.handle_message_distribution(message).await
+        // 优先级由服务端计算并写入消息，重试时沿用原通道
+        let mut message = message.clone();
+        let priority = self.priority.apply(&mut message);
+        debug!("Message {} routed with priority {:?}", message.server_msg_id, priority);
+        let message = &message;
+
+        self.message_repository.handle_message_distribution(message).await?;
+
+        // 频率与配额已在预处理时计入
+        if let Err(e) = self.record_mentions(message).await {
+            warn!("Failed to record mentions for {}: {}", message.server_msg_id, e);
+        }
+        Ok(())
+    }
+
+    fn prioritize(&self, message: &mut MessageData, rules: &PriorityRules) -> MessagePriority {
+        self.priority.apply_with(message, rules)
     }
 
     async fn handle_message_storage(&self, message: &MessageData) -> Result<()> {
@@ -508,16 +650,55 @@ impl MessageService for MessageServiceImpl {
         // 4. 保存更新后的消息
         self.message_repository.save_message(&retry_message).await?;
 
-        // 5. 重新分发消息
+        // 5. 延迟后重新分发消息
         info!(
-            "Retrying message {} ({}/{})",
+            "Retrying message {} ({}/{}) in {}ms",
             message.server_msg_id,
             retry_count + 1,
-            max_retries
+            max_retries,
+            delay
         );
 
-        // 根据消息类型选择不同的处理方式
-       self.message_repository.handle_message_distribution(&retry_message).await?;
+        // 进入延迟重试，到期后回到原优先级通道
+        self.message_repository.schedule_retry(&retry_message, delay).await?;
+        Ok(())
+    }
+
+    async fn expire_message(&self, message: &MessageData) -> Result<()> {
+        // 1. 从存储中删除消息并发布已过期状态；读扩散群同时删除时间线中的消息
+        self.message_repository
+            .delete_message(message, MessageStatus::Expired)
+            .await?;
+
+        if let (Some(timeline), true) = (&self.timeline, is_group_message(message)) {
+            timeline.remove(&message.group_id, &message.server_msg_id).await?;
+        }
+
+        // 2. 销毁通知经分发通道送达接收方，不计入发送者的频率与配额
+        let notification = expire_notification(message);
+        self.message_repository.handle_message_distribution(&notification).await?;
+
+        // 3. 发送方的其他在线设备直接推送
+        let routes = self.route_repository.get_routes_with_weight(&message.send_id).await?;
+        if !routes.is_empty() {
+            let outcome = self.message_repository.push_message(&notification, routes).await?;
+            if !outcome.failed_users().is_empty() {
+                warn!("Failed to notify sender devices of expired message {}", message.server_msg_id);
+            }
+        }
+        info!("Message {} expired", message.server_msg_id);
         Ok(())
     }
+
+    async fn release_quota(&self, message: &MessageData) -> Result<()> {
+        self.message_repository.release_send_quota(message).await
+    }
+
+    async fn is_recipient(&self, message: &MessageData, user_id: &str) -> Result<bool> {
+        if is_group_message(message) {
+            let member = self.group_repository.check_member_status(&message.group_id, user_id).await?;
+            return Ok(member.is_member);
+        }
+        Ok(message.recv_id == user_id)
+    }
 }
\ No newline at end of file
@@ -1,29 +1,47 @@
 use async_trait::async_trait;
-use proto_crate::api::im::common::MessageData;
+use proto_crate::api::im::common::{MessageData, MessagePriority};
+use proto_crate::api::im::service::router::PriorityRules;
+use crate::domain::repositories::GroupTimelinePage;
 use crate::entities::{MessageProcessResult, PreProcessCode};
 
 mod message_service;
+pub mod expiry;
+pub mod fanout;
+pub mod offline_push;
+pub mod pre_check;
+pub mod priority;
+pub use fanout::{FanoutConfig, FanoutPolicy, FanoutStrategy};
 pub use message_service::MessageServiceImpl;
+pub use offline_push::{OfflinePushConfig, OfflinePushScheduler, OfflinePushWorker};
+pub use pre_check::{PreCheckPipeline, PreCheckReport, PreCheckStage};
+pub use priority::PriorityResolver;
 
 /// 消息服务接口
+#[cfg_attr(test, mockall::automock)]
 #[async_trait]
 pub trait MessageService: Send + Sync {
     /// 消息预处理和校验
     ///
-    /// 包含:
+    /// 依次执行预处理检查链，遇到第一个未通过的阶段即返回:
     /// - 基础格式校验
-    /// - 敏感词过滤
-    /// - 权限校验
-    /// - 业务规则校验
+    /// - 内容安全检查
+    /// - 好友关系/黑名单校验
+    /// - 群成员身份/禁言校验
+    /// - 封禁状态校验
+    /// - 频率与数量限制，通过时计入配额，同一消息只计一次
     ///
     /// 返回:
     /// - PreProcessCode::Ok(0): 校验通过
     /// - PreProcessCode::InvalidFormat(1): 消息格式错误
     /// - PreProcessCode::InvalidContent(2): 内容违规
-    /// - PreProcessCode::NoPermission(3): 权限不足
-    /// - PreProcessCode::BusinessLimit(4): 业务规则限制
-    /// - PreProcessCode::SystemError(5): 系统错误
-    async fn pre_process(&self, message: &MessageData) -> anyhow::Result<PreProcessCode>;
+    /// - PreProcessCode::InvalidMention(3): @ 的用户不是群成员
+    /// - PreProcessCode::UnderReview(4): 内容待审核
+    /// - 10-29: 权限不足，如 NotFriend、NotGroupMember、Muted
+    /// - 30-49: 业务规则限制，如 FrequencyLimit、ContentLengthLimit
+    /// - 50 及以上: 系统错误，如 SystemError、ServiceUnavailable
+    ///
+    /// 命中替换规则时直接改写 `message.content`。
+    async fn pre_process(&self, message: &mut MessageData) -> anyhow::Result<PreProcessCode>;
 
 
     /// 处理单聊消息路由
@@ -37,18 +55,36 @@ pub trait MessageService: Send + Sync {
     /// 处理群聊消息路由
     ///
     /// 包含:
+    /// - 按扇出策略选择写扩散或读扩散
     /// - 获取群成员列表
     /// - 批量获取路由信息
     /// - 消息分发
     async fn handle_group_message(&self, message: &MessageData) -> anyhow::Result<MessageProcessResult>;
 
+    /// 拉取读扩散群的消息时间线
+    ///
+    /// 包含:
+    /// - 校验群成员身份
+    /// - 按序号增量拉取
+    async fn pull_group_messages(
+        &self,
+        group_id: &str,
+        user_id: &str,
+        after_seq: i64,
+        limit: usize,
+    ) -> anyhow::Result<GroupTimelinePage>;
+
     /// 处理消息分发
     ///
     /// 包含:
+    /// - 计算消息优先级，投递到对应的分发通道
     /// - 消息分发到各个网关
     /// - 消息分发到各个设备
     async fn handle_message_distribution(&self, message: &MessageData) -> anyhow::Result<()>;
 
+    /// 按服务配置与调用方携带的规则计算优先级并写入消息，分发时沿用
+    fn prioritize(&self, message: &mut MessageData, rules: &PriorityRules) -> MessagePriority;
+
     /// 处理消息存储
     ///
     /// 包含:
@@ -72,4 +108,17 @@ pub trait MessageService: Send + Sync {
     /// - 更新重试状态
     /// - 触发重试流程
     async fn handle_message_retry(&self, message: &MessageData) -> anyhow::Result<()>;
+
+    /// 销毁到期消息
+    ///
+    /// 包含:
+    /// - 从存储中删除消息，并发布已过期状态
+    /// - 向接收方与发送方的在线设备下发销毁通知
+    async fn expire_message(&self, message: &MessageData) -> anyhow::Result<()>;
+
+    /// 退回未送达消息在预处理时占用的频率与配额
+    async fn release_quota(&self, message: &MessageData) -> anyhow::Result<()>;
+
+    /// 用户是否为消息的接收方，群消息为群成员，单聊为接收者
+    async fn is_recipient(&self, message: &MessageData, user_id: &str) -> anyhow::Result<bool>;
 }
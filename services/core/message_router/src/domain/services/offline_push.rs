@@ -0,0 +1,323 @@
+//! 群消息离线推送
+//!
+//! 写扩散只推送在线成员，离线成员的推送通知作为任务写入离线推送任务主题，重启后不会丢失：
+//! 按全量成员减去在线成员得到离线成员，所有任务共享同一个发送速率，避免大群消息瞬间压垮推送通道；
+//! 多个任务并发执行，大群的任务不会阻塞其他群。
+//! 同一群在冷却时间内只推送一轮，离线成员收到一次通知后上线即可拉取全部新消息。
+//! @所有人 的推送不受冷却限制并忽略会话免打扰；被 @ 的用户由路由直接推送，此处跳过。
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use common::tenant;
+use common::utils::msg_utils::{mentioned_users, FORCE_PUSH_OPTION};
+use log::{debug, error};
+use proto_crate::api::im::common::MessageData;
+use tokio::time::{Interval, MissedTickBehavior};
+
+use crate::domain::repositories::{GroupMemberQuery, GroupRepository, MessageRepository};
+
+/// 离线推送配置
+#[derive(Debug, Clone)]
+pub struct OfflinePushConfig {
+    /// 所有任务合计每秒最多发送的离线通知数
+    pub max_per_second: u32,
+    /// 同一群两轮推送的最小间隔
+    pub group_cooldown: Duration,
+    /// 同时执行的任务数
+    pub max_concurrent_jobs: usize,
+}
+
+impl Default for OfflinePushConfig {
+    fn default() -> Self {
+        Self {
+            max_per_second: 200,
+            group_cooldown: Duration::from_secs(30),
+            max_concurrent_jobs: 16,
+        }
+    }
+}
+
+/// 标记为强制推送的消息副本，推送通道据此忽略接收者的会话免打扰
+pub fn force_push(message: &MessageData) -> MessageData {
+    let mut message = message.clone();
+    message.options.insert(FORCE_PUSH_OPTION.to_string(), "true".to_string());
+    message
+}
+
+/// 是否为强制推送的消息
+fn is_forced(message: &MessageData) -> bool {
+    message.options.get(FORCE_PUSH_OPTION).is_some_and(|v| v == "true")
+}
+
+/// 离线推送任务提交
+#[derive(Clone)]
+pub struct OfflinePushScheduler {
+    message_repository: Arc<dyn MessageRepository>,
+}
+
+impl OfflinePushScheduler {
+    pub fn new(message_repository: Arc<dyn MessageRepository>) -> Self {
+        Self { message_repository }
+    }
+
+    /// 提交群消息的离线推送
+    pub async fn enqueue(&self, message: &MessageData) -> Result<()> {
+        self.message_repository.enqueue_offline_push(message).await
+    }
+
+    /// 提交 @所有人 消息的强制离线推送
+    pub async fn enqueue_forced(&self, message: &MessageData) -> Result<()> {
+        self.message_repository.enqueue_offline_push(&force_push(message)).await
+    }
+}
+
+/// 离线推送任务执行
+///
+/// 由离线推送消费者并发调用，冷却状态与发送速率在任务间共享。
+pub struct OfflinePushWorker {
+    message_repository: Arc<dyn MessageRepository>,
+    group_repository: Arc<dyn GroupRepository>,
+    config: OfflinePushConfig,
+    /// (租户, 群) 最近一轮推送的时间
+    last_push: Mutex<HashMap<(String, String), Instant>>,
+    /// 全局发送节拍，等待中的任务按到达顺序轮流发送
+    ticker: tokio::sync::Mutex<Interval>,
+}
+
+impl OfflinePushWorker {
+    const BATCH_SIZE: i32 = 1000;
+
+    pub fn new(
+        message_repository: Arc<dyn MessageRepository>,
+        group_repository: Arc<dyn GroupRepository>,
+        config: OfflinePushConfig,
+    ) -> Self {
+        let period = Duration::from_secs(1) / config.max_per_second.max(1);
+        let mut ticker = tokio::time::interval(period);
+        // 空闲后不补发积压的节拍
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        Self {
+            message_repository,
+            group_repository,
+            config,
+            last_push: Mutex::new(HashMap::new()),
+            ticker: tokio::sync::Mutex::new(ticker),
+        }
+    }
+
+    /// 同时执行的任务数
+    pub fn max_concurrent_jobs(&self) -> usize {
+        self.config.max_concurrent_jobs.max(1)
+    }
+
+    /// 执行一个离线推送任务
+    ///
+    /// 加载成员失败时返回错误并撤销本轮冷却，由调用方重试；
+    /// 重试时本轮已通知的成员可能再次收到通知。
+    pub async fn process(&self, message: &MessageData) -> Result<()> {
+        let force = is_forced(message);
+        let key = (tenant::current().tenant_id().to_string(), message.group_id.clone());
+        if !self.begin_round(&key, force) {
+            debug!("Group {} is cooling down, skipping offline push", message.group_id);
+            return Ok(());
+        }
+
+        let result = self.notify_offline_members(message, force).await;
+        if result.is_err() {
+            self.last_push.lock().unwrap().remove(&key);
+        }
+        result
+    }
+
+    /// 登记一轮推送，冷却中且非强制推送时返回 false
+    fn begin_round(&self, key: &(String, String), force: bool) -> bool {
+        let now = Instant::now();
+        let cooldown = self.config.group_cooldown;
+        let mut last_push = self.last_push.lock().unwrap();
+        let cooling_down = last_push
+            .get(key)
+            .is_some_and(|last| now.duration_since(*last) < cooldown);
+        if cooling_down && !force {
+            return false;
+        }
+        last_push.retain(|_, last| now.duration_since(*last) < cooldown);
+        last_push.insert(key.clone(), now);
+        true
+    }
+
+    async fn notify_offline_members(&self, message: &MessageData, force: bool) -> Result<()> {
+        // 被 @ 的用户已由路由强制推送
+        let mentioned: HashSet<String> = if force {
+            HashSet::new()
+        } else {
+            mentioned_users(message).into_iter().collect()
+        };
+        let online = self.online_members(&message.group_id).await?;
+
+        let mut sent = 0usize;
+        let mut cursor = None;
+        loop {
+            let query = GroupMemberQuery {
+                page_size: Self::BATCH_SIZE,
+                cursor,
+                role_filter: None,
+                active_only: true,
+            };
+            let page = self.group_repository.get_group_members_paged(&message.group_id, query).await?;
+
+            for member_id in page.members {
+                if member_id == message.send_id || online.contains(&member_id) || mentioned.contains(&member_id) {
+                    continue;
+                }
+                self.ticker.lock().await.tick().await;
+                match self.message_repository.send_offline_notification(&member_id, message).await {
+                    Ok(()) => sent += 1,
+                    Err(e) => error!("Failed to send offline notification to {}: {}", member_id, e),
+                }
+            }
+
+            if !page.has_more {
+                break;
+            }
+            cursor = page.cursor;
+        }
+        debug!("Sent {} offline notifications for group message {}", sent, message.server_msg_id);
+        Ok(())
+    }
+
+    async fn online_members(&self, group_id: &str) -> Result<HashSet<String>> {
+        let mut online = HashSet::new();
+        let mut cursor = None;
+        loop {
+            let page = self
+                .group_repository
+                .get_online_members_paged(group_id, Self::BATCH_SIZE, cursor)
+                .await?;
+            online.extend(page.members);
+            if !page.has_more {
+                return Ok(online);
+            }
+            cursor = page.cursor;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mockall::Sequence;
+
+    use super::*;
+    use crate::domain::repositories::{GroupMemberPage, MockGroupRepository, MockMessageRepository};
+
+    fn page(members: Vec<String>) -> GroupMemberPage {
+        GroupMemberPage {
+            total: members.len() as i64,
+            members,
+            cursor: None,
+            has_more: false,
+        }
+    }
+
+    fn member_ids(group_id: &str, size: usize) -> Vec<String> {
+        (0..size).map(|i| format!("{}-user{}", group_id, i)).collect()
+    }
+
+    /// 群成员按 `(群ID, 人数)` 固定，在线成员为 `online`
+    fn groups(sizes: &[(&str, usize)], online: &[&str]) -> MockGroupRepository {
+        let members: HashMap<String, Vec<String>> = sizes
+            .iter()
+            .map(|&(group_id, size)| (group_id.to_string(), member_ids(group_id, size)))
+            .collect();
+        let online: Vec<String> = online.iter().map(|user| user.to_string()).collect();
+
+        let mut groups = MockGroupRepository::new();
+        groups
+            .expect_get_group_members_paged()
+            .returning(move |group_id, _| Ok(page(members.get(group_id).cloned().unwrap_or_default())));
+        groups
+            .expect_get_online_members_paged()
+            .returning(move |_, _, _| Ok(page(online.clone())));
+        groups
+    }
+
+    fn group_message(group_id: &str) -> MessageData {
+        MessageData {
+            server_msg_id: format!("{}-msg", group_id),
+            send_id: format!("{}-user0", group_id),
+            group_id: group_id.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn notifications(expected: usize) -> MockMessageRepository {
+        let mut repository = MockMessageRepository::new();
+        repository
+            .expect_send_offline_notification()
+            .times(expected)
+            .returning(|_, _| Ok(()));
+        repository
+    }
+
+    fn worker(repository: MockMessageRepository, groups: MockGroupRepository, max_per_second: u32) -> Arc<OfflinePushWorker> {
+        let config = OfflinePushConfig {
+            max_per_second,
+            ..Default::default()
+        };
+        Arc::new(OfflinePushWorker::new(Arc::new(repository), Arc::new(groups), config))
+    }
+
+    #[tokio::test]
+    async fn test_large_group_does_not_block_other_groups() {
+        let groups = groups(&[("large", 101), ("small", 2)], &[]);
+        // 大群 100 个离线成员按每秒 100 条需要约 1 秒
+        let worker = worker(notifications(101), groups, 100);
+
+        let large = tokio::spawn({
+            let worker = worker.clone();
+            async move { worker.process(&group_message("large")).await }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        tokio::time::timeout(Duration::from_millis(300), worker.process(&group_message("small")))
+            .await
+            .expect("small group waited for the large one")
+            .unwrap();
+        assert!(!large.is_finished());
+        large.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cooldown_skips_rounds_unless_forced() {
+        let groups = groups(&[("group", 3)], &["group-user1"]);
+        // 发送者与在线成员不通知，每轮只通知 user2
+        let worker = worker(notifications(2), groups, 1000);
+
+        worker.process(&group_message("group")).await.unwrap();
+        worker.process(&group_message("group")).await.unwrap();
+        worker.process(&force_push(&group_message("group"))).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_failed_round_does_not_start_cooldown() {
+        let mut groups = MockGroupRepository::new();
+        let mut sequence = Sequence::new();
+        groups
+            .expect_get_group_members_paged()
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_, _| Err(anyhow::anyhow!("group service unavailable")));
+        groups
+            .expect_get_group_members_paged()
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|group_id, _| Ok(page(member_ids(group_id, 2))));
+        groups.expect_get_online_members_paged().returning(|_, _, _| Ok(page(vec![])));
+        let worker = worker(notifications(1), groups, 1000);
+
+        assert!(worker.process(&group_message("group")).await.is_err());
+        worker.process(&group_message("group")).await.unwrap();
+    }
+}
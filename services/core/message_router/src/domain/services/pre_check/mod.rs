@@ -0,0 +1,269 @@
+//! 消息预处理检查链
+//!
+//! 预处理由一组有序的检查阶段组成，任一阶段返回非 `Ok` 的 [`PreProcessCode`] 即短路返回。
+//! 租户可通过 `TenantSettings.disabled_checks` 按阶段名关闭检查。
+
+mod stages;
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use common::tenant::{TenantConfigRegistry, TenantSettings};
+use log::{debug, warn};
+use proto_crate::api::im::common::MessageData;
+
+use crate::entities::PreProcessCode;
+
+pub use stages::{
+    BanStatusStage, ContentSecurityStage, FormatStage, FriendshipStage, GroupPermissionStage,
+    MentionStage, RateLimitStage,
+};
+
+/// 单个阶段耗时超过该值时告警
+const SLOW_STAGE_THRESHOLD: Duration = Duration::from_millis(200);
+
+/// 预处理检查阶段
+#[async_trait]
+pub trait PreCheckStage: Send + Sync {
+    /// 阶段名，用于租户开关与耗时统计
+    fn name(&self) -> &'static str;
+
+    /// 是否适用于该消息，如好友关系检查只适用于单聊
+    fn applies_to(&self, _message: &MessageData) -> bool {
+        true
+    }
+
+    /// 执行检查
+    async fn check(&self, message: &MessageData, settings: &TenantSettings) -> Result<PreProcessCode>;
+
+    /// 执行检查并按需改写消息，如替换敏感词；默认只做检查
+    async fn apply(&self, message: &mut MessageData, settings: &TenantSettings) -> Result<PreProcessCode> {
+        self.check(message, settings).await
+    }
+}
+
+/// 阶段耗时
+#[derive(Debug, Clone)]
+pub struct StageTiming {
+    pub stage: &'static str,
+    pub code: PreProcessCode,
+    pub elapsed: Duration,
+}
+
+/// 检查结果
+#[derive(Debug, Clone)]
+pub struct PreCheckReport {
+    /// 最终结果
+    pub code: PreProcessCode,
+    /// 拒绝消息的阶段
+    pub rejected_by: Option<&'static str>,
+    /// 已执行阶段的耗时
+    pub timings: Vec<StageTiming>,
+    /// 因租户配置跳过的阶段
+    pub skipped: Vec<&'static str>,
+}
+
+impl PreCheckReport {
+    pub fn total_elapsed(&self) -> Duration {
+        self.timings.iter().map(|t| t.elapsed).sum()
+    }
+}
+
+/// 预处理检查链
+pub struct PreCheckPipeline {
+    stages: Vec<Arc<dyn PreCheckStage>>,
+    tenants: Arc<TenantConfigRegistry>,
+}
+
+impl PreCheckPipeline {
+    pub fn new(tenants: Arc<TenantConfigRegistry>) -> Self {
+        Self {
+            stages: Vec::new(),
+            tenants,
+        }
+    }
+
+    /// 追加检查阶段
+    pub fn with_stage(mut self, stage: Arc<dyn PreCheckStage>) -> Self {
+        self.stages.push(stage);
+        self
+    }
+
+    /// 在指定阶段之前插入检查阶段，找不到时追加到末尾
+    pub fn with_stage_before(mut self, before: &str, stage: Arc<dyn PreCheckStage>) -> Self {
+        let index = self
+            .stages
+            .iter()
+            .position(|s| s.name() == before)
+            .unwrap_or(self.stages.len());
+        self.stages.insert(index, stage);
+        self
+    }
+
+    /// 阶段名列表
+    pub fn stage_names(&self) -> Vec<&'static str> {
+        self.stages.iter().map(|s| s.name()).collect()
+    }
+
+    /// 依次执行检查，遇到第一个非 `Ok` 结果即返回
+    ///
+    /// 阶段可改写消息内容，后续阶段与投递使用改写后的消息。
+    pub async fn run(&self, message: &mut MessageData) -> Result<PreCheckReport> {
+        let settings = self.tenants.current();
+        let mut report = PreCheckReport {
+            code: PreProcessCode::Ok,
+            rejected_by: None,
+            timings: Vec::with_capacity(self.stages.len()),
+            skipped: Vec::new(),
+        };
+
+        for stage in &self.stages {
+            let name = stage.name();
+            if settings.disabled_checks.iter().any(|c| c == name) {
+                report.skipped.push(name);
+                continue;
+            }
+            if !stage.applies_to(message) {
+                continue;
+            }
+
+            let started = Instant::now();
+            let code = stage
+                .apply(message, &settings)
+                .await
+                .with_context(|| format!("pre-check stage {} failed", name))?;
+            let elapsed = started.elapsed();
+            if elapsed > SLOW_STAGE_THRESHOLD {
+                warn!("Slow pre-check stage {} for {}: {:?}", name, message.server_msg_id, elapsed);
+            }
+            report.timings.push(StageTiming {
+                stage: name,
+                code,
+                elapsed,
+            });
+
+            if code != PreProcessCode::Ok {
+                report.code = code;
+                report.rejected_by = Some(name);
+                break;
+            }
+        }
+
+        debug!(
+            "Pre-check {} => {:?} in {:?}, stages: {:?}",
+            message.server_msg_id,
+            report.code,
+            report.total_elapsed(),
+            report
+                .timings
+                .iter()
+                .map(|t| (t.stage, t.elapsed))
+                .collect::<Vec<_>>()
+        );
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use common::tenant::{self, TenantContext, TenantOverride};
+
+    use super::*;
+
+    /// 返回固定结果并记录调用次数的阶段
+    struct FixedStage {
+        name: &'static str,
+        code: PreProcessCode,
+        calls: AtomicUsize,
+    }
+
+    impl FixedStage {
+        fn new(name: &'static str, code: PreProcessCode) -> Arc<Self> {
+            Arc::new(Self {
+                name,
+                code,
+                calls: AtomicUsize::new(0),
+            })
+        }
+
+        fn calls(&self) -> usize {
+            self.calls.load(Ordering::SeqCst)
+        }
+    }
+
+    #[async_trait]
+    impl PreCheckStage for FixedStage {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        async fn check(&self, _message: &MessageData, _settings: &TenantSettings) -> Result<PreProcessCode> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(self.code)
+        }
+    }
+
+    fn message() -> MessageData {
+        MessageData {
+            server_msg_id: "1001".to_string(),
+            send_id: "user1".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_stage_order() {
+        let pipeline = PreCheckPipeline::new(Arc::new(TenantConfigRegistry::default()))
+            .with_stage(FixedStage::new("format", PreProcessCode::Ok))
+            .with_stage(FixedStage::new("friendship", PreProcessCode::Ok))
+            .with_stage_before("friendship", FixedStage::new("ban_status", PreProcessCode::Ok))
+            .with_stage_before("missing", FixedStage::new("mention", PreProcessCode::Ok));
+        assert_eq!(pipeline.stage_names(), vec!["format", "ban_status", "friendship", "mention"]);
+    }
+
+    #[tokio::test]
+    async fn test_first_rejection_short_circuits() {
+        let first = FixedStage::new("format", PreProcessCode::Ok);
+        let rejecting = FixedStage::new("friendship", PreProcessCode::NotFriend);
+        let last = FixedStage::new("mention", PreProcessCode::Ok);
+        let pipeline = PreCheckPipeline::new(Arc::new(TenantConfigRegistry::default()))
+            .with_stage(first.clone())
+            .with_stage(rejecting.clone())
+            .with_stage(last.clone());
+
+        let report = pipeline.run(&mut message()).await.unwrap();
+        assert_eq!(report.code, PreProcessCode::NotFriend);
+        assert_eq!(report.rejected_by, Some("friendship"));
+        assert_eq!((first.calls(), rejecting.calls(), last.calls()), (1, 1, 0));
+        assert_eq!(report.timings.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_tenant_disabled_checks_are_skipped() {
+        let registry = TenantConfigRegistry::default().with_override(
+            "acme",
+            TenantOverride {
+                disabled_checks: Some(vec!["friendship".to_string()]),
+                ..Default::default()
+            },
+        );
+        let friendship = FixedStage::new("friendship", PreProcessCode::NotFriend);
+        let pipeline = PreCheckPipeline::new(Arc::new(registry)).with_stage(friendship.clone());
+
+        let report = tenant::scope(TenantContext::new("acme").unwrap(), pipeline.run(&mut message()))
+            .await
+            .unwrap();
+        assert_eq!(report.code, PreProcessCode::Ok);
+        assert_eq!(report.skipped, vec!["friendship"]);
+        assert_eq!(friendship.calls(), 0);
+
+        // 其他租户不受影响
+        let report = pipeline.run(&mut message()).await.unwrap();
+        assert_eq!(report.code, PreProcessCode::NotFriend);
+        assert_eq!(friendship.calls(), 1);
+    }
+}
@@ -0,0 +1,458 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use common::content::{ContentError, ContentLimits, MessageContent};
+use common::tenant::TenantSettings;
+use common::utils::msg_utils::{at_type, is_group_message, is_review_approved, mentioned_users, DEVICE_ID_OPTION};
+use futures::future::try_join_all;
+use log::{info, warn};
+use proto_crate::api::im::common::{AtType, MessageData};
+
+use super::PreCheckStage;
+use crate::domain::repositories::{
+    ContentFilterRepository, FilterResult, FriendRepository, GroupRepository, MessageRepository,
+};
+use crate::entities::{PreProcessCode, QuotaVerdict, SendQuota};
+
+/// 格式校验：必要字段、按内容类型解码、必填字段与大小限制
+pub struct FormatStage;
+
+#[async_trait]
+impl PreCheckStage for FormatStage {
+    fn name(&self) -> &'static str {
+        "format"
+    }
+
+    async fn check(&self, message: &MessageData, settings: &TenantSettings) -> Result<PreProcessCode> {
+        // 检查必要字段
+        if message.server_msg_id.is_empty() || message.send_id.is_empty() {
+            return Ok(PreProcessCode::InvalidFormat);
+        }
+
+        // 检查消息内容
+        Ok(match MessageContent::from_message_validated(message, &ContentLimits::from(settings)) {
+            Ok(_) => PreProcessCode::Ok,
+            Err(ContentError::TooLarge { field, .. }) if field == "content" || field == "text.content" => {
+                PreProcessCode::ContentLengthLimit
+            }
+            Err(ContentError::TooLarge { .. }) => PreProcessCode::AttachmentSizeLimit,
+            Err(e) => {
+                warn!("Invalid message content {}: {}", message.server_msg_id, e);
+                PreProcessCode::InvalidFormat
+            }
+        })
+    }
+}
+
+/// 内容安全检查，已通过人工审核的消息不再检查
+pub struct ContentSecurityStage {
+    content_filter_repository: Arc<dyn ContentFilterRepository>,
+}
+
+impl ContentSecurityStage {
+    pub fn new(content_filter_repository: Arc<dyn ContentFilterRepository>) -> Self {
+        Self { content_filter_repository }
+    }
+}
+
+#[async_trait]
+impl PreCheckStage for ContentSecurityStage {
+    fn name(&self) -> &'static str {
+        "content_security"
+    }
+
+    fn applies_to(&self, message: &MessageData) -> bool {
+        !is_review_approved(message)
+    }
+
+    async fn check(&self, message: &MessageData, _settings: &TenantSettings) -> Result<PreProcessCode> {
+        let result = self.content_filter_repository.check(message).await?;
+        Ok(Self::verdict(message, &result))
+    }
+
+    async fn apply(&self, message: &mut MessageData, _settings: &TenantSettings) -> Result<PreProcessCode> {
+        let result = self.content_filter_repository.check(message).await?;
+        let code = Self::verdict(message, &result);
+        if code == PreProcessCode::Ok {
+            if let Some(content) = result.replaced_content {
+                info!(
+                    "Message {} content replaced, sensitive words: {:?}",
+                    message.server_msg_id, result.sensitive_words
+                );
+                message.content = content;
+            }
+        }
+        Ok(code)
+    }
+}
+
+impl ContentSecurityStage {
+    fn verdict(message: &MessageData, result: &FilterResult) -> PreProcessCode {
+        if result.passed {
+            return PreProcessCode::Ok;
+        }
+        warn!(
+            "Message content security check failed: {:?}, reason: {:?}, held: {}",
+            message.server_msg_id, result.reason, result.held
+        );
+        if result.unavailable {
+            PreProcessCode::ServiceUnavailable
+        } else if result.held {
+            PreProcessCode::UnderReview
+        } else {
+            PreProcessCode::InvalidContent
+        }
+    }
+}
+
+/// 单聊好友关系与黑名单检查
+pub struct FriendshipStage {
+    friend_repository: Arc<dyn FriendRepository>,
+}
+
+impl FriendshipStage {
+    pub fn new(friend_repository: Arc<dyn FriendRepository>) -> Self {
+        Self { friend_repository }
+    }
+}
+
+#[async_trait]
+impl PreCheckStage for FriendshipStage {
+    fn name(&self) -> &'static str {
+        "friendship"
+    }
+
+    fn applies_to(&self, message: &MessageData) -> bool {
+        !is_group_message(message)
+    }
+
+    async fn check(&self, message: &MessageData, _settings: &TenantSettings) -> Result<PreProcessCode> {
+        let friendship = self
+            .friend_repository
+            .check_friendship(&message.send_id, &message.recv_id)
+            .await?;
+
+        if !friendship.is_friend {
+            return Ok(PreProcessCode::NotFriend);
+        }
+        if friendship.in_blacklist {
+            return Ok(PreProcessCode::InBlacklist);
+        }
+        Ok(PreProcessCode::Ok)
+    }
+}
+
+/// 群成员身份、成员禁言与群状态检查
+pub struct GroupPermissionStage {
+    group_repository: Arc<dyn GroupRepository>,
+}
+
+impl GroupPermissionStage {
+    pub fn new(group_repository: Arc<dyn GroupRepository>) -> Self {
+        Self { group_repository }
+    }
+}
+
+#[async_trait]
+impl PreCheckStage for GroupPermissionStage {
+    fn name(&self) -> &'static str {
+        "group_permission"
+    }
+
+    fn applies_to(&self, message: &MessageData) -> bool {
+        is_group_message(message)
+    }
+
+    async fn check(&self, message: &MessageData, _settings: &TenantSettings) -> Result<PreProcessCode> {
+        let group_status = self.group_repository.get_group_status(&message.group_id).await?;
+        if !group_status.is_active {
+            return Ok(PreProcessCode::GroupDissolved);
+        }
+
+        let member_status = self
+            .group_repository
+            .check_member_status(&message.group_id, &message.send_id)
+            .await?;
+        if !member_status.is_member {
+            return Ok(PreProcessCode::NotGroupMember);
+        }
+        if member_status.is_muted {
+            return Ok(PreProcessCode::Muted);
+        }
+
+        if group_status.is_muted {
+            return Ok(PreProcessCode::GroupMuted);
+        }
+        Ok(PreProcessCode::Ok)
+    }
+}
+
+/// 发送频率与会话每日消息数量限制
+///
+/// 限额取自租户配置，不读取消息 options，客户端无法自行调整。执行时检查并计入配额，
+/// 检查与计数原子执行，并发发送不会同时通过；需放在检查链末尾，避免被后续阶段拒绝的消息占用配额。
+pub struct RateLimitStage {
+    message_repository: Arc<dyn MessageRepository>,
+}
+
+impl RateLimitStage {
+    /// 发送频率统计窗口（秒）
+    const RATE_WINDOW_SECS: i32 = 60;
+
+    pub fn new(message_repository: Arc<dyn MessageRepository>) -> Self {
+        Self { message_repository }
+    }
+}
+
+#[async_trait]
+impl PreCheckStage for RateLimitStage {
+    fn name(&self) -> &'static str {
+        "rate_limit"
+    }
+
+    async fn check(&self, message: &MessageData, settings: &TenantSettings) -> Result<PreProcessCode> {
+        // 1. 检查发送频率
+        let count = self
+            .message_repository
+            .get_recent_message_count(&message.send_id, Self::RATE_WINDOW_SECS)
+            .await?;
+        if count as i64 >= settings.rate_limit_per_minute as i64 {
+            return Ok(PreProcessCode::FrequencyLimit);
+        }
+
+        // 2. 检查会话每日消息数量
+        if is_group_message(message) {
+            let daily_count = self
+                .message_repository
+                .get_group_daily_message_count(&message.group_id)
+                .await?;
+            if daily_count as i64 >= settings.group_daily_limit as i64 {
+                return Ok(PreProcessCode::GroupMessageLimit);
+            }
+        } else {
+            let daily_count = self
+                .message_repository
+                .get_private_daily_message_count(&message.send_id, &message.recv_id)
+                .await?;
+            if daily_count as i64 >= settings.private_daily_limit as i64 {
+                return Ok(PreProcessCode::PrivateMessageLimit);
+            }
+        }
+
+        Ok(PreProcessCode::Ok)
+    }
+
+    async fn apply(&self, message: &mut MessageData, settings: &TenantSettings) -> Result<PreProcessCode> {
+        let group = is_group_message(message);
+        let quota = SendQuota {
+            rate_per_minute: settings.rate_limit_per_minute,
+            daily: if group { settings.group_daily_limit } else { settings.private_daily_limit },
+            at_all_daily: settings.at_all_daily_limit,
+        };
+        Ok(match self.message_repository.reserve_send_quota(message, quota).await? {
+            QuotaVerdict::Reserved => PreProcessCode::Ok,
+            QuotaVerdict::RateLimited => PreProcessCode::FrequencyLimit,
+            QuotaVerdict::DailyLimited if group => PreProcessCode::GroupMessageLimit,
+            QuotaVerdict::DailyLimited => PreProcessCode::PrivateMessageLimit,
+            QuotaVerdict::AtAllLimited => PreProcessCode::AtAllLimit,
+        })
+    }
+}
+
+/// 用户与设备封禁检查
+pub struct BanStatusStage {
+    message_repository: Arc<dyn MessageRepository>,
+}
+
+impl BanStatusStage {
+    pub fn new(message_repository: Arc<dyn MessageRepository>) -> Self {
+        Self { message_repository }
+    }
+}
+
+#[async_trait]
+impl PreCheckStage for BanStatusStage {
+    fn name(&self) -> &'static str {
+        "ban_status"
+    }
+
+    async fn check(&self, message: &MessageData, _settings: &TenantSettings) -> Result<PreProcessCode> {
+        let user_status = self.message_repository.get_user_status(&message.send_id).await?;
+        if user_status.is_banned {
+            return Ok(PreProcessCode::UserBanned);
+        }
+
+        // 发送设备由入口按调用方元数据写入，见 `DEVICE_ID_OPTION`
+        if let Some(device_id) = message.options.get(DEVICE_ID_OPTION) {
+            let device_status = self.message_repository.get_device_status(device_id).await?;
+            if device_status.is_banned {
+                return Ok(PreProcessCode::DeviceBanned);
+            }
+        }
+        Ok(PreProcessCode::Ok)
+    }
+}
+
+/// 群消息 @ 检查
+///
+/// @所有人 只允许群主与管理员发送，并受租户 `at_all_daily_limit` 限制；
+/// @ 指定用户时每个被 @ 的用户都必须是群成员。
+pub struct MentionStage {
+    group_repository: Arc<dyn GroupRepository>,
+    message_repository: Arc<dyn MessageRepository>,
+}
+
+impl MentionStage {
+    /// 单条消息最多 @ 的用户数
+    const MAX_MENTIONS: usize = 100;
+    /// 管理员角色，群主为 2
+    const ROLE_ADMIN: i32 = 1;
+
+    pub fn new(group_repository: Arc<dyn GroupRepository>, message_repository: Arc<dyn MessageRepository>) -> Self {
+        Self {
+            group_repository,
+            message_repository,
+        }
+    }
+}
+
+#[async_trait]
+impl PreCheckStage for MentionStage {
+    fn name(&self) -> &'static str {
+        "mention"
+    }
+
+    fn applies_to(&self, message: &MessageData) -> bool {
+        is_group_message(message) && at_type(message) != AtType::None
+    }
+
+    async fn check(&self, message: &MessageData, settings: &TenantSettings) -> Result<PreProcessCode> {
+        if at_type(message) == AtType::AtAll {
+            let sender = self
+                .group_repository
+                .check_member_status(&message.group_id, &message.send_id)
+                .await?;
+            if sender.role < Self::ROLE_ADMIN {
+                let group_status = self.group_repository.get_group_status(&message.group_id).await?;
+                if group_status.owner_id != message.send_id {
+                    return Ok(PreProcessCode::AtAllNotAllowed);
+                }
+            }
+
+            let count = self
+                .message_repository
+                .get_group_at_all_daily_count(&message.group_id)
+                .await?;
+            if count as i64 >= settings.at_all_daily_limit as i64 {
+                return Ok(PreProcessCode::AtAllLimit);
+            }
+        }
+
+        let mentioned = mentioned_users(message);
+        if mentioned.len() > Self::MAX_MENTIONS {
+            return Ok(PreProcessCode::InvalidFormat);
+        }
+        let statuses = try_join_all(
+            mentioned
+                .iter()
+                .map(|user_id| self.group_repository.check_member_status(&message.group_id, user_id)),
+        )
+        .await?;
+        if let Some((user_id, _)) = mentioned.iter().zip(&statuses).find(|(_, status)| !status.is_member) {
+            warn!(
+                "Message {} mentions non-member {} of group {}",
+                message.server_msg_id, user_id, message.group_id
+            );
+            return Ok(PreProcessCode::InvalidMention);
+        }
+        Ok(PreProcessCode::Ok)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::utils::msg_utils::REVIEW_APPROVED_OPTION;
+
+    use super::*;
+    use crate::domain::repositories::{MockContentFilterRepository, MockMessageRepository};
+
+    fn message() -> MessageData {
+        MessageData {
+            server_msg_id: "1001".to_string(),
+            send_id: "user1".to_string(),
+            content: b"bad".to_vec(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_content_security_rewrites_replaced_content() {
+        let mut repository = MockContentFilterRepository::new();
+        repository.expect_check().times(1).returning(|_| {
+            Ok(FilterResult {
+                passed: true,
+                replaced_content: Some(b"***".to_vec()),
+                ..Default::default()
+            })
+        });
+        let stage = ContentSecurityStage::new(Arc::new(repository));
+
+        let mut message = message();
+        let code = stage.apply(&mut message, &TenantSettings::default()).await.unwrap();
+        assert_eq!(code, PreProcessCode::Ok);
+        assert_eq!(message.content, b"***".to_vec());
+    }
+
+    #[tokio::test]
+    async fn test_content_security_holds_for_review() {
+        let mut repository = MockContentFilterRepository::new();
+        repository.expect_check().times(1).returning(|_| {
+            Ok(FilterResult {
+                held: true,
+                ..Default::default()
+            })
+        });
+        let stage = ContentSecurityStage::new(Arc::new(repository));
+
+        let code = stage.check(&message(), &TenantSettings::default()).await.unwrap();
+        assert_eq!(code, PreProcessCode::UnderReview);
+    }
+
+    #[test]
+    fn test_content_security_skips_approved_message() {
+        let stage = ContentSecurityStage::new(Arc::new(MockContentFilterRepository::new()));
+        let mut message = message();
+        assert!(stage.applies_to(&message));
+
+        message.options.insert(REVIEW_APPROVED_OPTION.to_string(), "true".to_string());
+        assert!(!stage.applies_to(&message));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_reserves_tenant_quota() {
+        let settings = TenantSettings::default();
+        let expected = SendQuota {
+            rate_per_minute: settings.rate_limit_per_minute,
+            daily: settings.private_daily_limit,
+            at_all_daily: settings.at_all_daily_limit,
+        };
+        let mut repository = MockMessageRepository::new();
+        repository
+            .expect_reserve_send_quota()
+            .withf(move |_, quota| *quota == expected)
+            .times(2)
+            .returning({
+                let mut verdicts = vec![QuotaVerdict::DailyLimited, QuotaVerdict::Reserved];
+                move |_, _| Ok(verdicts.pop().unwrap())
+            });
+        let stage = RateLimitStage::new(Arc::new(repository));
+
+        let mut message = message();
+        assert_eq!(stage.apply(&mut message, &settings).await.unwrap(), PreProcessCode::Ok);
+        assert_eq!(
+            stage.apply(&mut message, &settings).await.unwrap(),
+            PreProcessCode::PrivateMessageLimit
+        );
+    }
+}
@@ -0,0 +1,177 @@
+//! 消息优先级计算
+//!
+//! 优先级决定消息进入的分发通道。规则按发送者、会话、消息类型匹配，
+//! 命中多条时取最高；均未命中时按会话类型给出基础优先级：
+//! 系统消息为紧急，单聊为高，普通群与工作群为普通，超级群为低。
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use common::config::Config;
+use common::utils::msg_utils::{conversation_id, message_priority, PRIORITY_OPTION};
+use proto_crate::api::im::common::{MessageData, MessagePriority, SessionType};
+use proto_crate::api::im::service::router::PriorityRules;
+use serde::Deserialize;
+
+/// 配置文件 `extensions.priority_rules`
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct PriorityRulesSection {
+    users: HashMap<String, i32>,
+    sessions: HashMap<String, i32>,
+    content_types: HashMap<i32, i32>,
+}
+
+/// 优先级计算器
+#[derive(Debug, Clone, Default)]
+pub struct PriorityResolver {
+    rules: PriorityRules,
+}
+
+impl PriorityResolver {
+    /// 使用服务配置的规则，路由时据此计算优先级
+    pub fn new(rules: PriorityRules) -> Self {
+        Self { rules }
+    }
+
+    /// 从配置文件 `extensions.priority_rules` 加载规则，未配置时只按会话类型计算
+    pub fn from_config(config: &Config) -> Result<Self> {
+        let section: PriorityRulesSection = match config.extensions.get("priority_rules") {
+            Some(value) => serde_json::from_value(value.clone())?,
+            None => PriorityRulesSection::default(),
+        };
+        Ok(Self::new(PriorityRules {
+            user_priorities: section.users,
+            session_priorities: section.sessions,
+            message_type_priorities: section.content_types,
+        }))
+    }
+
+    /// 会话类型对应的基础优先级
+    pub fn base_priority(message: &MessageData) -> MessagePriority {
+        match SessionType::try_from(message.session_type) {
+            Ok(SessionType::System) => MessagePriority::MsgPriorityUrgent,
+            Ok(SessionType::Single) => MessagePriority::MsgPriorityHigh,
+            Ok(SessionType::SuperGroup) => MessagePriority::MsgPriorityLow,
+            _ => MessagePriority::MsgPriorityNormal,
+        }
+    }
+
+    /// 按服务配置的规则计算优先级
+    pub fn resolve(&self, message: &MessageData) -> MessagePriority {
+        self.resolve_with(message, &PriorityRules::default())
+    }
+
+    /// 按服务配置的规则与调用方携带的规则计算优先级，两者命中时取最高
+    ///
+    /// 会话规则以 [`conversation_id`] 为键，越界的规则值截断到有效范围。
+    pub fn resolve_with(&self, message: &MessageData, rules: &PriorityRules) -> MessagePriority {
+        let conversation = conversation_id(message);
+        [&self.rules, rules]
+            .into_iter()
+            .flat_map(|rules| {
+                [
+                    rules.user_priorities.get(&message.send_id),
+                    rules.session_priorities.get(&conversation),
+                    rules.message_type_priorities.get(&message.content_type),
+                ]
+            })
+            .flatten()
+            .max()
+            .map(|&p| {
+                let p = p.clamp(MessagePriority::MsgPriorityLow as i32, MessagePriority::MsgPriorityUrgent as i32);
+                MessagePriority::try_from(p).unwrap_or(MessagePriority::MsgPriorityNormal)
+            })
+            .unwrap_or_else(|| Self::base_priority(message))
+    }
+
+    /// 确定消息优先级并写入消息 options
+    ///
+    /// 已由服务端写入优先级的消息(定时、审核后投递或按调用方规则路由的消息)沿用原值，
+    /// 客户端携带的值在路由入口已被移除。
+    pub fn apply(&self, message: &mut MessageData) -> MessagePriority {
+        if message.options.contains_key(PRIORITY_OPTION) {
+            return message_priority(message);
+        }
+        self.apply_with(message, &PriorityRules::default())
+    }
+
+    /// 按调用方携带的规则计算优先级并写入消息 options
+    pub fn apply_with(&self, message: &mut MessageData, rules: &PriorityRules) -> MessagePriority {
+        let priority = self.resolve_with(message, rules);
+        message
+            .options
+            .insert(PRIORITY_OPTION.to_string(), (priority as i32).to_string());
+        priority
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(session_type: SessionType) -> MessageData {
+        MessageData {
+            send_id: "u1".to_string(),
+            recv_id: "u2".to_string(),
+            group_id: "g1".to_string(),
+            session_type: session_type as i32,
+            content_type: 101,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_base_priority_by_session_type() {
+        let resolver = PriorityResolver::default();
+        assert_eq!(resolver.resolve(&message(SessionType::System)), MessagePriority::MsgPriorityUrgent);
+        assert_eq!(resolver.resolve(&message(SessionType::Single)), MessagePriority::MsgPriorityHigh);
+        assert_eq!(resolver.resolve(&message(SessionType::NormalGroup)), MessagePriority::MsgPriorityNormal);
+        assert_eq!(resolver.resolve(&message(SessionType::SuperGroup)), MessagePriority::MsgPriorityLow);
+    }
+
+    #[test]
+    fn test_highest_matching_rule_wins() {
+        let resolver = PriorityResolver::new(PriorityRules {
+            session_priorities: HashMap::from([("sg_g1".to_string(), MessagePriority::MsgPriorityLow as i32)]),
+            ..Default::default()
+        });
+        let message = message(SessionType::NormalGroup);
+        assert_eq!(resolver.resolve(&message), MessagePriority::MsgPriorityLow);
+
+        let rules = PriorityRules {
+            user_priorities: HashMap::from([("u1".to_string(), MessagePriority::MsgPriorityHigh as i32)]),
+            message_type_priorities: HashMap::from([(101, 99)]),
+            ..Default::default()
+        };
+        // 越界值截断为紧急
+        assert_eq!(resolver.resolve_with(&message, &rules), MessagePriority::MsgPriorityUrgent);
+    }
+
+    #[test]
+    fn test_apply_keeps_server_priority() {
+        let resolver = PriorityResolver::default();
+        let mut message = message(SessionType::SuperGroup);
+        assert_eq!(resolver.apply(&mut message), MessagePriority::MsgPriorityLow);
+
+        let rules = PriorityRules {
+            session_priorities: HashMap::from([("sg_g1".to_string(), MessagePriority::MsgPriorityUrgent as i32)]),
+            ..Default::default()
+        };
+        assert_eq!(resolver.apply_with(&mut message, &rules), MessagePriority::MsgPriorityUrgent);
+        assert_eq!(resolver.apply(&mut message), MessagePriority::MsgPriorityUrgent);
+        assert_eq!(message_priority(&message), MessagePriority::MsgPriorityUrgent);
+    }
+
+    #[test]
+    fn test_rules_section_from_config() {
+        let section: PriorityRulesSection = serde_json::from_value(serde_json::json!({
+            "users": { "system": 3 },
+            "content_types": { "1400": 2 },
+        }))
+        .unwrap();
+        assert_eq!(section.users.get("system"), Some(&3));
+        assert_eq!(section.content_types.get(&1400), Some(&2));
+        assert!(section.sessions.is_empty());
+    }
+}
@@ -0,0 +1,71 @@
+use once_cell::sync::Lazy;
+use opentelemetry::metrics::{Counter, Histogram};
+use opentelemetry::{global, KeyValue};
+
+static RETRY_METRICS: Lazy<RetryMetrics> = Lazy::new(RetryMetrics::new);
+
+/// 消息重试指标
+///
+/// - `message_retry_scheduled_total{tier, attempt}`: 进入延迟重试的次数
+/// - `message_retry_delay_ms{tier}`: 计划的退避时间
+/// - `message_retry_released_total{lane}`: 到期后重新投递到分发通道的次数
+/// - `message_retry_lag_ms{lane}`: 实际重新投递时间与计划时间的差
+/// - `message_retry_dead_letter_total`: 超过最大重试次数进入死信队列的次数
+pub struct RetryMetrics {
+    scheduled: Counter<u64>,
+    delay: Histogram<u64>,
+    released: Counter<u64>,
+    lag: Histogram<u64>,
+    dead_lettered: Counter<u64>,
+}
+
+impl RetryMetrics {
+    fn new() -> Self {
+        let meter = global::meter("message_router");
+        Self {
+            scheduled: meter
+                .u64_counter("message_retry_scheduled_total")
+                .with_description("Messages scheduled for delayed retry")
+                .build(),
+            delay: meter
+                .u64_histogram("message_retry_delay_ms")
+                .with_description("Planned retry backoff")
+                .with_unit("ms")
+                .build(),
+            released: meter
+                .u64_counter("message_retry_released_total")
+                .with_description("Retried messages re-injected into distribution lanes")
+                .build(),
+            lag: meter
+                .u64_histogram("message_retry_lag_ms")
+                .with_description("Delay between planned and actual retry time")
+                .with_unit("ms")
+                .build(),
+            dead_lettered: meter
+                .u64_counter("message_retry_dead_letter_total")
+                .with_description("Messages moved to the dead letter queue after exhausting retries")
+                .build(),
+        }
+    }
+
+    pub fn get() -> &'static RetryMetrics {
+        &RETRY_METRICS
+    }
+
+    pub fn record_scheduled(&self, tier: &'static str, attempt: i32, delay_ms: i64) {
+        self.scheduled.add(
+            1,
+            &[KeyValue::new("tier", tier), KeyValue::new("attempt", attempt as i64)],
+        );
+        self.delay.record(delay_ms.max(0) as u64, &[KeyValue::new("tier", tier)]);
+    }
+
+    pub fn record_released(&self, lane: &'static str, lag_ms: i64) {
+        self.released.add(1, &[KeyValue::new("lane", lane)]);
+        self.lag.record(lag_ms.max(0) as u64, &[KeyValue::new("lane", lane)]);
+    }
+
+    pub fn record_dead_lettered(&self) {
+        self.dead_lettered.add(1, &[]);
+    }
+}
@@ -1,2 +1,3 @@
 pub mod config;
+pub mod metrics;
 pub mod repositories;
\ No newline at end of file
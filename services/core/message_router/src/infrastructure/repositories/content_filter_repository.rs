@@ -1,26 +1,347 @@
-use crate::domain::repositories::ContentFilterRepository;
-use crate::domain::repositories::FilterResult;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::Arc;
+use std::time::Duration;
+
 use anyhow::Result;
 use async_trait::async_trait;
+use common::content::{MediaKind, MessageContent};
+use common::rpc::{context_interceptor, ServiceChannel};
+use log::warn;
 use proto_crate::api::im::common::MessageData;
+use proto_crate::api::im::service::filter::message_filter_client::MessageFilterClient;
+use proto_crate::api::im::service::filter::{
+    BatchFilterMessageRequest, FilterAction, FilterMessageRequest, FilterOptions, FilterRuleType,
+};
+use proto_crate::api::im::service::filter::FilterResult as RemoteFilterResult;
 
-pub struct ContentFilterRepositoryImpl;
+use crate::domain::repositories::ContentFilterRepository;
+use crate::domain::repositories::FilterResult;
+
+/// 过滤调用超时
+const FILTER_DEADLINE: Duration = Duration::from_millis(500);
+
+/// 过滤服务不可用时的处理策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FilterFailurePolicy {
+    /// 放行，优先保证消息可达
+    #[default]
+    FailOpen,
+    /// 拒绝，优先保证内容安全
+    FailClosed,
+}
+
+/// 待过滤的消息
+struct FilterSubject {
+    content: Option<MessageContent>,
+    options: FilterOptions,
+}
+
+/// 基于消息过滤服务的内容检查
+///
+/// 消息原样随请求发送，由过滤服务解析内容；按内容类型设置需要检查的项目。命中规则按动作映射：
+/// - `BLOCK`: 拒绝
+/// - `REVIEW`: 暂缓投递等待审核
+/// - `REPLACE`: 用替换后的文本重新编码消息内容
+pub struct ContentFilterRepositoryImpl {
+    filter: Arc<ServiceChannel>,
+    failure_policy: FilterFailurePolicy,
+    deadline: Duration,
+}
 
 impl ContentFilterRepositoryImpl {
-    pub fn new() -> Self {
-        Self {}
+    pub fn new(filter: Arc<ServiceChannel>) -> Self {
+        Self {
+            filter,
+            failure_policy: FilterFailurePolicy::default(),
+            deadline: FILTER_DEADLINE,
+        }
+    }
+
+    pub fn with_failure_policy(mut self, failure_policy: FilterFailurePolicy) -> Self {
+        self.failure_policy = failure_policy;
+        self
+    }
+
+    pub fn with_deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = deadline;
+        self
+    }
+
+    /// 解码消息内容确定检查项目，无文本也无媒体时无需过滤
+    fn subject(message: &MessageData) -> Option<FilterSubject> {
+        // 格式校验先于内容检查执行，解码失败的消息按原始内容交给过滤服务
+        let content = MessageContent::from_message(message).ok();
+        let text = content.as_ref().and_then(|c| c.searchable_text());
+        let media = content.as_ref().map(|c| c.media_refs()).unwrap_or_default();
+        if content.is_some() && text.is_none() && media.is_empty() {
+            return None;
+        }
+
+        let has = |kinds: &[MediaKind]| media.iter().any(|m| kinds.contains(&m.kind));
+        let options = FilterOptions {
+            check_text: text.is_some() || content.is_none(),
+            check_image: has(&[MediaKind::Image, MediaKind::Thumbnail, MediaKind::VideoSnapshot]),
+            check_audio: has(&[MediaKind::Audio]),
+            check_video: has(&[MediaKind::Video]),
+            custom_options: HashMap::new(),
+        };
+        Some(FilterSubject { content, options })
+    }
+
+    /// 用替换后的文本重新编码内容，只支持文本类元素
+    fn replace_text(content: &MessageContent, replaced: &[u8]) -> Option<Vec<u8>> {
+        let text = String::from_utf8(replaced.to_vec()).ok()?;
+        let content = match content.clone() {
+            MessageContent::Text(mut elem) => {
+                elem.content = text;
+                MessageContent::Text(elem)
+            }
+            MessageContent::Custom(mut elem) => {
+                elem.desc = text;
+                MessageContent::Custom(elem)
+            }
+            _ => return None,
+        };
+        Some(content.encode())
+    }
+
+    fn to_result(subject: &FilterSubject, remote: RemoteFilterResult) -> FilterResult {
+        let hit = |action: FilterAction| remote.hit_rules.iter().any(|r| r.action == action as i32);
+        let mut result = FilterResult {
+            passed: remote.passed,
+            reason: (!remote.hit_rules.is_empty()).then(|| {
+                remote
+                    .hit_rules
+                    .iter()
+                    .map(|r| r.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(",")
+            }),
+            sensitive_words: remote
+                .hit_rules
+                .iter()
+                .filter(|r| r.r#type == FilterRuleType::Keyword as i32)
+                .map(|r| r.content.clone())
+                .collect(),
+            ..Default::default()
+        };
+
+        if hit(FilterAction::Block) {
+            result.passed = false;
+            result.risk_level = 3;
+        } else if hit(FilterAction::Review) {
+            result.passed = false;
+            result.held = true;
+            result.risk_level = 2;
+        } else if !remote.replaced_content.is_empty() {
+            result.risk_level = 1;
+            // 无法替换的内容(如文件名命中)按拦截处理
+            result.replaced_content = subject
+                .content
+                .as_ref()
+                .and_then(|content| Self::replace_text(content, &remote.replaced_content));
+            result.passed = result.replaced_content.is_some();
+        }
+        result
+    }
+
+    fn on_unavailable(&self, message_id: &str, error: impl Display) -> FilterResult {
+        warn!(
+            "Content filter unavailable for message {}: {}, policy: {:?}",
+            message_id, error, self.failure_policy
+        );
+        match self.failure_policy {
+            FilterFailurePolicy::FailOpen => FilterResult::pass(),
+            FilterFailurePolicy::FailClosed => FilterResult {
+                reason: Some(error.to_string()),
+                unavailable: true,
+                ..Default::default()
+            },
+        }
     }
 }
 
 #[async_trait]
 impl ContentFilterRepository for ContentFilterRepositoryImpl {
-    async fn check(&self, _message: &MessageData) -> Result<FilterResult> {
-        // 模拟内容检查，这里简单实现返回通过
-        Ok(FilterResult {
-            passed: true,
-            reason: None,
-            sensitive_words: vec![],
-            risk_level: 0,
+    async fn check(&self, message: &MessageData) -> Result<FilterResult> {
+        let Some(subject) = Self::subject(message) else {
+            return Ok(FilterResult::pass());
+        };
+
+        let request = FilterMessageRequest {
+            message: Some(message.clone()),
+            options: Some(subject.options.clone()),
+        };
+        let deadline = self.deadline;
+        let response = self
+            .filter
+            .call(|channel| {
+                let request = request.clone();
+                async move {
+                    let mut request = tonic::Request::new(request);
+                    request.set_timeout(deadline);
+                    MessageFilterClient::with_interceptor(channel, context_interceptor)
+                        .filter_message(request)
+                        .await
+                }
+            })
+            .await;
+
+        let response = match response {
+            Ok(response) => response.into_inner(),
+            Err(status) => return Ok(self.on_unavailable(&message.server_msg_id, status)),
+        };
+        if let Some(error) = response.error.filter(|e| e.code != 0) {
+            return Ok(self.on_unavailable(&message.server_msg_id, error.message));
+        }
+        Ok(match response.result {
+            Some(remote) => Self::to_result(&subject, remote),
+            None => self.on_unavailable(&message.server_msg_id, "empty filter result"),
         })
     }
-} 
\ No newline at end of file
+
+    async fn check_batch(&self, messages: &[MessageData]) -> Result<HashMap<String, FilterResult>> {
+        let mut results = HashMap::with_capacity(messages.len());
+        let mut subjects = HashMap::new();
+        let mut pending = Vec::new();
+        let mut options = FilterOptions::default();
+        for message in messages {
+            match Self::subject(message) {
+                Some(subject) => {
+                    options.check_text |= subject.options.check_text;
+                    options.check_image |= subject.options.check_image;
+                    options.check_audio |= subject.options.check_audio;
+                    options.check_video |= subject.options.check_video;
+                    subjects.insert(message.server_msg_id.clone(), subject);
+                    pending.push(message.clone());
+                }
+                None => {
+                    results.insert(message.server_msg_id.clone(), FilterResult::pass());
+                }
+            }
+        }
+        if pending.is_empty() {
+            return Ok(results);
+        }
+
+        let request = BatchFilterMessageRequest {
+            messages: pending,
+            options: Some(options),
+        };
+        let deadline = self.deadline;
+        let response = self
+            .filter
+            .call(|channel| {
+                let request = request.clone();
+                async move {
+                    let mut request = tonic::Request::new(request);
+                    request.set_timeout(deadline);
+                    MessageFilterClient::with_interceptor(channel, context_interceptor)
+                        .batch_filter_message(request)
+                        .await
+                }
+            })
+            .await;
+
+        let mut remote_results = match response {
+            Ok(response) => {
+                let response = response.into_inner();
+                match response.error.filter(|e| e.code != 0) {
+                    Some(error) => {
+                        for message_id in subjects.keys() {
+                            results.insert(message_id.clone(), self.on_unavailable(message_id, &error.message));
+                        }
+                        return Ok(results);
+                    }
+                    None => response.results,
+                }
+            }
+            Err(status) => {
+                for message_id in subjects.keys() {
+                    results.insert(message_id.clone(), self.on_unavailable(message_id, &status));
+                }
+                return Ok(results);
+            }
+        };
+
+        for (message_id, subject) in subjects {
+            let result = match remote_results.remove(&message_id) {
+                Some(remote) => Self::to_result(&subject, remote),
+                None => self.on_unavailable(&message_id, "missing filter result"),
+            };
+            results.insert(message_id, result);
+        }
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::content::MessageContent;
+    use proto_crate::api::im::common::{ContentType, PictureElem, TextElem};
+    use proto_crate::api::im::service::filter::FilterRuleInfo;
+
+    use super::*;
+
+    fn text_message(text: &str) -> MessageData {
+        MessageData {
+            server_msg_id: "1001".to_string(),
+            content_type: ContentType::Text as i32,
+            content: MessageContent::Text(TextElem { content: text.to_string() }).encode(),
+            ..Default::default()
+        }
+    }
+
+    fn remote(action: FilterAction, replaced: &str) -> RemoteFilterResult {
+        RemoteFilterResult {
+            passed: action == FilterAction::Replace,
+            hit_rules: vec![FilterRuleInfo {
+                name: "rule".to_string(),
+                content: "bad".to_string(),
+                action: action as i32,
+                ..Default::default()
+            }],
+            replaced_content: replaced.as_bytes().to_vec(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_subject_uses_request_options_only() {
+        let subject = ContentFilterRepositoryImpl::subject(&text_message("hello")).unwrap();
+        assert!(subject.options.check_text);
+        assert!(!subject.options.check_image);
+        assert!(subject.options.custom_options.is_empty());
+
+        let picture = MessageData {
+            content_type: ContentType::Image as i32,
+            content: MessageContent::Picture(PictureElem {
+                source_path: "images/1.png".to_string(),
+                ..Default::default()
+            })
+            .encode(),
+            ..Default::default()
+        };
+        let subject = ContentFilterRepositoryImpl::subject(&picture).unwrap();
+        assert!(subject.options.check_image);
+        assert!(!subject.options.check_text);
+    }
+
+    #[test]
+    fn test_actions_map_to_results() {
+        let subject = ContentFilterRepositoryImpl::subject(&text_message("a bad word")).unwrap();
+
+        let blocked = ContentFilterRepositoryImpl::to_result(&subject, remote(FilterAction::Block, ""));
+        assert!(!blocked.passed && !blocked.held);
+        assert_eq!(blocked.sensitive_words, vec!["bad".to_string()]);
+
+        let held = ContentFilterRepositoryImpl::to_result(&subject, remote(FilterAction::Review, ""));
+        assert!(!held.passed && held.held);
+
+        let replaced = ContentFilterRepositoryImpl::to_result(&subject, remote(FilterAction::Replace, "a *** word"));
+        assert!(replaced.passed);
+        let content = MessageContent::decode(ContentType::Text as i32, &replaced.replaced_content.unwrap()).unwrap();
+        assert_eq!(content.searchable_text().as_deref(), Some("a *** word"));
+    }
+}
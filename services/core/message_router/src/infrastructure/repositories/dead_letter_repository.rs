@@ -0,0 +1,239 @@
+use std::collections::HashSet;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use common::tenant;
+use log::warn;
+use redis::aio::ConnectionManager;
+
+use crate::domain::entities::{AuditRecord, DeadLetterPage, DeadLetterQuery, DeadLetterRecord};
+use crate::domain::repositories::DeadLetterRepository;
+
+/// 死信保留时间，与死信主题的保留时间一致
+const RETENTION_SECS: i64 = 30 * 86_400;
+/// 审计记录保留条数
+const MAX_AUDIT_RECORDS: isize = 10_000;
+
+/// 基于 Redis 的死信索引
+///
+/// - `dlq:entry:{message_id}`: 死信记录 JSON，保留 30 天
+/// - `dlq:index:time`: ZSET，score 为进入时间(秒)
+/// - `dlq:index:reason:{reason}`: 按原因分类的 ZSET
+/// - `dlq:reasons`: 原因分类集合
+/// - `dlq:audit`: 审计记录列表，新记录在前
+/// - `dlq:replay_quota:{minute}`: 每分钟重放计数
+pub struct DeadLetterRepositoryImpl {
+    redis: ConnectionManager,
+}
+
+impl DeadLetterRepositoryImpl {
+    pub fn new(redis: ConnectionManager) -> Self {
+        Self { redis }
+    }
+
+    fn key(suffix: &str) -> String {
+        tenant::current().redis_key(&format!("dlq:{}", suffix))
+    }
+
+    fn entry_key(message_id: &str) -> String {
+        Self::key(&format!("entry:{}", message_id))
+    }
+
+    fn time_index_key() -> String {
+        Self::key("index:time")
+    }
+
+    fn reason_index_key(reason: &str) -> String {
+        Self::key(&format!("index:reason:{}", reason))
+    }
+
+    fn reasons_key() -> String {
+        Self::key("reasons")
+    }
+
+    fn audit_key() -> String {
+        Self::key("audit")
+    }
+
+    async fn get_many(&self, message_ids: &[String]) -> Result<Vec<Option<DeadLetterRecord>>> {
+        if message_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let keys: Vec<String> = message_ids.iter().map(|id| Self::entry_key(id)).collect();
+        let mut conn = self.redis.clone();
+        let values: Vec<Option<String>> = redis::cmd("MGET").arg(&keys).query_async(&mut conn).await?;
+        Ok(values
+            .into_iter()
+            .zip(message_ids)
+            .map(|(value, id)| {
+                value.and_then(|value| match serde_json::from_str(&value) {
+                    Ok(record) => Some(record),
+                    Err(e) => {
+                        warn!("Invalid dead letter record {}: {}", id, e);
+                        None
+                    }
+                })
+            })
+            .collect())
+    }
+}
+
+#[async_trait]
+impl DeadLetterRepository for DeadLetterRepositoryImpl {
+    async fn save(&self, record: &DeadLetterRecord) -> Result<()> {
+        let time_key = Self::time_index_key();
+        let reason_key = Self::reason_index_key(&record.reason);
+        let expired_before = chrono::Utc::now().timestamp() - RETENTION_SECS;
+
+        let mut conn = self.redis.clone();
+        redis::pipe()
+            .atomic()
+            .set_ex(
+                Self::entry_key(&record.message_id),
+                serde_json::to_string(record)?,
+                RETENTION_SECS as u64,
+            )
+            .ignore()
+            .zadd(&time_key, &record.message_id, record.dead_time)
+            .ignore()
+            .zadd(&reason_key, &record.message_id, record.dead_time)
+            .ignore()
+            .sadd(Self::reasons_key(), &record.reason)
+            .ignore()
+            // 记录随 TTL 过期，索引按时间同步清理
+            .zrembyscore(&time_key, "-inf", expired_before)
+            .ignore()
+            .zrembyscore(&reason_key, "-inf", expired_before)
+            .ignore()
+            .query_async::<()>(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    async fn get(&self, message_id: &str) -> Result<Option<DeadLetterRecord>> {
+        Ok(self.get_many(&[message_id.to_string()]).await?.pop().flatten())
+    }
+
+    async fn list(&self, query: &DeadLetterQuery) -> Result<DeadLetterPage> {
+        let key = match &query.reason {
+            Some(reason) => Self::reason_index_key(reason),
+            None => Self::time_index_key(),
+        };
+        let min = query.start_time.map_or("-inf".to_string(), |t| t.to_string());
+        let max = query.end_time.map_or("+inf".to_string(), |t| t.to_string());
+
+        let mut conn = self.redis.clone();
+        let (total, ids): (u64, Vec<String>) = redis::pipe()
+            .zcount(&key, &min, &max)
+            .zrevrangebyscore_limit(&key, &max, &min, query.offset as isize, query.limit as isize)
+            .query_async(&mut conn)
+            .await?;
+
+        let records = self.get_many(&ids).await?;
+        let missing: Vec<&String> = ids
+            .iter()
+            .zip(&records)
+            .filter(|(_, record)| record.is_none())
+            .map(|(id, _)| id)
+            .collect();
+        if !missing.is_empty() {
+            // 记录已过期，清理残留索引
+            redis::cmd("ZREM")
+                .arg(&key)
+                .arg(&missing)
+                .query_async::<()>(&mut conn)
+                .await?;
+        }
+
+        Ok(DeadLetterPage {
+            entries: records.into_iter().flatten().collect(),
+            total: total.saturating_sub(missing.len() as u64),
+        })
+    }
+
+    async fn reasons(&self) -> Result<Vec<String>> {
+        let mut conn = self.redis.clone();
+        let mut reasons: Vec<String> = redis::cmd("SMEMBERS")
+            .arg(Self::reasons_key())
+            .query_async(&mut conn)
+            .await?;
+        reasons.sort();
+        Ok(reasons)
+    }
+
+    async fn remove(&self, message_ids: &[String]) -> Result<u64> {
+        let records = self.get_many(message_ids).await?;
+        let reasons: HashSet<&str> = records.iter().flatten().map(|r| r.reason.as_str()).collect();
+        if records.iter().all(Option::is_none) {
+            return Ok(0);
+        }
+
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        for id in message_ids {
+            pipe.del(Self::entry_key(id));
+        }
+        pipe.zrem(Self::time_index_key(), message_ids).ignore();
+        for reason in reasons {
+            pipe.zrem(Self::reason_index_key(reason), message_ids).ignore();
+        }
+
+        let mut conn = self.redis.clone();
+        let deleted: Vec<u64> = pipe.query_async(&mut conn).await?;
+        Ok(deleted.into_iter().sum())
+    }
+
+    async fn try_acquire_replay_quota(&self, count: u32, limit_per_minute: u32) -> Result<bool> {
+        let minute = chrono::Utc::now().timestamp() / 60;
+        let key = Self::key(&format!("replay_quota:{}", minute));
+
+        let mut conn = self.redis.clone();
+        let (used,): (u32,) = redis::pipe()
+            .atomic()
+            .incr(&key, count)
+            .expire(&key, 120)
+            .ignore()
+            .query_async(&mut conn)
+            .await?;
+        if used > limit_per_minute {
+            redis::cmd("DECRBY")
+                .arg(&key)
+                .arg(count)
+                .query_async::<()>(&mut conn)
+                .await?;
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    async fn append_audit(&self, record: &AuditRecord) -> Result<()> {
+        let key = Self::audit_key();
+        let mut conn = self.redis.clone();
+        redis::pipe()
+            .atomic()
+            .lpush(&key, serde_json::to_string(record)?)
+            .ignore()
+            .ltrim(&key, 0, MAX_AUDIT_RECORDS - 1)
+            .ignore()
+            .query_async::<()>(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    async fn list_audit(&self, offset: usize, limit: usize) -> Result<Vec<AuditRecord>> {
+        if limit == 0 {
+            return Ok(Vec::new());
+        }
+        let mut conn = self.redis.clone();
+        let values: Vec<String> = redis::cmd("LRANGE")
+            .arg(Self::audit_key())
+            .arg(offset)
+            .arg(offset + limit - 1)
+            .query_async(&mut conn)
+            .await?;
+        Ok(values
+            .iter()
+            .filter_map(|value| serde_json::from_str(value).ok())
+            .collect())
+    }
+}
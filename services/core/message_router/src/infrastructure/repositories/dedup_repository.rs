@@ -0,0 +1,120 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use common::tenant;
+use redis::aio::ConnectionManager;
+
+use crate::domain::repositories::{DedupClaim, MessageDedupRepository};
+
+/// 处理中状态的值前缀
+const PENDING_PREFIX: &str = "p:";
+/// 已确认状态的值前缀，无前缀的历史记录同样视为已确认
+const DONE_PREFIX: &str = "d:";
+
+/// 键不存在时以处理中状态占用，否则返回现有值
+const CLAIM_SCRIPT: &str = r#"
+if redis.call('SET', KEYS[1], ARGV[1], 'NX', 'EX', ARGV[2]) then
+    return false
+end
+return redis.call('GET', KEYS[1])
+"#;
+
+/// 仅在值匹配时改写，避免改动其他请求重新占用的键
+const CONFIRM_SCRIPT: &str = r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    redis.call('SET', KEYS[1], ARGV[2], 'EX', ARGV[3])
+    return 1
+end
+return 0
+"#;
+
+const RELEASE_SCRIPT: &str = r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    return redis.call('DEL', KEYS[1])
+end
+return 0
+"#;
+
+/// 基于 Redis 的上行消息去重
+///
+/// - `dedup:{key}`: `p:{server_msg_id}` 处理中，`d:{server_msg_id}` 已确认
+pub struct MessageDedupRepositoryImpl {
+    redis: ConnectionManager,
+}
+
+impl MessageDedupRepositoryImpl {
+    pub fn new(redis: ConnectionManager) -> Self {
+        Self { redis }
+    }
+
+    fn key(key: &str) -> String {
+        tenant::current().redis_key(&format!("dedup:{}", key))
+    }
+
+    fn parse(value: String) -> DedupClaim {
+        if let Some(id) = value.strip_prefix(PENDING_PREFIX) {
+            return DedupClaim::InFlight(id.to_string());
+        }
+        match value.strip_prefix(DONE_PREFIX) {
+            Some(id) => DedupClaim::Completed(id.to_string()),
+            None => DedupClaim::Completed(value),
+        }
+    }
+}
+
+#[async_trait]
+impl MessageDedupRepository for MessageDedupRepositoryImpl {
+    async fn claim(&self, key: &str, server_msg_id: &str, pending_ttl_secs: u64) -> Result<DedupClaim> {
+        let mut conn = self.redis.clone();
+        let existing: Option<String> = redis::Script::new(CLAIM_SCRIPT)
+            .key(Self::key(key))
+            .arg(format!("{}{}", PENDING_PREFIX, server_msg_id))
+            .arg(pending_ttl_secs)
+            .invoke_async(&mut conn)
+            .await?;
+        Ok(existing.map_or(DedupClaim::Claimed, Self::parse))
+    }
+
+    async fn confirm(&self, key: &str, server_msg_id: &str, ttl_secs: u64) -> Result<()> {
+        let mut conn = self.redis.clone();
+        let _: i32 = redis::Script::new(CONFIRM_SCRIPT)
+            .key(Self::key(key))
+            .arg(format!("{}{}", PENDING_PREFIX, server_msg_id))
+            .arg(format!("{}{}", DONE_PREFIX, server_msg_id))
+            .arg(ttl_secs)
+            .invoke_async(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    async fn release(&self, key: &str, server_msg_id: &str) -> Result<()> {
+        let mut conn = self.redis.clone();
+        let _: i32 = redis::Script::new(RELEASE_SCRIPT)
+            .key(Self::key(key))
+            .arg(format!("{}{}", PENDING_PREFIX, server_msg_id))
+            .invoke_async(&mut conn)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_stored_state() {
+        assert_eq!(
+            MessageDedupRepositoryImpl::parse("p:1001".to_string()),
+            DedupClaim::InFlight("1001".to_string())
+        );
+        assert_eq!(
+            MessageDedupRepositoryImpl::parse("d:1001".to_string()),
+            DedupClaim::Completed("1001".to_string())
+        );
+        // 历史记录只有服务端消息ID
+        assert_eq!(
+            MessageDedupRepositoryImpl::parse("1001".to_string()),
+            DedupClaim::Completed("1001".to_string())
+        );
+    }
+}
@@ -0,0 +1,143 @@
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+
+use common::rpc::{context_interceptor, ServiceChannel};
+use futures::future::join_all;
+use log::{debug, warn};
+use proto_crate::api::im::common::MessageData;
+use proto_crate::api::im::gateway::message_gateway_client::MessageGatewayClient;
+use proto_crate::api::im::gateway::{BatchPushMessageRequest, PushMessageRequest, PushResult};
+
+use crate::domain::repositories::RouteInfo;
+use crate::entities::{PushOutcome, RecipientPushResult};
+
+/// 单个推送请求最多携带的接收者数量
+const MAX_RECEIVERS_PER_REQUEST: usize = 500;
+/// 推送调用超时
+const PUSH_DEADLINE: Duration = Duration::from_secs(2);
+
+/// 网关推送
+///
+/// 按路由所在网关分组，每个网关发起一次 `BatchPushMessage`，
+/// 各网关并发推送，连接复用消息网关服务通道中对应实例的连接。
+pub struct GatewayPusher {
+    gateways: Arc<ServiceChannel>,
+    deadline: Duration,
+}
+
+impl GatewayPusher {
+    pub fn new(gateways: Arc<ServiceChannel>) -> Self {
+        Self {
+            gateways,
+            deadline: PUSH_DEADLINE,
+        }
+    }
+
+    pub fn with_deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = deadline;
+        self
+    }
+
+    /// 推送消息到路由所在网关
+    pub async fn push(&self, message: &MessageData, routes: Vec<RouteInfo>) -> PushOutcome {
+        // 同一用户的多个设备连接同一网关时只推送一次，由网关投递到该用户的所有设备
+        let mut by_gateway: HashMap<String, BTreeSet<String>> = HashMap::new();
+        for route in routes {
+            by_gateway.entry(route.address).or_default().insert(route.user_id);
+        }
+
+        let pushes = by_gateway
+            .into_iter()
+            .map(|(address, users)| self.push_gateway(message, address, users.into_iter().collect()));
+
+        let mut outcome = PushOutcome::default();
+        for gateway_outcome in join_all(pushes).await {
+            outcome.merge(gateway_outcome);
+        }
+        outcome
+    }
+
+    async fn push_gateway(&self, message: &MessageData, address: String, users: Vec<String>) -> PushOutcome {
+        let request = BatchPushMessageRequest {
+            messages: users
+                .chunks(MAX_RECEIVERS_PER_REQUEST)
+                .map(|receivers| PushMessageRequest {
+                    message: Some(message.clone()),
+                    receiver_ids: receivers.to_vec(),
+                })
+                .collect(),
+        };
+        let deadline = self.deadline;
+
+        let response = self
+            .gateways
+            .call_instance(&address, |channel| {
+                let request = request.clone();
+                async move {
+                    let mut request = tonic::Request::new(request);
+                    request.set_timeout(deadline);
+                    MessageGatewayClient::with_interceptor(channel, context_interceptor)
+                        .batch_push_message(request)
+                        .await
+                }
+            })
+            .await;
+
+        let failed = |user_id: String, error: String| RecipientPushResult {
+            user_id,
+            gateway: address.clone(),
+            success: false,
+            error: Some(error),
+            device_ids: Vec::new(),
+        };
+
+        let response = match response {
+            Ok(response) => response.into_inner(),
+            Err(status) => {
+                warn!(
+                    "Push message {} to gateway {} failed: {}",
+                    message.server_msg_id, address, status
+                );
+                let error = format!("gateway {}: {}", status.code(), status.message());
+                return PushOutcome {
+                    results: users.into_iter().map(|user| failed(user, error.clone())).collect(),
+                };
+            }
+        };
+
+        let mut results: HashMap<String, PushResult> = response
+            .results
+            .into_iter()
+            .flat_map(|r| r.push_results)
+            .collect();
+        let missing_error = if response.error.is_empty() {
+            "no push result from gateway".to_string()
+        } else {
+            response.error
+        };
+
+        let results: Vec<RecipientPushResult> = users
+            .into_iter()
+            .map(|user| match results.remove(&user) {
+                Some(result) => RecipientPushResult {
+                    gateway: address.clone(),
+                    success: result.success,
+                    error: (!result.error.is_empty()).then_some(result.error),
+                    device_ids: result.device_ids,
+                    user_id: user,
+                },
+                None => failed(user, missing_error.clone()),
+            })
+            .collect();
+
+        debug!(
+            "Pushed message {} via gateway {}: {}/{} delivered",
+            message.server_msg_id,
+            address,
+            results.iter().filter(|r| r.success).count(),
+            results.len()
+        );
+        PushOutcome { results }
+    }
+}
@@ -1,15 +1,46 @@
+use std::sync::Arc;
+use std::time::Duration;
+
 use async_trait::async_trait;
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use common::rpc::{context_interceptor, ServiceChannel};
+use proto_crate::api::im::business::group::group_service_client::GroupServiceClient;
+use proto_crate::api::im::business::group::GetGroupListRequest;
 use crate::domain::{
     repositories::{GroupRepository, GroupMemberQuery, GroupMemberPage},
     entities::{GroupStatus, MemberStatus},
 };
 use proto_crate::api::im::common::MessageData;
-pub struct GroupRepositoryImpl;
+use super::online_member_index::OnlineMemberIndex;
+
+/// 群组服务调用超时
+const GROUP_DEADLINE: Duration = Duration::from_secs(2);
+/// 拉取已加入群列表的分页大小
+const GROUP_LIST_PAGE_SIZE: i32 = 500;
+
+pub struct GroupRepositoryImpl {
+    online_index: Option<OnlineMemberIndex>,
+    group_service: Option<Arc<ServiceChannel>>,
+}
 
 impl GroupRepositoryImpl {
     pub fn new() -> Self {
-        Self {}
+        Self {
+            online_index: None,
+            group_service: None,
+        }
+    }
+
+    /// 通过群组服务查询用户加入的群
+    pub fn with_group_service(mut self, channel: Arc<ServiceChannel>) -> Self {
+        self.group_service = Some(channel);
+        self
+    }
+
+    /// 使用 Redis 在线成员索引提供在线成员查询
+    pub fn with_online_index(mut self, index: OnlineMemberIndex) -> Self {
+        self.online_index = Some(index);
+        self
     }
 }
 
@@ -36,7 +67,10 @@ impl GroupRepository for GroupRepositoryImpl {
         })
     }
 
-    async fn get_online_members_paged(&self, _group_id: &str, _page_size: i32, _cursor: Option<String>) -> Result<GroupMemberPage> {
+    async fn get_online_members_paged(&self, group_id: &str, page_size: i32, cursor: Option<String>) -> Result<GroupMemberPage> {
+        if let Some(index) = &self.online_index {
+            return index.page(group_id, page_size, cursor).await;
+        }
         Ok(GroupMemberPage {
             members: vec!["user1".to_string(), "user2".to_string()],
             total: 2,
@@ -75,4 +109,58 @@ impl GroupRepository for GroupRepositoryImpl {
     async fn get_member_count(&self, _group_id: &str) -> Result<i64> {
         Ok(3)
     }
+
+    async fn get_joined_groups(&self, user_id: &str) -> Result<Vec<String>> {
+        let channel = self
+            .group_service
+            .as_ref()
+            .ok_or_else(|| anyhow!("group service is not configured"))?;
+
+        let mut groups = Vec::new();
+        let mut page_token = String::new();
+        loop {
+            let request = GetGroupListRequest {
+                user_id: user_id.to_string(),
+                page_size: GROUP_LIST_PAGE_SIZE,
+                page_token: page_token.clone(),
+            };
+            let response = channel
+                .call(|channel| {
+                    let request = request.clone();
+                    async move {
+                        let mut request = tonic::Request::new(request);
+                        request.set_timeout(GROUP_DEADLINE);
+                        GroupServiceClient::with_interceptor(channel, context_interceptor)
+                            .get_group_list(request)
+                            .await
+                    }
+                })
+                .await
+                .map_err(|status| anyhow!("get joined groups of {} failed: {}", user_id, status))?
+                .into_inner();
+
+            if let Some(error) = response.error.filter(|e| e.code != 0) {
+                return Err(anyhow!("get joined groups of {} failed: {}", user_id, error.message));
+            }
+            groups.extend(response.groups.into_iter().map(|group| group.group_id));
+            if response.next_page_token.is_empty() {
+                return Ok(groups);
+            }
+            page_token = response.next_page_token;
+        }
+    }
+
+    async fn set_member_online(&self, user_id: &str, group_ids: &[String], online: bool) -> Result<()> {
+        match &self.online_index {
+            Some(index) => index.set_online(user_id, group_ids, online).await,
+            None => Ok(()),
+        }
+    }
+
+    async fn remove_online_members(&self, group_id: &str, user_ids: &[String]) -> Result<()> {
+        match &self.online_index {
+            Some(index) => index.remove(group_id, user_ids).await,
+            None => Ok(()),
+        }
+    }
 } 
\ No newline at end of file
@@ -0,0 +1,235 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use common::tenant;
+use log::warn;
+use prost::Message;
+use proto_crate::api::im::common::MessageData;
+use redis::aio::ConnectionManager;
+
+use crate::domain::repositories::{GroupTimelinePage, GroupTimelineRepository};
+
+/// 每个群保留的时间线长度，更早的消息由客户端从消息存储拉取
+const MAX_TIMELINE_LEN: usize = 5000;
+/// 时间线在群无新消息后的保留时间
+const TIMELINE_TTL_SECS: i64 = 7 * 86_400;
+
+/// 追加消息，已追加过的消息返回原序号
+///
+/// 时间线过期或超长裁剪时记录已移出的最大序号，供拉取时判断缺口。
+const APPEND_SCRIPT: &str = r#"
+local existing = redis.call('ZSCORE', KEYS[3], ARGV[1])
+if existing then
+    return tonumber(existing)
+end
+if redis.call('EXISTS', KEYS[1]) == 0 then
+    redis.call('SET', KEYS[4], redis.call('GET', KEYS[2]) or 0)
+end
+local seq = redis.call('INCR', KEYS[2])
+redis.call('ZADD', KEYS[1], seq, ARGV[2])
+redis.call('ZADD', KEYS[3], seq, ARGV[1])
+local max_len = tonumber(ARGV[3])
+if redis.call('ZREMRANGEBYRANK', KEYS[1], 0, -(max_len + 1)) > 0 then
+    local oldest = redis.call('ZRANGE', KEYS[1], 0, 0, 'WITHSCORES')
+    redis.call('SET', KEYS[4], tonumber(oldest[2]) - 1)
+end
+redis.call('ZREMRANGEBYRANK', KEYS[3], 0, -(max_len + 1))
+redis.call('EXPIRE', KEYS[1], ARGV[4])
+redis.call('EXPIRE', KEYS[3], ARGV[4])
+return seq
+"#;
+
+/// 按消息ID删除时间线中的消息
+const REMOVE_SCRIPT: &str = r#"
+local seq = redis.call('ZSCORE', KEYS[2], ARGV[1])
+if not seq then
+    return 0
+end
+redis.call('ZREM', KEYS[2], ARGV[1])
+return redis.call('ZREMRANGEBYSCORE', KEYS[1], seq, seq)
+"#;
+
+/// 基于 Redis 的群消息时间线
+///
+/// - `timeline:group:{group_id}:seq`: 群内序号计数，不过期，保证序号不回退
+/// - `timeline:group:{group_id}`: ZSET，member 为 protobuf 编码的消息(不含序号)，score 为序号
+/// - `timeline:group:{group_id}:ids`: ZSET，member 为服务端消息ID，score 为序号，用于追加去重与删除
+/// - `timeline:group:{group_id}:floor`: 已移出时间线的最大序号，不过期
+pub struct GroupTimelineRepositoryImpl {
+    redis: ConnectionManager,
+    max_len: usize,
+}
+
+impl GroupTimelineRepositoryImpl {
+    pub fn new(redis: ConnectionManager) -> Self {
+        Self {
+            redis,
+            max_len: MAX_TIMELINE_LEN,
+        }
+    }
+
+    fn timeline_key(group_id: &str) -> String {
+        tenant::current().redis_key(&format!("timeline:group:{}", group_id))
+    }
+
+    fn seq_key(group_id: &str) -> String {
+        tenant::current().redis_key(&format!("timeline:group:{}:seq", group_id))
+    }
+
+    fn ids_key(group_id: &str) -> String {
+        tenant::current().redis_key(&format!("timeline:group:{}:ids", group_id))
+    }
+
+    fn floor_key(group_id: &str) -> String {
+        tenant::current().redis_key(&format!("timeline:group:{}:floor", group_id))
+    }
+}
+
+#[async_trait]
+impl GroupTimelineRepository for GroupTimelineRepositoryImpl {
+    async fn append(&self, group_id: &str, message: &MessageData) -> Result<i64> {
+        // 序号由分数给出，member 不含序号，重试时内容一致
+        let mut message = message.clone();
+        message.seq = 0;
+        let mut conn = self.redis.clone();
+        let seq: i64 = redis::Script::new(APPEND_SCRIPT)
+            .key(Self::timeline_key(group_id))
+            .key(Self::seq_key(group_id))
+            .key(Self::ids_key(group_id))
+            .key(Self::floor_key(group_id))
+            .arg(&message.server_msg_id)
+            .arg(message.encode_to_vec())
+            .arg(self.max_len)
+            .arg(TIMELINE_TTL_SECS)
+            .invoke_async(&mut conn)
+            .await?;
+        Ok(seq)
+    }
+
+    async fn pull(&self, group_id: &str, after_seq: i64, limit: usize) -> Result<GroupTimelinePage> {
+        let key = Self::timeline_key(group_id);
+        let mut conn = self.redis.clone();
+        let (entries, latest_seq, floor, exists): (Vec<(Vec<u8>, i64)>, Option<i64>, Option<i64>, bool) =
+            redis::pipe()
+                .zrangebyscore_limit_withscores(&key, format!("({}", after_seq), "+inf", 0, limit as isize + 1)
+                .get(Self::seq_key(group_id))
+                .get(Self::floor_key(group_id))
+                .exists(&key)
+                .query_async(&mut conn)
+                .await?;
+
+        let latest_seq = latest_seq.unwrap_or(0);
+        // 时间线已过期时此前的消息均不可拉取
+        let floor = if exists { floor.unwrap_or(0) } else { latest_seq };
+        let has_more = entries.len() > limit;
+        let messages = entries
+            .into_iter()
+            .take(limit)
+            .filter_map(|(entry, seq)| match MessageData::decode(entry.as_slice()) {
+                Ok(mut message) => {
+                    message.seq = seq;
+                    Some(message)
+                }
+                Err(e) => {
+                    warn!("Invalid timeline entry in group {}: {}", group_id, e);
+                    None
+                }
+            })
+            .collect();
+
+        Ok(GroupTimelinePage {
+            messages,
+            latest_seq,
+            min_seq: floor + 1,
+            has_more,
+        })
+    }
+
+    async fn remove(&self, group_id: &str, message_id: &str) -> Result<bool> {
+        let mut conn = self.redis.clone();
+        let removed: i32 = redis::Script::new(REMOVE_SCRIPT)
+            .key(Self::timeline_key(group_id))
+            .key(Self::ids_key(group_id))
+            .arg(message_id)
+            .invoke_async(&mut conn)
+            .await?;
+        Ok(removed > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn repository(max_len: usize) -> GroupTimelineRepositoryImpl {
+        let client = redis::Client::open("redis://127.0.0.1:6379").unwrap();
+        GroupTimelineRepositoryImpl {
+            redis: ConnectionManager::new(client).await.unwrap(),
+            max_len,
+        }
+    }
+
+    fn message(id: &str) -> MessageData {
+        MessageData {
+            server_msg_id: id.to_string(),
+            send_id: "user1".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "requires redis"]
+    async fn test_append_is_idempotent() {
+        let repository = repository(MAX_TIMELINE_LEN).await;
+        let group_id = format!("timeline-{}", uuid::Uuid::new_v4());
+
+        assert_eq!(repository.append(&group_id, &message("1")).await.unwrap(), 1);
+        // 分发重试不再分配新序号
+        assert_eq!(repository.append(&group_id, &message("1")).await.unwrap(), 1);
+        assert_eq!(repository.append(&group_id, &message("2")).await.unwrap(), 2);
+
+        let page = repository.pull(&group_id, 0, 10).await.unwrap();
+        let seqs: Vec<_> = page.messages.iter().map(|m| (m.server_msg_id.as_str(), m.seq)).collect();
+        assert_eq!(seqs, vec![("1", 1), ("2", 2)]);
+        assert_eq!((page.latest_seq, page.min_seq), (2, 1));
+    }
+
+    #[tokio::test]
+    #[ignore = "requires redis"]
+    async fn test_trimmed_messages_are_reported() {
+        let repository = repository(2).await;
+        let group_id = format!("timeline-{}", uuid::Uuid::new_v4());
+        for id in ["1", "2", "3"] {
+            repository.append(&group_id, &message(id)).await.unwrap();
+        }
+
+        let page = repository.pull(&group_id, 0, 10).await.unwrap();
+        assert_eq!(page.min_seq, 2);
+        assert_eq!(page.messages.len(), 2);
+
+        // 时间线过期后序号之前的消息均不可拉取
+        let mut conn = repository.redis.clone();
+        redis::cmd("DEL")
+            .arg(GroupTimelineRepositoryImpl::timeline_key(&group_id))
+            .query_async::<()>(&mut conn)
+            .await
+            .unwrap();
+        let page = repository.pull(&group_id, 0, 10).await.unwrap();
+        assert!(page.messages.is_empty());
+        assert_eq!(page.min_seq, 4);
+    }
+
+    #[tokio::test]
+    #[ignore = "requires redis"]
+    async fn test_remove_by_message_id() {
+        let repository = repository(MAX_TIMELINE_LEN).await;
+        let group_id = format!("timeline-{}", uuid::Uuid::new_v4());
+        repository.append(&group_id, &message("1")).await.unwrap();
+        repository.append(&group_id, &message("2")).await.unwrap();
+
+        assert!(repository.remove(&group_id, "1").await.unwrap());
+        assert!(!repository.remove(&group_id, "1").await.unwrap());
+        let page = repository.pull(&group_id, 0, 10).await.unwrap();
+        assert_eq!(page.messages.len(), 1);
+        assert_eq!(page.messages[0].server_msg_id, "2");
+    }
+}
@@ -0,0 +1,97 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use common::tenant;
+use common::utils::msg_utils::mention_unread_key;
+use redis::aio::ConnectionManager;
+
+use crate::domain::repositories::MentionRepository;
+
+/// 计数保留时间，用户长期未活跃时自动清理
+const MENTION_TTL_SECS: i64 = 30 * 86_400;
+/// 已计数标记的保留时间，覆盖分发重试的时间范围
+const RECORDED_TTL_SECS: i64 = 86_400;
+
+/// 标记不存在时为每个用户计数，已计入过时返回 0
+const INCREMENT_SCRIPT: &str = r#"
+if not redis.call('SET', KEYS[1], 1, 'NX', 'EX', ARGV[3]) then
+    return 0
+end
+for i = 2, #KEYS do
+    redis.call('HINCRBY', KEYS[i], ARGV[1], 1)
+    redis.call('EXPIRE', KEYS[i], ARGV[2])
+end
+return 1
+"#;
+
+/// 基于 Redis 的会话 @ 未读计数
+///
+/// - `mention:unread:{user_id}`: HASH，field 为会话ID，value 为 @ 未读数
+/// - `mention:recorded:{record_id}`: 已计数的批次
+pub struct MentionRepositoryImpl {
+    redis: ConnectionManager,
+}
+
+impl MentionRepositoryImpl {
+    pub fn new(redis: ConnectionManager) -> Self {
+        Self { redis }
+    }
+
+    fn key(user_id: &str) -> String {
+        tenant::current().redis_key(&mention_unread_key(user_id))
+    }
+
+    fn recorded_key(record_id: &str) -> String {
+        tenant::current().redis_key(&format!("mention:recorded:{}", record_id))
+    }
+}
+
+#[async_trait]
+impl MentionRepository for MentionRepositoryImpl {
+    async fn increment(&self, record_id: &str, conversation_id: &str, user_ids: &[String]) -> Result<bool> {
+        if user_ids.is_empty() {
+            return Ok(false);
+        }
+
+        let script = redis::Script::new(INCREMENT_SCRIPT);
+        let mut invocation = script.key(Self::recorded_key(record_id));
+        for user_id in user_ids {
+            invocation.key(Self::key(user_id));
+        }
+        let mut conn = self.redis.clone();
+        let recorded: i32 = invocation
+            .arg(conversation_id)
+            .arg(MENTION_TTL_SECS)
+            .arg(RECORDED_TTL_SECS)
+            .invoke_async(&mut conn)
+            .await?;
+        Ok(recorded == 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    #[ignore = "requires redis"]
+    async fn test_increment_once_per_record() {
+        let client = redis::Client::open("redis://127.0.0.1:6379").unwrap();
+        let mut conn = ConnectionManager::new(client).await.unwrap();
+        let repository = MentionRepositoryImpl::new(conn.clone());
+        let record_id = uuid::Uuid::new_v4().to_string();
+        let user_id = format!("user-{}", uuid::Uuid::new_v4());
+        let users = vec![user_id.clone()];
+
+        assert!(repository.increment(&record_id, "sg_1", &users).await.unwrap());
+        assert!(!repository.increment(&record_id, "sg_1", &users).await.unwrap());
+        assert!(repository.increment(&format!("{}:1", record_id), "sg_1", &users).await.unwrap());
+
+        let count: i64 = redis::cmd("HGET")
+            .arg(MentionRepositoryImpl::key(&user_id))
+            .arg("sg_1")
+            .query_async(&mut conn)
+            .await
+            .unwrap();
+        assert_eq!(count, 2);
+    }
+}
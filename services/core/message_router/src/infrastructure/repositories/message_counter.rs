@@ -0,0 +1,292 @@
+use anyhow::Result;
+use chrono::Utc;
+use common::tenant;
+use common::utils::msg_utils::{at_type, is_group_message};
+use proto_crate::api::im::common::{AtType, MessageData};
+use redis::aio::ConnectionManager;
+
+use crate::domain::entities::{QuotaVerdict, SendQuota};
+
+/// 发送频率滑动窗口
+const RATE_WINDOW_MS: i64 = 60_000;
+/// 每日计数保留时间，跨天查询前一天的计数时仍可用
+const DAILY_TTL_SECS: i64 = 2 * 86_400;
+/// 已计数标记的保留时间，覆盖定时消息的最远投递时间
+const RESERVED_TTL_SECS: i64 = 31 * 86_400;
+
+/// 检查并计数，超出任一上限时不计数
+///
+/// 返回 0 表示已计入(或此前已计入)，1/2/3 分别表示超出发送频率、每日数量与 @所有人 次数。
+/// 标记记录本次计数的每日键，退回时按原日期扣减。
+const RESERVE_SCRIPT: &str = r#"
+if redis.call('EXISTS', KEYS[1]) == 1 then
+    return 0
+end
+local now = tonumber(ARGV[1])
+redis.call('ZREMRANGEBYSCORE', KEYS[2], '-inf', now - tonumber(ARGV[2]))
+if redis.call('ZCARD', KEYS[2]) >= tonumber(ARGV[3]) then
+    return 1
+end
+if tonumber(redis.call('GET', KEYS[3]) or '0') >= tonumber(ARGV[4]) then
+    return 2
+end
+local at_all = ARGV[5] ~= ''
+if at_all and tonumber(redis.call('GET', KEYS[4]) or '0') >= tonumber(ARGV[5]) then
+    return 3
+end
+redis.call('ZADD', KEYS[2], now, ARGV[6])
+redis.call('PEXPIRE', KEYS[2], ARGV[2])
+redis.call('INCR', KEYS[3])
+redis.call('EXPIRE', KEYS[3], ARGV[7])
+local recorded = KEYS[3] .. '\n'
+if at_all then
+    redis.call('INCR', KEYS[4])
+    redis.call('EXPIRE', KEYS[4], ARGV[7])
+    recorded = recorded .. KEYS[4]
+end
+redis.call('SET', KEYS[1], recorded, 'EX', ARGV[8])
+return 0
+"#;
+
+/// 按标记扣减计数并删除标记
+const RELEASE_SCRIPT: &str = r#"
+local recorded = redis.call('GET', KEYS[1])
+if not recorded then
+    return 0
+end
+redis.call('DEL', KEYS[1])
+redis.call('ZREM', KEYS[2], ARGV[1])
+for key in string.gmatch(recorded, '[^\n]+') do
+    if tonumber(redis.call('GET', key) or '0') > 0 then
+        redis.call('DECR', key)
+    end
+end
+return 1
+"#;
+
+/// 消息计数器
+///
+/// - `rate:user:{user_id}`: ZSET，member 为消息ID，score 为发送时间(毫秒)，用于滑动窗口计数
+/// - `quota:group:{group_id}:{yyyymmdd}` / `quota:private:{sender}:{receiver}:{yyyymmdd}`: 每日计数
+/// - `quota:at_all:{group_id}:{yyyymmdd}`: 群每日 @所有人 次数
+/// - `quota:reserved:{server_msg_id}`: 已计数的消息，记录计数的每日键
+///
+/// 预处理通过时检查并计数，检查与计数原子执行；未送达的消息退回配额。按租户隔离。
+#[derive(Clone)]
+pub struct MessageCounter {
+    redis: ConnectionManager,
+}
+
+impl MessageCounter {
+    pub fn new(redis: ConnectionManager) -> Self {
+        Self { redis }
+    }
+
+    fn rate_key(user_id: &str) -> String {
+        tenant::current().redis_key(&format!("rate:user:{}", user_id))
+    }
+
+    fn group_daily_key(group_id: &str, day: &str) -> String {
+        tenant::current().redis_key(&format!("quota:group:{}:{}", group_id, day))
+    }
+
+    fn private_daily_key(sender_id: &str, receiver_id: &str, day: &str) -> String {
+        tenant::current().redis_key(&format!("quota:private:{}:{}:{}", sender_id, receiver_id, day))
+    }
+
+    fn at_all_daily_key(group_id: &str, day: &str) -> String {
+        tenant::current().redis_key(&format!("quota:at_all:{}:{}", group_id, day))
+    }
+
+    fn reserved_key(server_msg_id: &str) -> String {
+        tenant::current().redis_key(&format!("quota:reserved:{}", server_msg_id))
+    }
+
+    fn today() -> String {
+        Utc::now().format("%Y%m%d").to_string()
+    }
+
+    /// 最近 `seconds` 秒内的发送数量
+    pub async fn recent_count(&self, user_id: &str, seconds: i32) -> Result<i32> {
+        let key = Self::rate_key(user_id);
+        let now = Utc::now().timestamp_millis();
+        let window_start = now - (seconds as i64 * 1000).min(RATE_WINDOW_MS);
+
+        let mut conn = self.redis.clone();
+        let (count,): (i32,) = redis::pipe()
+            .atomic()
+            .zrembyscore(&key, "-inf", now - RATE_WINDOW_MS)
+            .ignore()
+            .zcount(&key, window_start, "+inf")
+            .query_async(&mut conn)
+            .await?;
+        Ok(count)
+    }
+
+    /// 群今日消息数量
+    pub async fn group_daily_count(&self, group_id: &str) -> Result<i32> {
+        let mut conn = self.redis.clone();
+        let count: Option<i32> = redis::cmd("GET")
+            .arg(Self::group_daily_key(group_id, &Self::today()))
+            .query_async(&mut conn)
+            .await?;
+        Ok(count.unwrap_or(0))
+    }
+
+    /// 群今日 @所有人 次数
+    pub async fn at_all_daily_count(&self, group_id: &str) -> Result<i32> {
+        let mut conn = self.redis.clone();
+        let count: Option<i32> = redis::cmd("GET")
+            .arg(Self::at_all_daily_key(group_id, &Self::today()))
+            .query_async(&mut conn)
+            .await?;
+        Ok(count.unwrap_or(0))
+    }
+
+    /// 单聊今日消息数量
+    pub async fn private_daily_count(&self, sender_id: &str, receiver_id: &str) -> Result<i32> {
+        let mut conn = self.redis.clone();
+        let count: Option<i32> = redis::cmd("GET")
+            .arg(Self::private_daily_key(sender_id, receiver_id, &Self::today()))
+            .query_async(&mut conn)
+            .await?;
+        Ok(count.unwrap_or(0))
+    }
+
+    /// 检查并计入发送配额，同一消息只计一次
+    pub async fn reserve(&self, message: &MessageData, quota: SendQuota) -> Result<QuotaVerdict> {
+        let day = Self::today();
+        let daily_key = if is_group_message(message) {
+            Self::group_daily_key(&message.group_id, &day)
+        } else {
+            Self::private_daily_key(&message.send_id, &message.recv_id, &day)
+        };
+        // 非 @所有人 消息不检查也不计数 @所有人 次数
+        let at_all_limit = match (is_group_message(message), at_type(message)) {
+            (true, AtType::AtAll) => quota.at_all_daily.to_string(),
+            _ => String::new(),
+        };
+
+        let mut conn = self.redis.clone();
+        let verdict: i32 = redis::Script::new(RESERVE_SCRIPT)
+            .key(Self::reserved_key(&message.server_msg_id))
+            .key(Self::rate_key(&message.send_id))
+            .key(daily_key)
+            .key(Self::at_all_daily_key(&message.group_id, &day))
+            .arg(Utc::now().timestamp_millis())
+            .arg(RATE_WINDOW_MS)
+            .arg(quota.rate_per_minute)
+            .arg(quota.daily)
+            .arg(at_all_limit)
+            .arg(&message.server_msg_id)
+            .arg(DAILY_TTL_SECS)
+            .arg(RESERVED_TTL_SECS)
+            .invoke_async(&mut conn)
+            .await?;
+        Ok(match verdict {
+            1 => QuotaVerdict::RateLimited,
+            2 => QuotaVerdict::DailyLimited,
+            3 => QuotaVerdict::AtAllLimited,
+            _ => QuotaVerdict::Reserved,
+        })
+    }
+
+    /// 退回消息占用的配额，未计入的消息忽略
+    pub async fn release(&self, message: &MessageData) -> Result<()> {
+        let mut conn = self.redis.clone();
+        redis::Script::new(RELEASE_SCRIPT)
+            .key(Self::reserved_key(&message.server_msg_id))
+            .key(Self::rate_key(&message.send_id))
+            .arg(&message.server_msg_id)
+            .invoke_async::<()>(&mut conn)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proto_crate::api::im::common::SessionType;
+
+    use super::*;
+
+    async fn counter() -> MessageCounter {
+        let client = redis::Client::open("redis://127.0.0.1:6379").unwrap();
+        MessageCounter::new(ConnectionManager::new(client).await.unwrap())
+    }
+
+    fn message(server_msg_id: &str, send_id: &str) -> MessageData {
+        MessageData {
+            server_msg_id: server_msg_id.to_string(),
+            send_id: send_id.to_string(),
+            recv_id: "receiver".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn quota(rate_per_minute: u32, daily: u32) -> SendQuota {
+        SendQuota {
+            rate_per_minute,
+            daily,
+            at_all_daily: 1,
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "requires redis"]
+    async fn test_reserve_counts_each_message_once() {
+        let counter = counter().await;
+        let sender = format!("sender-{}", uuid::Uuid::new_v4());
+        let first = message(&uuid::Uuid::new_v4().to_string(), &sender);
+
+        assert_eq!(counter.reserve(&first, quota(2, 10)).await.unwrap(), QuotaVerdict::Reserved);
+        // 重试与重放不重复计数
+        assert_eq!(counter.reserve(&first, quota(2, 10)).await.unwrap(), QuotaVerdict::Reserved);
+        assert_eq!(counter.recent_count(&sender, 60).await.unwrap(), 1);
+        assert_eq!(counter.private_daily_count(&sender, "receiver").await.unwrap(), 1);
+
+        let second = message(&uuid::Uuid::new_v4().to_string(), &sender);
+        assert_eq!(counter.reserve(&second, quota(2, 10)).await.unwrap(), QuotaVerdict::Reserved);
+        let third = message(&uuid::Uuid::new_v4().to_string(), &sender);
+        assert_eq!(counter.reserve(&third, quota(2, 10)).await.unwrap(), QuotaVerdict::RateLimited);
+        // 超限的消息不计数
+        assert_eq!(counter.recent_count(&sender, 60).await.unwrap(), 2);
+        assert_eq!(counter.reserve(&third, quota(10, 2)).await.unwrap(), QuotaVerdict::DailyLimited);
+    }
+
+    #[tokio::test]
+    #[ignore = "requires redis"]
+    async fn test_release_returns_quota() {
+        let counter = counter().await;
+        let sender = format!("sender-{}", uuid::Uuid::new_v4());
+        let first = message(&uuid::Uuid::new_v4().to_string(), &sender);
+
+        counter.reserve(&first, quota(1, 10)).await.unwrap();
+        counter.release(&first).await.unwrap();
+        // 重复退回无影响
+        counter.release(&first).await.unwrap();
+        assert_eq!(counter.recent_count(&sender, 60).await.unwrap(), 0);
+        assert_eq!(counter.private_daily_count(&sender, "receiver").await.unwrap(), 0);
+
+        let second = message(&uuid::Uuid::new_v4().to_string(), &sender);
+        assert_eq!(counter.reserve(&second, quota(1, 10)).await.unwrap(), QuotaVerdict::Reserved);
+    }
+
+    #[tokio::test]
+    #[ignore = "requires redis"]
+    async fn test_at_all_limit() {
+        let counter = counter().await;
+        let group_id = format!("group-{}", uuid::Uuid::new_v4());
+        let at_all = |id: &str| MessageData {
+            group_id: group_id.clone(),
+            session_type: SessionType::NormalGroup as i32,
+            at_type: AtType::AtAll as i32,
+            ..message(id, "owner")
+        };
+
+        assert_eq!(counter.reserve(&at_all("a1"), quota(10, 10)).await.unwrap(), QuotaVerdict::Reserved);
+        assert_eq!(counter.reserve(&at_all("a2"), quota(10, 10)).await.unwrap(), QuotaVerdict::AtAllLimited);
+        assert_eq!(counter.at_all_daily_count(&group_id).await.unwrap(), 1);
+        assert_eq!(counter.group_daily_count(&group_id).await.unwrap(), 1);
+    }
+}
@@ -3,102 +3,100 @@ use async_trait::async_trait;
 use anyhow::{Result, anyhow};
 use tracing::{info, warn, error, instrument, debug};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
-use rdkafka::{
-    producer::{FutureProducer, FutureRecord},
-    ClientConfig,
-    util::Timeout,
-    error::KafkaError,
-    types::RDKafkaErrorCode,
-};
 use std::sync::Arc;
 use prost::Message;
 use tokio::sync::Semaphore;
 
 use proto_crate::api::im::common::{MessageData, MessagePayload};
 use common::topic::KafkaTopics;
+use common::bus::{BusError, MessageProducer, OutgoingMessage};
+use common::tenant;
+use common::utils::msg_utils::{conversation_id, message_priority};
 use crate::domain::{
     repositories::{MessageRepository, RouteInfo},
-    entities::{MessageStatus, DeviceStatus, UserStatus},
+    entities::{
+        MessageStatus, DeviceStatus, PushOutcome, QuotaVerdict, SendQuota, UserStatus,
+        DEAD_LETTER_REASON_KEY, REASON_PROCESSING_FAILED, REASON_RETRY_EXHAUSTED,
+    },
 };
+use crate::infrastructure::metrics::RetryMetrics;
+use super::gateway_pusher::GatewayPusher;
+use super::message_counter::MessageCounter;
+use super::store_client::StoreClient;
 
 const MAX_RETRY_COUNT: i32 = 3;
 const BASE_RETRY_DELAY_MS: u64 = 100;
-const KAFKA_TIMEOUT_MS: u64 = 1500;
 const MAX_INFLIGHT_MESSAGES: usize = 10000;
 
-
+/// 构建消息总线记录，消息体为 protobuf 编码的 `MessagePayload`
+pub(crate) fn payload_record(message: &MessageData) -> OutgoingMessage {
+    let payload = MessagePayload {
+        msg_id: message.server_msg_id.clone(),
+        msg: Some(message.clone()),
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as i64,
+        metadata: HashMap::new(),
+    };
+    OutgoingMessage::new(payload.encode_to_vec())
+        .timestamp(payload.timestamp)
+        .with_current_context()
+}
 
 pub struct MessageRepositoryImpl {
-    producer: FutureProducer,
+    producer: Arc<dyn MessageProducer>,
+    gateway_pusher: GatewayPusher,
+    counter: MessageCounter,
+    store: StoreClient,
     inflight_semaphore: Arc<Semaphore>,
 }
 
 impl MessageRepositoryImpl {
-    pub fn new() -> Result<Self> {
-        let mut config = ClientConfig::new();
-        
-        // Kafka 基础配置
-        config.set("bootstrap.servers", "localhost:9092")  // Kafka 服务器地址
-             .set("client.id", "message-router")          // 客户端标识
-             .set("acks", "all")                         // 需要所有副本确认
-             .set("enable.idempotence", "true")          // 启用幂等性
-             .set("message.timeout.ms", "30000")         // 消息超时时间
-             .set("request.timeout.ms", "15000")         // 请求超时时间
-             .set("retries", "3")                        // 重试次数
-             .set("retry.backoff.ms", "100")             // 重试间隔
-             .set("compression.type", "snappy")          // 压缩类型
-             .set("queue.buffering.max.messages", "10000") // 最大缓冲消息数
-             .set("queue.buffering.max.ms", "5");        // 最大缓冲时间
-        
-        let producer = config.create()
-            .map_err(|e| anyhow!("Failed to create Kafka producer: {}", e))?;
-
-        Ok(Self {
+    pub fn new(
+        producer: Arc<dyn MessageProducer>,
+        gateway_pusher: GatewayPusher,
+        counter: MessageCounter,
+        store: StoreClient,
+    ) -> Self {
+        Self {
             producer,
+            gateway_pusher,
+            counter,
+            store,
             inflight_semaphore: Arc::new(Semaphore::new(MAX_INFLIGHT_MESSAGES)),
-        })
+        }
+    }
+
+    /// 以会话为键写入，同一会话的消息落在同一分区，保持发送顺序
+    async fn send_conversation(&self, topic: &str, message: &MessageData) -> Result<()> {
+        self.send_to_bus(topic, &conversation_id(message), message).await
     }
 
     #[instrument(skip(self, message))]
-    async fn send_to_kafka(&self, topic: &str, message: &MessageData) -> Result<()> {
+    async fn send_to_bus(&self, topic: &str, key: &str, message: &MessageData) -> Result<()> {
         let _permit = self.inflight_semaphore.acquire().await?;
         
         debug!("Sending message {} to topic {}", message.server_msg_id, topic);
-        let key = message.server_msg_id.clone();
-        // 构建消息负载
-        let payload = MessagePayload {
-            msg_id: message.server_msg_id.clone(),
-            msg: Some(message.clone()),
-            timestamp: SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap_or_default()
-                .as_millis() as i64,
-            metadata: HashMap::new(),
-        };
+        let key = tenant::current().kafka_key(key);
+        let record = payload_record(message).key(key);
 
-        let mut payload_bytes = Vec::new();
-        payload.encode(&mut payload_bytes)?;
-        let record = FutureRecord::to(topic)
-            .key(&key)
-            .payload(&payload_bytes)
-            .timestamp(payload.timestamp);
-
-        match self.producer.send(record, Timeout::After(Duration::from_millis(KAFKA_TIMEOUT_MS))).await {
-            Ok((partition, offset)) => {
+        match self.producer.send(topic, record).await {
+            Ok(receipt) => {
                 info!(
                     "Message sent successfully: topic={}, partition={}, offset={}", 
-                    topic, partition, offset
+                    topic, receipt.partition, receipt.offset
                 );
                 Ok(())
             }
-            Err((KafkaError::MessageProduction(RDKafkaErrorCode::QueueFull), _)) => {
-                warn!("Kafka producer queue full, retrying...");
+            Err(BusError::QueueFull) => {
+                warn!("Message bus producer queue full, retrying...");
                 tokio::time::sleep(Duration::from_millis(100)).await;
-                Err(anyhow!("Kafka producer queue full"))
+                Err(anyhow!("Message bus producer queue full"))
             }
-            Err((err, _)) => {
+            Err(err) => {
                 error!("Failed to send message: {}", err);
-                Err(anyhow!("Kafka send error: {}", err))
+                Err(anyhow!("Message bus send error: {}", err))
             }
         }
     }
@@ -132,50 +130,57 @@ impl MessageRepository for MessageRepositoryImpl {
     #[instrument(skip(self, message))]
     async fn save_message(&self, message: &MessageData) -> Result<()> {
         self.retry_with_backoff(|| async { 
-            self.send_to_kafka(KafkaTopics::MESSAGE_STORE, message).await 
+            self.send_conversation(KafkaTopics::MESSAGE_STORE, message).await 
         }).await
     }
 
     #[instrument(skip(self, message))]
     async fn handle_message_distribution(&self, message: &MessageData) -> Result<()> {
         self.retry_with_backoff(|| async {
-            self.send_to_kafka(KafkaTopics::MESSAGE_DISTRIBUTION, message).await
+            self.send_conversation(KafkaTopics::distribution_topic(message_priority(message)), message).await
         }).await
     }
 
-    async fn push_message(&self, message: &MessageData, routers: Vec<RouteInfo>) -> Result<()> {
+    #[instrument(skip(self, message))]
+    async fn schedule_retry(&self, message: &MessageData, delay_ms: i64) -> Result<()> {
+        let (topic, _) = KafkaTopics::retry_topic(delay_ms);
+        self.retry_with_backoff(|| async {
+            self.send_conversation(topic, message).await
+        }).await?;
+
+        let attempt = message.options.get("retry_count")
+            .and_then(|v| v.parse::<i32>().ok())
+            .unwrap_or(0);
+        RetryMetrics::get().record_scheduled(topic, attempt, delay_ms);
+        Ok(())
+    }
+
+    async fn push_message(&self, message: &MessageData, routers: Vec<RouteInfo>) -> Result<PushOutcome> {
         if routers.is_empty() {
             return Err(anyhow!("No available routes for message {}", message.server_msg_id));
         }
 
         info!("Starting to push message {} to {} routes", message.server_msg_id, routers.len());
-
-        let semaphore = Arc::new(Semaphore::new(10));
-        let mut tasks = Vec::new();
-
-        for route in routers {
-            let message = message.clone();
-            let sem = semaphore.clone();
-            let task = tokio::spawn(async move {
-                let _permit = sem.acquire().await?;
-                info!("Pushing message {} to route {}", message.server_msg_id, route.address);
-                Ok::<(), anyhow::Error>(())
-            });
-            tasks.push(task);
-        }
-
-        for task in tasks {
-            task.await??;
-        }
-
-        info!("Message {} pushed to all routes successfully", message.server_msg_id);
-        Ok(())
+        let outcome = self.gateway_pusher.push(message, routers).await;
+        info!(
+            "Message {} pushed: {}/{} recipients delivered",
+            message.server_msg_id,
+            outcome.delivered_count(),
+            outcome.results.len()
+        );
+        Ok(outcome)
     }
 
     async fn send_offline_notification(&self, userid: &str, message: &MessageData) -> Result<()> {
         info!("Sending offline notification for user {}", userid);
         self.retry_with_backoff(|| async {
-            self.send_to_kafka(KafkaTopics::OFFLINE_NOTIFICATIONS, message).await
+            self.send_conversation(KafkaTopics::OFFLINE_NOTIFICATIONS, message).await
+        }).await
+    }
+
+    async fn enqueue_offline_push(&self, message: &MessageData) -> Result<()> {
+        self.retry_with_backoff(|| async {
+            self.send_conversation(KafkaTopics::OFFLINE_GROUP_PUSH, message).await
         }).await
     }
 
@@ -186,33 +191,39 @@ impl MessageRepository for MessageRepositoryImpl {
             ..Default::default()
         };
 
+        // 状态主题按消息压缩，以 server_msg_id 为键
         self.retry_with_backoff(|| async {
-            self.send_to_kafka(KafkaTopics::MESSAGE_STATUS, &message).await
+            self.send_to_bus(KafkaTopics::MESSAGE_STATUS, message_id, &message).await
         }).await
     }
 
+    async fn delete_message(&self, message: &MessageData, status: MessageStatus) -> Result<()> {
+        self.store.delete(message, &status.to_string()).await?;
+        self.update_message_status(&message.server_msg_id, status).await
+    }
+
     async fn get_recent_message_count(&self, user_id: &str, seconds: i32) -> Result<i32> {
-        let current_time = self.current_timestamp();
-        let start_time = current_time - seconds as i64;
-        
-        info!("Getting message count for user {} from {} to {}", user_id, start_time, current_time);
-        Ok(5)
+        self.counter.recent_count(user_id, seconds).await
     }
 
     async fn get_group_daily_message_count(&self, group_id: &str) -> Result<i32> {
-        let current_time = self.current_timestamp();
-        let day_start = current_time - (current_time % 86400);
-        
-        info!("Getting daily message count for group {} on {}", group_id, day_start);
-        Ok(100)
+        self.counter.group_daily_count(group_id).await
+    }
+
+    async fn get_group_at_all_daily_count(&self, group_id: &str) -> Result<i32> {
+        self.counter.at_all_daily_count(group_id).await
     }
 
     async fn get_private_daily_message_count(&self, sender_id: &str, receiver_id: &str) -> Result<i32> {
-        let current_time = self.current_timestamp();
-        let day_start = current_time - (current_time % 86400);
-        
-        info!("Getting daily message count between {} and {} on {}", sender_id, receiver_id, day_start);
-        Ok(50)
+        self.counter.private_daily_count(sender_id, receiver_id).await
+    }
+
+    async fn reserve_send_quota(&self, message: &MessageData, quota: SendQuota) -> Result<QuotaVerdict> {
+        self.counter.reserve(message, quota).await
+    }
+
+    async fn release_send_quota(&self, message: &MessageData) -> Result<()> {
+        self.counter.release(message).await
     }
 
     async fn get_user_status(&self, user_id: &str) -> Result<UserStatus> {
@@ -261,20 +272,28 @@ impl MessageRepository for MessageRepositoryImpl {
             metadata: message.options.clone(),
         };
 
+        let max_retry_count = message.options.get("max_retries")
+            .and_then(|v| v.parse::<i32>().ok())
+            .unwrap_or(3);
+        let reason = if retry_count >= max_retry_count {
+            REASON_RETRY_EXHAUSTED
+        } else {
+            REASON_PROCESSING_FAILED
+        };
+
         // 构建死信消息
         let dead_letter = proto_crate::api::im::common::DeadLetterMessage {
             original_message: Some(original_payload),
             error_reason: error.clone(),
             retry_count,
-            max_retry_count: message.options.get("max_retries")
-                .and_then(|v| v.parse::<i32>().ok())
-                .unwrap_or(3),
+            max_retry_count,
             last_retry_time: message.options.get("last_retry_time")
                 .and_then(|v| v.parse::<i64>().ok())
                 .unwrap_or_else(|| self.current_timestamp()),
             dead_time: self.current_timestamp(),
             error_metadata: {
                 let mut metadata = HashMap::new();
+                metadata.insert(DEAD_LETTER_REASON_KEY.to_string(), reason.to_string());
                 metadata.insert("error_time".to_string(), self.current_timestamp().to_string());
                 metadata.insert("original_status".to_string(), message.status.to_string());
                 if let Some(device_id) = message.options.get("device_id") {
@@ -289,33 +308,34 @@ impl MessageRepository for MessageRepositoryImpl {
         dead_letter.encode(&mut dead_letter_bytes)?;
 
         // 发送到死信队列
-        let record = FutureRecord::to(KafkaTopics::DEAD_LETTER)
-            .key(&message.server_msg_id)
-            .payload(&dead_letter_bytes)
-            .timestamp(self.current_timestamp());
+        let record = OutgoingMessage::new(dead_letter_bytes)
+            .key(tenant::current().kafka_key(&message.server_msg_id))
+            .timestamp(self.current_timestamp())
+            .with_current_context();
 
         debug!(
             "Saving message {} to dead letter queue, error: {}, retry count: {}", 
             message.server_msg_id, error, retry_count
         );
 
-        match self.producer.send(record, Timeout::After(Duration::from_millis(KAFKA_TIMEOUT_MS))).await {
-            Ok((partition, offset)) => {
+        match self.producer.send(KafkaTopics::DEAD_LETTER, record).await {
+            Ok(receipt) => {
+                RetryMetrics::get().record_dead_lettered();
                 info!(
                     "Message saved to dead letter queue: msg_id={}, partition={}, offset={}", 
-                    message.server_msg_id, partition, offset
+                    message.server_msg_id, receipt.partition, receipt.offset
                 );
                 Ok(())
             }
-            Err((KafkaError::MessageProduction(RDKafkaErrorCode::QueueFull), _)) => {
+            Err(BusError::QueueFull) => {
                 warn!("Dead letter queue full, retrying...");
                 tokio::time::sleep(Duration::from_millis(100)).await;
                 Err(anyhow!("Dead letter queue full"))
             }
-            Err((err, _)) => {
+            Err(err) => {
                 error!("Failed to save message to dead letter queue: {}", err);
                 Err(anyhow!("Dead letter queue error: {}", err))
             }
         }
     }
-} 
\ No newline at end of file
+}
\ No newline at end of file
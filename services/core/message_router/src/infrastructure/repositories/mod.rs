@@ -1,11 +1,31 @@
+mod dead_letter_repository;
+mod dedup_repository;
 mod friend_repository;
+mod gateway_pusher;
 mod group_repository;
+mod group_timeline_repository;
+mod mention_repository;
+mod message_counter;
 mod message_repository;
+mod online_member_index;
 mod route_repository;
+mod schedule_repository;
+mod store_client;
 mod content_filter_repository;
 
+pub use dead_letter_repository::DeadLetterRepositoryImpl;
+pub use dedup_repository::MessageDedupRepositoryImpl;
 pub use friend_repository::FriendRepositoryImpl;
+pub use gateway_pusher::GatewayPusher;
 pub use group_repository::GroupRepositoryImpl;
+pub use group_timeline_repository::GroupTimelineRepositoryImpl;
+pub use mention_repository::MentionRepositoryImpl;
+pub use message_counter::MessageCounter;
 pub use message_repository::MessageRepositoryImpl;
+#[cfg(test)]
+pub(crate) use message_repository::payload_record;
+pub use online_member_index::OnlineMemberIndex;
 pub use route_repository::RouteRepositoryImpl;
-pub use content_filter_repository::ContentFilterRepositoryImpl;
\ No newline at end of file
+pub use schedule_repository::MessageScheduleRepositoryImpl;
+pub use store_client::StoreClient;
+pub use content_filter_repository::{ContentFilterRepositoryImpl, FilterFailurePolicy};
\ No newline at end of file
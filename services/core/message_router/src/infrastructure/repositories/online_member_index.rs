@@ -0,0 +1,186 @@
+use anyhow::Result;
+use common::tenant;
+use redis::aio::ConnectionManager;
+use redis::Script;
+
+use crate::domain::repositories::GroupMemberPage;
+
+/// 在线成员索引在最后一次上线后的保留时间，避免解散或沉寂的群残留索引
+const INDEX_TTL_SECS: i64 = 7 * 86_400;
+
+/// 基于 Redis 的群在线成员索引
+///
+/// - `group:online:{group_id}`: ZSET，member 为用户ID，score 为上线时间(毫秒)
+///
+/// 由上下线与群成员变更事件维护；网关异常退出时不会产生下线事件，推送时发现无路由的成员会被移除。
+/// 分页游标为上一页最后一个成员的 `{score}:{member}`，翻页期间移除成员不会跳过后续成员；
+/// 翻页期间重新上线的成员分数变大，可能在后续页中再出现一次。
+#[derive(Clone)]
+pub struct OnlineMemberIndex {
+    redis: ConnectionManager,
+}
+
+/// 按 (score, member) 游标取下一页，与 ZSET 的排序一致：分数升序，同分按成员字节序
+///
+/// KEYS[1]: 索引键; ARGV: 游标分数, 游标成员, 页大小
+/// 返回 `[总数, member1, score1, member2, score2, ...]`
+const PAGE_SCRIPT: &str = r#"
+local score = tonumber(ARGV[1]) or -math.huge
+local member = ARGV[2]
+local limit = tonumber(ARGV[3])
+local out = { redis.call('ZCARD', KEYS[1]) }
+local offset = 0
+while #out < limit * 2 + 1 do
+    local batch = redis.call('ZRANGEBYSCORE', KEYS[1], ARGV[1], '+inf', 'WITHSCORES', 'LIMIT', offset, limit)
+    if #batch == 0 then
+        break
+    end
+    for i = 1, #batch, 2 do
+        if #out < limit * 2 + 1 and (tonumber(batch[i + 1]) > score or batch[i] > member) then
+            table.insert(out, batch[i])
+            table.insert(out, batch[i + 1])
+        end
+    end
+    offset = offset + #batch / 2
+end
+return out
+"#;
+
+/// 解析分页游标，缺失或无效时从头开始
+fn parse_cursor(cursor: Option<&str>) -> (String, String) {
+    cursor
+        .and_then(|c| c.split_once(':'))
+        .filter(|(score, _)| score.parse::<f64>().is_ok())
+        .map(|(score, member)| (score.to_string(), member.to_string()))
+        .unwrap_or_else(|| ("-inf".to_string(), String::new()))
+}
+
+impl OnlineMemberIndex {
+    pub fn new(redis: ConnectionManager) -> Self {
+        Self { redis }
+    }
+
+    fn key(group_id: &str) -> String {
+        tenant::current().redis_key(&format!("group:online:{}", group_id))
+    }
+
+    /// 更新用户在多个群的在线状态
+    pub async fn set_online(&self, user_id: &str, group_ids: &[String], online: bool) -> Result<()> {
+        if group_ids.is_empty() {
+            return Ok(());
+        }
+        let now = chrono::Utc::now().timestamp_millis();
+        let mut pipe = redis::pipe();
+        for group_id in group_ids {
+            let key = Self::key(group_id);
+            if online {
+                pipe.zadd(&key, user_id, now).ignore().expire(&key, INDEX_TTL_SECS).ignore();
+            } else {
+                pipe.zrem(&key, user_id).ignore();
+            }
+        }
+        let mut conn = self.redis.clone();
+        pipe.query_async::<()>(&mut conn).await?;
+        Ok(())
+    }
+
+    /// 移除群内的在线成员
+    pub async fn remove(&self, group_id: &str, user_ids: &[String]) -> Result<()> {
+        if user_ids.is_empty() {
+            return Ok(());
+        }
+        let mut conn = self.redis.clone();
+        redis::cmd("ZREM")
+            .arg(Self::key(group_id))
+            .arg(user_ids)
+            .query_async::<()>(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    /// 按上线时间分页获取在线成员
+    pub async fn page(&self, group_id: &str, page_size: i32, cursor: Option<String>) -> Result<GroupMemberPage> {
+        let (score, member) = parse_cursor(cursor.as_deref());
+        let page_size = page_size.max(1);
+
+        let mut conn = self.redis.clone();
+        let reply: Vec<redis::Value> = Script::new(PAGE_SCRIPT)
+            .key(Self::key(group_id))
+            .arg(score)
+            .arg(member)
+            .arg(page_size)
+            .invoke_async(&mut conn)
+            .await?;
+
+        let mut reply = reply.into_iter();
+        let total: i64 = match reply.next() {
+            Some(value) => redis::from_redis_value(&value)?,
+            None => 0,
+        };
+        let entries: Vec<String> = reply
+            .map(|value| redis::from_redis_value(&value))
+            .collect::<redis::RedisResult<_>>()?;
+        let next = entries.len().checked_sub(2).map(|i| format!("{}:{}", entries[i + 1], entries[i]));
+        let members: Vec<String> = entries.into_iter().step_by(2).collect();
+
+        let has_more = members.len() as i32 == page_size;
+        Ok(GroupMemberPage {
+            members,
+            total,
+            cursor: next.filter(|_| has_more),
+            has_more,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cursor() {
+        assert_eq!(parse_cursor(None), ("-inf".to_string(), String::new()));
+        assert_eq!(parse_cursor(Some("garbage")), ("-inf".to_string(), String::new()));
+        assert_eq!(
+            parse_cursor(Some("1700000000000:user:1")),
+            ("1700000000000".to_string(), "user:1".to_string())
+        );
+    }
+
+    async fn index() -> OnlineMemberIndex {
+        let client = redis::Client::open("redis://127.0.0.1:6379").unwrap();
+        OnlineMemberIndex::new(ConnectionManager::new(client).await.unwrap())
+    }
+
+    #[tokio::test]
+    #[ignore = "requires redis"]
+    async fn test_removing_members_while_paging_skips_none() {
+        let index = index().await;
+        let group_id = format!("paging-{}", uuid::Uuid::new_v4());
+        let members: Vec<String> = (0..10).map(|i| format!("user{}", i)).collect();
+        // 同一时间上线，分数相同，按成员排序
+        let mut conn = index.redis.clone();
+        for member in &members {
+            redis::cmd("ZADD")
+                .arg(OnlineMemberIndex::key(&group_id))
+                .arg(1000)
+                .arg(member)
+                .query_async::<()>(&mut conn)
+                .await
+                .unwrap();
+        }
+
+        let mut seen = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = index.page(&group_id, 3, cursor).await.unwrap();
+            index.remove(&group_id, &page.members).await.unwrap();
+            seen.extend(page.members);
+            if !page.has_more {
+                break;
+            }
+            cursor = page.cursor;
+        }
+        assert_eq!(seen, members);
+    }
+}
@@ -1,46 +1,52 @@
 use async_trait::async_trait;
 use anyhow::Result;
 use std::collections::HashMap;
+use common::route::{ResolvedRoute, RouteStore};
 use crate::domain::repositories::{RouteRepository, RouteInfo};
 
-pub struct RouteRepositoryImpl;
+/// 网关未上报负载时的默认权重
+const DEFAULT_WEIGHT: i32 = 100;
+
+/// 基于 Redis 的路由仓储，路由由网关在连接建立、心跳和关闭时维护
+pub struct RouteRepositoryImpl {
+    store: RouteStore,
+}
 
 impl RouteRepositoryImpl {
-    pub fn new() -> Self {
-        Self {}
+    pub fn new(store: RouteStore) -> Self {
+        Self { store }
     }
 
-    // 模拟获取路由信息的内部方法
-    fn mock_route_info(&self, user_id: &str) -> Vec<RouteInfo> {
-        // 模拟返回两个网关的路由信息
-        vec![
-            RouteInfo {
-                address: format!("gateway1.example.com:{}:{}", user_id, 8001),
-                weight: 100,
-                load: 50,
-            },
-            RouteInfo {
-                address: format!("gateway2.example.com:{}:{}", user_id, 8002),
-                weight: 80,
-                load: 30,
-            },
-        ]
+    fn to_route_info(resolved: ResolvedRoute) -> RouteInfo {
+        let (weight, load) = match &resolved.gateway {
+            Some(gateway) => (gateway.weight as i32, gateway.load_percent() as i32),
+            None => (DEFAULT_WEIGHT, 0),
+        };
+        let route = resolved.route;
+        RouteInfo {
+            user_id: route.user_id,
+            device_id: route.device_id,
+            platform: route.platform,
+            gateway_id: route.gateway_id,
+            address: route.gateway_addr,
+            weight,
+            load,
+        }
     }
 }
 
 #[async_trait]
 impl RouteRepository for RouteRepositoryImpl {
     async fn get_routes_with_weight(&self, user_id: &str) -> Result<Vec<RouteInfo>> {
-        // 模拟从缓存或数据库获取路由信息
-        Ok(self.mock_route_info(user_id))
+        let routes = self.store.get_routes(user_id).await?;
+        Ok(routes.into_iter().map(Self::to_route_info).collect())
     }
 
     async fn get_routes_with_weight_batch(&self, user_ids: &[String]) -> Result<HashMap<String, Vec<RouteInfo>>> {
-        // 为每个用户ID生成路由信息
-        let mut routes_map = HashMap::new();
-        for user_id in user_ids {
-            routes_map.insert(user_id.clone(), self.mock_route_info(user_id));
-        }
-        Ok(routes_map)
+        let routes = self.store.get_routes_batch(user_ids).await?;
+        Ok(routes
+            .into_iter()
+            .map(|(user_id, routes)| (user_id, routes.into_iter().map(Self::to_route_info).collect()))
+            .collect())
     }
-} 
\ No newline at end of file
+}
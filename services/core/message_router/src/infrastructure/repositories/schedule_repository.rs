@@ -0,0 +1,283 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::Utc;
+use common::tenant::{self, TenantContext};
+use log::warn;
+use prost::Message;
+use proto_crate::api::im::common::MessageData;
+use redis::aio::ConnectionManager;
+
+use crate::domain::entities::{ScheduledTask, ScheduledTaskKind};
+use crate::domain::repositories::MessageScheduleRepository;
+
+/// 跨租户共享的到期索引
+const DUE_KEY: &str = "schedule:due";
+/// 任务触发后保留的时间，覆盖调度器短暂停机
+const TASK_RETENTION_MS: i64 = 86_400_000;
+/// 未设置触发时间的任务保留时间，如始终未读的阅后即焚消息
+const HELD_TASK_TTL_MS: i64 = 30 * 86_400_000;
+
+/// 任务存在时设置触发时间，已设置的任务保持不变
+const ARM_SCRIPT: &str = r#"
+if redis.call('EXISTS', KEYS[1]) == 0 then
+    return 0
+end
+local added = redis.call('ZADD', KEYS[2], 'NX', ARGV[1], ARGV[2])
+if added == 1 then
+    redis.call('PEXPIRE', KEYS[1], ARGV[3])
+end
+return added
+"#;
+
+/// 取出到期任务并顺延到租约结束，执行失败的任务租约到期后重新触发
+const CLAIM_SCRIPT: &str = r#"
+local members = redis.call('ZRANGEBYSCORE', KEYS[1], '-inf', ARGV[1], 'LIMIT', 0, ARGV[3])
+for _, member in ipairs(members) do
+    redis.call('ZADD', KEYS[1], 'XX', ARGV[2], member)
+end
+return members
+"#;
+
+/// 基于 Redis 的定时任务
+///
+/// - `schedule:{kind}:{message_id}`: 任务关联的消息(protobuf)，按租户加前缀
+/// - `schedule:due`: ZSET，member 为 `{tenant}:{kind}:{message_id}`，score 为触发时间(毫秒)
+pub struct MessageScheduleRepositoryImpl {
+    redis: ConnectionManager,
+}
+
+impl MessageScheduleRepositoryImpl {
+    pub fn new(redis: ConnectionManager) -> Self {
+        Self { redis }
+    }
+
+    fn task_key(tenant: &TenantContext, kind: ScheduledTaskKind, message_id: &str) -> String {
+        tenant.redis_key(&format!("schedule:{}:{}", kind.as_str(), message_id))
+    }
+
+    fn member(tenant: &TenantContext, kind: ScheduledTaskKind, message_id: &str) -> String {
+        format!("{}:{}:{}", tenant.tenant_id(), kind.as_str(), message_id)
+    }
+
+    fn parse_member(member: &str) -> Option<(TenantContext, ScheduledTaskKind, String)> {
+        let mut parts = member.splitn(3, ':');
+        let tenant = TenantContext::new(parts.next()?).ok()?;
+        let kind = ScheduledTaskKind::parse(parts.next()?)?;
+        let message_id = parts.next().filter(|id| !id.is_empty())?;
+        Some((tenant, kind, message_id.to_string()))
+    }
+
+    async fn remove(&self, tenant: &TenantContext, kind: ScheduledTaskKind, message_id: &str) -> Result<bool> {
+        let mut conn = self.redis.clone();
+        let (deleted,): (i32,) = redis::pipe()
+            .atomic()
+            .del(Self::task_key(tenant, kind, message_id))
+            .zrem(DUE_KEY, Self::member(tenant, kind, message_id))
+            .ignore()
+            .query_async(&mut conn)
+            .await?;
+        Ok(deleted > 0)
+    }
+}
+
+#[async_trait]
+impl MessageScheduleRepository for MessageScheduleRepositoryImpl {
+    async fn schedule(&self, kind: ScheduledTaskKind, message: &MessageData, fire_at: Option<i64>) -> Result<()> {
+        let tenant = tenant::current();
+        let ttl_ms = match fire_at {
+            Some(fire_at) => (fire_at - Utc::now().timestamp_millis()).max(0) + TASK_RETENTION_MS,
+            None => HELD_TASK_TTL_MS,
+        };
+
+        let mut pipe = redis::pipe();
+        pipe.atomic()
+            .pset_ex(
+                Self::task_key(&tenant, kind, &message.server_msg_id),
+                message.encode_to_vec(),
+                ttl_ms as u64,
+            )
+            .ignore();
+        if let Some(fire_at) = fire_at {
+            pipe.zadd(DUE_KEY, Self::member(&tenant, kind, &message.server_msg_id), fire_at)
+                .ignore();
+        }
+
+        let mut conn = self.redis.clone();
+        pipe.query_async::<()>(&mut conn).await?;
+        Ok(())
+    }
+
+    async fn arm(&self, kind: ScheduledTaskKind, message_id: &str, fire_at: i64) -> Result<bool> {
+        let tenant = tenant::current();
+        let ttl_ms = (fire_at - Utc::now().timestamp_millis()).max(0) + TASK_RETENTION_MS;
+        let mut conn = self.redis.clone();
+        let added: i32 = redis::Script::new(ARM_SCRIPT)
+            .key(Self::task_key(&tenant, kind, message_id))
+            .key(DUE_KEY)
+            .arg(fire_at)
+            .arg(Self::member(&tenant, kind, message_id))
+            .arg(ttl_ms)
+            .invoke_async(&mut conn)
+            .await?;
+        Ok(added == 1)
+    }
+
+    async fn get(&self, kind: ScheduledTaskKind, message_id: &str) -> Result<Option<MessageData>> {
+        let mut conn = self.redis.clone();
+        let bytes: Option<Vec<u8>> = redis::cmd("GET")
+            .arg(Self::task_key(&tenant::current(), kind, message_id))
+            .query_async(&mut conn)
+            .await?;
+        Ok(match bytes {
+            Some(bytes) => Some(MessageData::decode(bytes.as_slice())?),
+            None => None,
+        })
+    }
+
+    async fn cancel(&self, kind: ScheduledTaskKind, message_id: &str) -> Result<bool> {
+        self.remove(&tenant::current(), kind, message_id).await
+    }
+
+    async fn claim_due(&self, now: i64, lease_ms: i64, limit: usize) -> Result<Vec<ScheduledTask>> {
+        let mut conn = self.redis.clone();
+        let members: Vec<String> = redis::Script::new(CLAIM_SCRIPT)
+            .key(DUE_KEY)
+            .arg(now)
+            .arg(now + lease_ms)
+            .arg(limit)
+            .invoke_async(&mut conn)
+            .await?;
+
+        let mut tasks = Vec::with_capacity(members.len());
+        for member in members {
+            let Some((tenant, kind, message_id)) = Self::parse_member(&member) else {
+                warn!("Dropping malformed scheduled task {}", member);
+                let _: i32 = redis::cmd("ZREM").arg(DUE_KEY).arg(&member).query_async(&mut conn).await?;
+                continue;
+            };
+
+            // 任务已取消或过期时只留下到期索引，一并清理
+            let bytes: Option<Vec<u8>> = redis::cmd("GET")
+                .arg(Self::task_key(&tenant, kind, &message_id))
+                .query_async(&mut conn)
+                .await?;
+            let message = match bytes.map(|bytes| MessageData::decode(bytes.as_slice())) {
+                Some(Ok(message)) => message,
+                Some(Err(e)) => {
+                    warn!("Dropping undecodable scheduled task {}: {}", member, e);
+                    self.remove(&tenant, kind, &message_id).await?;
+                    continue;
+                }
+                None => {
+                    let _: i32 = redis::cmd("ZREM").arg(DUE_KEY).arg(&member).query_async(&mut conn).await?;
+                    continue;
+                }
+            };
+            tasks.push(ScheduledTask { tenant, kind, message });
+        }
+        Ok(tasks)
+    }
+
+    async fn complete(&self, task: &ScheduledTask) -> Result<()> {
+        self.remove(&task.tenant, task.kind, &task.message.server_msg_id).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_member_round_trip() {
+        let tenant = TenantContext::new("acme").unwrap();
+        let member = MessageScheduleRepositoryImpl::member(&tenant, ScheduledTaskKind::Expire, "1001:2");
+        let (parsed, kind, message_id) = MessageScheduleRepositoryImpl::parse_member(&member).unwrap();
+        assert_eq!(parsed.tenant_id(), "acme");
+        assert_eq!(kind, ScheduledTaskKind::Expire);
+        assert_eq!(message_id, "1001:2");
+
+        assert!(MessageScheduleRepositoryImpl::parse_member("acme:unknown:1").is_none());
+        assert!(MessageScheduleRepositoryImpl::parse_member("acme:expire:").is_none());
+    }
+
+    /// 以下用例需要本地 Redis，会清空到期索引：`cargo test -- --ignored`
+    async fn repository() -> MessageScheduleRepositoryImpl {
+        let url = std::env::var("REDIS_URL").unwrap_or_else(|_| "redis://127.0.0.1:6379/".to_string());
+        let redis = ConnectionManager::new(redis::Client::open(url).unwrap()).await.unwrap();
+        let mut conn = redis.clone();
+        let _: i32 = redis::cmd("DEL").arg(DUE_KEY).query_async(&mut conn).await.unwrap();
+        MessageScheduleRepositoryImpl::new(redis)
+    }
+
+    fn message(id: &str) -> MessageData {
+        MessageData {
+            server_msg_id: id.to_string(),
+            send_id: "user1".to_string(),
+            recv_id: "user2".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "requires redis"]
+    async fn test_claim_leases_due_tasks() {
+        let repository = repository().await;
+        let now = Utc::now().timestamp_millis();
+        tenant::scope(TenantContext::new("acme").unwrap(), async {
+            repository.schedule(ScheduledTaskKind::Deliver, &message("due"), Some(now - 1)).await.unwrap();
+            repository.schedule(ScheduledTaskKind::Deliver, &message("later"), Some(now + 60_000)).await.unwrap();
+        })
+        .await;
+
+        let tasks = repository.claim_due(now, 1_000, 10).await.unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].message.server_msg_id, "due");
+        assert_eq!(tasks[0].tenant.tenant_id(), "acme");
+
+        // 租约内不会被再次取出，租约到期后重新触发
+        assert!(repository.claim_due(now, 1_000, 10).await.unwrap().is_empty());
+        assert_eq!(repository.claim_due(now + 1_000, 1_000, 10).await.unwrap().len(), 1);
+
+        repository.complete(&tasks[0]).await.unwrap();
+        assert!(repository.claim_due(now + 10_000, 1_000, 10).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    #[ignore = "requires redis"]
+    async fn test_arm_sets_fire_time_once() {
+        let repository = repository().await;
+        let now = Utc::now().timestamp_millis();
+        tenant::scope(TenantContext::new("acme").unwrap(), async {
+            // 未保存的任务不会设置触发时间
+            assert!(!repository.arm(ScheduledTaskKind::Expire, "missing", now).await.unwrap());
+
+            repository.schedule(ScheduledTaskKind::Expire, &message("held"), None).await.unwrap();
+            assert!(repository.claim_due(now, 1_000, 10).await.unwrap().is_empty());
+
+            assert!(repository.arm(ScheduledTaskKind::Expire, "held", now).await.unwrap());
+            // 再次阅读保持首次的触发时间
+            assert!(!repository.arm(ScheduledTaskKind::Expire, "held", now + 60_000).await.unwrap());
+        })
+        .await;
+
+        let tasks = repository.claim_due(now, 1_000, 10).await.unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].kind, ScheduledTaskKind::Expire);
+    }
+
+    #[tokio::test]
+    #[ignore = "requires redis"]
+    async fn test_cancelled_task_is_not_claimed() {
+        let repository = repository().await;
+        let now = Utc::now().timestamp_millis();
+        tenant::scope(TenantContext::new("acme").unwrap(), async {
+            repository.schedule(ScheduledTaskKind::Deliver, &message("cancelled"), Some(now - 1)).await.unwrap();
+            assert!(repository.cancel(ScheduledTaskKind::Deliver, "cancelled").await.unwrap());
+            assert!(!repository.cancel(ScheduledTaskKind::Deliver, "cancelled").await.unwrap());
+        })
+        .await;
+
+        assert!(repository.claim_due(now, 1_000, 10).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,68 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use common::rpc::{context_interceptor, ServiceChannel};
+use common::utils::msg_utils::conversation_id;
+use proto_crate::api::im::common::MessageData;
+use proto_crate::api::im::service::store::message_store_client::MessageStoreClient;
+use proto_crate::api::im::service::store::DeleteMessageRequest;
+
+/// 存储调用超时
+const STORE_DEADLINE: Duration = Duration::from_secs(2);
+/// 删除原因，写入 `DeleteMessageRequest.options`
+const DELETE_REASON_OPTION: &str = "reason";
+
+/// 消息存储服务调用
+pub struct StoreClient {
+    store: Arc<ServiceChannel>,
+    deadline: Duration,
+}
+
+impl StoreClient {
+    pub fn new(store: Arc<ServiceChannel>) -> Self {
+        Self {
+            store,
+            deadline: STORE_DEADLINE,
+        }
+    }
+
+    pub fn with_deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = deadline;
+        self
+    }
+
+    /// 从存储中删除消息
+    pub async fn delete(&self, message: &MessageData, reason: &str) -> Result<()> {
+        let request = DeleteMessageRequest {
+            message_id: message.server_msg_id.clone(),
+            session_id: conversation_id(message),
+            options: HashMap::from([(DELETE_REASON_OPTION.to_string(), reason.to_string())]),
+        };
+        let deadline = self.deadline;
+        let response = self
+            .store
+            .call(|channel| {
+                let request = request.clone();
+                async move {
+                    let mut request = tonic::Request::new(request);
+                    request.set_timeout(deadline);
+                    MessageStoreClient::with_interceptor(channel, context_interceptor)
+                        .delete_message(request)
+                        .await
+                }
+            })
+            .await
+            .map_err(|status| anyhow!("delete message {} failed: {}", message.server_msg_id, status))?
+            .into_inner();
+
+        if let Some(error) = response.error.filter(|e| e.code != 0) {
+            return Err(anyhow!("delete message {} failed: {}", message.server_msg_id, error.message));
+        }
+        if !response.success {
+            return Err(anyhow!("delete message {} rejected by store", message.server_msg_id));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,129 @@
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use common::bus::{BusMessage, MessageBus, MessageConsumer, MessageProducer, OutgoingMessage};
+use common::tenant;
+use common::topic::KafkaTopics;
+use log::{debug, error, warn};
+use prost::Message;
+use proto_crate::api::im::common::DeadLetterMessage;
+use tracing::instrument;
+
+use crate::domain::entities::{undecodable_dead_letter, DeadLetterRecord};
+use crate::domain::repositories::DeadLetterRepository;
+use crate::infrastructure::metrics::RetryMetrics;
+
+/// 无法处理的消息写入死信主题，保留原始字节与消息头
+///
+/// 用于无法解码或租户头非法的消息，这类消息不含可重放的原始消息。
+pub(super) async fn dead_letter_undecodable(
+    message: &BusMessage,
+    error: String,
+    producer: &dyn MessageProducer,
+) -> Result<()> {
+    let source = format!("{}/{}/{}", message.topic, message.partition, message.offset);
+    error!("Dead-lettering undecodable message {}: {}", source, error);
+
+    let now = chrono::Utc::now();
+    let dead_letter = undecodable_dead_letter(&source, &message.payload, error, now.timestamp());
+    // 死信主题以消息ID为键，无法解码时以来源位置代替；租户非法时不加租户前缀
+    let key = match message.tenant() {
+        Ok(tenant) => tenant.kafka_key(&source),
+        Err(_) => source.clone(),
+    };
+    let record = OutgoingMessage {
+        key: Some(key),
+        payload: dead_letter.encode_to_vec(),
+        headers: message.headers.clone(),
+        timestamp: Some(now.timestamp_millis()),
+    };
+    producer
+        .send(KafkaTopics::DEAD_LETTER, record)
+        .await
+        .map_err(|e| anyhow!("send {} to dead letter failed: {}", source, e))?;
+    RetryMetrics::get().record_dead_lettered();
+    Ok(())
+}
+
+/// 死信索引消费者
+///
+/// 读取死信主题，按消息所属租户写入死信索引，写入成功后提交位点。
+pub struct DeadLetterConsumer {
+    consumer: Arc<dyn MessageConsumer>,
+    repository: Arc<dyn DeadLetterRepository>,
+}
+
+impl DeadLetterConsumer {
+    const GROUP_ID: &'static str = "dead_letter_index_group";
+    /// 索引写入失败后的重试间隔
+    const RETRY_BACKOFF: std::time::Duration = std::time::Duration::from_secs(1);
+
+    pub fn new(repository: Arc<dyn DeadLetterRepository>, bus: &dyn MessageBus) -> Result<Self> {
+        let consumer = bus.subscribe(Self::GROUP_ID, &[KafkaTopics::DEAD_LETTER])?;
+        Ok(Self { consumer, repository })
+    }
+
+    #[instrument(skip(self))]
+    pub async fn start(&self) -> Result<()> {
+        debug!("Starting dead letter consumer");
+
+        loop {
+            let message = match self.consumer.recv().await {
+                Ok(message) => message,
+                Err(e) => {
+                    error!("Error receiving dead letter: {}", e);
+                    continue;
+                }
+            };
+
+            // 租户头非法的死信无法归属到任何租户的索引，保留在死信主题中
+            let tenant = match message.tenant() {
+                Ok(tenant) => tenant,
+                Err(e) => {
+                    warn!("Skipping dead letter at offset {} from unknown tenant: {}", message.offset, e);
+                    if let Err(e) = self.consumer.commit_message(&message).await {
+                        warn!("Failed to commit dead letter offset: {}", e);
+                    }
+                    continue;
+                }
+            };
+
+            // 写入失败时原地重试，不提交位点
+            while let Err(e) = tenant::scope(tenant.clone(), self.index(&message)).await {
+                error!("Failed to index dead letter at offset {}: {}", message.offset, e);
+                tokio::time::sleep(Self::RETRY_BACKOFF).await;
+            }
+
+            if let Err(e) = self.consumer.commit_message(&message).await {
+                warn!("Failed to commit dead letter offset: {}", e);
+            }
+        }
+    }
+
+    async fn index(&self, message: &BusMessage) -> Result<()> {
+        // 死信由 MessageRepository::save_to_dead_letter 以 protobuf 写入
+        let dead_letter = match DeadLetterMessage::decode(message.payload.as_slice()) {
+            Ok(dead_letter) => dead_letter,
+            Err(e) => {
+                warn!("Skipping undecodable dead letter at offset {}: {}", message.offset, e);
+                return Ok(());
+            }
+        };
+        let Some(record) = DeadLetterRecord::from_dead_letter(dead_letter) else {
+            warn!("Skipping dead letter without original message at offset {}", message.offset);
+            return Ok(());
+        };
+
+        // 已重放过的消息再次进入死信时保留重放次数
+        let record = match self.repository.get(&record.message_id).await? {
+            Some(existing) => DeadLetterRecord {
+                replay_count: existing.replay_count,
+                last_replay_time: existing.last_replay_time,
+                ..record
+            },
+            None => record,
+        };
+        debug!("Indexing dead letter {} ({})", record.message_id, record.reason);
+        self.repository.save(&record).await
+    }
+}
@@ -0,0 +1,144 @@
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+type Job = Pin<Box<dyn Future<Output = ()> + Send + 'static>>;
+
+/// 按键串行执行
+///
+/// 同一键的任务按提交顺序逐个执行，不同键的任务并发执行。
+/// 键空闲时任务立即开始，否则排在该键队列末尾，由正在执行的任务依次取出。
+#[derive(Clone, Default)]
+pub struct KeyedExecutor {
+    queues: Arc<Mutex<HashMap<String, VecDeque<Job>>>>,
+}
+
+impl KeyedExecutor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 提交任务
+    pub fn submit<F>(&self, key: String, job: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        {
+            let mut queues = self.queues.lock().unwrap();
+            if let Some(queue) = queues.get_mut(&key) {
+                queue.push_back(Box::pin(job));
+                return;
+            }
+            queues.insert(key.clone(), VecDeque::new());
+        }
+
+        let queues = self.queues.clone();
+        tokio::spawn(async move {
+            let mut next: Job = Box::pin(job);
+            loop {
+                next.await;
+                next = {
+                    let mut queues = queues.lock().unwrap();
+                    match queues.get_mut(&key).and_then(VecDeque::pop_front) {
+                        Some(job) => job,
+                        None => {
+                            queues.remove(&key);
+                            return;
+                        }
+                    }
+                };
+            }
+        });
+    }
+
+    /// 有任务执行或排队的键数量
+    pub fn active_keys(&self) -> usize {
+        self.queues.lock().unwrap().len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::sync::{mpsc, oneshot};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_same_key_runs_in_submit_order() {
+        let executor = KeyedExecutor::new();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        for i in 0..20u64 {
+            let tx = tx.clone();
+            executor.submit("conversation".to_string(), async move {
+                // 前面的任务耗时更长，乱序执行时会被后面的任务超过
+                tokio::time::sleep(Duration::from_millis(20 - i)).await;
+                tx.send(i).unwrap();
+            });
+        }
+        drop(tx);
+
+        let mut order = Vec::new();
+        while let Some(i) = rx.recv().await {
+            order.push(i);
+        }
+        assert_eq!(order, (0..20).collect::<Vec<_>>());
+        assert_eq!(executor.active_keys(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_different_keys_run_concurrently() {
+        let executor = KeyedExecutor::new();
+        let (release_tx, release_rx) = oneshot::channel::<()>();
+        let (done_tx, done_rx) = oneshot::channel();
+
+        // 第一个键一直阻塞，第二个键仍能执行
+        executor.submit("blocked".to_string(), async move {
+            let _ = release_rx.await;
+        });
+        executor.submit("other".to_string(), async move {
+            done_tx.send(()).unwrap();
+        });
+
+        tokio::time::timeout(Duration::from_secs(1), done_rx)
+            .await
+            .expect("other key should not wait for the blocked key")
+            .unwrap();
+        assert_eq!(executor.active_keys(), 1);
+
+        release_tx.send(()).unwrap();
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while executor.active_keys() > 0 {
+                tokio::task::yield_now().await;
+            }
+        })
+        .await
+        .expect("idle key should be removed");
+    }
+
+    #[tokio::test]
+    async fn test_key_reused_after_idle() {
+        let executor = KeyedExecutor::new();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+
+        let first = tx.clone();
+        executor.submit("conversation".to_string(), async move {
+            first.send(1).unwrap();
+        });
+        assert_eq!(rx.recv().await, Some(1));
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while executor.active_keys() > 0 {
+                tokio::task::yield_now().await;
+            }
+        })
+        .await
+        .unwrap();
+
+        executor.submit("conversation".to_string(), async move {
+            tx.send(2).unwrap();
+        });
+        assert_eq!(rx.recv().await, Some(2));
+    }
+}
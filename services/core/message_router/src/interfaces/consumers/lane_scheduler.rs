@@ -0,0 +1,194 @@
+//! 优先级通道调度
+//!
+//! 每个分发通道由独立的拉取任务读入有界队列，调度器在有待处理消息的通道间做平滑加权轮询:
+//! 高优先级通道按权重获得更多份额，低优先级通道不会被饿死；空闲通道不占份额。
+
+use std::sync::Arc;
+
+use tokio::sync::mpsc::error::TryRecvError;
+use tokio::sync::{mpsc, Notify};
+
+/// 通道发送端，拉取任务通过它把消息交给调度器
+pub struct LaneSender<T> {
+    tx: mpsc::Sender<T>,
+    notify: Arc<Notify>,
+}
+
+impl<T> Clone for LaneSender<T> {
+    fn clone(&self) -> Self {
+        Self {
+            tx: self.tx.clone(),
+            notify: self.notify.clone(),
+        }
+    }
+}
+
+impl<T> LaneSender<T> {
+    /// 写入消息，队列满时等待，返回 false 表示调度器已停止
+    pub async fn send(&self, message: T) -> bool {
+        if self.tx.send(message).await.is_err() {
+            return false;
+        }
+        self.notify.notify_one();
+        true
+    }
+}
+
+impl<T> Drop for LaneSender<T> {
+    fn drop(&mut self) {
+        // 唤醒调度器检查通道是否已全部关闭
+        self.notify.notify_one();
+    }
+}
+
+struct Lane<T> {
+    topic: &'static str,
+    weight: i64,
+    current: i64,
+    rx: mpsc::Receiver<T>,
+    pending: Option<T>,
+    closed: bool,
+}
+
+/// 加权公平调度器
+pub struct LaneScheduler<T> {
+    lanes: Vec<Lane<T>>,
+    notify: Arc<Notify>,
+}
+
+impl<T> Default for LaneScheduler<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> LaneScheduler<T> {
+    pub fn new() -> Self {
+        Self {
+            lanes: Vec::new(),
+            notify: Arc::new(Notify::new()),
+        }
+    }
+
+    /// 注册通道，`capacity` 为该通道预取的消息上限
+    pub fn add_lane(&mut self, topic: &'static str, weight: u32, capacity: usize) -> LaneSender<T> {
+        let (tx, rx) = mpsc::channel(capacity.max(1));
+        self.lanes.push(Lane {
+            topic,
+            weight: weight.max(1) as i64,
+            current: 0,
+            rx,
+            pending: None,
+            closed: false,
+        });
+        LaneSender {
+            tx,
+            notify: self.notify.clone(),
+        }
+    }
+
+    /// 取下一条消息及其所属通道，所有通道关闭且无待处理消息时返回 None
+    pub async fn next(&mut self) -> Option<(&'static str, T)> {
+        loop {
+            let mut total = 0;
+            let mut open = false;
+            for lane in &mut self.lanes {
+                if lane.pending.is_none() && !lane.closed {
+                    match lane.rx.try_recv() {
+                        Ok(message) => lane.pending = Some(message),
+                        Err(TryRecvError::Empty) => {}
+                        Err(TryRecvError::Disconnected) => lane.closed = true,
+                    }
+                }
+                if lane.pending.is_some() {
+                    lane.current += lane.weight;
+                    total += lane.weight;
+                } else {
+                    // 空闲通道不累积份额
+                    lane.current = 0;
+                }
+                open |= !lane.closed || lane.pending.is_some();
+            }
+
+            let selected = self
+                .lanes
+                .iter_mut()
+                .filter(|lane| lane.pending.is_some())
+                .max_by_key(|lane| lane.current);
+            if let Some(lane) = selected {
+                lane.current -= total;
+                return lane.pending.take().map(|message| (lane.topic, message));
+            }
+
+            if !open {
+                return None;
+            }
+            self.notify.notified().await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_busy_lanes_share_by_weight() {
+        let mut scheduler = LaneScheduler::new();
+        let urgent = scheduler.add_lane("urgent", 3, 16);
+        let low = scheduler.add_lane("low", 1, 16);
+        for i in 0..8 {
+            assert!(urgent.send(i).await);
+            assert!(low.send(i).await);
+        }
+
+        let mut picked = Vec::new();
+        for _ in 0..8 {
+            picked.push(scheduler.next().await.unwrap().0);
+        }
+        assert_eq!(picked.iter().filter(|topic| **topic == "urgent").count(), 6);
+        // 低优先级通道在每轮中都能得到份额
+        assert!(picked[..4].contains(&"low"));
+        assert!(picked[4..].contains(&"low"));
+    }
+
+    #[tokio::test]
+    async fn test_idle_lane_takes_no_share() {
+        let mut scheduler = LaneScheduler::new();
+        let _urgent = scheduler.add_lane("urgent", 8, 4);
+        let low = scheduler.add_lane("low", 1, 4);
+        for i in 0..3 {
+            assert!(low.send(i).await);
+        }
+
+        for i in 0..3 {
+            assert_eq!(scheduler.next().await, Some(("low", i)));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lane_keeps_message_order() {
+        let mut scheduler = LaneScheduler::new();
+        let lane = scheduler.add_lane("normal", 1, 4);
+        tokio::spawn(async move {
+            for i in 0..10 {
+                assert!(lane.send(i).await);
+            }
+        });
+
+        for i in 0..10 {
+            assert_eq!(scheduler.next().await, Some(("normal", i)));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stops_after_lanes_closed_and_drained() {
+        let mut scheduler = LaneScheduler::new();
+        let lane = scheduler.add_lane("normal", 1, 4);
+        assert!(lane.send(1).await);
+        drop(lane);
+
+        assert_eq!(scheduler.next().await, Some(("normal", 1)));
+        assert_eq!(scheduler.next().await, None);
+    }
+}
@@ -1,79 +1,206 @@
 use anyhow::{Result, anyhow};
-use rdkafka::{
-    consumer::{Consumer, StreamConsumer, CommitMode},
-    ClientConfig,
-    Message,
-    message::OwnedMessage,
-};
-use tracing::{error, instrument, warn};
+use tracing::{error, instrument, warn, Span};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
 use std::sync::Arc;
 use log::debug;
+use prost::Message;
 use tokio::sync::Semaphore;
-use serde_json::from_slice;
 use common::utils::msg_utils::is_group_message;
 use common::topic::KafkaTopics;
+use common::bus::{BusMessage, MessageBus, MessageConsumer, MessageProducer, OffsetTicket};
+use common::tenant;
 use proto_crate::api::im::common::{MessageData, MessagePayload};
 use crate::domain::services::MessageService;
+use super::dead_letter_consumer::dead_letter_undecodable;
+use super::keyed_executor::KeyedExecutor;
+use super::lane_scheduler::{LaneScheduler, LaneSender};
+use super::tracked_offsets::TrackedOffsets;
+
+/// 分发通道: (主题, 调度权重)
+///
+/// 紧急与高优先级通道承载系统消息与单聊，权重较大，不会被大群广播阻塞。
+const LANES: [(&str, u32); 4] = [
+    (KafkaTopics::MESSAGE_DISTRIBUTION_URGENT, 8),
+    (KafkaTopics::MESSAGE_DISTRIBUTION_HIGH, 4),
+    (KafkaTopics::MESSAGE_DISTRIBUTION, 2),
+    (KafkaTopics::MESSAGE_DISTRIBUTION_LOW, 1),
+];
+
+/// 单个分发通道
+struct Lane {
+    topic: &'static str,
+    weight: u32,
+    consumer: Arc<dyn MessageConsumer>,
+    offsets: TrackedOffsets,
+}
+
+/// 调度器中的待处理消息
+struct Delivery {
+    lane: Arc<Lane>,
+    message: BusMessage,
+    ticket: OffsetTicket,
+}
 
 pub struct MessageDistributionConsumer {
-    consumer: StreamConsumer,
+    lanes: Vec<Arc<Lane>>,
     message_service: Arc<dyn MessageService>,
+    /// 无法解码的消息写入死信主题
+    producer: Arc<dyn MessageProducer>,
+    /// 同时处理的消息数
     concurrent_limit: Arc<Semaphore>,
+    /// 已调度未完成（处理中或排队等待同会话前序消息）的消息数
+    pending_limit: Arc<Semaphore>,
 }
 
 impl MessageDistributionConsumer {
     const MAX_CONCURRENT_MESSAGES: usize = 100;
+    const MAX_PENDING_MESSAGES: usize = 1000;
+    /// 每个通道预取的消息数
+    const LANE_PREFETCH: usize = 32;
 
-    pub fn new(message_service: Arc<dyn MessageService>) -> Result<Self> {
-        let consumer: StreamConsumer = ClientConfig::new()
-            .set("group.id", "message_distribution_group")
-            .set("bootstrap.servers", "localhost:9092")
-            .set("enable.auto.commit", "false")  // 禁用自动提交
-            .set("auto.offset.reset", "earliest")
-            .set("max.poll.interval.ms", "300000")
-            .set("session.timeout.ms", "30000")
-            .create()?;
-
-        consumer.subscribe(&[KafkaTopics::MESSAGE_DISTRIBUTION])?;
+    pub fn new(message_service: Arc<dyn MessageService>, bus: &dyn MessageBus) -> Result<Self> {
+        // 每个通道使用独立的消费组，普通通道沿用原有的 message_distribution_group
+        let lanes = LANES
+            .iter()
+            .map(|&(topic, weight)| {
+                let consumer = bus.subscribe(&Self::group_id(topic), &[topic])?;
+                Ok(Arc::new(Lane {
+                    topic,
+                    weight,
+                    offsets: TrackedOffsets::new(topic, consumer.clone()),
+                    consumer,
+                }))
+            })
+            .collect::<Result<Vec<_>>>()?;
 
         Ok(Self {
-            consumer,
+            lanes,
             message_service,
+            producer: bus.producer(),
             concurrent_limit: Arc::new(Semaphore::new(Self::MAX_CONCURRENT_MESSAGES)),
+            pending_limit: Arc::new(Semaphore::new(Self::MAX_PENDING_MESSAGES)),
         })
     }
 
+    fn group_id(topic: &str) -> String {
+        format!("{}_group", topic)
+    }
+
     #[instrument(skip(self))]
     pub async fn start(&self) -> Result<()> {
         debug!("Starting message distribution consumer");
 
+        let mut scheduler = LaneScheduler::new();
+        for lane in &self.lanes {
+            let sender = scheduler.add_lane(lane.topic, lane.weight, Self::LANE_PREFETCH);
+            tokio::spawn(Self::fetch_lane(lane.clone(), sender));
+        }
+
+        // 同一会话的消息按到达顺序串行处理，不同会话并发
+        let executor = KeyedExecutor::new();
+
+        // 调度前只占用排队名额，处理许可在轮到该消息执行时才申请，
+        // 热点会话的排队消息不会占满处理许可而阻塞其他会话和高优先级通道
         loop {
-            match self.consumer.recv().await {
-                Ok(borrowed_message) => {
-                    let _permit = self.concurrent_limit.acquire().await?;
-                    let service = self.message_service.clone();
-                    
-                    // 转换为 OwnedMessage
-                    let owned_message = borrowed_message.detach();
-                    
-                    tokio::spawn(async move {
-                        match Self::process_message(owned_message.clone(), service.clone()).await {
-                            Ok(_) => {
-                                // 消息处理成功，提交 offset
-                                debug!("Message processed successfully");
-                            }
-                            Err(e) => {
-                                error!("Failed to process message: {}", e);
-                                // 消息处理失败，根据重试策略处理
-                                if let Err(retry_err) = Self::handle_message_failure(&owned_message, e, &service).await {
-                                    error!("Failed to handle message failure: {}", retry_err);
-                                }
+            let pending = self.pending_limit.clone().acquire_owned().await?;
+            let Some((lane, delivery)) = scheduler.next().await else {
+                return Err(anyhow!("All distribution lanes stopped"));
+            };
+            let Delivery { lane: state, message, ticket } = delivery;
+
+            // 分区已被收回，消息会由新的分配者重新消费
+            if !state.offsets.is_current(&ticket).await {
+                debug!("Skipping message from revoked partition {}[{}]", ticket.topic, ticket.partition);
+                continue;
+            }
+            debug!("Dispatching message from lane {}", lane);
+
+            // 按消息头中的租户执行，仓储访问使用对应租户的存储；
+            // 租户头非法时不能回落到默认租户，转入死信后视为已处理
+            let tenant = match message.tenant() {
+                Ok(tenant) => tenant,
+                Err(e) => {
+                    match dead_letter_undecodable(&message, e.to_string(), self.producer.as_ref()).await {
+                        Ok(()) => state.offsets.complete(&ticket).await,
+                        Err(e) => error!("Failed to dead-letter message with invalid tenant: {}", e),
+                    }
+                    continue;
+                }
+            };
+            let service = self.message_service.clone();
+            let producer = self.producer.clone();
+            let concurrent_limit = self.concurrent_limit.clone();
+            let key = Self::ordering_key(&message);
+
+            executor.submit(key, tenant::scope(tenant, async move {
+                let _pending = pending;
+                // 排队期间分区可能已被收回
+                if !state.offsets.is_current(&ticket).await {
+                    debug!("Skipping queued message from revoked partition {}[{}]", ticket.topic, ticket.partition);
+                    return;
+                }
+                let Ok(_permit) = concurrent_limit.acquire_owned().await else {
+                    return;
+                };
+                let handled = match Self::process_message(&message, service.clone()).await {
+                    Ok(_) => {
+                        debug!("Message processed successfully");
+                        true
+                    }
+                    Err(e) => {
+                        error!("Failed to process message: {}", e);
+                        // 消息处理失败，根据重试策略处理（进入重试主题或死信）
+                        match Self::handle_message_failure(&message, e, &service, producer.as_ref()).await {
+                            Ok(_) => true,
+                            Err(retry_err) => {
+                                error!("Failed to handle message failure: {}", retry_err);
+                                false
                             }
                         }
-                    });
+                    }
+                };
+
+                // 失败处理也未成功时保留该位点，之后的位点不会被提交，重启或再均衡后重新消费
+                if handled {
+                    state.offsets.complete(&ticket).await;
+                } else {
+                    warn!(
+                        "Holding offset {} of {}[{}] until redelivery",
+                        ticket.offset, ticket.topic, ticket.partition
+                    );
+                }
+            }));
+        }
+    }
+
+    /// 串行执行的键
+    ///
+    /// 生产端以租户加会话ID作为消息键，跨通道也能保证同一会话串行；
+    /// 没有消息键的消息互不约束。
+    fn ordering_key(message: &BusMessage) -> String {
+        message
+            .key
+            .clone()
+            .unwrap_or_else(|| format!("{}:{}:{}", message.topic, message.partition, message.offset))
+    }
+
+    /// 拉取单个通道的消息，登记位点后交给调度器
+    async fn fetch_lane(lane: Arc<Lane>, sender: LaneSender<Delivery>) {
+        loop {
+            match lane.consumer.recv().await {
+                Ok(message) => {
+                    let ticket = lane.offsets.start(&message).await;
+                    let delivery = Delivery {
+                        lane: lane.clone(),
+                        message,
+                        ticket,
+                    };
+                    if !sender.send(delivery).await {
+                        return;
+                    }
                 }
                 Err(e) => {
-                    error!("Error receiving message: {}", e);
+                    error!("Error receiving message from {}: {}", lane.topic, e);
                 }
             }
         }
@@ -81,16 +208,13 @@ impl MessageDistributionConsumer {
 
     #[instrument(skip(message, service))]
     async fn process_message(
-        message: OwnedMessage,
+        message: &BusMessage,
         service: Arc<dyn MessageService>,
     ) -> Result<()> {
-        let payload = match message.payload() {
-            Some(data) => {
-                let payload: MessagePayload = from_slice(data)?;
-                payload
-            }
-            None => return Err(anyhow!("Empty message payload")),
-        };
+        // 接续生产端的链路上下文
+        Span::current().set_parent(message.trace_context());
+
+        let payload = Self::decode(message)?;
 
         debug!(
             "Processing message: id={}, timestamp={}", 
@@ -180,29 +304,105 @@ impl MessageDistributionConsumer {
         Ok(())
     }
 
-    #[instrument(skip(message, error, service))]
+    #[instrument(skip(message, error, service, producer))]
     async fn handle_message_failure(
-        message: &OwnedMessage, 
+        message: &BusMessage,
         error: anyhow::Error,
         service: &Arc<dyn MessageService>,
+        producer: &dyn MessageProducer,
     ) -> Result<()> {
-        let payload = match message.payload() {
-            Some(data) => {
-                let payload: MessagePayload = from_slice(data)?;
-                payload
-            }
-            None => return Err(anyhow!("Empty message payload")),
+        // 无法解析的消息无从重试，转入死信后视为已处理，避免阻塞分区位点
+        let payload = match Self::decode(message) {
+            Ok(payload) => payload,
+            Err(e) => return dead_letter_undecodable(message, e.to_string(), producer).await,
         };
 
-        if let Some(msg) = payload.msg {
-            error!(
-                "Message {} processing failed with error: {}, entering retry flow",
-                msg.server_msg_id,
-                error
-            );
-            service.handle_message_retry(&msg).await?;
+        match payload.msg {
+            Some(msg) => {
+                error!(
+                    "Message {} processing failed with error: {}, entering retry flow",
+                    msg.server_msg_id,
+                    error
+                );
+                service.handle_message_retry(&msg).await?;
+            }
+            None => {
+                dead_letter_undecodable(message, "message content is required".to_string(), producer).await?;
+            }
         }
 
         Ok(())
     }
-} 
\ No newline at end of file
+
+    /// 分发消息由 MessageRepository::send_to_bus 以 protobuf 写入
+    fn decode(message: &BusMessage) -> Result<MessagePayload, prost::DecodeError> {
+        MessagePayload::decode(message.payload.as_slice())
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use common::bus::{InMemoryBus, OutgoingMessage};
+
+    use super::*;
+    use crate::domain::services::MockMessageService;
+    use crate::entities::MessageProcessResult;
+    use crate::infrastructure::repositories::payload_record;
+
+    #[tokio::test]
+    async fn test_repository_payload_round_trip() {
+        let bus = InMemoryBus::new(1);
+        let message = MessageData {
+            server_msg_id: "1001".to_string(),
+            send_id: "user1".to_string(),
+            recv_id: "user2".to_string(),
+            ..Default::default()
+        };
+        bus.producer()
+            .send(KafkaTopics::MESSAGE_DISTRIBUTION, payload_record(&message).key("si_user1_user2"))
+            .await
+            .unwrap();
+        let consumer = bus.subscribe("group", &[KafkaTopics::MESSAGE_DISTRIBUTION]).unwrap();
+        let received = consumer.recv().await.unwrap();
+
+        let mut service = MockMessageService::new();
+        service
+            .expect_handle_message()
+            .withf(|m| m.server_msg_id == "1001" && m.recv_id == "user2")
+            .times(1)
+            .returning(|m| {
+                Ok(MessageProcessResult {
+                    message_id: m.server_msg_id.clone(),
+                    success: true,
+                    error: None,
+                    routes: vec![],
+                    push_results: vec![],
+                })
+            });
+        MessageDistributionConsumer::process_message(&received, Arc::new(service))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_undecodable_payload_is_dead_lettered() {
+        let bus = InMemoryBus::new(1);
+        bus.producer()
+            .send(KafkaTopics::MESSAGE_DISTRIBUTION, OutgoingMessage::new(vec![0xff, 0xff]))
+            .await
+            .unwrap();
+        let consumer = bus.subscribe("group", &[KafkaTopics::MESSAGE_DISTRIBUTION]).unwrap();
+        let received = consumer.recv().await.unwrap();
+
+        // 没有设置期望，任何重试调用都会使测试失败
+        let service: Arc<dyn MessageService> = Arc::new(MockMessageService::new());
+        let error = MessageDistributionConsumer::process_message(&received, service.clone())
+            .await
+            .unwrap_err();
+        MessageDistributionConsumer::handle_message_failure(&received, error, &service, bus.producer().as_ref())
+            .await
+            .unwrap();
+        assert_eq!(bus.message_count(KafkaTopics::DEAD_LETTER), 1);
+    }
+}
@@ -1,3 +1,18 @@
+mod dead_letter_consumer;
+mod keyed_executor;
+mod lane_scheduler;
 mod message_distribution_consumer;
+mod offline_push_consumer;
+mod presence_consumer;
+mod retry_consumer;
+mod schedule_dispatcher;
+mod tracked_offsets;
 
-pub use message_distribution_consumer::MessageDistributionConsumer; 
\ No newline at end of file
+pub use dead_letter_consumer::DeadLetterConsumer;
+pub use keyed_executor::KeyedExecutor;
+pub use lane_scheduler::{LaneScheduler, LaneSender};
+pub use message_distribution_consumer::MessageDistributionConsumer;
+pub use offline_push_consumer::OfflinePushConsumer;
+pub use presence_consumer::PresenceConsumer;
+pub use retry_consumer::RetryConsumer;
+pub use schedule_dispatcher::ScheduleDispatcher;
@@ -0,0 +1,108 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use common::bus::{BusMessage, MessageBus, MessageConsumer, MessageProducer};
+use common::tenant;
+use common::topic::KafkaTopics;
+use log::{debug, error, warn};
+use prost::Message;
+use proto_crate::api::im::common::{MessageData, MessagePayload};
+use tokio::sync::Semaphore;
+use tracing::instrument;
+
+use crate::domain::services::OfflinePushWorker;
+use super::dead_letter_consumer::dead_letter_undecodable;
+use super::tracked_offsets::TrackedOffsets;
+
+/// 群消息离线推送任务消费者
+///
+/// 任务由 MessageRepository::enqueue_offline_push 以群为键写入，多个任务并发执行，
+/// 完成后按分区位点顺序提交，重启或再均衡后未完成的任务重新消费。
+pub struct OfflinePushConsumer {
+    consumer: Arc<dyn MessageConsumer>,
+    offsets: Arc<TrackedOffsets>,
+    worker: Arc<OfflinePushWorker>,
+    job_limit: Arc<Semaphore>,
+    /// 租户头非法的任务写入死信主题
+    producer: Arc<dyn MessageProducer>,
+}
+
+impl OfflinePushConsumer {
+    const GROUP_ID: &'static str = "offline_group_push_group";
+    /// 任务失败后的重试间隔
+    const RETRY_BACKOFF: Duration = Duration::from_secs(1);
+
+    pub fn new(worker: Arc<OfflinePushWorker>, bus: &dyn MessageBus) -> Result<Self> {
+        let consumer = bus.subscribe(Self::GROUP_ID, &[KafkaTopics::OFFLINE_GROUP_PUSH])?;
+        let offsets = Arc::new(TrackedOffsets::new(KafkaTopics::OFFLINE_GROUP_PUSH, consumer.clone()));
+        Ok(Self {
+            consumer,
+            offsets,
+            job_limit: Arc::new(Semaphore::new(worker.max_concurrent_jobs())),
+            worker,
+            producer: bus.producer(),
+        })
+    }
+
+    #[instrument(skip(self))]
+    pub async fn start(&self) -> Result<()> {
+        debug!("Starting offline push consumer");
+
+        loop {
+            let permit = self.job_limit.clone().acquire_owned().await?;
+            let message = match self.consumer.recv().await {
+                Ok(message) => message,
+                Err(e) => {
+                    error!("Error receiving offline push job: {}", e);
+                    continue;
+                }
+            };
+            let ticket = self.offsets.start(&message).await;
+            let offsets = self.offsets.clone();
+            let worker = self.worker.clone();
+
+            // 租户头非法时不能回落到默认租户，转入死信后视为已处理
+            let tenant = match message.tenant() {
+                Ok(tenant) => tenant,
+                Err(e) => {
+                    match dead_letter_undecodable(&message, e.to_string(), self.producer.as_ref()).await {
+                        Ok(()) => offsets.complete(&ticket).await,
+                        Err(e) => error!("Failed to dead-letter offline push job at offset {}: {}", message.offset, e),
+                    }
+                    continue;
+                }
+            };
+
+            tokio::spawn(tenant::scope(tenant, async move {
+                let _permit = permit;
+                if let Some(job) = Self::decode(&message) {
+                    // 失败时原地重试，分区被收回后交给新的分配者
+                    while let Err(e) = worker.process(&job).await {
+                        error!("Offline push of group message {} failed: {}", job.server_msg_id, e);
+                        tokio::time::sleep(Self::RETRY_BACKOFF).await;
+                        if !offsets.is_current(&ticket).await {
+                            return;
+                        }
+                    }
+                }
+                offsets.complete(&ticket).await;
+            }));
+        }
+    }
+
+    /// 解码任务，无效的任务跳过
+    fn decode(message: &BusMessage) -> Option<MessageData> {
+        match MessagePayload::decode(message.payload.as_slice()) {
+            Ok(MessagePayload { msg: Some(job), .. }) => Some(job),
+            Ok(_) => {
+                warn!("Skipping empty offline push job at offset {}", message.offset);
+                None
+            }
+            Err(e) => {
+                warn!("Skipping invalid offline push job at offset {}: {}", message.offset, e);
+                None
+            }
+        }
+    }
+}
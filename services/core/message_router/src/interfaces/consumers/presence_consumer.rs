@@ -0,0 +1,142 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use common::bus::{BusMessage, MessageBus, MessageConsumer, MessageProducer};
+use common::route::{GroupMemberEvent, PresenceEvent};
+use common::tenant;
+use common::topic::KafkaTopics;
+use log::{debug, error, warn};
+use tracing::instrument;
+
+use crate::domain::repositories::{GroupRepository, RouteRepository};
+use super::dead_letter_consumer::dead_letter_undecodable;
+
+/// 上下线事件消费者
+///
+/// 读取网关发布的上下线事件与群组服务发布的成员变更事件，维护用户所在群的在线成员索引。
+/// 事件以用户ID为键，同一用户的事件按顺序处理。
+pub struct PresenceConsumer {
+    consumer: Arc<dyn MessageConsumer>,
+    group_repository: Arc<dyn GroupRepository>,
+    route_repository: Arc<dyn RouteRepository>,
+    /// 租户头非法的事件写入死信主题
+    producer: Arc<dyn MessageProducer>,
+}
+
+impl PresenceConsumer {
+    const GROUP_ID: &'static str = "presence_index_group";
+    /// 索引更新失败后的重试间隔
+    const RETRY_BACKOFF: std::time::Duration = std::time::Duration::from_secs(1);
+
+    pub fn new(
+        group_repository: Arc<dyn GroupRepository>,
+        route_repository: Arc<dyn RouteRepository>,
+        bus: &dyn MessageBus,
+    ) -> Result<Self> {
+        let consumer = bus.subscribe(Self::GROUP_ID, &[KafkaTopics::USER_PRESENCE, KafkaTopics::GROUP_MEMBERSHIP])?;
+        Ok(Self {
+            consumer,
+            group_repository,
+            route_repository,
+            producer: bus.producer(),
+        })
+    }
+
+    #[instrument(skip(self))]
+    pub async fn start(&self) -> Result<()> {
+        debug!("Starting presence consumer");
+
+        loop {
+            let message = match self.consumer.recv().await {
+                Ok(message) => message,
+                Err(e) => {
+                    error!("Error receiving presence event: {}", e);
+                    continue;
+                }
+            };
+
+            // 租户头非法时不能回落到默认租户，转入死信
+            match message.tenant() {
+                Ok(tenant) => {
+                    // 更新失败时原地重试，不提交位点
+                    while let Err(e) = tenant::scope(tenant.clone(), self.apply(&message)).await {
+                        error!("Failed to apply presence event at offset {}: {}", message.offset, e);
+                        tokio::time::sleep(Self::RETRY_BACKOFF).await;
+                    }
+                }
+                Err(e) => {
+                    while let Err(e) = dead_letter_undecodable(&message, e.to_string(), self.producer.as_ref()).await {
+                        error!("Failed to dead-letter presence event at offset {}: {}", message.offset, e);
+                        tokio::time::sleep(Self::RETRY_BACKOFF).await;
+                    }
+                }
+            }
+
+            if let Err(e) = self.consumer.commit_message(&message).await {
+                warn!("Failed to commit presence offset: {}", e);
+            }
+        }
+    }
+
+    async fn apply(&self, message: &BusMessage) -> Result<()> {
+        if message.topic == KafkaTopics::GROUP_MEMBERSHIP {
+            self.apply_membership(message).await
+        } else {
+            self.apply_presence(message).await
+        }
+    }
+
+    /// 在线用户加入群时写入该群的在线索引，退出时移除
+    async fn apply_membership(&self, message: &BusMessage) -> Result<()> {
+        let event: GroupMemberEvent = match serde_json::from_slice(&message.payload) {
+            Ok(event) => event,
+            Err(e) => {
+                warn!("Skipping invalid group member event at offset {}: {}", message.offset, e);
+                return Ok(());
+            }
+        };
+
+        let groups = [event.group_id];
+        if !event.joined {
+            return self.group_repository.set_member_online(&event.user_id, &groups, false).await;
+        }
+        if self.route_repository.get_routes_with_weight(&event.user_id).await?.is_empty() {
+            debug!("User {} joined group {} while offline", event.user_id, groups[0]);
+            return Ok(());
+        }
+        self.group_repository.set_member_online(&event.user_id, &groups, true).await
+    }
+
+    async fn apply_presence(&self, message: &BusMessage) -> Result<()> {
+        let event: PresenceEvent = match serde_json::from_slice(&message.payload) {
+            Ok(event) => event,
+            Err(e) => {
+                warn!("Skipping invalid presence event at offset {}: {}", message.offset, e);
+                return Ok(());
+            }
+        };
+
+        // 单个设备下线时用户可能仍有其他设备在线
+        if !event.online
+            && !self
+                .route_repository
+                .get_routes_with_weight(&event.user_id)
+                .await?
+                .is_empty()
+        {
+            debug!("User {} still has online devices", event.user_id);
+            return Ok(());
+        }
+
+        let groups = self.group_repository.get_joined_groups(&event.user_id).await?;
+        debug!(
+            "User {} is {} in {} groups",
+            event.user_id,
+            if event.online { "online" } else { "offline" },
+            groups.len()
+        );
+        self.group_repository
+            .set_member_online(&event.user_id, &groups, event.online)
+            .await
+    }
+}
@@ -0,0 +1,141 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use common::bus::{BusMessage, MessageBus, MessageConsumer, MessageProducer, OutgoingMessage};
+use common::topic::KafkaTopics;
+use common::utils::msg_utils::message_priority;
+use log::{debug, error, warn};
+use prost::Message;
+use proto_crate::api::im::common::MessagePayload;
+use tracing::instrument;
+
+use crate::infrastructure::metrics::RetryMetrics;
+
+/// 延迟重试消费者
+///
+/// 每个重试档位一个消费循环：消息写入档位主题后等待该档位的固定延迟，
+/// 到期时若距 `next_retry_time` 仍超过最小档位则转入下一档位，否则按原优先级投递回分发通道。
+/// 消息体与消息头原样转发，租户与链路上下文随之保留。
+pub struct RetryConsumer {
+    tiers: Vec<(&'static str, i64, Arc<dyn MessageConsumer>)>,
+    producer: Arc<dyn MessageProducer>,
+}
+
+impl RetryConsumer {
+    /// 转发失败后的重试间隔
+    const FORWARD_BACKOFF: Duration = Duration::from_secs(1);
+
+    pub fn new(bus: &dyn MessageBus) -> Result<Self> {
+        let tiers = KafkaTopics::RETRY_TIERS
+            .iter()
+            .map(|&(topic, delay)| {
+                let consumer = bus.subscribe(&format!("{}_group", topic), &[topic])?;
+                Ok((topic, delay, consumer))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            tiers,
+            producer: bus.producer(),
+        })
+    }
+
+    #[instrument(skip(self))]
+    pub async fn start(&self) -> Result<()> {
+        debug!("Starting message retry consumer");
+
+        let handles: Vec<_> = self
+            .tiers
+            .iter()
+            .map(|&(topic, delay, ref consumer)| {
+                tokio::spawn(Self::run_tier(topic, delay, consumer.clone(), self.producer.clone()))
+            })
+            .collect();
+        for handle in handles {
+            handle.await?;
+        }
+        Err(anyhow!("All retry tiers stopped"))
+    }
+
+    async fn run_tier(
+        topic: &'static str,
+        delay_ms: i64,
+        consumer: Arc<dyn MessageConsumer>,
+        producer: Arc<dyn MessageProducer>,
+    ) {
+        loop {
+            let message = match consumer.recv().await {
+                Ok(message) => message,
+                Err(e) => {
+                    error!("Error receiving message from {}: {}", topic, e);
+                    continue;
+                }
+            };
+
+            // 同一档位延迟固定，按写入顺序等待即可
+            let wait = message.timestamp + delay_ms - chrono::Utc::now().timestamp_millis();
+            if wait > 0 {
+                tokio::time::sleep(Duration::from_millis(wait as u64)).await;
+            }
+
+            // 转发失败时原地重试，不提交位点，避免丢失消息
+            while let Err(e) = Self::forward(&message, producer.as_ref()).await {
+                error!("Failed to forward retry message from {}: {}", topic, e);
+                tokio::time::sleep(Self::FORWARD_BACKOFF).await;
+            }
+
+            if let Err(e) = consumer.commit_message(&message).await {
+                warn!("Failed to commit retry offset on {}: {}", topic, e);
+            }
+        }
+    }
+
+    /// 转入下一档位或投递回分发通道
+    async fn forward(message: &BusMessage, producer: &dyn MessageProducer) -> Result<()> {
+        // 重试消息由 MessageRepository::schedule_retry 以 protobuf 写入
+        let payload = match MessagePayload::decode(message.payload.as_slice()) {
+            Ok(payload) => payload,
+            Err(e) => {
+                warn!("Dropping undecodable retry message at {}/{}: {}", message.topic, message.offset, e);
+                return Ok(());
+            }
+        };
+        let Some(msg) = payload.msg.as_ref() else {
+            warn!("Dropping retry message {} without content", payload.msg_id);
+            return Ok(());
+        };
+
+        let now = chrono::Utc::now().timestamp_millis();
+        let next_retry_time = msg
+            .options
+            .get("next_retry_time")
+            .and_then(|v| v.parse::<i64>().ok())
+            .unwrap_or(now);
+        let remaining = next_retry_time - now;
+        let target = if remaining >= KafkaTopics::RETRY_TIERS[0].1 {
+            KafkaTopics::retry_topic(remaining).0
+        } else {
+            KafkaTopics::distribution_topic(message_priority(msg))
+        };
+
+        let record = OutgoingMessage {
+            key: message.key.clone(),
+            payload: message.payload.clone(),
+            headers: message.headers.clone(),
+            timestamp: Some(now),
+        };
+        producer
+            .send(target, record)
+            .await
+            .map_err(|e| anyhow!("send to {} failed: {}", target, e))?;
+
+        if KafkaTopics::DISTRIBUTION_LANES.contains(&target) {
+            RetryMetrics::get().record_released(target, now - next_retry_time);
+            debug!("Retry message {} released to {}", payload.msg_id, target);
+        } else {
+            debug!("Retry message {} moved to {}, {}ms remaining", payload.msg_id, target, remaining);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,226 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use common::tenant;
+use futures::future::join_all;
+use log::{debug, error, warn};
+use tracing::instrument;
+
+use crate::application::message_router::MessageRouterService;
+use crate::domain::entities::ScheduledTask;
+use crate::domain::repositories::MessageScheduleRepository;
+
+/// 定时任务调度器
+///
+/// 定期取出到期的定时投递与销毁任务，在任务所属租户下执行。
+/// 多实例共同调度时由租约保证同一任务同一时间只被一个实例执行，
+/// 执行失败或实例退出的任务在租约到期后重新触发。
+/// 同一批任务并发执行，单个任务的执行时限短于租约，租约到期前任务已完成或放弃。
+pub struct ScheduleDispatcher {
+    repository: Arc<dyn MessageScheduleRepository>,
+    message_router: Arc<MessageRouterService>,
+}
+
+// 任务在租约到期前完成或放弃，同一任务不会被两个实例同时执行
+const _: () = assert!(ScheduleDispatcher::TASK_TIMEOUT.as_millis() < ScheduleDispatcher::LEASE.as_millis());
+
+impl ScheduleDispatcher {
+    /// 轮询间隔
+    const POLL_INTERVAL: Duration = Duration::from_secs(1);
+    /// 任务租约
+    const LEASE: Duration = Duration::from_secs(60);
+    /// 单个任务的执行时限，需小于租约，超时的任务租约到期后重新触发
+    const TASK_TIMEOUT: Duration = Duration::from_secs(30);
+    /// 每次最多取出的任务数
+    const BATCH_SIZE: usize = 100;
+
+    pub fn new(repository: Arc<dyn MessageScheduleRepository>, message_router: Arc<MessageRouterService>) -> Self {
+        Self {
+            repository,
+            message_router,
+        }
+    }
+
+    #[instrument(skip(self))]
+    pub async fn start(&self) -> Result<()> {
+        debug!("Starting schedule dispatcher");
+
+        let mut ticker = tokio::time::interval(Self::POLL_INTERVAL);
+        loop {
+            ticker.tick().await;
+            // 一批取满时说明仍有积压，继续取下一批
+            loop {
+                match self.dispatch_due().await {
+                    Ok(count) if count >= Self::BATCH_SIZE => continue,
+                    Ok(_) => break,
+                    Err(e) => {
+                        error!("Failed to claim scheduled tasks: {}", e);
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    async fn dispatch_due(&self) -> Result<usize> {
+        let now = chrono::Utc::now().timestamp_millis();
+        let tasks = self
+            .repository
+            .claim_due(now, Self::LEASE.as_millis() as i64, Self::BATCH_SIZE)
+            .await?;
+
+        let count = tasks.len();
+        join_all(tasks.iter().map(|task| self.fire(task, Self::TASK_TIMEOUT))).await;
+        Ok(count)
+    }
+
+    /// 在任务所属租户下执行任务，完成后移除
+    async fn fire(&self, task: &ScheduledTask, timeout: Duration) {
+        let message_id = &task.message.server_msg_id;
+        let result = tenant::scope(task.tenant.clone(), async {
+            tokio::time::timeout(timeout, self.message_router.fire_scheduled(task))
+                .await
+                .map_err(|_| anyhow!("timed out after {:?}", timeout))??;
+            self.repository.complete(task).await
+        })
+        .await;
+        match result {
+            Ok(()) => debug!("Scheduled {:?} task {} fired", task.kind, message_id),
+            Err(e) => warn!("Scheduled {:?} task {} failed, will retry: {}", task.kind, message_id, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use async_trait::async_trait;
+    use common::id::SnowflakeGenerator;
+    use common::tenant::TenantContext;
+    use proto_crate::api::im::common::MessageData;
+
+    use super::*;
+    use crate::domain::entities::{PreProcessCode, ScheduledTaskKind};
+    use crate::domain::services::MockMessageService;
+
+    /// 内存中的定时任务，只记录取出与完成
+    #[derive(Default)]
+    struct MemoryScheduleRepository {
+        due: Mutex<Vec<ScheduledTask>>,
+        completed: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl MessageScheduleRepository for MemoryScheduleRepository {
+        async fn schedule(&self, kind: ScheduledTaskKind, message: &MessageData, _fire_at: Option<i64>) -> Result<()> {
+            self.due.lock().unwrap().push(ScheduledTask {
+                tenant: tenant::current(),
+                kind,
+                message: message.clone(),
+            });
+            Ok(())
+        }
+
+        async fn arm(&self, _kind: ScheduledTaskKind, _message_id: &str, _fire_at: i64) -> Result<bool> {
+            Ok(false)
+        }
+
+        async fn get(&self, _kind: ScheduledTaskKind, _message_id: &str) -> Result<Option<MessageData>> {
+            Ok(None)
+        }
+
+        async fn cancel(&self, _kind: ScheduledTaskKind, _message_id: &str) -> Result<bool> {
+            Ok(false)
+        }
+
+        async fn claim_due(&self, _now: i64, _lease_ms: i64, limit: usize) -> Result<Vec<ScheduledTask>> {
+            let mut due = self.due.lock().unwrap();
+            let count = due.len().min(limit);
+            Ok(due.drain(..count).collect())
+        }
+
+        async fn complete(&self, task: &ScheduledTask) -> Result<()> {
+            self.completed.lock().unwrap().push(task.message.server_msg_id.clone());
+            Ok(())
+        }
+    }
+
+    fn message(id: &str) -> MessageData {
+        MessageData {
+            server_msg_id: id.to_string(),
+            send_id: "user1".to_string(),
+            recv_id: "user2".to_string(),
+            ..Default::default()
+        }
+    }
+
+    async fn dispatcher(service: MockMessageService, ids: &[&str]) -> (ScheduleDispatcher, Arc<MemoryScheduleRepository>) {
+        let repository = Arc::new(MemoryScheduleRepository::default());
+        let tenant = TenantContext::new("acme").unwrap();
+        for id in ids {
+            tenant::scope(tenant.clone(), repository.schedule(ScheduledTaskKind::Deliver, &message(id), Some(0)))
+                .await
+                .unwrap();
+        }
+        let router = MessageRouterService::new(Arc::new(service), Arc::new(SnowflakeGenerator::new(1).unwrap()));
+        (ScheduleDispatcher::new(repository.clone(), Arc::new(router)), repository)
+    }
+
+    #[tokio::test]
+    async fn test_due_tasks_are_prechecked_routed_and_completed() {
+        let mut service = MockMessageService::new();
+        service.expect_pre_process().times(2).returning(|_| Ok(PreProcessCode::Ok));
+        service
+            .expect_handle_message_storage()
+            .times(2)
+            .returning(|_| {
+                // 任务在所属租户下执行
+                assert_eq!(tenant::current().tenant_id(), "acme");
+                Ok(())
+            });
+        service.expect_handle_message_distribution().times(2).returning(|_| Ok(()));
+        service.expect_handle_message_sync().times(2).returning(|_| Ok(()));
+
+        let (dispatcher, repository) = dispatcher(service, &["1", "2"]).await;
+        assert_eq!(dispatcher.dispatch_due().await.unwrap(), 2);
+
+        let mut completed = repository.completed.lock().unwrap().clone();
+        completed.sort();
+        assert_eq!(completed, vec!["1", "2"]);
+    }
+
+    #[tokio::test]
+    async fn test_rejected_scheduled_message_is_dropped() {
+        let mut service = MockMessageService::new();
+        service.expect_pre_process().times(1).returning(|_| Ok(PreProcessCode::NotFriend));
+        service.expect_handle_message_storage().never();
+        service.expect_handle_message_distribution().never();
+
+        let (dispatcher, repository) = dispatcher(service, &["1"]).await;
+        dispatcher.dispatch_due().await.unwrap();
+
+        // 不再重试
+        assert_eq!(*repository.completed.lock().unwrap(), vec!["1"]);
+    }
+
+    #[tokio::test]
+    async fn test_failed_task_is_kept_for_retry() {
+        let mut service = MockMessageService::new();
+        service.expect_pre_process().times(2).returning(|m| {
+            Ok(if m.server_msg_id == "1" { PreProcessCode::SystemError } else { PreProcessCode::Ok })
+        });
+        service.expect_handle_message_storage().times(1).returning(|_| Ok(()));
+        service
+            .expect_handle_message_distribution()
+            .times(1)
+            .returning(|_| Err(anyhow!("bus unavailable")));
+
+        let (dispatcher, repository) = dispatcher(service, &["1", "2"]).await;
+        assert_eq!(dispatcher.dispatch_due().await.unwrap(), 2);
+
+        // 两个任务均未完成，租约到期后重新触发
+        assert!(repository.completed.lock().unwrap().is_empty());
+    }
+}
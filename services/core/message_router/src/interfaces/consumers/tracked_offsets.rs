@@ -0,0 +1,120 @@
+use std::sync::Arc;
+
+use common::bus::{BusMessage, MessageConsumer, OffsetTicket, OffsetTracker, RebalanceEvent};
+use log::{debug, warn};
+use tokio::sync::{broadcast, Mutex};
+
+/// 位点跟踪与尚未应用的再均衡事件
+struct OffsetState {
+    tracker: OffsetTracker,
+    events: broadcast::Receiver<RebalanceEvent>,
+}
+
+impl OffsetState {
+    /// 应用已到达的再均衡事件后返回位点跟踪
+    ///
+    /// 再均衡回调在拉取消息时同步发出事件，新分配下的消息返回之前事件已经入队，
+    /// 每次访问位点状态前先应用事件，新分配的位点状态就不会被迟到的事件清除。
+    fn tracker(&mut self, topic: &str) -> &mut OffsetTracker {
+        loop {
+            match self.events.try_recv() {
+                Ok(RebalanceEvent::Revoked(partitions)) => {
+                    debug!("Partitions revoked from {}: {:?}", topic, partitions);
+                    self.tracker.revoke(&partitions);
+                }
+                Ok(RebalanceEvent::Assigned(partitions)) => {
+                    debug!("Partitions assigned to {}: {:?}", topic, partitions);
+                    self.tracker.revoke(&partitions);
+                }
+                Err(broadcast::error::TryRecvError::Lagged(skipped)) => {
+                    warn!("Missed {} rebalance events on {}, resetting offset tracking", skipped, topic);
+                    self.tracker.revoke_all();
+                }
+                Err(broadcast::error::TryRecvError::Empty | broadcast::error::TryRecvError::Closed) => {
+                    return &mut self.tracker;
+                }
+            }
+        }
+    }
+}
+
+/// 乱序完成的消息的位点提交
+///
+/// 按分区跟踪处理中的位点，提交时持锁保证同一分区的位点单调推进。
+pub(crate) struct TrackedOffsets {
+    topic: &'static str,
+    consumer: Arc<dyn MessageConsumer>,
+    state: Mutex<OffsetState>,
+}
+
+impl TrackedOffsets {
+    /// 须在拉取消息前创建，避免错过首次分配的再均衡事件
+    pub(crate) fn new(topic: &'static str, consumer: Arc<dyn MessageConsumer>) -> Self {
+        let events = consumer.rebalance_events();
+        Self {
+            topic,
+            consumer,
+            state: Mutex::new(OffsetState {
+                tracker: OffsetTracker::new(),
+                events,
+            }),
+        }
+    }
+
+    /// 登记开始处理的消息
+    pub(crate) async fn start(&self, message: &BusMessage) -> OffsetTicket {
+        self.state.lock().await.tracker(self.topic).start(message)
+    }
+
+    /// 消息所属分区是否仍在当前分配中
+    pub(crate) async fn is_current(&self, ticket: &OffsetTicket) -> bool {
+        self.state.lock().await.tracker(self.topic).is_current(ticket)
+    }
+
+    /// 标记消息处理完成，位点推进时提交
+    pub(crate) async fn complete(&self, ticket: &OffsetTicket) {
+        let mut state = self.state.lock().await;
+        if let Some(offset) = state.tracker(self.topic).complete(ticket) {
+            if let Err(e) = self.consumer.commit(&ticket.topic, ticket.partition, offset).await {
+                warn!("Failed to commit {}[{}] at {}: {}", ticket.topic, ticket.partition, offset, e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn bus_message(partition: i32, offset: i64) -> BusMessage {
+        BusMessage {
+            topic: "topic".to_string(),
+            partition,
+            offset,
+            key: None,
+            payload: Vec::new(),
+            headers: HashMap::new(),
+            timestamp: 0,
+        }
+    }
+
+    #[test]
+    fn test_assignment_applied_before_new_messages() {
+        let (sender, events) = broadcast::channel(8);
+        let mut offsets = OffsetState {
+            tracker: OffsetTracker::new(),
+            events,
+        };
+        let stale = offsets.tracker("topic").start(&bus_message(0, 5));
+
+        // 分配事件先于新分配下的消息到达，登记新消息前即被应用
+        sender.send(RebalanceEvent::Assigned(vec![("topic".to_string(), 0)])).unwrap();
+        let fresh = offsets.tracker("topic").start(&bus_message(0, 3));
+
+        assert!(!offsets.tracker("topic").is_current(&stale));
+        assert_eq!(offsets.tracker("topic").complete(&stale), None);
+        assert_eq!(offsets.tracker("topic").complete(&fresh), Some(4));
+    }
+}
@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use common::tenant::TenantContext;
+use tonic::{Request, Status};
+
+/// 鉴权令牌所在的请求头
+const AUTHORIZATION: &str = "authorization";
+/// 可管理全部租户
+const ALL_TENANTS: &str = "*";
+
+/// 通过鉴权的管理员
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdminIdentity {
+    /// 操作人，写入审计记录
+    pub operator: String,
+    /// 可管理的租户，`None` 表示全部租户
+    pub tenant: Option<String>,
+}
+
+/// 管理接口鉴权
+///
+/// 请求以 `authorization: Bearer {token}` 携带静态令牌，令牌对应的身份决定操作人与可管理的租户，
+/// 请求头中的租户不在身份范围内时拒绝。鉴权通过后身份写入请求扩展，由 [`admin_identity`] 取出。
+#[derive(Clone, Default)]
+pub struct AdminAuth {
+    tokens: Arc<HashMap<String, AdminIdentity>>,
+}
+
+impl AdminAuth {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_token(mut self, token: impl Into<String>, identity: AdminIdentity) -> Self {
+        Arc::make_mut(&mut self.tokens).insert(token.into(), identity);
+        self
+    }
+
+    /// 解析令牌配置
+    ///
+    /// 格式为逗号分隔的 `{token}={operator}@{tenant}`，租户为 `*` 时可管理全部租户。
+    pub fn parse(spec: &str) -> Result<Self> {
+        let mut auth = Self::new();
+        for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (token, identity) = entry
+                .split_once('=')
+                .ok_or_else(|| anyhow!("invalid admin token entry, expected token=operator@tenant"))?;
+            let (operator, tenant) = identity
+                .rsplit_once('@')
+                .ok_or_else(|| anyhow!("admin token of {} has no tenant", identity))?;
+            if token.is_empty() || operator.is_empty() {
+                return Err(anyhow!("admin token entry of {} is incomplete", identity));
+            }
+            let tenant = match tenant {
+                ALL_TENANTS => None,
+                tenant => Some(
+                    TenantContext::new(tenant)?
+                        .tenant_id()
+                        .to_string(),
+                ),
+            };
+            auth = auth.with_token(
+                token,
+                AdminIdentity {
+                    operator: operator.to_string(),
+                    tenant,
+                },
+            );
+        }
+        Ok(auth)
+    }
+
+    /// 从环境变量 `ADMIN_TOKENS` 读取令牌，未配置时拒绝全部请求
+    pub fn from_env() -> Result<Self> {
+        Self::parse(&std::env::var("ADMIN_TOKENS").unwrap_or_default())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// 校验令牌与租户，作为拦截器使用
+    #[allow(clippy::result_large_err)]
+    pub fn check(&self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        let token = request
+            .metadata()
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or_else(|| Status::unauthenticated("missing admin token"))?;
+        let identity = self
+            .tokens
+            .get(token.trim())
+            .cloned()
+            .ok_or_else(|| Status::unauthenticated("invalid admin token"))?;
+
+        let tenant = TenantContext::from_request(&request)?;
+        if identity.tenant.as_deref().is_some_and(|t| t != tenant.tenant_id()) {
+            return Err(Status::permission_denied(format!(
+                "{} is not allowed to manage tenant {}",
+                identity.operator,
+                tenant.tenant_id()
+            )));
+        }
+        request.extensions_mut().insert(identity);
+        Ok(request)
+    }
+}
+
+/// 取出鉴权后的管理员身份，未经 [`AdminAuth::check`] 的请求返回 None
+pub fn admin_identity<T>(request: &Request<T>) -> Option<AdminIdentity> {
+    request.extensions().get::<AdminIdentity>().cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use common::tenant::TENANT_METADATA_KEY;
+
+    use super::*;
+
+    fn request(token: Option<&str>, tenant: Option<&str>) -> Request<()> {
+        let mut request = Request::new(());
+        if let Some(token) = token {
+            request
+                .metadata_mut()
+                .insert(AUTHORIZATION, format!("Bearer {}", token).parse().unwrap());
+        }
+        if let Some(tenant) = tenant {
+            request.metadata_mut().insert(TENANT_METADATA_KEY, tenant.parse().unwrap());
+        }
+        request
+    }
+
+    fn auth() -> AdminAuth {
+        AdminAuth::parse("t1=alice@acme, t2=root@*").unwrap()
+    }
+
+    #[test]
+    fn test_parse_tokens() {
+        let auth = auth();
+        assert_eq!(
+            auth.tokens.get("t1"),
+            Some(&AdminIdentity {
+                operator: "alice".to_string(),
+                tenant: Some("acme".to_string()),
+            })
+        );
+        assert_eq!(auth.tokens.get("t2").unwrap().tenant, None);
+        assert!(AdminAuth::parse("").unwrap().is_empty());
+        assert!(AdminAuth::parse("t1=alice").is_err());
+        assert!(AdminAuth::parse("t1=alice@Bad_Tenant").is_err());
+    }
+
+    #[test]
+    fn test_identity_attached_to_request() {
+        let request = auth().check(request(Some("t1"), Some("acme"))).unwrap();
+        assert_eq!(admin_identity(&request).unwrap().operator, "alice");
+    }
+
+    #[test]
+    fn test_missing_or_unknown_token_rejected() {
+        let status = auth().check(request(None, Some("acme"))).unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unauthenticated);
+        let status = auth().check(request(Some("nope"), Some("acme"))).unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unauthenticated);
+        assert!(AdminAuth::new().check(request(Some("t1"), None)).is_err());
+    }
+
+    #[test]
+    fn test_other_tenant_rejected() {
+        let status = auth().check(request(Some("t1"), Some("other"))).unwrap_err();
+        assert_eq!(status.code(), tonic::Code::PermissionDenied);
+        // 未携带租户头时为默认租户
+        assert!(auth().check(request(Some("t1"), None)).is_err());
+        assert!(auth().check(request(Some("t2"), Some("other"))).is_ok());
+    }
+}
@@ -0,0 +1,184 @@
+use std::sync::Arc;
+
+use common::error::AppError;
+use proto_crate::api::im::service::deadletter::{
+    dead_letter_service_server::DeadLetterService as DeadLetterApi, AuditRecord as ProtoAuditRecord,
+    DeadLetterEntry, GetDeadLetterRequest, GetDeadLetterResponse, ListAuditRecordsRequest,
+    ListAuditRecordsResponse, ListDeadLettersRequest, ListDeadLettersResponse, PurgeDeadLettersRequest,
+    PurgeDeadLettersResponse, ReplayDeadLettersRequest, ReplayDeadLettersResponse, ReplayResult,
+};
+use tonic::{Request, Response, Status};
+use tracing::instrument;
+
+use super::admin_auth::admin_identity;
+use crate::application::dead_letter::{DeadLetterService, PurgeRequest, ReplayRequest};
+use crate::domain::entities::{AuditRecord, DeadLetterQuery, DeadLetterRecord};
+
+/// 死信管理接口
+///
+/// 须挂在管理端口并以 [`AdminAuth`](super::admin_auth::AdminAuth) 鉴权，操作人取自鉴权身份。
+pub struct DeadLetterGrpcService {
+    dead_letters: Arc<DeadLetterService>,
+}
+
+impl DeadLetterGrpcService {
+    pub fn new(dead_letters: Arc<DeadLetterService>) -> Self {
+        Self { dead_letters }
+    }
+}
+
+fn to_status(err: anyhow::Error) -> Status {
+    AppError::from(err).into()
+}
+
+fn to_entry(record: DeadLetterRecord) -> DeadLetterEntry {
+    DeadLetterEntry {
+        message_id: record.message_id,
+        reason: record.reason,
+        dead_letter: Some(record.dead_letter),
+        dead_time: record.dead_time,
+        replay_count: record.replay_count,
+        last_replay_time: record.last_replay_time,
+    }
+}
+
+fn to_audit(record: AuditRecord) -> ProtoAuditRecord {
+    ProtoAuditRecord {
+        action: record.action.as_str().to_string(),
+        operator: record.operator,
+        comment: record.comment,
+        message_ids: record.message_ids,
+        set_options: record.set_options,
+        remove_options: record.remove_options,
+        succeeded: record.succeeded,
+        failed: record.failed,
+        time: record.time,
+    }
+}
+
+fn non_empty(value: String) -> Option<String> {
+    (!value.is_empty()).then_some(value)
+}
+
+fn positive(value: i64) -> Option<i64> {
+    (value > 0).then_some(value)
+}
+
+#[tonic::async_trait]
+impl DeadLetterApi for DeadLetterGrpcService {
+    #[instrument(skip_all)]
+    async fn list_dead_letters(
+        &self,
+        request: Request<ListDeadLettersRequest>,
+    ) -> Result<Response<ListDeadLettersResponse>, Status> {
+        let req = request.into_inner();
+        let query = DeadLetterQuery {
+            reason: non_empty(req.reason),
+            start_time: positive(req.start_time),
+            end_time: positive(req.end_time),
+            offset: req.offset.max(0) as usize,
+            limit: req.limit.max(0) as usize,
+        };
+
+        let (page, reasons) = self.dead_letters.list(query).await.map_err(to_status)?;
+        Ok(Response::new(ListDeadLettersResponse {
+            entries: page.entries.into_iter().map(to_entry).collect(),
+            total: page.total as i64,
+            reasons,
+            error: None,
+        }))
+    }
+
+    #[instrument(skip_all)]
+    async fn get_dead_letter(
+        &self,
+        request: Request<GetDeadLetterRequest>,
+    ) -> Result<Response<GetDeadLetterResponse>, Status> {
+        let req = request.into_inner();
+        let record = self.dead_letters.get(&req.message_id).await.map_err(to_status)?;
+        Ok(Response::new(GetDeadLetterResponse {
+            entry: Some(to_entry(record)),
+            error: None,
+        }))
+    }
+
+    #[instrument(skip_all)]
+    async fn replay_dead_letters(
+        &self,
+        request: Request<ReplayDeadLettersRequest>,
+    ) -> Result<Response<ReplayDeadLettersResponse>, Status> {
+        let operator = admin_identity(&request)
+            .ok_or_else(|| Status::unauthenticated("admin identity is missing"))?
+            .operator;
+        let req = request.into_inner();
+        let results = self
+            .dead_letters
+            .replay(ReplayRequest {
+                message_ids: req.message_ids,
+                set_options: req.set_options,
+                remove_options: req.remove_options,
+                operator,
+                comment: req.comment,
+                remove_after_replay: req.remove_after_replay,
+            })
+            .await
+            .map_err(to_status)?;
+
+        Ok(Response::new(ReplayDeadLettersResponse {
+            results: results
+                .into_iter()
+                .map(|(message_id, error)| ReplayResult {
+                    message_id,
+                    success: error.is_none(),
+                    error: error.map(|e| e.to_proto()),
+                })
+                .collect(),
+            error: None,
+        }))
+    }
+
+    #[instrument(skip_all)]
+    async fn purge_dead_letters(
+        &self,
+        request: Request<PurgeDeadLettersRequest>,
+    ) -> Result<Response<PurgeDeadLettersResponse>, Status> {
+        let operator = admin_identity(&request)
+            .ok_or_else(|| Status::unauthenticated("admin identity is missing"))?
+            .operator;
+        let req = request.into_inner();
+        let purged = self
+            .dead_letters
+            .purge(PurgeRequest {
+                message_ids: req.message_ids,
+                before_time: positive(req.before_time),
+                reason: non_empty(req.reason),
+                operator,
+                comment: req.comment,
+            })
+            .await
+            .map_err(to_status)?;
+
+        Ok(Response::new(PurgeDeadLettersResponse {
+            purged: purged as i64,
+            error: None,
+        }))
+    }
+
+    #[instrument(skip_all)]
+    async fn list_audit_records(
+        &self,
+        request: Request<ListAuditRecordsRequest>,
+    ) -> Result<Response<ListAuditRecordsResponse>, Status> {
+        let req = request.into_inner();
+        let records = self
+            .dead_letters
+            .audit_records(req.offset.max(0) as usize, req.limit.max(0) as usize)
+            .await
+            .map_err(to_status)?;
+
+        Ok(Response::new(ListAuditRecordsResponse {
+            records: records.into_iter().map(to_audit).collect(),
+            error: None,
+        }))
+    }
+}
@@ -1,16 +1,44 @@
 use crate::application::message_router::MessageRouterService;
 use proto_crate::api::im::service::router::{
     message_router_server::MessageRouter,
+    CancelScheduledMessageRequest, CancelScheduledMessageResponse,
     DistributeMessagesRequest, DistributeMessagesResponse,
     DistributeResult, FilterMessagesRequest,
     FilterMessagesResponse, FilterResult,
     HandleMessagesPriorityRequest, HandleMessagesPriorityResponse,
-    PriorityResult, RouteUpstreamMessagesRequest, RouteUpstreamMessagesResponse, RouteUpstreamResult,
+    PriorityResult, PullGroupMessagesRequest, PullGroupMessagesResponse,
+    ReportMessagesReadRequest, ReportMessagesReadResponse,
+    RouteUpstreamMessagesRequest, RouteUpstreamMessagesResponse, RouteUpstreamResult,
 };
 use std::collections::HashMap;
 use std::sync::Arc;
 use tonic::{Request, Response, Status};
-use proto_crate::api::im::common::Error;
+use tracing::instrument;
+use common::error::AppError;
+use common::utils::msg_utils::DEVICE_ID_OPTION;
+use proto_crate::api::im::common::MessageData;
+use crate::domain::entities::PreProcessCode;
+
+/// 上行调用方(网关)按已鉴权连接写入的发送设备
+pub const DEVICE_METADATA_KEY: &str = "x-device-id";
+
+/// 调用方元数据中的发送设备
+fn sender_device<T>(request: &Request<T>) -> Option<String> {
+    request
+        .metadata()
+        .get(DEVICE_METADATA_KEY)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+}
+
+/// 以调用方元数据中的发送设备替换消息中客户端填写的设备ID
+fn stamp_sender_device(message: &mut MessageData, device_id: Option<&str>) {
+    message.options.remove(DEVICE_ID_OPTION);
+    if let Some(device_id) = device_id {
+        message.options.insert(DEVICE_ID_OPTION.to_string(), device_id.to_string());
+    }
+}
 
 pub struct MessageRouterGrpcService {
     message_router: Arc<MessageRouterService>,
@@ -24,33 +52,39 @@ impl MessageRouterGrpcService {
 
 #[tonic::async_trait]
 impl MessageRouter for MessageRouterGrpcService {
+    #[instrument(skip_all)]
     async fn filter_messages(
         &self,
         request: Request<FilterMessagesRequest>,
     ) -> Result<Response<FilterMessagesResponse>, Status> {
+        let device_id = sender_device(&request);
         let req = request.into_inner();
         let mut results = Vec::new();
 
         for filter_message in req.messages {
-            let proto_msg = filter_message.message.as_ref()
+            let proto_msg = filter_message.message
                 .ok_or_else(|| Status::invalid_argument("message is required"))?;
+            let proto_msg = &mut self.message_router.assign_server_msg_id(proto_msg).await
+                .map_err(|e| Status::from(AppError::from(e)))?;
+            stamp_sender_device(proto_msg, device_id.as_deref());
             
             let pre_process_code = self.message_router.pre_process(proto_msg).await
-                .map_err(|e| Status::internal(e.to_string()))?;
+                .map_err(|e| Status::from(AppError::from(e)))?;
+            // 只做检查，不占用发送配额
+            if pre_process_code == PreProcessCode::Ok {
+                self.message_router.release_quota(proto_msg).await;
+            }
 
             results.push(FilterResult {
                 message_id: proto_msg.server_msg_id.clone(),
-                passed: pre_process_code == 0,
+                passed: pre_process_code == PreProcessCode::Ok,
                 filter_results: HashMap::new(),
-                error: if pre_process_code != 0 {
-                    Some(Error {
-                        code: pre_process_code,
-                        message: format!("Message filter failed with code: {}", pre_process_code),
-                        details: "".to_string(),
-                    })
+                error: if pre_process_code != PreProcessCode::Ok {
+                    Some(AppError::from(pre_process_code).to_proto())
                 } else {
                     None
                 },
+                message: Some(proto_msg.clone()),
             });
         }
 
@@ -60,28 +94,29 @@ impl MessageRouter for MessageRouterGrpcService {
         }))
     }
 
+    #[instrument(skip_all)]
     async fn route_upstream_messages(
         &self,
         request: Request<RouteUpstreamMessagesRequest>,
     ) -> Result<Response<RouteUpstreamMessagesResponse>, Status> {
+        let device_id = sender_device(&request);
         let req = request.into_inner();
         let mut results = Vec::new();
 
         for upstream_message in req.messages {
-            let proto_msg = upstream_message.message.as_ref()
+            let proto_msg = upstream_message.message
                 .ok_or_else(|| Status::invalid_argument("message is required"))?;
+            let proto_msg = &mut self.message_router.assign_server_msg_id(proto_msg).await
+                .map_err(|e| Status::from(AppError::from(e)))?;
+            stamp_sender_device(proto_msg, device_id.as_deref());
             
-            let (success, error, routes) = self.message_router.route_message(proto_msg).await
-                .map_err(|e| Status::internal(e.to_string()))?;
+            let (message_id, success, error) = self.message_router.route_upstream(proto_msg).await
+                .map_err(|e| Status::from(AppError::from(e)))?;
 
             results.push(RouteUpstreamResult {
-                message_id: proto_msg.server_msg_id.clone(),
+                message_id,
                 success,
-                error: error.map(|e|Error {
-                    code: 0,
-                    message: e,
-                    details: "".to_string(),
-                }),
+                error: error.map(|e| e.to_proto()),
             });
         }
 
@@ -91,6 +126,7 @@ impl MessageRouter for MessageRouterGrpcService {
         }))
     }
 
+    #[instrument(skip_all)]
     async fn distribute_messages(
         &self,
         request: Request<DistributeMessagesRequest>,
@@ -100,13 +136,14 @@ impl MessageRouter for MessageRouterGrpcService {
 
         for distribute_message in req.messages {
             let proto_msg = distribute_message.message
-                .ok_or_else(|| Status::invalid_argument("message is required"))?
-                .clone();
+                .ok_or_else(|| Status::invalid_argument("message is required"))?;
+            let proto_msg = self.message_router.ensure_server_msg_id(proto_msg).await
+                .map_err(|e| Status::from(AppError::from(e)))?;
             messages.push(proto_msg);
         }
 
         let results_map = self.message_router.process_messages(messages).await
-            .map_err(|e| Status::internal(e.to_string()))?;
+            .map_err(|e| Status::from(AppError::from(e)))?;
 
         let results = results_map.into_iter()
             .map(|(message_id, (success, error, routes))| {
@@ -118,11 +155,7 @@ impl MessageRouter for MessageRouterGrpcService {
                 DistributeResult {
                     message_id,
                     distribution_results,
-                    error: error.map(|e| Error {
-                        code: 0,
-                        message: e,
-                        details: "".to_string(),
-                    }),
+                    error: error.map(|e| e.to_proto()),
                 }
             })
             .collect();
@@ -133,32 +166,130 @@ impl MessageRouter for MessageRouterGrpcService {
         }))
     }
 
+    #[instrument(skip_all)]
     async fn handle_messages_priority(
         &self,
         request: Request<HandleMessagesPriorityRequest>,
     ) -> Result<Response<HandleMessagesPriorityResponse>, Status> {
         let req = request.into_inner();
-        let mut results = Vec::new();
+        let mut messages = Vec::new();
+        let mut priorities = Vec::new();
 
         for priority_message in req.messages {
-            let proto_msg = priority_message.message.as_ref()
+            let proto_msg = priority_message.message
                 .ok_or_else(|| Status::invalid_argument("message is required"))?;
-            
-            // 默认优先级处理
-            let priority = proto_msg.options.get("priority")
-                .and_then(|v| v.parse::<i32>().ok())
-                .unwrap_or(0);
+            let mut proto_msg = self.message_router.ensure_server_msg_id(proto_msg).await
+                .map_err(|e| Status::from(AppError::from(e)))?;
 
-            results.push(PriorityResult {
-                message_id: proto_msg.server_msg_id.clone(),
-                priority,
-                error: None,
-            });
+            // 请求携带的规则与服务配置的规则同时生效，写入的优先级决定分发通道
+            let rules = priority_message.priority_rules.unwrap_or_default();
+            let priority = self.message_router.prioritize(&mut proto_msg, &rules);
+            priorities.push((proto_msg.server_msg_id.clone(), priority));
+            messages.push(proto_msg);
         }
 
+        let mut results_map = self.message_router.process_messages(messages).await
+            .map_err(|e| Status::from(AppError::from(e)))?;
+
+        let results = priorities.into_iter()
+            .map(|(message_id, priority)| {
+                let error = results_map.remove(&message_id).and_then(|(_, error, _)| error);
+                PriorityResult {
+                    message_id,
+                    priority: priority as i32,
+                    error: error.map(|e| e.to_proto()),
+                }
+            })
+            .collect();
+
         Ok(Response::new(HandleMessagesPriorityResponse {
             results,
             error: None,
         }))
     }
-} 
\ No newline at end of file
+
+    #[instrument(skip_all)]
+    async fn pull_group_messages(
+        &self,
+        request: Request<PullGroupMessagesRequest>,
+    ) -> Result<Response<PullGroupMessagesResponse>, Status> {
+        let req = request.into_inner();
+        if req.group_id.is_empty() || req.user_id.is_empty() {
+            return Err(Status::invalid_argument("group_id and user_id are required"));
+        }
+
+        let page = self.message_router
+            .pull_group_messages(&req.group_id, &req.user_id, req.after_seq, req.limit.max(0) as usize)
+            .await
+            .map_err(|e| Status::from(AppError::from(e)))?;
+
+        Ok(Response::new(PullGroupMessagesResponse {
+            messages: page.messages,
+            latest_seq: page.latest_seq,
+            has_more: page.has_more,
+            error: None,
+            min_seq: page.min_seq,
+        }))
+    }
+
+    #[instrument(skip_all)]
+    async fn cancel_scheduled_message(
+        &self,
+        request: Request<CancelScheduledMessageRequest>,
+    ) -> Result<Response<CancelScheduledMessageResponse>, Status> {
+        let req = request.into_inner();
+        if req.message_id.is_empty() || req.user_id.is_empty() {
+            return Err(Status::invalid_argument("message_id and user_id are required"));
+        }
+
+        self.message_router
+            .cancel_scheduled(&req.user_id, &req.message_id)
+            .await
+            .map_err(|e| Status::from(AppError::from(e)))?;
+
+        Ok(Response::new(CancelScheduledMessageResponse { error: None }))
+    }
+
+    #[instrument(skip_all)]
+    async fn report_messages_read(
+        &self,
+        request: Request<ReportMessagesReadRequest>,
+    ) -> Result<Response<ReportMessagesReadResponse>, Status> {
+        let req = request.into_inner();
+        if req.user_id.is_empty() {
+            return Err(Status::invalid_argument("user_id is required"));
+        }
+
+        let armed = self.message_router
+            .report_read(&req.user_id, &req.message_ids)
+            .await
+            .map_err(|e| Status::from(AppError::from(e)))?;
+
+        Ok(Response::new(ReportMessagesReadResponse {
+            armed_count: armed as i32,
+            error: None,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sender_device_replaces_client_value() {
+        let mut request = Request::new(());
+        assert_eq!(sender_device(&request), None);
+        request.metadata_mut().insert(DEVICE_METADATA_KEY, "ios-1".parse().unwrap());
+        let device_id = sender_device(&request);
+
+        let mut message = MessageData::default();
+        message.options.insert(DEVICE_ID_OPTION.to_string(), "forged".to_string());
+        stamp_sender_device(&mut message, device_id.as_deref());
+        assert_eq!(message.options.get(DEVICE_ID_OPTION).map(String::as_str), Some("ios-1"));
+
+        // 调用方未提供设备时不保留客户端填写的值
+        stamp_sender_device(&mut message, None);
+        assert!(!message.options.contains_key(DEVICE_ID_OPTION));
+    }
+}
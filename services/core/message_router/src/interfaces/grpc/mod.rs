@@ -1 +1,4 @@
-pub mod message_router_service;
\ No newline at end of file
+pub mod admin_auth;
+pub mod dead_letter_service;
+pub mod message_router_service;
+pub mod review_service;
\ No newline at end of file
@@ -0,0 +1,49 @@
+use std::sync::Arc;
+
+use common::error::AppError;
+use log::info;
+use proto_crate::api::im::service::router::{
+    message_review_server::MessageReview, ResolveReviewRequest, ResolveReviewResponse,
+};
+use tonic::{Request, Response, Status};
+use tracing::instrument;
+
+use super::admin_auth::admin_identity;
+use crate::application::message_router::MessageRouterService;
+
+/// 人工审核接口
+///
+/// 须挂在管理端口并以 [`AdminAuth`](super::admin_auth::AdminAuth) 鉴权。
+pub struct MessageReviewGrpcService {
+    message_router: Arc<MessageRouterService>,
+}
+
+impl MessageReviewGrpcService {
+    pub fn new(message_router: Arc<MessageRouterService>) -> Self {
+        Self { message_router }
+    }
+}
+
+#[tonic::async_trait]
+impl MessageReview for MessageReviewGrpcService {
+    #[instrument(skip_all)]
+    async fn resolve_review(
+        &self,
+        request: Request<ResolveReviewRequest>,
+    ) -> Result<Response<ResolveReviewResponse>, Status> {
+        let operator = admin_identity(&request)
+            .ok_or_else(|| Status::unauthenticated("admin identity is missing"))?
+            .operator;
+        let req = request.into_inner();
+        if req.message_id.is_empty() {
+            return Err(Status::invalid_argument("message_id is required"));
+        }
+
+        self.message_router
+            .resolve_review(&req.message_id, req.approved)
+            .await
+            .map_err(|e| Status::from(AppError::from(e)))?;
+        info!("Review of message {} resolved by {}, approved: {}", req.message_id, operator, req.approved);
+        Ok(Response::new(ResolveReviewResponse { error: None }))
+    }
+}
@@ -4,6 +4,8 @@ use crate::domain::{
     services::message_service::MessageService,
 };
 use chrono::Utc;
+use common::error::AppError;
+use proto_crate::api::im::common::ErrorCode;
 use uuid::Uuid;
 
 pub struct MessageManager<R: MessageRepository, S: MessageService> {
@@ -55,6 +57,14 @@ impl<R: MessageRepository, S: MessageService> MessageManager<R, S> {
         Ok(processed_messages)
     }
 
+    // 获取单条消息，内容还原为写入时的形式
+    pub async fn get_message(&self, message_id: &str) -> Result<Option<Message>, Error> {
+        match self.message_repository.get_by_id(message_id).await? {
+            Some(message) => Ok(Some(self.message_service.restore_message(message).await?)),
+            None => Ok(None),
+        }
+    }
+
     // 查询消息历史
     pub async fn query_history(&self, query: MessageQuery) -> Result<MessageBatch, Error> {
         self.message_service.query_messages(query).await
@@ -63,25 +73,30 @@ impl<R: MessageRepository, S: MessageService> MessageManager<R, S> {
 
     // 获取会话消息
     pub async fn get_session_messages(&self, session_id: &str, limit: u32, offset: u32) -> Result<MessageBatch, Error> {
-        self.message_repository.get_session_messages(session_id, limit, offset).await
-            .map_err(Error::from)
+        let mut batch = self.message_repository.get_session_messages(session_id, limit, offset).await?;
+        let mut messages = Vec::with_capacity(batch.messages.len());
+        for message in batch.messages {
+            messages.push(self.message_service.restore_message(message).await?);
+        }
+        batch.messages = messages;
+        Ok(batch)
     }
 
     // 标记消息已读
     pub async fn mark_messages_read(&self, message_ids: Vec<String>) -> Result<(), Error> {
-        self.message_service.mark_as_read(message_ids).await
+        self.message_repository.batch_update_status(message_ids, MessageStatus::Read).await
             .map_err(Error::from)
     }
 
     // 标记消息已送达
     pub async fn mark_messages_delivered(&self, message_ids: Vec<String>) -> Result<(), Error> {
-        self.message_service.mark_as_delivered(message_ids).await
+        self.message_repository.batch_update_status(message_ids, MessageStatus::Delivered).await
             .map_err(Error::from)
     }
 
     // 删除消息
     pub async fn delete_messages(&self, message_ids: Vec<String>) -> Result<(), Error> {
-        self.message_service.delete_messages(message_ids).await
+        self.message_repository.batch_delete(message_ids).await
             .map_err(Error::from)
     }
 
@@ -116,4 +131,19 @@ pub enum Error {
     
     #[error("Validation error: {0}")]
     ValidationError(String),
-} 
\ No newline at end of file
+}
+
+impl From<Error> for AppError {
+    fn from(err: Error) -> Self {
+        use crate::domain::repositories::message_repository::Error as RepositoryError;
+
+        let code = match &err {
+            Error::Repository(RepositoryError::NotFound(_)) => ErrorCode::NotFound,
+            Error::Repository(RepositoryError::InvalidData(_)) | Error::ValidationError(_) => {
+                ErrorCode::InvalidParams
+            }
+            _ => ErrorCode::MessageStoreFailed,
+        };
+        AppError::new(code, err.to_string())
+    }
+}
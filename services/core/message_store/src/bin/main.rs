@@ -0,0 +1,158 @@
+use anyhow::Result;
+use flare_core::logs::Logger;
+use log::info;
+use mongodb::bson::doc;
+use std::sync::Arc;
+use message_store::{
+    application::message_manager::MessageManager,
+    infrastructure::{
+        messaging::message_event_producer::MessageEventProducer,
+        repositories::mongodb_message_repository::MongoDBMessageRepository,
+        services::message_service_impl::MessageServiceImpl,
+    },
+    interfaces::grpc::message_service::MessageGrpcService,
+};
+use proto_crate::api::im::service::store::message_store_server::MessageStoreServer;
+use flare_rpc_core::{
+    discover::consul::{ConsulConfig, ConsulRegistry},
+    AppBuilder,
+};
+use std::time::Duration;
+use common::bus::{InMemoryBus, KafkaBus, MessageBus};
+use common::health::{ConsulHealthRegistrar, FnHealthCheck, HealthRegistry, KafkaProducerHealthCheck, RedisHealthCheck};
+use common::id::{RedisWorkerLease, WorkerLeaseConfig};
+use common::rpc::ServiceNames;
+use common::tenant::TenantLayer;
+use common::telemetry::{init_telemetry, server_interceptor, TelemetryConfig, TraceContextLayer};
+use common::topic::{KafkaTopics, TopicRegistry};
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    // 初始化日志
+    Logger::init("message_store", "debug")?;
+
+    // 初始化链路追踪
+    let _telemetry = init_telemetry(&TelemetryConfig::from_env(ServiceNames::MESSAGE_STORE))?;
+    info!("Starting Message Store Service...");
+
+    // 消息存储
+    let database_url = std::env::var("MONGODB_URL")
+        .unwrap_or_else(|_| "mongodb://localhost:27017".to_string());
+    let repository = MongoDBMessageRepository::new(&database_url).await?;
+    let mongo = mongodb::Client::with_uri_str(&database_url).await?;
+
+    // 消息总线：MESSAGE_BUS=memory 时使用进程内总线，用于不依赖 Kafka 的单机开发
+    let (message_bus, kafka_bus): (Arc<dyn MessageBus>, Option<Arc<KafkaBus>>) =
+        match std::env::var("MESSAGE_BUS").as_deref() {
+            Ok("memory") => {
+                info!("Using in-memory message bus");
+                (Arc::new(InMemoryBus::with_registry(&TopicRegistry::from_env())), None)
+            }
+            _ => {
+                let kafka_brokers = std::env::var("KAFKA_BROKERS").unwrap_or_else(|_| "localhost:9092".to_string());
+                let bus = Arc::new(KafkaBus::new(&kafka_brokers, ServiceNames::MESSAGE_STORE)?);
+                (bus.clone(), Some(bus))
+            }
+        };
+
+    // 申请 snowflake worker 租约，为未携带 server_msg_id 的消息分配ID
+    let redis_url = std::env::var("REDIS_URL").unwrap_or_else(|_| "redis://127.0.0.1:6379/".to_string());
+    let redis_conn = redis::aio::ConnectionManager::new(redis::Client::open(redis_url)?).await?;
+    let worker_lease = RedisWorkerLease::acquire(redis_conn.clone(), WorkerLeaseConfig {
+        service_name: ServiceNames::MESSAGE_STORE.to_string(),
+        ..Default::default()
+    }).await?;
+    let id_generator = Arc::new(worker_lease.generator()?);
+
+    // 健康检查
+    let mut health = HealthRegistry::new(ServiceNames::MESSAGE_STORE)
+        .with_check(Arc::new(RedisHealthCheck::new(redis_conn)))
+        .with_check(Arc::new(FnHealthCheck::new("mongodb", move || {
+            let mongo = mongo.clone();
+            Box::pin(async move {
+                mongo
+                    .database("admin")
+                    .run_command(doc! { "ping": 1 })
+                    .await
+                    .map(|_| ())
+                    .map_err(|e| e.to_string())
+            })
+        })));
+    if let Some(bus) = &kafka_bus {
+        health = health.with_check(Arc::new(KafkaProducerHealthCheck::new(bus.kafka_producer())));
+    }
+    let health = Arc::new(health);
+    let health_addr = std::env::var("HEALTH_ADDR").unwrap_or_else(|_| "127.0.0.1:9053".to_string());
+    let health_service = health.start(&health_addr, Duration::from_secs(5)).await?;
+
+    // 创建存储服务组件
+    let event_producer = MessageEventProducer::new(message_bus.producer(), KafkaTopics::MESSAGE_STORED);
+    let message_service = MessageServiceImpl::new(event_producer);
+    let message_manager = MessageManager::new(repository, message_service);
+
+    // 创建 gRPC 服务
+    let grpc_service = MessageGrpcService::new(message_manager, id_generator);
+
+    // 配置 Consul
+    let consul_host = std::env::var("CONSUL_HOST").unwrap_or_else(|_| "127.0.0.1".to_string());
+    let consul_port = std::env::var("CONSUL_PORT")
+        .unwrap_or_else(|_| "8500".to_string())
+        .parse::<u16>()?;
+    let consul_addr = format!("{}:{}", consul_host, consul_port).parse()?;
+
+    let consul_config = ConsulConfig {
+        addr: consul_addr,
+        timeout: Duration::from_secs(3),
+        protocol: "http".to_string(),
+        token: None,
+    };
+
+    // 创建服务注册器
+    let registry = ConsulRegistry::new(
+        consul_config,
+        Duration::from_secs(30), // 注册间隔
+    ).await?;
+
+    // 配置服务
+    let service_host = std::env::var("SERVICE_HOST").unwrap_or_else(|_| "127.0.0.1".to_string());
+    let service_port = std::env::var("SERVICE_PORT")
+        .unwrap_or_else(|_| "50053".to_string())
+        .parse::<u16>()?;
+    let addr = format!("{}:{}", service_host, service_port).parse()?;
+
+    // 就绪检查失败时由 Consul 将实例摘除
+    let consul_health = ConsulHealthRegistrar::new(&common::config::ConsulConfig {
+        host: consul_host.clone(),
+        port: consul_port,
+        register_interval: 10,
+        heartbeat_interval: 5,
+    });
+    consul_health.spawn_register(ServiceNames::MESSAGE_STORE, addr, format!("http://{}/readyz", health_addr));
+
+    // 以 ServiceNames::MESSAGE_STORE 注册，路由服务按该名称发现实例
+    let app = AppBuilder::new(ServiceNames::MESSAGE_STORE)
+        .version("1.0.0")
+        .weight(1)
+        .tag("prod")
+        .meta("type", "store")
+        .register(registry)
+        .build();
+
+    info!("Message Store Service listening on {}", addr);
+
+    // 运行服务
+    app.run(service_host.as_str(), service_port, |mut server, addr| async move {
+        server
+            .layer(TenantLayer)
+            .layer(TraceContextLayer)
+            .add_service(health_service)
+            .add_service(MessageStoreServer::with_interceptor(grpc_service, server_interceptor))
+            .serve(addr)
+            .await
+            .map_err(|e| e.into())
+    })
+    .await?;
+
+    worker_lease.release().await?;
+    Ok(())
+}
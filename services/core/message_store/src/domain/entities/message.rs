@@ -1,13 +1,15 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use uuid::Uuid;
+use common::id::SnowflakeId;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Message {
-    pub id: Uuid,
+    /// 服务端消息ID(server_msg_id)，同时作为 Mongo `_id`
+    pub id: SnowflakeId,
     pub session_id: String,
     pub sender_id: String,
     pub content_type: String,
+    /// 消息内容，gRPC 写入时为完整 `MessageData` 的 protobuf 编码再经 base64 转为文本
     pub content: String,
     pub status: MessageStatus,
     pub metadata: MessageMetadata,
@@ -29,7 +31,7 @@ pub enum MessageStatus {
 pub struct MessageMetadata {
     pub device_id: String,
     pub client_msg_id: String,
-    pub reply_to: Option<Uuid>,
+    pub reply_to: Option<SnowflakeId>,
     pub mentions: Vec<String>,
     pub is_encrypted: bool,
     pub compression: Option<String>,
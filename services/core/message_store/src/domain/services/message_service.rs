@@ -6,6 +6,8 @@ pub trait MessageService {
     // 消息处理
     async fn process_message(&self, message: Message) -> Result<Message, Error>;
     async fn batch_process_messages(&self, messages: Vec<Message>) -> Result<Vec<Message>, Error>;
+    // 还原 process_message 的处理(解压等)，读取时使用
+    async fn restore_message(&self, message: Message) -> Result<Message, Error>;
     
     // 消息分发
     async fn dispatch_message(&self, message: Message) -> Result<(), Error>;
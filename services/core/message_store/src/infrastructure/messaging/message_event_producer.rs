@@ -1,29 +1,25 @@
-use rdkafka::producer::{FutureProducer, FutureRecord};
-use rdkafka::ClientConfig;
+use std::sync::Arc;
 use serde_json::json;
+use common::bus::{MessageProducer, OutgoingMessage};
+use common::tenant;
 use crate::domain::entities::message::Message;
-use std::time::Duration;
 
-pub struct KafkaMessageProducer {
-    producer: FutureProducer,
+/// 消息事件生产者，通过消息总线发布已存储的消息
+pub struct MessageEventProducer {
+    producer: Arc<dyn MessageProducer>,
     topic: String,
 }
 
-impl KafkaMessageProducer {
-    pub fn new(brokers: &str, topic: &str) -> Result<Self, rdkafka::error::KafkaError> {
-        let producer: FutureProducer = ClientConfig::new()
-            .set("bootstrap.servers", brokers)
-            .set("message.timeout.ms", "5000")
-            .create()?;
-
-        Ok(Self {
+impl MessageEventProducer {
+    pub fn new(producer: Arc<dyn MessageProducer>, topic: &str) -> Self {
+        Self {
             producer,
             topic: topic.to_string(),
-        })
+        }
     }
 
     pub async fn send_message(&self, message: &Message) -> Result<(), Error> {
-        let payload = serde_json::to_string(&json!({
+        let payload = serde_json::to_vec(&json!({
             "id": message.id.to_string(),
             "session_id": message.session_id,
             "sender_id": message.sender_id,
@@ -35,13 +31,13 @@ impl KafkaMessageProducer {
             "updated_at": message.updated_at,
         })).map_err(|e| Error::Serialization(e.to_string()))?;
 
-        let record = FutureRecord::to(&self.topic)
-            .key(&message.id.to_string())
-            .payload(&payload);
+        let record = OutgoingMessage::new(payload)
+            .key(tenant::current().kafka_key(&message.id.to_string()))
+            .with_current_context();
 
-        self.producer.send(record, Duration::from_secs(5))
+        self.producer.send(&self.topic, record)
             .await
-            .map_err(|(e, _)| Error::Producer(e.to_string()))?;
+            .map_err(|e| Error::Producer(e.to_string()))?;
 
         Ok(())
     }
@@ -56,9 +52,9 @@ impl KafkaMessageProducer {
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
-    #[error("Kafka producer error: {0}")]
+    #[error("Message bus producer error: {0}")]
     Producer(String),
 
     #[error("Serialization error: {0}")]
     Serialization(String),
-} 
\ No newline at end of file
+}
@@ -0,0 +1,3 @@
+pub mod messaging;
+pub mod repositories;
+pub mod services;
@@ -0,0 +1 @@
+pub mod mongodb_message_repository;
@@ -1,6 +1,6 @@
 use async_trait::async_trait;
 use mongodb::{
-    bson::{doc, Document, DateTime, Uuid as BsonUuid},
+    bson::{doc, Document, DateTime},
     Collection, Database,
     options::{ClientOptions, FindOptions, UpdateOptions},
 };
@@ -9,11 +9,18 @@ use crate::domain::{
     repositories::message_repository::{MessageRepository, Error as RepoError},
 };
 use chrono::Utc;
-use uuid::Uuid;
+use common::id::SnowflakeId;
 use futures::StreamExt;
+use common::tenant;
+use std::collections::HashSet;
+use tokio::sync::Mutex;
+
+const MESSAGE_COLLECTION: &str = "messages";
 
 pub struct MongoDBMessageRepository {
-    collection: Collection<Document>,
+    db: Database,
+    // 已创建索引的租户集合
+    indexed: Mutex<HashSet<String>>,
 }
 
 impl MongoDBMessageRepository {
@@ -26,24 +33,52 @@ impl MongoDBMessageRepository {
             .map_err(|e| RepoError::Repository(e.to_string()))?;
         
         let db = client.database("flare_im");
-        let collection = db.collection("messages");
+        let repo = Self {
+            db,
+            indexed: Mutex::new(HashSet::new()),
+        };
 
-        // 创建索引
-        collection.create_index(
-            doc! {
-                "session_id": 1,
-                "created_at": -1
-            },
-            None
-        ).await.map_err(|e| RepoError::Repository(e.to_string()))?;
+        // 创建默认租户集合索引
+        repo.collection().await?;
+
+        Ok(repo)
+    }
+
+    // 当前租户的消息集合，每个租户一个集合，首次访问时创建索引
+    async fn collection(&self) -> Result<Collection<Document>, RepoError> {
+        let name = tenant::current().collection_name(MESSAGE_COLLECTION);
+        let collection = self.db.collection(&name);
+
+        if !self.indexed.lock().await.contains(&name) {
+            collection.create_index(
+                doc! {
+                    "session_id": 1,
+                    "created_at": -1
+                },
+                None
+            ).await.map_err(|e| RepoError::Repository(e.to_string()))?;
+            self.indexed.lock().await.insert(name);
+        }
+
+        Ok(collection)
+    }
+
+    // 消息ID为 snowflake ID，`_id` 统一存为定长字符串
+    fn parse_id(message_id: &str) -> Result<SnowflakeId, RepoError> {
+        SnowflakeId::parse(message_id)
+            .ok_or_else(|| RepoError::InvalidData(format!("invalid message id: {}", message_id)))
+    }
 
-        Ok(Self { collection })
+    fn document_ids(message_ids: &[String]) -> Result<Vec<String>, RepoError> {
+        message_ids.iter()
+            .map(|id| Self::parse_id(id).map(|id| id.to_sortable_string()))
+            .collect()
     }
 
     // 将 Message 转换为 BSON Document
     fn to_document(message: &Message) -> Document {
         doc! {
-            "_id": BsonUuid::from_uuid_v4(message.id),
+            "_id": message.id.to_sortable_string(),
             "session_id": &message.session_id,
             "sender_id": &message.sender_id,
             "content_type": &message.content_type,
@@ -59,7 +94,7 @@ impl MongoDBMessageRepository {
             "metadata": {
                 "device_id": &message.metadata.device_id,
                 "client_msg_id": &message.metadata.client_msg_id,
-                "reply_to": message.metadata.reply_to.map(|id| BsonUuid::from_uuid_v4(id)),
+                "reply_to": message.metadata.reply_to.map(|id| id.to_sortable_string()),
                 "mentions": &message.metadata.mentions,
                 "is_encrypted": message.metadata.is_encrypted,
                 "compression": &message.metadata.compression,
@@ -73,9 +108,8 @@ impl MongoDBMessageRepository {
     // 将 BSON Document 转换为 Message
     fn from_document(doc: Document) -> Result<Message, RepoError> {
         Ok(Message {
-            id: doc.get_uuid("_id")
-                .map_err(|e| RepoError::InvalidData(e.to_string()))?
-                .into(),
+            id: Self::parse_id(doc.get_str("_id")
+                .map_err(|e| RepoError::InvalidData(e.to_string()))?)?,
             session_id: doc.get_str("session_id")
                 .map_err(|e| RepoError::InvalidData(e.to_string()))?
                 .to_string(),
@@ -107,7 +141,7 @@ impl MongoDBMessageRepository {
                     client_msg_id: metadata.get_str("client_msg_id")
                         .map_err(|e| RepoError::InvalidData(e.to_string()))?
                         .to_string(),
-                    reply_to: metadata.get_uuid("reply_to").ok().map(|id| id.into()),
+                    reply_to: metadata.get_str("reply_to").ok().and_then(SnowflakeId::parse),
                     mentions: metadata.get_array("mentions")
                         .map_err(|e| RepoError::InvalidData(e.to_string()))?
                         .iter()
@@ -138,19 +172,18 @@ impl MongoDBMessageRepository {
 impl MessageRepository for MongoDBMessageRepository {
     async fn save(&self, message: Message) -> Result<(), RepoError> {
         let doc = Self::to_document(&message);
-        self.collection.insert_one(doc, None)
+        self.collection().await?.insert_one(doc, None)
             .await
             .map_err(|e| RepoError::Repository(e.to_string()))?;
         Ok(())
     }
 
     async fn get_by_id(&self, message_id: &str) -> Result<Option<Message>, RepoError> {
-        let id = Uuid::parse_str(message_id)
-            .map_err(|e| RepoError::InvalidData(e.to_string()))?;
+        let id = Self::parse_id(message_id)?;
         
-        let filter = doc! { "_id": BsonUuid::from_uuid_v4(id) };
+        let filter = doc! { "_id": id.to_sortable_string() };
         
-        if let Some(doc) = self.collection.find_one(filter, None)
+        if let Some(doc) = self.collection().await?.find_one(filter, None)
             .await
             .map_err(|e| RepoError::Repository(e.to_string()))? {
             Ok(Some(Self::from_document(doc)?))
@@ -160,12 +193,11 @@ impl MessageRepository for MongoDBMessageRepository {
     }
 
     async fn delete(&self, message_id: &str) -> Result<(), RepoError> {
-        let id = Uuid::parse_str(message_id)
-            .map_err(|e| RepoError::InvalidData(e.to_string()))?;
+        let id = Self::parse_id(message_id)?;
         
-        let filter = doc! { "_id": BsonUuid::from_uuid_v4(id) };
+        let filter = doc! { "_id": id.to_sortable_string() };
         
-        self.collection.delete_one(filter, None)
+        self.collection().await?.delete_one(filter, None)
             .await
             .map_err(|e| RepoError::Repository(e.to_string()))?;
         
@@ -177,7 +209,7 @@ impl MessageRepository for MongoDBMessageRepository {
             .map(Self::to_document)
             .collect();
         
-        self.collection.insert_many(docs, None)
+        self.collection().await?.insert_many(docs, None)
             .await
             .map_err(|e| RepoError::Repository(e.to_string()))?;
         
@@ -185,13 +217,11 @@ impl MessageRepository for MongoDBMessageRepository {
     }
 
     async fn batch_delete(&self, message_ids: Vec<String>) -> Result<(), RepoError> {
-        let uuids: Result<Vec<BsonUuid>, _> = message_ids.iter()
-            .map(|id| Uuid::parse_str(id).map(BsonUuid::from_uuid_v4))
-            .collect();
+        let ids = Self::document_ids(&message_ids)?;
         
-        let filter = doc! { "_id": { "$in": uuids? } };
+        let filter = doc! { "_id": { "$in": ids } };
         
-        self.collection.delete_many(filter, None)
+        self.collection().await?.delete_many(filter, None)
             .await
             .map_err(|e| RepoError::Repository(e.to_string()))?;
         
@@ -230,7 +260,7 @@ impl MessageRepository for MongoDBMessageRepository {
             .limit(query.limit as i64)
             .build();
 
-        let mut cursor = self.collection.find(filter.clone(), options)
+        let mut cursor = self.collection().await?.find(filter.clone(), options)
             .await
             .map_err(|e| RepoError::Repository(e.to_string()))?;
 
@@ -240,7 +270,7 @@ impl MessageRepository for MongoDBMessageRepository {
             messages.push(Self::from_document(doc)?);
         }
 
-        let total_count = self.collection.count_documents(filter, None)
+        let total_count = self.collection().await?.count_documents(filter, None)
             .await
             .map_err(|e| RepoError::Repository(e.to_string()))?;
 
@@ -274,7 +304,7 @@ impl MessageRepository for MongoDBMessageRepository {
             .limit(limit as i64)
             .build();
 
-        let mut cursor = self.collection.find(filter.clone(), options)
+        let mut cursor = self.collection().await?.find(filter.clone(), options)
             .await
             .map_err(|e| RepoError::Repository(e.to_string()))?;
 
@@ -284,7 +314,7 @@ impl MessageRepository for MongoDBMessageRepository {
             messages.push(Self::from_document(doc)?);
         }
 
-        let total_count = self.collection.count_documents(filter, None)
+        let total_count = self.collection().await?.count_documents(filter, None)
             .await
             .map_err(|e| RepoError::Repository(e.to_string()))?;
 
@@ -296,10 +326,9 @@ impl MessageRepository for MongoDBMessageRepository {
     }
 
     async fn update_status(&self, message_id: &str, status: MessageStatus) -> Result<(), RepoError> {
-        let id = Uuid::parse_str(message_id)
-            .map_err(|e| RepoError::InvalidData(e.to_string()))?;
+        let id = Self::parse_id(message_id)?;
         
-        let filter = doc! { "_id": BsonUuid::from_uuid_v4(id) };
+        let filter = doc! { "_id": id.to_sortable_string() };
         let update = doc! { 
             "$set": { 
                 "status": match status {
@@ -314,7 +343,7 @@ impl MessageRepository for MongoDBMessageRepository {
             } 
         };
         
-        self.collection.update_one(filter, update, None)
+        self.collection().await?.update_one(filter, update, None)
             .await
             .map_err(|e| RepoError::Repository(e.to_string()))?;
         
@@ -322,11 +351,9 @@ impl MessageRepository for MongoDBMessageRepository {
     }
 
     async fn batch_update_status(&self, message_ids: Vec<String>, status: MessageStatus) -> Result<(), RepoError> {
-        let uuids: Result<Vec<BsonUuid>, _> = message_ids.iter()
-            .map(|id| Uuid::parse_str(id).map(BsonUuid::from_uuid_v4))
-            .collect();
+        let ids = Self::document_ids(&message_ids)?;
         
-        let filter = doc! { "_id": { "$in": uuids? } };
+        let filter = doc! { "_id": { "$in": ids } };
         let update = doc! { 
             "$set": { 
                 "status": match status {
@@ -341,7 +368,7 @@ impl MessageRepository for MongoDBMessageRepository {
             } 
         };
         
-        self.collection.update_many(filter, update, None)
+        self.collection().await?.update_many(filter, update, None)
             .await
             .map_err(|e| RepoError::Repository(e.to_string()))?;
         
@@ -351,7 +378,7 @@ impl MessageRepository for MongoDBMessageRepository {
     async fn count_session_messages(&self, session_id: &str) -> Result<u64, RepoError> {
         let filter = doc! { "session_id": session_id };
         
-        self.collection.count_documents(filter, None)
+        self.collection().await?.count_documents(filter, None)
             .await
             .map_err(|e| RepoError::Repository(e.to_string()))
     }
@@ -359,7 +386,7 @@ impl MessageRepository for MongoDBMessageRepository {
     async fn count_user_messages(&self, user_id: &str) -> Result<u64, RepoError> {
         let filter = doc! { "sender_id": user_id };
         
-        self.collection.count_documents(filter, None)
+        self.collection().await?.count_documents(filter, None)
             .await
             .map_err(|e| RepoError::Repository(e.to_string()))
     }
@@ -1,40 +1,35 @@
 use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
 use crate::{
     domain::{
         entities::message::{Message, MessageQuery, MessageBatch, MessageStatus},
         services::message_service::{MessageService, Error as ServiceError},
     },
-    infrastructure::messaging::kafka_message_producer::{KafkaMessageProducer, Error as KafkaError},
+    infrastructure::messaging::message_event_producer::MessageEventProducer,
 };
 use lz4::block::CompressionMode;
 
 pub struct MessageServiceImpl {
-    kafka_producer: KafkaMessageProducer,
+    event_producer: MessageEventProducer,
 }
 
 impl MessageServiceImpl {
-    pub fn new(kafka_producer: KafkaMessageProducer) -> Self {
-        Self { kafka_producer }
+    pub fn new(event_producer: MessageEventProducer) -> Self {
+        Self { event_producer }
     }
 
     // 压缩消息内容
     fn compress_content(&self, content: &str) -> Result<Vec<u8>, ServiceError> {
-        let mut compressed = Vec::new();
-        lz4::block::compress_to_vec(
-            content.as_bytes(),
-            &mut compressed,
-            CompressionMode::HIGHCOMPRESSION,
-        ).map_err(|e| ServiceError::Processing(e.to_string()))?;
-        Ok(compressed)
+        // 压缩结果带原始长度前缀，解压时无需额外记录
+        lz4::block::compress(content.as_bytes(), Some(CompressionMode::HIGHCOMPRESSION(9)), true)
+            .map_err(|e| ServiceError::Processing(e.to_string()))
     }
 
     // 解压消息内容
     fn decompress_content(&self, compressed: &[u8]) -> Result<String, ServiceError> {
-        let mut decompressed = Vec::new();
-        lz4::block::decompress_to_vec(
-            compressed,
-            &mut decompressed,
-        ).map_err(|e| ServiceError::Processing(e.to_string()))?;
+        let decompressed = lz4::block::decompress(compressed, None)
+            .map_err(|e| ServiceError::Processing(e.to_string()))?;
 
         String::from_utf8(decompressed)
             .map_err(|e| ServiceError::Processing(e.to_string()))
@@ -47,7 +42,7 @@ impl MessageService for MessageServiceImpl {
         // 检查是否需要压缩
         if message.content.len() > 1024 && message.metadata.compression.is_none() {
             let compressed = self.compress_content(&message.content)?;
-            message.content = base64::encode(&compressed);
+            message.content = STANDARD.encode(&compressed);
             message.metadata.compression = Some("lz4".to_string());
         }
 
@@ -62,14 +57,31 @@ impl MessageService for MessageServiceImpl {
         Ok(processed_messages)
     }
 
+    async fn restore_message(&self, mut message: Message) -> Result<Message, ServiceError> {
+        match message.metadata.compression.as_deref() {
+            None => {}
+            Some("lz4") => {
+                let compressed = STANDARD
+                    .decode(&message.content)
+                    .map_err(|e| ServiceError::Processing(e.to_string()))?;
+                message.content = self.decompress_content(&compressed)?;
+                message.metadata.compression = None;
+            }
+            Some(other) => {
+                return Err(ServiceError::Processing(format!("unsupported compression: {}", other)));
+            }
+        }
+        Ok(message)
+    }
+
     async fn dispatch_message(&self, message: Message) -> Result<(), ServiceError> {
-        self.kafka_producer.send_message(&message).await
+        self.event_producer.send_message(&message).await
             .map_err(|e| ServiceError::Dispatch(e.to_string()))?;
         Ok(())
     }
 
     async fn batch_dispatch_messages(&self, messages: Vec<Message>) -> Result<(), ServiceError> {
-        self.kafka_producer.send_messages(&messages).await
+        self.event_producer.send_messages(&messages).await
             .map_err(|e| ServiceError::Dispatch(e.to_string()))?;
         Ok(())
     }
@@ -1,131 +1,161 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use common::error::AppError;
+use common::id::{SnowflakeGenerator, SnowflakeId};
+use common::tenant::TenantService;
+use common::utils::msg_utils::conversation_id;
+use log::info;
+use prost::Message as _;
+use proto_crate::api::im::common::MessageData;
+use proto_crate::api::im::service::store::{
+    message_store_server::{MessageStore, MessageStoreServer},
+    BatchGetMessageRequest, BatchGetMessageResponse, BatchStoreMessageRequest, BatchStoreMessageResponse,
+    DeleteMessageRequest, DeleteMessageResponse, GetMessageRequest, GetMessageResponse,
+    GetMessageStatsRequest, GetMessageStatsResponse, GetSessionMessagesRequest, GetSessionMessagesResponse,
+    MessageStoreInfo, MessageStoreStatus, SearchMessageRequest, SearchMessageResponse, StoreMessageRequest,
+    StoreMessageResponse, UpdateMessageRequest, UpdateMessageResponse,
+};
 use tonic::{Request, Response, Status};
-use uuid::Uuid;
-use chrono::Utc;
+
 use crate::{
     application::message_manager::MessageManager,
     domain::{
-        entities::message::{Message, MessageMetadata, MessageQuery, MessageStatus},
+        entities::message::{Message, MessageMetadata, MessageStatus},
         repositories::message_repository::MessageRepository,
         services::message_service::MessageService,
     },
 };
-use api::im::service::store::{
-    store_server::Store as GrpcStore,
-    StoreMessageRequest,
-    StoreMessageResponse,
-    BatchStoreMessageRequest,
-    BatchStoreMessageResponse,
-    GetMessageRequest,
-    GetMessageResponse,
-    QueryMessagesRequest,
-    QueryMessagesResponse,
-    GetSessionMessagesRequest,
-    GetSessionMessagesResponse,
-    UpdateMessageStatusRequest,
-    UpdateMessageStatusResponse,
-    DeleteMessagesRequest,
-    DeleteMessagesResponse,
-    ClearSessionMessagesRequest,
-    ClearSessionMessagesResponse,
-};
+
+/// 会话消息默认分页大小
+const DEFAULT_PAGE_SIZE: u32 = 20;
+/// 会话消息最大分页大小
+const MAX_PAGE_SIZE: u32 = 100;
 
 pub struct MessageGrpcService<R: MessageRepository, S: MessageService> {
     message_manager: MessageManager<R, S>,
+    /// 未携带 `server_msg_id` 的消息由存储服务分配，Worker ID 需通过租约获取
+    id_generator: Arc<SnowflakeGenerator>,
 }
 
 impl<R: MessageRepository, S: MessageService> MessageGrpcService<R, S> {
-    pub fn new(message_manager: MessageManager<R, S>) -> Self {
-        Self { message_manager }
+    pub fn new(message_manager: MessageManager<R, S>, id_generator: Arc<SnowflakeGenerator>) -> Self {
+        Self { message_manager, id_generator }
     }
 
-    // 转换消息状态
-    fn convert_status(status: i32) -> MessageStatus {
-        match status {
-            0 => MessageStatus::Pending,
-            1 => MessageStatus::Sent,
-            2 => MessageStatus::Delivered,
-            3 => MessageStatus::Read,
-            4 => MessageStatus::Failed,
-            5 => MessageStatus::Deleted,
-            _ => MessageStatus::Pending,
-        }
+    /// gRPC 服务端，请求在 `x-tenant-id` 指定的租户下处理
+    pub fn into_server(self) -> TenantService<MessageStoreServer<Self>>
+    where
+        R: Send + Sync + 'static,
+        S: Send + Sync + 'static,
+    {
+        TenantService::new(MessageStoreServer::new(self))
     }
+}
 
-    // 转换为 gRPC 消息
-    fn to_grpc_message(message: &Message) -> api::im::service::store::Message {
-        api::im::service::store::Message {
-            id: message.id.to_string(),
-            session_id: message.session_id.clone(),
-            sender_id: message.sender_id.clone(),
-            content_type: message.content_type.clone(),
-            content: message.content.clone(),
-            status: match message.status {
-                MessageStatus::Pending => 0,
-                MessageStatus::Sent => 1,
-                MessageStatus::Delivered => 2,
-                MessageStatus::Read => 3,
-                MessageStatus::Failed => 4,
-                MessageStatus::Deleted => 5,
-            },
-            metadata: Some(api::im::service::store::MessageMetadata {
-                device_id: message.metadata.device_id.clone(),
-                client_msg_id: message.metadata.client_msg_id.clone(),
-                reply_to: message.metadata.reply_to.map(|id| id.to_string()),
-                mentions: message.metadata.mentions.clone(),
-                is_encrypted: message.metadata.is_encrypted,
-                compression: message.metadata.compression.clone().unwrap_or_default(),
-                custom_properties: message.metadata.custom_properties.clone(),
-            }),
-            created_at: message.created_at.timestamp_millis(),
-            updated_at: message.updated_at.timestamp_millis(),
-        }
+// 转换消息状态
+fn convert_status(status: i32) -> MessageStatus {
+    match status {
+        0 => MessageStatus::Pending,
+        1 => MessageStatus::Sent,
+        2 => MessageStatus::Delivered,
+        3 => MessageStatus::Read,
+        4 => MessageStatus::Failed,
+        5 => MessageStatus::Deleted,
+        // 路由服务的已过期状态，到期销毁的消息视为已删除
+        11 => MessageStatus::Deleted,
+        _ => MessageStatus::Pending,
     }
+}
 
-    // 从 gRPC 请求转换为领域消息
-    fn from_grpc_request(req: &StoreMessageRequest) -> Result<Message, Status> {
-        Ok(Message {
-            id: Uuid::new_v4(),
-            session_id: req.session_id.clone(),
-            sender_id: req.sender_id.clone(),
-            content_type: req.content_type.clone(),
-            content: req.content.clone(),
-            status: Self::convert_status(req.status),
-            metadata: MessageMetadata {
-                device_id: req.metadata.as_ref().map(|m| m.device_id.clone()).unwrap_or_default(),
-                client_msg_id: req.metadata.as_ref().map(|m| m.client_msg_id.clone()).unwrap_or_default(),
-                reply_to: req.metadata.as_ref().and_then(|m| m.reply_to.as_ref()).and_then(|id| Uuid::parse_str(id).ok()),
-                mentions: req.metadata.as_ref().map(|m| m.mentions.clone()).unwrap_or_default(),
-                is_encrypted: req.metadata.as_ref().map(|m| m.is_encrypted).unwrap_or_default(),
-                compression: if req.metadata.as_ref().map(|m| m.compression.is_empty()).unwrap_or(true) {
-                    None
-                } else {
-                    req.metadata.as_ref().map(|m| m.compression.clone())
-                },
-                custom_properties: req.metadata.as_ref().map(|m| m.custom_properties.clone()).unwrap_or_default(),
-            },
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
-        })
+// 领域消息状态转换为 gRPC 状态值
+fn status_value(status: &MessageStatus) -> i32 {
+    match status {
+        MessageStatus::Pending => 0,
+        MessageStatus::Sent => 1,
+        MessageStatus::Delivered => 2,
+        MessageStatus::Read => 3,
+        MessageStatus::Failed => 4,
+        MessageStatus::Deleted => 5,
     }
 }
 
+// 从 gRPC 消息转换为领域消息，完整的 MessageData 保存在 content 中
+async fn to_message(id_generator: &SnowflakeGenerator, mut data: MessageData) -> Result<Message, Status> {
+    let id = if data.server_msg_id.is_empty() {
+        id_generator.next_id().await.map_err(|e| Status::unavailable(e.to_string()))?
+    } else {
+        SnowflakeId::parse(&data.server_msg_id)
+            .ok_or_else(|| Status::invalid_argument(format!("invalid server_msg_id: {}", data.server_msg_id)))?
+    };
+    data.server_msg_id = id.to_sortable_string();
+
+    let created_at = DateTime::from_timestamp_millis(data.create_time as i64)
+        .filter(|_| data.create_time > 0)
+        .unwrap_or_else(Utc::now);
+    Ok(Message {
+        id,
+        session_id: conversation_id(&data),
+        sender_id: data.send_id.clone(),
+        content_type: data.content_type.to_string(),
+        content: STANDARD.encode(data.encode_to_vec()),
+        status: convert_status(data.status),
+        metadata: MessageMetadata {
+            device_id: String::new(),
+            client_msg_id: data.client_msg_id.clone(),
+            reply_to: None,
+            mentions: data.at_user_list.clone(),
+            is_encrypted: false,
+            compression: None,
+            custom_properties: data.options.clone(),
+        },
+        created_at,
+        updated_at: Utc::now(),
+    })
+}
+
+// 从领域消息还原 gRPC 存储信息
+fn to_store_info(message: &Message) -> Result<MessageStoreInfo, Status> {
+    let bytes = STANDARD
+        .decode(&message.content)
+        .map_err(|e| Status::data_loss(format!("message {} content is corrupted: {}", message.id, e)))?;
+    let mut data = MessageData::decode(bytes.as_slice())
+        .map_err(|e| Status::data_loss(format!("message {} content is corrupted: {}", message.id, e)))?;
+    // 状态可能在写入后被更新
+    data.status = status_value(&message.status);
+
+    Ok(MessageStoreInfo {
+        message: Some(data),
+        store_time: message.updated_at.timestamp_millis(),
+        store_status: MessageStoreStatus::Stored as i32,
+        sharding_info: None,
+        store_options: HashMap::new(),
+    })
+}
+
 #[tonic::async_trait]
-impl<R: MessageRepository + Send + Sync + 'static, S: MessageService + Send + Sync + 'static> 
-    GrpcStore for MessageGrpcService<R, S> 
+impl<R: MessageRepository + Send + Sync + 'static, S: MessageService + Send + Sync + 'static>
+    MessageStore for MessageGrpcService<R, S>
 {
     async fn store_message(
         &self,
         request: Request<StoreMessageRequest>,
     ) -> Result<Response<StoreMessageResponse>, Status> {
         let req = request.into_inner();
-        let message = Self::from_grpc_request(&req)?;
+        let data = req.message.ok_or_else(|| Status::invalid_argument("message is required"))?;
+        let message = to_message(&self.id_generator, data).await?;
+        let store_info = to_store_info(&message)?;
 
-        let stored_message = self.message_manager.store_message(message)
+        let stored = self.message_manager.store_message(message)
             .await
-            .map_err(|e| Status::internal(e.to_string()))?;
+            .map_err(|e| Status::from(AppError::from(e)))?;
 
         Ok(Response::new(StoreMessageResponse {
-            message_id: stored_message.id.to_string(),
+            message_id: stored.id.to_string(),
+            store_info: Some(store_info),
             error: None,
         }))
     }
@@ -135,16 +165,20 @@ impl<R: MessageRepository + Send + Sync + 'static, S: MessageService + Send + Sy
         request: Request<BatchStoreMessageRequest>,
     ) -> Result<Response<BatchStoreMessageResponse>, Status> {
         let req = request.into_inner();
-        let messages = req.messages.into_iter()
-            .map(|m| Self::from_grpc_request(&m))
-            .collect::<Result<Vec<_>, _>>()?;
+        let mut messages = Vec::with_capacity(req.messages.len());
+        for data in req.messages {
+            messages.push(to_message(&self.id_generator, data).await?);
+        }
+        let store_results = messages.iter()
+            .map(|m| Ok((m.id.to_string(), to_store_info(m)?)))
+            .collect::<Result<HashMap<_, _>, Status>>()?;
 
-        let stored_messages = self.message_manager.store_messages(messages)
+        self.message_manager.store_messages(messages)
             .await
-            .map_err(|e| Status::internal(e.to_string()))?;
+            .map_err(|e| Status::from(AppError::from(e)))?;
 
         Ok(Response::new(BatchStoreMessageResponse {
-            message_ids: stored_messages.into_iter().map(|m| m.id.to_string()).collect(),
+            store_results,
             error: None,
         }))
     }
@@ -154,44 +188,36 @@ impl<R: MessageRepository + Send + Sync + 'static, S: MessageService + Send + Sy
         request: Request<GetMessageRequest>,
     ) -> Result<Response<GetMessageResponse>, Status> {
         let req = request.into_inner();
-        
-        let message = self.message_manager.message_repository.get_by_id(&req.message_id)
+
+        let message = self.message_manager.get_message(&req.message_id)
             .await
-            .map_err(|e| Status::internal(e.to_string()))?;
+            .map_err(|e| Status::from(AppError::from(e)))?
+            .ok_or_else(|| Status::from(AppError::not_found(format!("message {} not found", req.message_id))))?;
 
         Ok(Response::new(GetMessageResponse {
-            message: message.map(|m| Self::to_grpc_message(&m)),
+            message: Some(to_store_info(&message)?),
             error: None,
         }))
     }
 
-    async fn query_messages(
+    async fn batch_get_message(
         &self,
-        request: Request<QueryMessagesRequest>,
-    ) -> Result<Response<QueryMessagesResponse>, Status> {
+        request: Request<BatchGetMessageRequest>,
+    ) -> Result<Response<BatchGetMessageResponse>, Status> {
         let req = request.into_inner();
-        
-        let query = MessageQuery {
-            session_id: req.session_id,
-            start_time: req.start_time.map(|ts| chrono::DateTime::from_timestamp_millis(ts).unwrap_or_default()),
-            end_time: req.end_time.map(|ts| chrono::DateTime::from_timestamp_millis(ts).unwrap_or_default()),
-            limit: req.limit,
-            offset: req.offset,
-            content_types: if req.content_types.is_empty() { None } else { Some(req.content_types) },
-            sender_id: if req.sender_id.is_empty() { None } else { Some(req.sender_id) },
-            status: req.status.map(Self::convert_status),
-        };
 
-        let result = self.message_manager.query_history(query)
-            .await
-            .map_err(|e| Status::internal(e.to_string()))?;
+        // 不存在的消息不出现在结果中
+        let mut messages = HashMap::with_capacity(req.message_ids.len());
+        for message_id in req.message_ids {
+            let message = self.message_manager.get_message(&message_id)
+                .await
+                .map_err(|e| Status::from(AppError::from(e)))?;
+            if let Some(message) = message {
+                messages.insert(message_id, to_store_info(&message)?);
+            }
+        }
 
-        Ok(Response::new(QueryMessagesResponse {
-            messages: result.messages.iter().map(Self::to_grpc_message).collect(),
-            total_count: result.total_count,
-            has_more: result.has_more,
-            error: None,
-        }))
+        Ok(Response::new(BatchGetMessageResponse { messages, error: None }))
     }
 
     async fn get_session_messages(
@@ -199,74 +225,110 @@ impl<R: MessageRepository + Send + Sync + 'static, S: MessageService + Send + Sy
         request: Request<GetSessionMessagesRequest>,
     ) -> Result<Response<GetSessionMessagesResponse>, Status> {
         let req = request.into_inner();
-        
-        let result = self.message_manager.get_session_messages(&req.session_id, req.limit, req.offset)
+        if !req.start_message_id.is_empty() || !req.end_message_id.is_empty() {
+            return Err(Status::unimplemented("paging by message id is not supported yet"));
+        }
+        let limit = match req.limit {
+            limit if limit <= 0 => DEFAULT_PAGE_SIZE,
+            limit => (limit as u32).min(MAX_PAGE_SIZE),
+        };
+
+        // 仓储按时间倒序返回最新的消息
+        let batch = self.message_manager.get_session_messages(&req.session_id, limit, 0)
             .await
-            .map_err(|e| Status::internal(e.to_string()))?;
+            .map_err(|e| Status::from(AppError::from(e)))?;
+        let mut messages = batch.messages.iter()
+            .map(to_store_info)
+            .collect::<Result<Vec<_>, _>>()?;
+        if !req.reverse {
+            messages.reverse();
+        }
 
         Ok(Response::new(GetSessionMessagesResponse {
-            messages: result.messages.iter().map(Self::to_grpc_message).collect(),
-            total_count: result.total_count,
-            has_more: result.has_more,
+            messages,
+            has_more: batch.has_more,
+            next_message_id: String::new(),
             error: None,
         }))
     }
 
-    async fn update_message_status(
+    async fn delete_message(
         &self,
-        request: Request<UpdateMessageStatusRequest>,
-    ) -> Result<Response<UpdateMessageStatusResponse>, Status> {
+        request: Request<DeleteMessageRequest>,
+    ) -> Result<Response<DeleteMessageResponse>, Status> {
         let req = request.into_inner();
-        
-        match req.status {
-            2 => { // Delivered
-                self.message_manager.mark_messages_delivered(req.message_ids)
-                    .await
-                    .map_err(|e| Status::internal(e.to_string()))?;
-            }
-            3 => { // Read
-                self.message_manager.mark_messages_read(req.message_ids)
-                    .await
-                    .map_err(|e| Status::internal(e.to_string()))?;
-            }
-            _ => return Err(Status::invalid_argument("Invalid status")),
-        }
 
-        Ok(Response::new(UpdateMessageStatusResponse {
+        self.message_manager.delete_messages(vec![req.message_id.clone()])
+            .await
+            .map_err(|e| Status::from(AppError::from(e)))?;
+        info!(
+            "Deleted message {} of {}, options: {:?}",
+            req.message_id, req.session_id, req.options
+        );
+
+        Ok(Response::new(DeleteMessageResponse {
             success: true,
             error: None,
         }))
     }
 
-    async fn delete_messages(
+    async fn update_message(
         &self,
-        request: Request<DeleteMessagesRequest>,
-    ) -> Result<Response<DeleteMessagesResponse>, Status> {
-        let req = request.into_inner();
-        
-        self.message_manager.delete_messages(req.message_ids)
-            .await
-            .map_err(|e| Status::internal(e.to_string()))?;
+        _request: Request<UpdateMessageRequest>,
+    ) -> Result<Response<UpdateMessageResponse>, Status> {
+        Err(Status::unimplemented("update_message is not supported yet"))
+    }
 
-        Ok(Response::new(DeleteMessagesResponse {
-            success: true,
-            error: None,
-        }))
+    async fn search_message(
+        &self,
+        _request: Request<SearchMessageRequest>,
+    ) -> Result<Response<SearchMessageResponse>, Status> {
+        Err(Status::unimplemented("search_message is not supported yet"))
     }
 
-    async fn clear_session_messages(
+    async fn get_message_stats(
         &self,
-        request: Request<ClearSessionMessagesRequest>,
-    ) -> Result<Response<ClearSessionMessagesResponse>, Status> {
-        let req = request.into_inner();
-        
-        self.message_manager.clear_session_messages(&req.session_id)
-            .await
-            .map_err(|e| Status::internal(e.to_string()))?;
+        _request: Request<GetMessageStatsRequest>,
+    ) -> Result<Response<GetMessageStatsResponse>, Status> {
+        Err(Status::unimplemented("get_message_stats is not supported yet"))
+    }
+}
 
-        Ok(Response::new(ClearSessionMessagesResponse {
-            success: true,
-            error: None,
-        }))
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_message_data_round_trip() {
+        let generator = SnowflakeGenerator::new(1).unwrap();
+        let data = MessageData {
+            send_id: "user1".to_string(),
+            recv_id: "user2".to_string(),
+            content: vec![0xff, 0x00, 0x10],
+            content_type: 1,
+            client_msg_id: "c1".to_string(),
+            create_time: 1_700_000_000_000,
+            at_user_list: vec!["user2".to_string()],
+            ..Default::default()
+        };
+
+        let message = to_message(&generator, data.clone()).await.unwrap();
+        assert_eq!(message.session_id, "si_user1_user2");
+        assert_eq!(message.created_at.timestamp_millis(), 1_700_000_000_000);
+
+        let restored = to_store_info(&message).unwrap().message.unwrap();
+        assert_eq!(restored.content, data.content);
+        assert_eq!(restored.server_msg_id, message.id.to_sortable_string());
     }
-} 
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_invalid_server_msg_id_rejected() {
+        let generator = SnowflakeGenerator::new(1).unwrap();
+        let data = MessageData {
+            server_msg_id: "not-a-snowflake".to_string(),
+            ..Default::default()
+        };
+        let status = to_message(&generator, data).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    }
+}
@@ -1,5 +1,7 @@
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
+use common::error::AppError;
+use proto_crate::api::im::common::ErrorCode;
 use uuid::Uuid;
 use crate::domain::entities::*;
 
@@ -67,4 +69,18 @@ pub enum Error {
 
     #[error("Storage error: {0}")]
     StorageError(String),
-} 
\ No newline at end of file
+}
+
+impl From<Error> for AppError {
+    fn from(err: Error) -> Self {
+        let code = match &err {
+            Error::Service(_) | Error::ConflictError(_) => ErrorCode::SyncFailed,
+            Error::InvalidRequest(_) => ErrorCode::InvalidParams,
+            Error::NotFound(_) => ErrorCode::NotFound,
+            Error::PermissionDenied(_) => ErrorCode::Forbidden,
+            Error::SequenceError(_) => ErrorCode::SyncSequenceGap,
+            Error::StorageError(_) => ErrorCode::SystemError,
+        };
+        AppError::new(code, err.to_string())
+    }
+}
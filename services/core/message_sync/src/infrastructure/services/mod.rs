@@ -1,3 +1,5 @@
 mod redis_sync_service;
+mod session_client;
 
-pub use redis_sync_service::*; 
\ No newline at end of file
+pub use redis_sync_service::*;
+pub use session_client::*;
@@ -3,6 +3,9 @@ use crate::domain::{
     services::{SyncService, SyncType, StatusType, Error},
 };
 use async_trait::async_trait;
+use super::SessionClient;
+use common::rpc::GrpcClientFactory;
+use common::tenant;
 use redis::{aio::ConnectionManager, AsyncCommands};
 use std::sync::Arc;
 use uuid::Uuid;
@@ -28,10 +31,21 @@ impl RedisSyncService {
         }
     }
 
+    /// 通过服务发现查询会话服务中的用户在线状态
+    pub async fn with_discovery(
+        redis: Arc<ConnectionManager>,
+        message_store: Arc<dyn MessageStore>,
+        factory: &GrpcClientFactory,
+    ) -> anyhow::Result<Self> {
+        let session_service = Arc::new(SessionClient::from_factory(factory).await?);
+        Ok(Self::new(redis, message_store, session_service))
+    }
+
     // 生成序列号
     async fn generate_sequence(&self, conversation_id: &str, count: i32) -> Result<(i64, i64), Error> {
         let mut conn = self.redis.clone();
-        let key = format!("sequence:conversation:{}", conversation_id);
+        // 会话序列号按租户隔离
+        let key = tenant::current().redis_key(&format!("sequence:conversation:{}", conversation_id));
         
         let start: i64 = conn.incr(&key, count as i64).await.map_err(|e| {
             Error::StorageError(format!("Failed to generate sequence: {}", e))
@@ -43,7 +57,7 @@ impl RedisSyncService {
 
     // 获取同步点键
     fn get_sync_point_key(&self, user_id: &str, device_id: &str) -> String {
-        format!("sync:point:{}:{}", user_id, device_id)
+        tenant::current().redis_key(&format!("sync:point:{}:{}", user_id, device_id))
     }
 }
 
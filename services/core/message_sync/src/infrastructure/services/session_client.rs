@@ -0,0 +1,107 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use common::rpc::{context_interceptor, GrpcClientFactory, ServiceChannel, ServiceNames};
+use proto_crate::api::im::service::session::session_service_client::SessionServiceClient;
+use proto_crate::api::im::service::session::{GetUsersStatusRequest, UserOnlineStatus};
+
+use super::SessionService;
+use crate::domain::entities::{OnlineStatus, UserStatus};
+use crate::domain::services::Error;
+
+/// 会话服务调用超时
+const SESSION_DEADLINE: Duration = Duration::from_secs(2);
+
+/// 通过服务发现调用会话服务，查询用户在线状态
+pub struct SessionClient {
+    session: Arc<ServiceChannel>,
+    deadline: Duration,
+}
+
+impl SessionClient {
+    pub fn new(session: Arc<ServiceChannel>) -> Self {
+        Self {
+            session,
+            deadline: SESSION_DEADLINE,
+        }
+    }
+
+    /// 按 `ServiceNames::SESSION` 发现会话服务实例
+    pub async fn from_factory(factory: &GrpcClientFactory) -> anyhow::Result<Self> {
+        Ok(Self::new(factory.service(ServiceNames::SESSION).await?))
+    }
+
+    pub fn with_deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = deadline;
+        self
+    }
+}
+
+// 会话服务的在线状态转换为领域状态
+fn to_user_status(status: UserOnlineStatus) -> UserStatus {
+    UserStatus {
+        user_id: status.user_id,
+        online_status: if status.online { OnlineStatus::Online } else { OnlineStatus::Offline },
+        device_id: status.device_id,
+        last_active_at: DateTime::from_timestamp_millis(status.last_active_time)
+            .unwrap_or(DateTime::<Utc>::UNIX_EPOCH),
+    }
+}
+
+#[async_trait]
+impl SessionService for SessionClient {
+    async fn get_users_status(&self, user_ids: Vec<String>) -> Result<Vec<UserStatus>, Error> {
+        if user_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let request = GetUsersStatusRequest { user_ids };
+        let deadline = self.deadline;
+        let response = self
+            .session
+            .call(|channel| {
+                let request = request.clone();
+                async move {
+                    let mut request = tonic::Request::new(request);
+                    request.set_timeout(deadline);
+                    SessionServiceClient::with_interceptor(channel, context_interceptor)
+                        .get_users_status(request)
+                        .await
+                }
+            })
+            .await
+            .map_err(|status| Error::Service(format!("get users status failed: {}", status)))?
+            .into_inner();
+
+        if let Some(error) = response.error.filter(|e| e.code != 0) {
+            return Err(Error::Service(format!("get users status failed: {}", error.message)));
+        }
+        Ok(response.statuses.into_iter().map(to_user_status).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_user_status() {
+        let status = to_user_status(UserOnlineStatus {
+            user_id: "user1".to_string(),
+            online: true,
+            device_id: "ios-1".to_string(),
+            last_active_time: 1_700_000_000_000,
+        });
+        assert!(matches!(status.online_status, OnlineStatus::Online));
+        assert_eq!(status.device_id, "ios-1");
+        assert_eq!(status.last_active_at.timestamp_millis(), 1_700_000_000_000);
+
+        let status = to_user_status(UserOnlineStatus {
+            user_id: "user2".to_string(),
+            online: false,
+            ..Default::default()
+        });
+        assert!(matches!(status.online_status, OnlineStatus::Offline));
+    }
+}
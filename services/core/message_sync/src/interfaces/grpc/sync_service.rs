@@ -4,6 +4,8 @@ use crate::domain::{
     services::{SyncType, StatusType, Error},
 };
 use proto_crate::api::im::service::sync::*;
+use common::error::AppError;
+use common::tenant::TenantService;
 use std::sync::Arc;
 use tonic::{Request, Response, Status};
 use uuid::Uuid;
@@ -17,6 +19,11 @@ impl MessageSyncGrpcService {
         Self { sync_manager }
     }
 
+    /// gRPC 服务端，请求在 `x-tenant-id` 指定的租户下处理
+    pub fn into_server(self) -> TenantService<message_sync_server::MessageSyncServer<Self>> {
+        TenantService::new(message_sync_server::MessageSyncServer::new(self))
+    }
+
     // 转换同步类型
     fn convert_sync_type(&self, sync_type: i32) -> SyncType {
         match sync_type {
@@ -40,15 +47,7 @@ impl MessageSyncGrpcService {
 
     // 转换错误为 Status
     fn convert_error(&self, error: Error) -> Status {
-        match error {
-            Error::Service(msg) => Status::internal(msg),
-            Error::InvalidRequest(msg) => Status::invalid_argument(msg),
-            Error::NotFound(msg) => Status::not_found(msg),
-            Error::PermissionDenied(msg) => Status::permission_denied(msg),
-            Error::SequenceError(msg) => Status::failed_precondition(msg),
-            Error::ConflictError(msg) => Status::already_exists(msg),
-            Error::StorageError(msg) => Status::internal(msg),
-        }
+        AppError::from(error).into()
     }
 }
 
@@ -1,6 +1,6 @@
 use anyhow::Result;
 use flare_core::logs::Logger;
-use log::{info, error};
+use log::info;
 use sqlx::postgres::PgPoolOptions;
 use std::sync::Arc;
 use notification::{
@@ -22,11 +22,17 @@ use flare_rpc_core::{
 };
 use std::time::Duration;
 use tonic::transport::Server;
+use common::health::{ConsulHealthRegistrar, HealthRegistry, PostgresHealthCheck};
+use common::tenant::TenantLayer;
+use common::telemetry::{init_telemetry, server_interceptor, TelemetryConfig, TraceContextLayer};
 
 #[tokio::main]
 async fn main() -> Result<()> {
     // 初始化日志
     Logger::init("notification", "debug")?;
+
+    // 初始化链路追踪
+    let _telemetry = init_telemetry(&TelemetryConfig::from_env("notification"))?;
     info!("Starting Notification Service...");
 
     // 创建数据库连接池
@@ -37,9 +43,17 @@ async fn main() -> Result<()> {
         .connect(&database_url)
         .await?;
 
+    // 健康检查
+    let health = Arc::new(
+        HealthRegistry::new("notification").with_check(Arc::new(PostgresHealthCheck::new(pool.clone()))),
+    );
+    let health_addr = std::env::var("HEALTH_ADDR").unwrap_or_else(|_| "127.0.0.1:9055".to_string());
+    let health_service = health.start(&health_addr, Duration::from_secs(5)).await?;
+
     // 初始化存储库
+    // 默认租户启动时建表，其他租户首次访问时建表
     let repository = Arc::new(PostgresRepository::new(pool.clone()));
-    repository.create_tables().await?;
+    repository.ensure_tables().await?;
 
     // 创建通知服务
     let notification_service = NotificationServiceImpl::new(
@@ -105,6 +119,15 @@ async fn main() -> Result<()> {
         .parse::<u16>()?;
     let addr = format!("{}:{}", service_host, service_port).parse()?;
 
+    // 就绪检查失败时由 Consul 将实例摘除
+    let consul_health = ConsulHealthRegistrar::new(&common::config::ConsulConfig {
+        host: consul_host.clone(),
+        port: consul_port,
+        register_interval: 10,
+        heartbeat_interval: 5,
+    });
+    consul_health.spawn_register("notification", addr, format!("http://{}/readyz", health_addr));
+
     // 创建应用构建器
     let app = AppBuilder::new("notification")
         .version("1.0.0")
@@ -119,7 +142,10 @@ async fn main() -> Result<()> {
     // 运行服务
     app.run(service_host.as_str(), service_port, |mut server, addr| async move {
         server
-            .add_service(NotificationServer::new(grpc_service))
+            .layer(TenantLayer)
+            .layer(TraceContextLayer)
+            .add_service(health_service)
+            .add_service(NotificationServer::with_interceptor(grpc_service, server_interceptor))
             .serve(addr)
             .await
             .map_err(|e| e.into())
@@ -2,6 +2,7 @@ use async_trait::async_trait;
 use sqlx::{PgPool, Row};
 use uuid::Uuid;
 use chrono::Utc;
+use common::tenant::{self, TenantSchemas};
 use crate::{
     domain::entities::notification::*,
     infrastructure::services::notification_service_impl::{
@@ -12,18 +13,43 @@ use crate::{
 
 pub struct PostgresRepository {
     pool: PgPool,
+    schemas: TenantSchemas,
 }
 
 impl PostgresRepository {
     pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+        Self {
+            pool,
+            schemas: TenantSchemas::new(),
+        }
+    }
+
+    // 租户首次访问时创建其 schema 与数据表
+    pub async fn ensure_tables(&self) -> Result<(), ServiceError> {
+        self.schemas.ensure(|| self.create_tables()).await
+    }
+
+    // 将 SQL 中的 `{table}` 占位符替换为当前租户 schema 下的表名
+    fn sql(sql: &str) -> String {
+        let tenant = tenant::current();
+        ["devices", "notification_templates", "notifications", "notification_results"]
+            .iter()
+            .fold(sql.to_string(), |sql, table| {
+                sql.replace(&format!("{{{}}}", table), &tenant.pg_table(table))
+            })
     }
 
-    // 创建数据库表
-    pub async fn create_tables(&self) -> Result<(), ServiceError> {
+    // 创建当前租户的数据库表
+    async fn create_tables(&self) -> Result<(), ServiceError> {
+        // 创建租户 schema
+        sqlx::query(&format!("CREATE SCHEMA IF NOT EXISTS {}", tenant::current().pg_schema()))
+            .execute(&self.pool)
+            .await
+            .map_err(|e| ServiceError::Service(e.to_string()))?;
+
         // 创建设备表
-        sqlx::query(r#"
-            CREATE TABLE IF NOT EXISTS devices (
+        sqlx::query(&Self::sql(r#"
+            CREATE TABLE IF NOT EXISTS {devices} (
                 user_id VARCHAR(255) NOT NULL,
                 device_id VARCHAR(255) NOT NULL,
                 platform VARCHAR(50) NOT NULL,
@@ -34,14 +60,14 @@ impl PostgresRepository {
                 last_active_at TIMESTAMP WITH TIME ZONE NOT NULL,
                 PRIMARY KEY (user_id, device_id)
             )
-        "#)
+        "#))
         .execute(&self.pool)
         .await
         .map_err(|e| ServiceError::Service(e.to_string()))?;
 
         // 创建模板表
-        sqlx::query(r#"
-            CREATE TABLE IF NOT EXISTS notification_templates (
+        sqlx::query(&Self::sql(r#"
+            CREATE TABLE IF NOT EXISTS {notification_templates} (
                 id UUID PRIMARY KEY,
                 name VARCHAR(255) NOT NULL,
                 title_template TEXT NOT NULL,
@@ -52,14 +78,14 @@ impl PostgresRepository {
                 created_at TIMESTAMP WITH TIME ZONE NOT NULL,
                 updated_at TIMESTAMP WITH TIME ZONE NOT NULL
             )
-        "#)
+        "#))
         .execute(&self.pool)
         .await
         .map_err(|e| ServiceError::Service(e.to_string()))?;
 
         // 创建通知表
-        sqlx::query(r#"
-            CREATE TABLE IF NOT EXISTS notifications (
+        sqlx::query(&Self::sql(r#"
+            CREATE TABLE IF NOT EXISTS {notifications} (
                 id UUID PRIMARY KEY,
                 title TEXT NOT NULL,
                 content TEXT NOT NULL,
@@ -75,14 +101,14 @@ impl PostgresRepository {
                 scheduled_at TIMESTAMP WITH TIME ZONE,
                 expired_at TIMESTAMP WITH TIME ZONE
             )
-        "#)
+        "#))
         .execute(&self.pool)
         .await
         .map_err(|e| ServiceError::Service(e.to_string()))?;
 
         // 创建通知结果表
-        sqlx::query(r#"
-            CREATE TABLE IF NOT EXISTS notification_results (
+        sqlx::query(&Self::sql(r#"
+            CREATE TABLE IF NOT EXISTS {notification_results} (
                 notification_id UUID PRIMARY KEY,
                 success BOOLEAN NOT NULL,
                 platform_results JSONB NOT NULL,
@@ -91,7 +117,7 @@ impl PostgresRepository {
                 error TEXT,
                 created_at TIMESTAMP WITH TIME ZONE NOT NULL
             )
-        "#)
+        "#))
         .execute(&self.pool)
         .await
         .map_err(|e| ServiceError::Service(e.to_string()))?;
@@ -103,8 +129,9 @@ impl PostgresRepository {
 #[async_trait]
 impl DeviceRepository for PostgresRepository {
     async fn save_device(&self, device: DeviceInfo) -> Result<(), ServiceError> {
-        sqlx::query(r#"
-            INSERT INTO devices (
+        self.ensure_tables().await?;
+        sqlx::query(&Self::sql(r#"
+            INSERT INTO {devices} (
                 user_id, device_id, platform, push_token, app_version,
                 provider, is_active, last_active_at
             )
@@ -116,7 +143,7 @@ impl DeviceRepository for PostgresRepository {
                 provider = $6,
                 is_active = $7,
                 last_active_at = $8
-        "#)
+        "#))
         .bind(&device.user_id)
         .bind(&device.device_id)
         .bind(format!("{:?}", device.platform))
@@ -133,7 +160,8 @@ impl DeviceRepository for PostgresRepository {
     }
 
     async fn delete_device(&self, user_id: &str, device_id: &str) -> Result<(), ServiceError> {
-        sqlx::query("DELETE FROM devices WHERE user_id = $1 AND device_id = $2")
+        self.ensure_tables().await?;
+        sqlx::query(&Self::sql("DELETE FROM {devices} WHERE user_id = $1 AND device_id = $2"))
             .bind(user_id)
             .bind(device_id)
             .execute(&self.pool)
@@ -144,12 +172,13 @@ impl DeviceRepository for PostgresRepository {
     }
 
     async fn update_device_token(&self, user_id: &str, device_id: &str, new_token: &str) -> Result<(), ServiceError> {
-        sqlx::query(r#"
-            UPDATE devices
+        self.ensure_tables().await?;
+        sqlx::query(&Self::sql(r#"
+            UPDATE {devices}
             SET push_token = $3,
                 last_active_at = $4
             WHERE user_id = $1 AND device_id = $2
-        "#)
+        "#))
         .bind(user_id)
         .bind(device_id)
         .bind(new_token)
@@ -162,12 +191,13 @@ impl DeviceRepository for PostgresRepository {
     }
 
     async fn get_user_devices(&self, user_id: &str) -> Result<Vec<DeviceInfo>, ServiceError> {
-        let rows = sqlx::query(r#"
+        self.ensure_tables().await?;
+        let rows = sqlx::query(&Self::sql(r#"
             SELECT user_id, device_id, platform, push_token, app_version,
                    provider, is_active, last_active_at
-            FROM devices
+            FROM {devices}
             WHERE user_id = $1 AND is_active = true
-        "#)
+        "#))
         .bind(user_id)
         .fetch_all(&self.pool)
         .await
@@ -195,8 +225,9 @@ impl DeviceRepository for PostgresRepository {
 #[async_trait]
 impl TemplateRepository for PostgresRepository {
     async fn save_template(&self, template: NotificationTemplate) -> Result<NotificationTemplate, ServiceError> {
-        sqlx::query(r#"
-            INSERT INTO notification_templates (
+        self.ensure_tables().await?;
+        sqlx::query(&Self::sql(r#"
+            INSERT INTO {notification_templates} (
                 id, name, title_template, content_template, category,
                 platform, metadata, created_at, updated_at
             )
@@ -210,7 +241,7 @@ impl TemplateRepository for PostgresRepository {
                 metadata = $7,
                 updated_at = $9
             RETURNING *
-        "#)
+        "#))
         .bind(template.id)
         .bind(&template.name)
         .bind(&template.title_template)
@@ -237,10 +268,11 @@ impl TemplateRepository for PostgresRepository {
     }
 
     async fn delete_template(&self, template_id: &str) -> Result<(), ServiceError> {
+        self.ensure_tables().await?;
         let uuid = Uuid::parse_str(template_id)
             .map_err(|e| ServiceError::InvalidRequest(e.to_string()))?;
 
-        sqlx::query("DELETE FROM notification_templates WHERE id = $1")
+        sqlx::query(&Self::sql("DELETE FROM {notification_templates} WHERE id = $1"))
             .bind(uuid)
             .execute(&self.pool)
             .await
@@ -250,15 +282,16 @@ impl TemplateRepository for PostgresRepository {
     }
 
     async fn get_template(&self, template_id: &str) -> Result<Option<NotificationTemplate>, ServiceError> {
+        self.ensure_tables().await?;
         let uuid = Uuid::parse_str(template_id)
             .map_err(|e| ServiceError::InvalidRequest(e.to_string()))?;
 
-        sqlx::query(r#"
+        sqlx::query(&Self::sql(r#"
             SELECT id, name, title_template, content_template, category,
                    platform, metadata, created_at, updated_at
-            FROM notification_templates
+            FROM {notification_templates}
             WHERE id = $1
-        "#)
+        "#))
         .bind(uuid)
         .fetch_optional(&self.pool)
         .await
@@ -279,12 +312,13 @@ impl TemplateRepository for PostgresRepository {
     }
 
     async fn get_templates_by_category(&self, category: &str) -> Result<Vec<NotificationTemplate>, ServiceError> {
-        sqlx::query(r#"
+        self.ensure_tables().await?;
+        sqlx::query(&Self::sql(r#"
             SELECT id, name, title_template, content_template, category,
                    platform, metadata, created_at, updated_at
-            FROM notification_templates
+            FROM {notification_templates}
             WHERE category = $1
-        "#)
+        "#))
         .bind(category)
         .fetch_all(&self.pool)
         .await
@@ -310,12 +344,13 @@ impl TemplateRepository for PostgresRepository {
 #[async_trait]
 impl NotificationRepository for PostgresRepository {
     async fn save_notification(&self, notification: &Notification) -> Result<(), ServiceError> {
+        self.ensure_tables().await?;
         let mut tx = self.pool.begin().await
             .map_err(|e| ServiceError::Service(e.to_string()))?;
 
         // 保存通知
-        sqlx::query(r#"
-            INSERT INTO notifications (
+        sqlx::query(&Self::sql(r#"
+            INSERT INTO {notifications} (
                 id, title, content, notification_type, priority,
                 target_type, target_users, platform, status, metadata,
                 created_at, updated_at, scheduled_at, expired_at
@@ -334,7 +369,7 @@ impl NotificationRepository for PostgresRepository {
                 updated_at = $12,
                 scheduled_at = $13,
                 expired_at = $14
-        "#)
+        "#))
         .bind(notification.id)
         .bind(&notification.title)
         .bind(&notification.content)
@@ -360,16 +395,17 @@ impl NotificationRepository for PostgresRepository {
     }
 
     async fn get_notification(&self, notification_id: &str) -> Result<Option<NotificationResult>, ServiceError> {
+        self.ensure_tables().await?;
         let uuid = Uuid::parse_str(notification_id)
             .map_err(|e| ServiceError::InvalidRequest(e.to_string()))?;
 
-        sqlx::query(r#"
+        sqlx::query(&Self::sql(r#"
             SELECT n.*, r.success, r.platform_results, r.sent_count,
                    r.failed_count, r.error
-            FROM notifications n
-            LEFT JOIN notification_results r ON n.id = r.notification_id
+            FROM {notifications} n
+            LEFT JOIN {notification_results} r ON n.id = r.notification_id
             WHERE n.id = $1
-        "#)
+        "#))
         .bind(uuid)
         .fetch_optional(&self.pool)
         .await
@@ -391,15 +427,16 @@ impl NotificationRepository for PostgresRepository {
         notification_id: &str,
         status: NotificationStatus,
     ) -> Result<(), ServiceError> {
+        self.ensure_tables().await?;
         let uuid = Uuid::parse_str(notification_id)
             .map_err(|e| ServiceError::InvalidRequest(e.to_string()))?;
 
-        sqlx::query(r#"
-            UPDATE notifications
+        sqlx::query(&Self::sql(r#"
+            UPDATE {notifications}
             SET status = $2,
                 updated_at = $3
             WHERE id = $1
-        "#)
+        "#))
         .bind(uuid)
         .bind(format!("{:?}", status))
         .bind(Utc::now())
@@ -417,29 +454,30 @@ impl NotificationRepository for PostgresRepository {
         limit: u32,
         offset: u32,
     ) -> Result<Vec<Notification>, ServiceError> {
+        self.ensure_tables().await?;
         let query = match notification_type {
             Some(nt) => {
-                sqlx::query(r#"
+                sqlx::query(&Self::sql(r#"
                     SELECT *
-                    FROM notifications
+                    FROM {notifications}
                     WHERE target_users ? $1
                     AND notification_type = $2
                     ORDER BY created_at DESC
                     LIMIT $3 OFFSET $4
-                "#)
+                "#))
                 .bind(user_id)
                 .bind(format!("{:?}", nt))
                 .bind(limit as i64)
                 .bind(offset as i64)
             },
             None => {
-                sqlx::query(r#"
+                sqlx::query(&Self::sql(r#"
                     SELECT *
-                    FROM notifications
+                    FROM {notifications}
                     WHERE target_users ? $1
                     ORDER BY created_at DESC
                     LIMIT $2 OFFSET $3
-                "#)
+                "#))
                 .bind(user_id)
                 .bind(limit as i64)
                 .bind(offset as i64)
@@ -477,18 +515,19 @@ impl NotificationRepository for PostgresRepository {
         start_time: chrono::DateTime<chrono::Utc>,
         end_time: chrono::DateTime<chrono::Utc>,
     ) -> Result<PlatformStatistics, ServiceError> {
-        let rows = sqlx::query(r#"
+        self.ensure_tables().await?;
+        let rows = sqlx::query(&Self::sql(r#"
             SELECT COUNT(*) as total,
                    SUM(CASE WHEN success THEN 1 ELSE 0 END) as success_count,
                    SUM(CASE WHEN NOT success THEN 1 ELSE 0 END) as failed_count,
                    AVG(EXTRACT(EPOCH FROM (updated_at - created_at))) as avg_latency,
                    jsonb_object_agg(COALESCE(error, 'none'), COUNT(*)) as error_counts
-            FROM notification_results r
-            JOIN notifications n ON r.notification_id = n.id
+            FROM {notification_results} r
+            JOIN {notifications} n ON r.notification_id = n.id
             WHERE n.platform ? $1
             AND n.created_at BETWEEN $2 AND $3
             GROUP BY n.platform
-        "#)
+        "#))
         .bind(format!("{:?}", platform))
         .bind(start_time)
         .bind(end_time)
@@ -4,6 +4,8 @@ use crate::domain::{
     services::session_service::{SessionService, SessionState, UnreadMessage},
 };
 use chrono::Utc;
+use common::error::AppError;
+use proto_crate::api::im::common::ErrorCode;
 use uuid::Uuid;
 
 pub struct SessionManager<R: SessionRepository, S: SessionService> {
@@ -66,10 +68,12 @@ impl<R: SessionRepository, S: SessionService> SessionManager<R, S> {
     // 同步会话状态
     pub async fn sync_session(&self, session_id: &str, user_id: &str) -> Result<SessionState, Error> {
         // 同步会话状态
-        let state = self.session_service.sync_session_state(session_id, user_id).await?;
+        let mut state = self.session_service.sync_session_state(session_id, user_id).await?;
+        state.mention_count = self.session_repository.get_mention_count(session_id, user_id).await?;
 
-        // 重置未读消息计数
+        // 重置未读消息与 @ 未读计数
         self.session_repository.reset_unread_count(session_id, user_id).await?;
+        self.session_repository.reset_mention_count(session_id, user_id).await?;
 
         Ok(state)
     }
@@ -126,4 +130,28 @@ pub enum Error {
     
     #[error("Not found: {0}")]
     NotFound(String),
-} 
\ No newline at end of file
+}
+
+impl From<Error> for AppError {
+    fn from(err: Error) -> Self {
+        use crate::domain::repositories::session_repository::Error as RepositoryError;
+        use crate::domain::services::session_service::Error as ServiceError;
+
+        let code = match &err {
+            Error::NotFound(_) | Error::Repository(RepositoryError::NotFound(_)) => ErrorCode::SessionNotFound,
+            Error::Repository(RepositoryError::MemberNotFound(_)) => ErrorCode::NotFound,
+            Error::ValidationError(_) | Error::Repository(RepositoryError::InvalidData(_)) => {
+                ErrorCode::InvalidParams
+            }
+            Error::Repository(RepositoryError::Duplicate(_)) => ErrorCode::SessionCreateFailed,
+            Error::Service(ServiceError::Session(_) | ServiceError::InvalidState(_)) => {
+                ErrorCode::SessionUpdateFailed
+            }
+            Error::Service(ServiceError::Connection(_)) => ErrorCode::ConnectionFailed,
+            Error::Service(ServiceError::Sync(_)) => ErrorCode::SyncFailed,
+            Error::Repository(RepositoryError::Repository(_))
+            | Error::Service(ServiceError::RecoveryFailed(_)) => ErrorCode::SystemError,
+        };
+        AppError::new(code, err.to_string())
+    }
+}
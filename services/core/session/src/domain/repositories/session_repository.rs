@@ -21,6 +21,9 @@ pub trait SessionRepository {
     async fn update_latest_message(&self, session_id: &str, message_id: &str, preview: &str) -> Result<(), Error>;
     async fn increment_unread_count(&self, session_id: &str, user_id: &str) -> Result<i32, Error>;
     async fn reset_unread_count(&self, session_id: &str, user_id: &str) -> Result<(), Error>;
+    // @ 未读计数由路由服务按会话ID写入，会话服务只读取与清零
+    async fn get_mention_count(&self, session_id: &str, user_id: &str) -> Result<i32, Error>;
+    async fn reset_mention_count(&self, session_id: &str, user_id: &str) -> Result<(), Error>;
     
     // 会话设置操作
     async fn update_settings(&self, session_id: &str, settings: std::collections::HashMap<String, String>) -> Result<(), Error>;
@@ -25,6 +25,8 @@ pub struct SessionState {
     pub session: Session,
     pub online_members: Vec<SessionMember>,
     pub last_sync_time: i64,
+    // 同步前的 @ 未读数，由会话管理器从仓储读取
+    pub mention_count: i32,
 }
 
 #[derive(Debug)]
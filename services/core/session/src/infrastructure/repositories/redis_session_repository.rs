@@ -1,4 +1,6 @@
 use async_trait::async_trait;
+use common::tenant;
+use common::utils::msg_utils::mention_unread_key;
 use redis::{AsyncCommands, RedisError};
 use crate::domain::{
     entities::session::{Session, SessionMember, OnlineStatus},
@@ -15,24 +17,29 @@ impl RedisSessionRepository {
         Ok(Self { client })
     }
 
+    // 所有键按当前租户加前缀，默认租户沿用原有键
     fn session_key(session_id: &str) -> String {
-        format!("session:{}", session_id)
+        tenant::current().redis_key(&format!("session:{}", session_id))
     }
 
     fn user_sessions_key(user_id: &str) -> String {
-        format!("user:{}:sessions", user_id)
+        tenant::current().redis_key(&format!("user:{}:sessions", user_id))
     }
 
     fn session_members_key(session_id: &str) -> String {
-        format!("session:{}:members", session_id)
+        tenant::current().redis_key(&format!("session:{}:members", session_id))
     }
 
     fn member_key(session_id: &str, user_id: &str) -> String {
-        format!("session:{}:member:{}", session_id, user_id)
+        tenant::current().redis_key(&format!("session:{}:member:{}", session_id, user_id))
     }
 
     fn unread_count_key(session_id: &str, user_id: &str) -> String {
-        format!("session:{}:unread:{}", session_id, user_id)
+        tenant::current().redis_key(&format!("session:{}:unread:{}", session_id, user_id))
+    }
+
+    fn mention_key(user_id: &str) -> String {
+        tenant::current().redis_key(&mention_unread_key(user_id))
     }
 }
 
@@ -256,6 +263,26 @@ impl SessionRepository for RedisSessionRepository {
         Ok(())
     }
 
+    async fn get_mention_count(&self, session_id: &str, user_id: &str) -> Result<i32, RepoError> {
+        let mut conn = self.client.get_async_connection().await
+            .map_err(|e| RepoError::Repository(e.to_string()))?;
+
+        let count: Option<i32> = conn.hget(Self::mention_key(user_id), session_id).await
+            .map_err(|e| RepoError::Repository(e.to_string()))?;
+
+        Ok(count.unwrap_or(0))
+    }
+
+    async fn reset_mention_count(&self, session_id: &str, user_id: &str) -> Result<(), RepoError> {
+        let mut conn = self.client.get_async_connection().await
+            .map_err(|e| RepoError::Repository(e.to_string()))?;
+
+        conn.hdel(Self::mention_key(user_id), session_id).await
+            .map_err(|e| RepoError::Repository(e.to_string()))?;
+
+        Ok(())
+    }
+
     async fn update_settings(&self, session_id: &str, settings: std::collections::HashMap<String, String>) -> Result<(), RepoError> {
         if let Some(mut session) = self.get_by_id(session_id).await? {
             // 更新设置
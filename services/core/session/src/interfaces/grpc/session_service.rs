@@ -1,3 +1,5 @@
+use common::error::AppError;
+use common::tenant::TenantService;
 use tonic::{Request, Response, Status};
 use crate::{
     application::session_manager::SessionManager,
@@ -8,7 +10,7 @@ use crate::{
     },
 };
 use api::im::service::session::{
-    session_server::Session as GrpcSession,
+    session_server::{Session as GrpcSession, SessionServer},
     CreateSessionRequest,
     CreateSessionResponse,
     ConnectRequest,
@@ -32,6 +34,15 @@ impl<R: SessionRepository, S: SessionService> SessionGrpcService<R, S> {
         Self { session_manager }
     }
 
+    /// gRPC 服务端，请求在 `x-tenant-id` 指定的租户下处理
+    pub fn into_server(self) -> TenantService<SessionServer<Self>>
+    where
+        R: Send + Sync + 'static,
+        S: Send + Sync + 'static,
+    {
+        TenantService::new(SessionServer::new(self))
+    }
+
     // 转换设备平台
     fn convert_platform(platform: i32) -> Platform {
         match platform {
@@ -95,7 +106,7 @@ impl<R: SessionRepository + Send + Sync + 'static, S: SessionService + Send + Sy
 
         let created_session = self.session_manager.create_session(session)
             .await
-            .map_err(|e| Status::internal(e.to_string()))?;
+            .map_err(|e| Status::from(AppError::from(e)))?;
 
         Ok(Response::new(CreateSessionResponse {
             session_id: created_session.id,
@@ -119,7 +130,7 @@ impl<R: SessionRepository + Send + Sync + 'static, S: SessionService + Send + Sy
 
         self.session_manager.handle_connection(&req.session_id, &req.user_id, device_info)
             .await
-            .map_err(|e| Status::internal(e.to_string()))?;
+            .map_err(|e| Status::from(AppError::from(e)))?;
 
         Ok(Response::new(ConnectResponse {
             success: true,
@@ -135,7 +146,7 @@ impl<R: SessionRepository + Send + Sync + 'static, S: SessionService + Send + Sy
 
         self.session_manager.handle_disconnection(&req.session_id, &req.user_id, &req.device_id)
             .await
-            .map_err(|e| Status::internal(e.to_string()))?;
+            .map_err(|e| Status::from(AppError::from(e)))?;
 
         Ok(Response::new(DisconnectResponse {
             success: true,
@@ -151,7 +162,7 @@ impl<R: SessionRepository + Send + Sync + 'static, S: SessionService + Send + Sy
 
         self.session_manager.handle_heartbeat(&req.session_id, &req.user_id, &req.device_id)
             .await
-            .map_err(|e| Status::internal(e.to_string()))?;
+            .map_err(|e| Status::from(AppError::from(e)))?;
 
         Ok(Response::new(HeartbeatResponse {
             success: true,
@@ -167,7 +178,7 @@ impl<R: SessionRepository + Send + Sync + 'static, S: SessionService + Send + Sy
 
         let state = self.session_manager.sync_session(&req.session_id, &req.user_id)
             .await
-            .map_err(|e| Status::internal(e.to_string()))?;
+            .map_err(|e| Status::from(AppError::from(e)))?;
 
         Ok(Response::new(SyncSessionResponse {
             session: Some(api::im::service::session::Session {
@@ -180,6 +191,7 @@ impl<R: SessionRepository + Send + Sync + 'static, S: SessionService + Send + Sy
                     crate::domain::entities::session::SessionType::System => 2,
                 },
                 unread_count: state.session.unread_count,
+                mention_count: state.mention_count,
                 last_message: state.session.latest_message.map(|m| api::im::service::session::LatestMessage {
                     message_id: m.message_id.to_string(),
                     sender_id: m.sender_id,
@@ -228,7 +240,7 @@ impl<R: SessionRepository + Send + Sync + 'static, S: SessionService + Send + Sy
 
         let sessions = self.session_manager.recover_user_sessions(&req.user_id, device_info)
             .await
-            .map_err(|e| Status::internal(e.to_string()))?;
+            .map_err(|e| Status::from(AppError::from(e)))?;
 
         Ok(Response::new(RecoverSessionResponse {
             sessions: sessions.into_iter().map(|s| api::im::service::session::Session {
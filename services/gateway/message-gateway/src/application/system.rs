@@ -1,19 +1,34 @@
 use anyhow::Result;
+use std::sync::Arc;
+use common::tenant::TenantContext;
 use crate::domain::system::SystemManager;
 
+#[derive(Clone)]
 pub struct SystemService {
-    system_manager: SystemManager,
+    system_manager: Arc<SystemManager>,
 }
 
 impl SystemService {
-    pub fn new() -> Self {
-        Self {
-            system_manager: SystemManager::new(),
-        }
+    pub fn new(system_manager: Arc<SystemManager>) -> Self {
+        Self { system_manager }
     }
 
-    pub async fn update_heartbeat(&self, user_id: &str) -> Result<()> {
-        self.system_manager.update_heartbeat(user_id).await
+    pub async fn register_connection(
+        &self,
+        tenant: &TenantContext,
+        user_id: &str,
+        device_id: &str,
+        platform: i32,
+    ) -> Result<()> {
+        self.system_manager.register_connection(tenant, user_id, device_id, platform).await
+    }
+
+    pub async fn update_heartbeat(&self, tenant: &TenantContext, user_id: &str, device_id: &str) -> Result<()> {
+        self.system_manager.update_heartbeat(tenant, user_id, device_id).await
+    }
+
+    pub async fn remove_connection(&self, tenant: &TenantContext, user_id: &str, device_id: &str) -> Result<()> {
+        self.system_manager.remove_connection(tenant, user_id, device_id).await
     }
 
     pub async fn process_system_notice(&self, notice_data: &[u8]) -> Result<()> {
@@ -23,4 +38,4 @@ impl SystemService {
     pub async fn update_config(&self, config_data: &[u8]) -> Result<()> {
         self.system_manager.update_config(config_data).await
     }
-} 
\ No newline at end of file
+}
@@ -8,7 +8,7 @@ use common::config::{Config, Environment};
 use message_gateway::infrastructure::config::init_config;
 use message_gateway::infrastructure::log::init_log;
 use message_gateway::interfaces::grpc::server::start_grpc_server;
-use message_gateway::interfaces::im::start_im_server;
+use message_gateway::interfaces::im::{init_system_service, start_im_server};
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -18,10 +18,11 @@ async fn main() -> Result<()> {
     // 初始化日志
     init_log()?;
 
-    // 启动 gRPC 服务和 IM 服务
+    // 启动 gRPC 服务和 IM 服务，共用连接管理
+    let system_service = init_system_service().await;
     try_join!(
-        start_grpc_server(),
-        start_im_server()
+        start_grpc_server(system_service.clone()),
+        start_im_server(system_service)
     )?;
 
     Ok(())
@@ -1,25 +1,216 @@
 use anyhow::Result;
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 use dashmap::DashMap;
 use chrono::Utc;
+use common::bus::{MessageProducer, OutgoingMessage};
+use common::route::{GatewayLoad, PresenceEvent, RouteStore, UserRoute};
+use common::tenant::{self, TenantContext};
+use common::topic::KafkaTopics;
+use log::{info, warn};
+
+/// 当前网关实例信息，登记到用户路由中供路由服务定向推送
+#[derive(Debug, Clone)]
+pub struct GatewayIdentity {
+    /// 网关实例ID
+    pub gateway_id: String,
+    /// gRPC 地址 (`host:port`)
+    pub gateway_addr: String,
+    /// 调度权重
+    pub weight: u32,
+    /// 连接容量
+    pub capacity: u64,
+}
+
+/// 本机连接
+#[derive(Debug, Clone)]
+struct LocalConnection {
+    /// 连接所属租户，路由按租户写入
+    tenant: TenantContext,
+    route: UserRoute,
+}
 
 pub struct SystemManager {
     heartbeats: Arc<DashMap<String, i64>>,
+    connections: Arc<DashMap<String, LocalConnection>>,
+    route_store: Option<RouteStore>,
+    /// 上下线事件，未配置时不发布
+    presence: Option<Arc<dyn MessageProducer>>,
+    gateway: GatewayIdentity,
 }
 
 impl SystemManager {
-    pub fn new() -> Self {
+    pub fn new(gateway: GatewayIdentity, route_store: Option<RouteStore>) -> Self {
         Self {
             heartbeats: Arc::new(DashMap::new()),
+            connections: Arc::new(DashMap::new()),
+            route_store,
+            presence: None,
+            gateway,
         }
     }
 
-    pub async fn update_heartbeat(&self, user_id: &str) -> Result<()> {
+    /// 发布用户上下线事件，供路由服务维护群在线成员索引
+    pub fn with_presence(mut self, producer: Arc<dyn MessageProducer>) -> Self {
+        self.presence = Some(producer);
+        self
+    }
+
+    /// 发布上下线事件，失败只记录日志，不影响连接
+    async fn publish_presence(&self, user_id: &str, device_id: &str, online: bool) {
+        let Some(producer) = &self.presence else {
+            return;
+        };
+        let event = PresenceEvent {
+            user_id: user_id.to_string(),
+            device_id: device_id.to_string(),
+            gateway_id: self.gateway.gateway_id.clone(),
+            online,
+            time: Utc::now().timestamp_millis(),
+        };
+        let payload = match serde_json::to_vec(&event) {
+            Ok(payload) => payload,
+            Err(e) => {
+                warn!("Failed to encode presence event of {}: {}", user_id, e);
+                return;
+            }
+        };
+        let record = OutgoingMessage::new(payload)
+            .key(tenant::current().kafka_key(user_id))
+            .timestamp(event.time)
+            .with_current_context();
+        if let Err(e) = producer.send(KafkaTopics::USER_PRESENCE, record).await {
+            warn!("Failed to publish presence of {}/{}: {}", user_id, device_id, e);
+        }
+    }
+
+    fn connection_key(tenant: &TenantContext, user_id: &str, device_id: &str) -> String {
+        format!("{}:{}:{}", tenant.tenant_id(), user_id, device_id)
+    }
+
+    /// 连接建立，在连接所属租户下登记用户路由
+    pub async fn register_connection(
+        &self,
+        tenant: &TenantContext,
+        user_id: &str,
+        device_id: &str,
+        platform: i32,
+    ) -> Result<()> {
+        let route = UserRoute {
+            user_id: user_id.to_string(),
+            device_id: device_id.to_string(),
+            platform,
+            gateway_id: self.gateway.gateway_id.clone(),
+            gateway_addr: self.gateway.gateway_addr.clone(),
+            connected_at: Utc::now().timestamp_millis(),
+            expires_at: 0,
+        };
+        let key = Self::connection_key(tenant, user_id, device_id);
+        self.heartbeats.insert(key.clone(), Utc::now().timestamp());
+        self.connections.insert(key, LocalConnection {
+            tenant: tenant.clone(),
+            route: route.clone(),
+        });
+
+        tenant::scope(tenant.clone(), async {
+            if let Some(store) = &self.route_store {
+                store.register(&route).await?;
+            }
+            self.publish_presence(user_id, device_id, true).await;
+            Ok::<_, anyhow::Error>(())
+        })
+        .await?;
+        info!("Registered route {}/{} of tenant {} on {}", user_id, device_id, tenant, self.gateway.gateway_id);
+        Ok(())
+    }
+
+    /// 心跳，续期用户路由
+    pub async fn update_heartbeat(&self, tenant: &TenantContext, user_id: &str, device_id: &str) -> Result<()> {
+        let key = Self::connection_key(tenant, user_id, device_id);
         let now = Utc::now().timestamp();
-        self.heartbeats.insert(user_id.to_string(), now);
+        self.heartbeats.insert(key.clone(), now);
+
+        let route = self.connections.get(&key).map(|c| c.route.clone());
+        if let (Some(store), Some(route)) = (&self.route_store, route) {
+            tenant::scope(tenant.clone(), store.refresh(&route)).await?;
+        }
         Ok(())
     }
 
+    /// 连接关闭，删除用户路由
+    pub async fn remove_connection(&self, tenant: &TenantContext, user_id: &str, device_id: &str) -> Result<()> {
+        let key = Self::connection_key(tenant, user_id, device_id);
+        self.heartbeats.remove(&key);
+        self.connections.remove(&key);
+
+        tenant::scope(tenant.clone(), async {
+            if let Some(store) = &self.route_store {
+                store.remove(user_id, device_id, &self.gateway.gateway_id).await?;
+            }
+            self.publish_presence(user_id, device_id, false).await;
+            Ok(())
+        })
+        .await
+    }
+
+    /// 续期本机全部连接的路由，按租户分批写入
+    ///
+    /// 客户端心跳由连接层处理，不一定经过网关业务代码，定期续期保证存活连接的路由不过期。
+    pub async fn refresh_routes(&self) -> Result<()> {
+        let Some(store) = &self.route_store else {
+            return Ok(());
+        };
+        let mut by_tenant: HashMap<TenantContext, Vec<UserRoute>> = HashMap::new();
+        for connection in self.connections.iter() {
+            by_tenant
+                .entry(connection.tenant.clone())
+                .or_default()
+                .push(connection.route.clone());
+        }
+        for (tenant, routes) in by_tenant {
+            tenant::scope(tenant, store.refresh_many(&routes)).await?;
+        }
+        Ok(())
+    }
+
+    /// 上报网关负载
+    pub async fn report_load(&self) -> Result<()> {
+        let Some(store) = &self.route_store else {
+            return Ok(());
+        };
+        store
+            .report_load(&GatewayLoad {
+                gateway_id: self.gateway.gateway_id.clone(),
+                gateway_addr: self.gateway.gateway_addr.clone(),
+                weight: self.gateway.weight,
+                connections: self.connections.len() as u64,
+                capacity: self.gateway.capacity,
+                updated_at: Utc::now().timestamp_millis(),
+            })
+            .await
+    }
+
+    /// 定期上报负载并续期本机连接的路由，间隔应小于路由有效期
+    pub fn spawn_load_reporter(self: &Arc<Self>, interval: Duration) {
+        let manager = Arc::downgrade(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                let Some(manager) = manager.upgrade() else {
+                    break;
+                };
+                if let Err(e) = manager.report_load().await {
+                    warn!("Failed to report gateway load: {}", e);
+                }
+                if let Err(e) = manager.refresh_routes().await {
+                    warn!("Failed to refresh gateway routes: {}", e);
+                }
+            }
+        });
+    }
+
     pub async fn process_system_notice(&self, notice_data: &[u8]) -> Result<()> {
         // TODO: 实现系统通知处理逻辑
         Ok(())
@@ -29,4 +220,4 @@ impl SystemManager {
         // TODO: 实现配置更新逻辑
         Ok(())
     }
-} 
\ No newline at end of file
+}
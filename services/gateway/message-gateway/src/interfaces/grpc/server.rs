@@ -1,4 +1,5 @@
 use crate::application::message::MessageService;
+use crate::application::system::SystemService;
 use crate::infrastructure::config::get_config;
 use crate::interfaces::grpc::service::GrpcMessageService;
 use anyhow::Result;
@@ -6,11 +7,14 @@ use flare_rpc_core::discover::consul::{ConsulConfig, ConsulRegistry};
 use flare_rpc_core::AppBuilder;
 use log::info;
 use proto_crate::api::im::gateway::message_gateway_server::MessageGatewayServer;
+use common::telemetry::{server_interceptor, TraceContextLayer};
+use common::health::{ConsulHealthRegistrar, HealthRegistry};
+use std::sync::Arc;
 use std::net::SocketAddr;
 use std::time::Duration;
 use tonic::transport::Server;
 
-pub async fn start_grpc_server() -> Result<()> {
+pub async fn start_grpc_server(system_service: SystemService) -> Result<()> {
     info!("Starting gRPC server...");
 
     // 获取全局配置
@@ -44,13 +48,27 @@ pub async fn start_grpc_server() -> Result<()> {
     }
     let app = app_builder.build();
 
+    // 健康检查
+    let health = Arc::new(HealthRegistry::new(config.service.name.clone()));
+    let health_addr = format!("{}:{}", config.service.host, config.service.port + 1000);
+    let health_service = health.start(&health_addr, Duration::from_secs(5)).await?;
+
+    // 就绪检查失败时由 Consul 将实例摘除
+    ConsulHealthRegistrar::new(&config.consul).spawn_register(
+        config.service.name.clone(),
+        addr,
+        format!("http://{}/readyz", health_addr),
+    );
+
     // 创建服务实例
     let message_service = MessageService::new();
-    let grpc_handler = GrpcMessageService::new(message_service);
+    let grpc_handler = GrpcMessageService::new(message_service, system_service);
 
     // 运行服务器
     app.run(config.service.host.clone().as_str(), config.service.port, |mut server, addr| async move {
-        server.add_service(MessageGatewayServer::new(grpc_handler))
+        server.layer(TraceContextLayer)
+            .add_service(health_service)
+            .add_service(MessageGatewayServer::with_interceptor(grpc_handler, server_interceptor))
             .serve(addr)
             .await
             .map_err(|e| e.into())
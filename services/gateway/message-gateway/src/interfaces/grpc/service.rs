@@ -1,15 +1,23 @@
 use tonic::{Request, Response, Status};
 use proto_crate::api::im::gateway::{message_gateway_server::MessageGateway, PushMessageRequest, PushMessageResponse, BatchPushMessageRequest, BatchPushMessageResponse, BroadcastMessageRequest, BroadcastMessageResponse, GetUserStatusRequest, GetUserStatusResponse, RegisterConnectionRequest, RegisterConnectionResponse, UnregisterConnectionRequest, UnregisterConnectionResponse, HeartBeatRequest, HeartBeatResponse};
 
+use common::tenant::TenantContext;
+
 use crate::application::message::MessageService;
+use crate::application::system::SystemService;
 
 pub struct GrpcMessageService {
     message_service: MessageService,
+    system_service: SystemService,
 }
 
 impl GrpcMessageService {
-    pub fn new(message_service: MessageService) -> Self {
-        Self { message_service }
+    pub fn new(message_service: MessageService, system_service: SystemService) -> Self {
+        Self { message_service, system_service }
+    }
+
+    fn connection_id(user_id: &str, device_id: &str) -> String {
+        format!("{}:{}", user_id, device_id)
     }
 }
 
@@ -60,14 +68,44 @@ impl MessageGateway for GrpcMessageService {
     }
 
     async fn register_connection(&self, request: Request<RegisterConnectionRequest>) -> Result<Response<RegisterConnectionResponse>, Status> {
-        todo!()
+        // 路由写入请求所属租户
+        let tenant = TenantContext::from_request(&request)?;
+        let req = request.into_inner();
+        if req.user_id.is_empty() || req.device_id.is_empty() {
+            return Err(Status::invalid_argument("user_id and device_id are required"));
+        }
+        match self.system_service.register_connection(&tenant, &req.user_id, &req.device_id, req.platform).await {
+            Ok(_) => Ok(Response::new(RegisterConnectionResponse {
+                success: true,
+                error: String::new(),
+                connection_id: Self::connection_id(&req.user_id, &req.device_id),
+            })),
+            Err(e) => Err(Status::internal(e.to_string()))
+        }
     }
 
     async fn unregister_connection(&self, request: Request<UnregisterConnectionRequest>) -> Result<Response<UnregisterConnectionResponse>, Status> {
-        todo!()
+        let tenant = TenantContext::from_request(&request)?;
+        let req = request.into_inner();
+        match self.system_service.remove_connection(&tenant, &req.user_id, &req.device_id).await {
+            Ok(_) => Ok(Response::new(UnregisterConnectionResponse {
+                success: true,
+                error: String::new(),
+            })),
+            Err(e) => Err(Status::internal(e.to_string()))
+        }
     }
 
     async fn heart_beat(&self, request: Request<HeartBeatRequest>) -> Result<Response<HeartBeatResponse>, Status> {
-        todo!()
+        let tenant = TenantContext::from_request(&request)?;
+        let req = request.into_inner();
+        match self.system_service.update_heartbeat(&tenant, &req.user_id, &req.device_id).await {
+            Ok(_) => Ok(Response::new(HeartBeatResponse {
+                success: true,
+                error: String::new(),
+                server_time: chrono::Utc::now().timestamp_millis(),
+            })),
+            Err(e) => Err(Status::internal(e.to_string()))
+        }
     }
 } 
\ No newline at end of file
@@ -3,4 +3,4 @@ mod message;
 mod system;
 mod server;
 
-pub use server::start_im_server; 
\ No newline at end of file
+pub use server::{init_system_service, start_im_server}; 
\ No newline at end of file
@@ -4,17 +4,35 @@ use flare_im_core::server::handlers::ServerMessageHandler;
 use flare_im_core::server::server_handler::ServerCommandHandler;
 use flare_im_core::server::sys_handler::SystemCommandHandler;
 use flare_im_core::telecom::FlareServer;
-use log::{info, error};
+use log::{info, error, warn};
+use common::config::Config;
+use common::bus::{KafkaBus, MessageBus, MessageProducer};
+use common::route::RouteStore;
+use std::sync::Arc;
+use std::time::Duration;
 
 use crate::application::auth::AuthService;
 use crate::application::message::MessageService;
 use crate::application::system::SystemService;
+use crate::domain::system::{GatewayIdentity, SystemManager};
 use crate::infrastructure::config::get_config;
 use super::auth::CustomAuthHandler;
 use super::message::CustomMessageHandler;
 use super::system::CustomSystemHandler;
 
-pub async fn start_im_server() -> Result<()> {
+/// 连接管理，IM 长连接与 gRPC 连接管理接口共用
+pub async fn init_system_service() -> SystemService {
+    let config = get_config();
+    let mut system_manager = SystemManager::new(gateway_identity(config), init_route_store(config).await);
+    if let Some(producer) = init_presence_producer(config) {
+        system_manager = system_manager.with_presence(producer);
+    }
+    let system_manager = Arc::new(system_manager);
+    system_manager.spawn_load_reporter(Duration::from_secs(10));
+    SystemService::new(system_manager)
+}
+
+pub async fn start_im_server(system_service: SystemService) -> Result<()> {
     info!("Starting IM server...");
 
     // 获取全局配置
@@ -43,7 +61,6 @@ pub async fn start_im_server() -> Result<()> {
     // 创建服务实例
     let auth_service = AuthService::new();
     let message_service = MessageService::new();
-    let system_service = SystemService::new();
 
     // 创建自定义处理器
     let auth_handler = CustomAuthHandler::new(auth_service);
@@ -78,4 +95,53 @@ pub async fn start_im_server() -> Result<()> {
     }
 
     Ok(())
-} 
\ No newline at end of file
+}
+
+/// 当前网关实例，地址为注册到 Consul 的 gRPC 地址
+fn gateway_identity(config: &Config) -> GatewayIdentity {
+    let gateway_addr = format!("{}:{}", config.service.host, config.service.port);
+    GatewayIdentity {
+        gateway_id: format!("{}-{}", config.service.name, gateway_addr),
+        gateway_addr,
+        weight: config.service.weight,
+        capacity: config.extensions.get("gateway")
+            .and_then(|v| v.get("capacity"))
+            .and_then(|v| v.as_u64())
+            .unwrap_or(100_000),
+    }
+}
+
+/// 用户路由存储，未配置或连接失败时仅在本地维护连接
+async fn init_route_store(config: &Config) -> Option<RouteStore> {
+    let redis = config.redis.as_ref()?;
+    let url = match &redis.password {
+        Some(password) => format!("redis://:{}@{}:{}/{}", password, redis.host, redis.port, redis.database),
+        None => format!("redis://{}:{}/{}", redis.host, redis.port, redis.database),
+    };
+    let client = match redis::Client::open(url) {
+        Ok(client) => client,
+        Err(e) => {
+            warn!("Invalid redis config, routes will not be registered: {}", e);
+            return None;
+        }
+    };
+    match redis::aio::ConnectionManager::new(client).await {
+        Ok(conn) => Some(RouteStore::new(conn)),
+        Err(e) => {
+            warn!("Failed to connect redis, routes will not be registered: {}", e);
+            None
+        }
+    }
+}
+
+/// 上下线事件生产者，未配置 Kafka 时不发布
+fn init_presence_producer(config: &Config) -> Option<Arc<dyn MessageProducer>> {
+    let kafka = config.kafka.as_ref()?;
+    match KafkaBus::new(&kafka.brokers.join(","), &config.service.name) {
+        Ok(bus) => Some(bus.producer()),
+        Err(e) => {
+            warn!("Failed to create kafka producer, presence events will not be published: {}", e);
+            None
+        }
+    }
+}
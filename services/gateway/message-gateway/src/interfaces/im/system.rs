@@ -1,5 +1,6 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use async_trait::async_trait;
+use common::tenant::{TenantContext, TENANT_METADATA_KEY};
 use flare_core::context::AppContext;
 use flare_core::flare_net::net::{Response, ResCode};
 use flare_im_core::server::server::ConnectionInfo;
@@ -8,6 +9,32 @@ use log::{info, error};
 
 use crate::application::system::SystemService;
 
+/// 连接身份
+struct ConnectionIdentity {
+    tenant: TenantContext,
+    user_id: String,
+    device_id: String,
+    platform: i32,
+}
+
+impl ConnectionIdentity {
+    /// 从连接上下文解析身份，租户取自登录时携带的 `x-tenant-id`，缺失时为默认租户
+    fn from_context(ctx: &AppContext) -> Result<Self> {
+        let user_id = ctx.user_id().filter(|id| !id.is_empty()).ok_or_else(|| anyhow!("missing user id"))?;
+        let device_id = ctx.device_id().filter(|id| !id.is_empty()).ok_or_else(|| anyhow!("missing device id"))?;
+        let tenant = match ctx.get_metadata(TENANT_METADATA_KEY) {
+            Some(tenant_id) => TenantContext::new(tenant_id)?,
+            None => TenantContext::default(),
+        };
+        Ok(Self {
+            tenant,
+            user_id,
+            device_id,
+            platform: ctx.platform(),
+        })
+    }
+}
+
 pub struct CustomSystemHandler {
     system_service: SystemService,
 }
@@ -16,24 +43,61 @@ impl CustomSystemHandler {
     pub fn new(system_service: SystemService) -> Self {
         Self { system_service }
     }
+
+    fn response(result: Result<()>, message: &str) -> Response {
+        let mut response = Response::default();
+        match result {
+            Ok(_) => {
+                response.code = ResCode::Success as i32;
+                response.message = message.to_string();
+            }
+            Err(e) => {
+                response.code = ResCode::BusinessError as i32;
+                response.message = e.to_string();
+            }
+        }
+        response
+    }
 }
 
 #[async_trait]
 impl SystemHandler for CustomSystemHandler {
 
-    async fn handle_new_connection(&self, ctx: &AppContext, conn: &ConnectionInfo) -> flare_core::error::Result<Response> {
-        todo!()
+    async fn handle_new_connection(&self, ctx: &AppContext, _conn: &ConnectionInfo) -> flare_core::error::Result<Response> {
+        let result = match ConnectionIdentity::from_context(ctx) {
+            Ok(identity) => self
+                .system_service
+                .register_connection(&identity.tenant, &identity.user_id, &identity.device_id, identity.platform)
+                .await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = &result {
+            error!("Failed to register connection of {:?}: {}", ctx.user_id(), e);
+        }
+        Ok(Self::response(result, "Connection registered"))
     }
 
     async fn handle_set_background(&self, ctx: &AppContext, background: bool) -> flare_core::error::Result<Response> {
-        todo!()
+        info!("Connection of {:?} set background: {}", ctx.user_id(), background);
+        Ok(Self::response(Ok(()), "Background updated"))
     }
 
     async fn handle_set_language(&self, ctx: &AppContext, language: String) -> flare_core::error::Result<Response> {
-        todo!()
+        info!("Connection of {:?} set language: {}", ctx.user_id(), language);
+        Ok(Self::response(Ok(()), "Language updated"))
     }
 
     async fn handle_close(&self, ctx: &AppContext) -> flare_core::error::Result<Response> {
-        todo!()
+        let result = match ConnectionIdentity::from_context(ctx) {
+            Ok(identity) => self
+                .system_service
+                .remove_connection(&identity.tenant, &identity.user_id, &identity.device_id)
+                .await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = &result {
+            error!("Failed to remove connection of {:?}: {}", ctx.user_id(), e);
+        }
+        Ok(Self::response(result, "Connection closed"))
     }
-} 
\ No newline at end of file
+}